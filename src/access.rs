@@ -0,0 +1,222 @@
+use crate::config::Config;
+use crate::generator::Post;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Location of the request log `scribe serve --access-log` writes, relative to the project root.
+pub const LOG_PATH: &str = ".scribe/access.log";
+
+/// Timestamp format of the Combined Log Format.
+const TIME_FORMAT: &str = "%d/%b/%Y:%H:%M:%S %z";
+
+/// Words in user agents of crawlers, link previews and other automated clients.
+const BOT_MARKERS: &[&str] = &["bot", "crawl", "spider", "slurp", "preview", "fetch", "curl", "wget", "python", "go-http", "headless"];
+
+/// One request, as a line of the Combined Log Format. The client address is never recorded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub time: DateTime<Utc>,
+    pub method: String,
+    pub path: String,
+    pub protocol: String,
+    pub status: u16,
+    /// Page the reader came from, left out for links within the site
+    pub referrer: Option<String>,
+    pub agent: Option<String>,
+}
+
+impl Entry {
+    /// The entry as a log line, without the trailing newline.
+    pub fn line(&self) -> String {
+        let quoted = |value: &Option<String>| value.as_deref().map_or("-".to_string(), escape);
+        format!(
+            "- - - [{}] \"{} {} {}\" {} - \"{}\" \"{}\"",
+            self.time.format(TIME_FORMAT),
+            self.method,
+            escape(&self.path),
+            self.protocol,
+            self.status,
+            quoted(&self.referrer),
+            quoted(&self.agent)
+        )
+    }
+
+    /// Read a log line back; None for lines in another format.
+    pub fn parse(line: &str) -> Option<Entry> {
+        let rest = &line[line.find('[')? + 1..];
+        let (time, rest) = rest.split_once(']')?;
+        let time = DateTime::parse_from_str(time, TIME_FORMAT).ok()?.with_timezone(&Utc);
+        let (request, rest) = quoted(rest.trim_start())?;
+        let (method, request) = request.split_once(' ')?;
+        let (path, protocol) = request.rsplit_once(' ').unwrap_or((request, ""));
+        let (method, path, protocol) = (method.to_string(), path.to_string(), protocol.to_string());
+        // Status, response size, then the quoted referrer and user agent
+        let mut fields = rest.trim_start().splitn(3, ' ');
+        let status = fields.next()?.parse().ok()?;
+        fields.next()?;
+        let (referrer, rest) = quoted(fields.next()?.trim_start())?;
+        let (agent, _) = quoted(rest.trim_start())?;
+        let field = |value: String| (value != "-" && !value.is_empty()).then_some(value);
+        Some(Entry { time, method, path, protocol, status, referrer: field(referrer), agent: field(agent) })
+    }
+}
+
+/// `value` for a quoted log field, with quotes and backslashes escaped.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The quoted field opening `text`, unescaped, and the text after it.
+fn quoted(text: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = text.strip_prefix('"')?.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => value.extend(chars.next().map(|(_, c)| c)),
+            '"' => return Some((value, &text[i + 2..])),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// An access log open for appending, shared by the server's requests.
+pub struct Log {
+    file: Mutex<File>,
+}
+
+impl Log {
+    pub fn open(path: &Path) -> Result<Log> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path).context(format!("Failed to open {}", path.display()))?;
+        Ok(Log { file: Mutex::new(file) })
+    }
+
+    /// Append `entry`; a failed write loses the line rather than the request.
+    pub fn write(&self, entry: &Entry) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(file, "{}", entry.line());
+        }
+    }
+}
+
+/// Every entry in the log at `path`, skipping lines that can't be read.
+pub fn read(path: &Path) -> Result<Vec<Entry>> {
+    let content = fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    Ok(content.lines().filter_map(Entry::parse).collect())
+}
+
+/// Page views summarized from an access log, each list busiest first.
+#[derive(Debug, Default)]
+pub struct Stats<'a> {
+    /// Page views by people, the ones counted below
+    pub views: usize,
+    /// Page views by crawlers and other automated clients, left out of the counts
+    pub bots: usize,
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+    pub posts: Vec<(&'a Post, usize)>,
+    /// Views of pages other than posts (index, archive, series), by path from the site root
+    pub pages: Vec<(String, usize)>,
+    /// Views by the host of the page readers came from
+    pub referrers: Vec<(String, usize)>,
+    /// Views by browser
+    pub agents: Vec<(String, usize)>,
+}
+
+/// Summarize `entries`: successful page requests (not assets, feeds or errors), by post, page,
+/// referring site and browser.
+pub fn summarize<'a>(entries: &[Entry], config: &Config, posts: &'a [Post]) -> Stats<'a> {
+    let base_path = config.base_path();
+    let by_slug: HashMap<&str, &Post> = posts.iter().flat_map(|p| [(p.slug.as_str(), p), (p.original_slug.as_str(), p)]).collect();
+    let mut stats = Stats::default();
+    let (mut post_views, mut pages, mut referrers, mut agents) = (HashMap::new(), HashMap::new(), HashMap::new(), HashMap::new());
+    for entry in entries {
+        let Some(page) = page(&entry.path, &base_path) else { continue };
+        if entry.method != "GET" || !matches!(entry.status, 200 | 304) {
+            continue;
+        }
+        let agent = entry.agent.as_deref().unwrap_or_default();
+        if is_bot(agent) {
+            stats.bots += 1;
+            continue;
+        }
+        stats.views += 1;
+        stats.first = Some(stats.first.map_or(entry.time, |first| first.min(entry.time)));
+        stats.last = stats.last.max(Some(entry.time));
+        match by_slug.get(page.as_str()) {
+            Some(post) => *post_views.entry(post.slug.as_str()).or_insert(0) += 1,
+            None => *pages.entry(format!("/{}", page)).or_insert(0) += 1,
+        }
+        if let Some(host) = entry.referrer.as_deref().and_then(referrer_host) {
+            *referrers.entry(host).or_insert(0) += 1;
+        }
+        *agents.entry(browser(agent)).or_insert(0) += 1;
+    }
+    stats.posts = busiest(post_views).into_iter().filter_map(|(slug, views)| Some((*by_slug.get(slug)?, views))).collect();
+    stats.pages = busiest(pages);
+    stats.referrers = busiest(referrers);
+    stats.agents = busiest(agents.into_iter().map(|(name, views)| (name.to_string(), views)).collect());
+    stats
+}
+
+/// Counts busiest first, ties by name.
+fn busiest<K: Ord>(counts: HashMap<K, usize>) -> Vec<(K, usize)> {
+    let mut counts: Vec<(K, usize)> = counts.into_iter().collect();
+    counts.sort_by(|(a, a_views), (b, b_views)| b_views.cmp(a_views).then_with(|| a.cmp(b)));
+    counts
+}
+
+/// The page a request path is for, relative to the site root without `index.html`, `.html` or
+/// a trailing slash (`""` for the index); None for other files and paths outside the site.
+fn page(path: &str, base_path: &str) -> Option<String> {
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let path = path.strip_prefix(base_path).or_else(|| (path.to_string() + "/" == base_path).then_some(""))?;
+    let path = path.strip_suffix("index.html").unwrap_or(path);
+    let path = path.strip_suffix(".html").unwrap_or(path).trim_end_matches('/');
+    if path.rsplit('/').next().is_some_and(|name| name.contains('.')) || path.starts_with("__") {
+        return None;
+    }
+    Some(path.to_string())
+}
+
+/// Whether a user agent is a crawler or other automated client rather than a reader's browser.
+fn is_bot(agent: &str) -> bool {
+    let agent = agent.to_lowercase();
+    agent.is_empty() || BOT_MARKERS.iter().any(|marker| agent.contains(marker))
+}
+
+/// Browser family of a user agent, by the product tokens browsers add in front of each other's.
+fn browser(agent: &str) -> &'static str {
+    const FAMILIES: &[(&str, &str)] = &[
+        ("Firefox/", "Firefox"),
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Vivaldi/", "Vivaldi"),
+        ("SamsungBrowser/", "Samsung Internet"),
+        ("Chrome/", "Chrome"),
+        ("CriOS/", "Chrome"),
+        ("Safari/", "Safari"),
+    ];
+    FAMILIES.iter().find(|(token, _)| agent.contains(token)).map_or("Other", |(_, name)| name)
+}
+
+/// Host of a referring page, without `www.`.
+fn referrer_host(referrer: &str) -> Option<String> {
+    let host = referrer.split_once("://").map_or(referrer, |(_, rest)| rest).split(['/', '?', '#']).next()?;
+    let host = host.trim_start_matches("www.");
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+/// Whether a referring page is on the site itself, served at `host`, so following links within
+/// the site doesn't count as a referral.
+pub fn is_internal(referrer: &str, host: &str) -> bool {
+    referrer.split_once("://").map_or(referrer, |(_, rest)| rest).split(['/', '?', '#']).next() == Some(host)
+}
@@ -0,0 +1,103 @@
+use crate::config::MarkdownOptions;
+use crate::markdown;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Suffix of sidecar annotation files: `my-post.md` pairs with `my-post.annotations.yaml`.
+pub const SIDECAR_SUFFIX: &str = ".annotations.yaml";
+
+/// One annotation: a line in the ```links syntax, a structured link, or a plain note.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Line(String),
+    Link {
+        url: String,
+        #[serde(default)]
+        title: Option<String>,
+        #[serde(default)]
+        description: Option<String>,
+    },
+    Note {
+        note: String,
+    },
+}
+
+impl Entry {
+    fn to_line(&self) -> String {
+        match self {
+            Entry::Line(line) => line.trim().to_string(),
+            Entry::Link { url, title, description } => {
+                let link = match title {
+                    Some(title) => format!("[{}]({})", title, url),
+                    None => url.clone(),
+                };
+                match description {
+                    Some(desc) => format!("{} - {}", link, desc),
+                    None => link,
+                }
+            }
+            Entry::Note { note } => note.trim().to_string(),
+        }
+    }
+}
+
+pub fn sidecar_path(post: &Path) -> PathBuf {
+    let stem = post.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    post.with_file_name(format!("{}{}", stem, SIDECAR_SUFFIX))
+}
+
+/// Merge the post's sidecar annotations (if any) into `markdown` as ```links blocks after their
+/// target paragraph or list. Keys are 1-based block numbers or text quoted from the block.
+/// Returns the merged Markdown and the keys that matched no block.
+pub fn merge_sidecar(post: &Path, markdown: &str, options: &MarkdownOptions) -> Result<(String, Vec<String>)> {
+    let path = sidecar_path(post);
+    if !path.exists() {
+        return Ok((markdown.to_string(), Vec::new()));
+    }
+    let content = fs::read_to_string(&path)
+        .context(format!("Failed to read {}", path.display()))?;
+    let sidecar: serde_yaml::Mapping = serde_yaml::from_str(&content)
+        .context(format!("Failed to parse {}", path.display()))?;
+
+    let blocks = markdown::annotatable_blocks(markdown, options);
+    let mut insertions: Vec<(usize, usize, String)> = Vec::new();
+    let mut unmatched = Vec::new();
+
+    for (key, value) in sidecar {
+        let entries: Vec<Entry> = serde_yaml::from_value(value)
+            .context(format!("{}: annotations must be a list of links or notes", path.display()))?;
+        let (label, block) = match &key {
+            serde_yaml::Value::Number(n) => {
+                let index = n.as_u64().unwrap_or(0) as usize;
+                (n.to_string(), index.checked_sub(1).and_then(|i| blocks.get(i)))
+            }
+            serde_yaml::Value::String(quote) => {
+                (format!("\"{}\"", quote), blocks.iter().find(|b| normalize(&markdown[(*b).clone()]).contains(&normalize(quote))))
+            }
+            other => (format!("{:?}", other), None),
+        };
+        match block {
+            Some(block) => {
+                let lines: Vec<String> = entries.iter().map(Entry::to_line).filter(|l| !l.is_empty()).collect();
+                insertions.push((block.end, insertions.len(), format!("\n\n```links\n{}\n```\n", lines.join("\n"))));
+            }
+            None => unmatched.push(label),
+        }
+    }
+
+    // Insert from the end so earlier offsets stay valid, and later entries first so blocks
+    // sharing a target keep their sidecar order
+    insertions.sort_by_key(|(at, order, _)| std::cmp::Reverse((*at, *order)));
+    let mut merged = markdown.to_string();
+    for (at, _, block) in insertions {
+        merged.insert_str(at, &block);
+    }
+    Ok((merged, unmatched))
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
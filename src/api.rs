@@ -0,0 +1,123 @@
+use crate::config::{Config, MermaidMode, TrailingSlash};
+use crate::export;
+use crate::generator::Post;
+use crate::graph::{GraphFormat, LinkGraph};
+use crate::markdown;
+use crate::mermaid;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Every post, newest first, in a headless build.
+pub const POSTS_FILE: &str = "posts.json";
+
+/// Tags with the slugs of their posts, in a headless build.
+pub const TAGS_FILE: &str = "tags.json";
+
+/// Link graph between posts, in a headless build and alongside the graph page.
+pub const GRAPH_FILE: &str = "graph.json";
+
+/// What `scribe generate` builds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum BuildFormat {
+    /// The website
+    #[default]
+    Html,
+    /// Only the content as JSON (posts, tags and link graph), for a frontend of your own
+    Json,
+}
+
+/// Structured data of a post, as written next to its page when `api` is on and in `posts.json`.
+#[derive(Serialize)]
+struct PostData<'a> {
+    slug: &'a str,
+    /// Address of the post's page relative to the site root, e.g. `hello/`
+    path: String,
+    /// Absolute address of the post's page, when the site has a `url`
+    url: Option<String>,
+    title: &'a str,
+    date: DateTime<Utc>,
+    updated: Option<DateTime<Utc>>,
+    lang: &'a str,
+    tags: &'a [String],
+    series: Option<&'a str>,
+    series_order: Option<i64>,
+    excerpt: Option<&'a str>,
+    tldr: Option<&'a str>,
+    words: usize,
+    reading_minutes: usize,
+    html: String,
+}
+
+#[derive(Serialize)]
+struct TagData<'a> {
+    name: &'a str,
+    /// Slugs of the tagged posts, newest first
+    posts: Vec<&'a str>,
+}
+
+/// Path of a post's JSON relative to the output directory: `<slug>/index.json`, or
+/// `<slug>.json` without trailing slashes.
+pub fn post_path(policy: TrailingSlash, slug: &str) -> PathBuf {
+    policy.post_path(slug).with_extension("json")
+}
+
+/// Link to a post's JSON from the post's own page.
+pub fn href(policy: TrailingSlash, slug: &str) -> String {
+    post_path(policy, slug).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// JSON of `post`: its metadata and the HTML of its body as on its page, with links made
+/// absolute when the site has a `url`.
+pub fn render_post(config: &Config, post: &Post) -> String {
+    format!("{}\n", serde_json::to_string_pretty(&data(config, post)).unwrap_or_default())
+}
+
+/// The files of a headless build, relative to the output directory: `posts.json` with every
+/// post in `posts` (newest first), `tags.json` and `graph.json`.
+pub fn content_bundle(config: &Config, posts: &[Post]) -> Vec<(PathBuf, String)> {
+    let data: Vec<PostData> = posts.iter().map(|post| data(config, post)).collect();
+    let mut tags: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for post in posts {
+        for tag in post.tags() {
+            tags.entry(tag.as_str()).or_default().push(&post.slug);
+        }
+    }
+    let tags: Vec<TagData> = tags.into_iter().map(|(name, posts)| TagData { name, posts }).collect();
+    let graph = LinkGraph::build(posts, config.graph.tags, None, None, config.trailing_slash);
+    vec![
+        (PathBuf::from(POSTS_FILE), format!("{}\n", serde_json::to_string_pretty(&data).unwrap_or_default())),
+        (PathBuf::from(TAGS_FILE), format!("{}\n", serde_json::to_string_pretty(&tags).unwrap_or_default())),
+        (PathBuf::from(GRAPH_FILE), graph.render(GraphFormat::Json)),
+    ]
+}
+
+fn data<'a>(config: &Config, post: &'a Post) -> PostData<'a> {
+    let url = config.site_url().map(|site| config.trailing_slash.post_href(&site, &post.slug));
+    let mut html = markdown::restore_raw_blocks(&post.html_content, &post.raw_blocks);
+    if config.mermaid.mode == MermaidMode::Svg {
+        html = mermaid::inline(&html, &config.mermaid);
+    }
+    if let Some(url) = &url {
+        html = export::absolute_links(&html, url);
+    }
+    PostData {
+        slug: &post.slug,
+        path: config.trailing_slash.post_href("", &post.slug),
+        url,
+        title: &post.title,
+        date: post.date,
+        updated: post.frontmatter.parsed_updated(),
+        lang: &post.lang,
+        tags: post.tags(),
+        series: post.series(),
+        series_order: post.frontmatter.series_order,
+        excerpt: post.excerpt.as_deref().map(str::trim).filter(|e| !e.is_empty()),
+        tldr: post.frontmatter.tldr.as_deref().map(str::trim).filter(|t| !t.is_empty()),
+        words: post.words,
+        reading_minutes: post.reading_minutes(),
+        html,
+    }
+}
@@ -0,0 +1,88 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory of post archetypes (`review.md`, `til.md`...) that `scribe new --kind` starts from.
+pub const DIR: &str = "archetypes";
+
+/// Archetype used without `--kind`, if the project has one.
+pub const DEFAULT_KIND: &str = "default";
+
+/// What a new post starts as without an archetype; `scribe new` fills in the frontmatter.
+pub const BUILT_IN: &str = "---\n---\n\nWrite your post content here...\n\n";
+
+/// Values substituted for `{{title}}`, `{{date}}`, `{{author}}` and `{{slug}}`.
+pub struct Variables<'a> {
+    pub title: &'a str,
+    pub date: &'a str,
+    pub author: &'a str,
+    pub slug: &'a str,
+}
+
+/// Names of the archetypes in `dir`, sorted.
+pub fn kinds(dir: &Path) -> Vec<String> {
+    let mut kinds: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "md"))
+                .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    kinds.sort();
+    kinds
+}
+
+/// Path of the archetype `kind` in `dir`, if there is one.
+pub fn find(dir: &Path, kind: &str) -> Option<PathBuf> {
+    let path = dir.join(format!("{}.md", kind));
+    path.is_file().then_some(path)
+}
+
+/// Read the archetype `kind` from `dir`, naming the ones there when it is missing.
+pub fn load(dir: &Path, kind: &str) -> Result<String> {
+    let Some(path) = find(dir, kind) else {
+        let kinds = kinds(dir);
+        if kinds.is_empty() {
+            anyhow::bail!("no archetype '{}': {} has no archetypes (e.g. {}/{}.md)", kind, dir.display(), dir.display(), kind);
+        }
+        anyhow::bail!("no archetype '{}' in {} (expected one of {})", kind, dir.display(), kinds.join(", "));
+    };
+    fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))
+}
+
+/// Substitute `variables` into `archetype`. Whitespace inside the braces is allowed
+/// (`{{ title }}`). In the frontmatter, values are escaped for double-quoted strings (which
+/// YAML, TOML and JSON write alike), so variables there belong in quotes: `author: "{{author}}"`.
+pub fn render(archetype: &str, variables: &Variables) -> String {
+    let lines: Vec<&str> = archetype.split_inclusive('\n').collect();
+    let closes = |i: usize, fences: &[&str]| fences.contains(&lines[i].trim_end());
+    let frontmatter_end = match lines.first().map(|l| l.trim_end()) {
+        Some("---") => (1..lines.len()).find(|&i| closes(i, &["---", "..."])),
+        Some("+++") => (1..lines.len()).find(|&i| closes(i, &["+++"])),
+        Some("{") => (1..lines.len()).find(|&i| closes(i, &["}"])),
+        _ => None,
+    }
+    .map_or(0, |i| i + 1);
+    let escape = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"");
+    let frontmatter = substitute(&lines[..frontmatter_end].concat(), variables, escape);
+    let body = substitute(&lines[frontmatter_end..].concat(), variables, str::to_string);
+    frontmatter + &body
+}
+
+fn substitute(text: &str, variables: &Variables, escape: impl Fn(&str) -> String) -> String {
+    let placeholder = Regex::new(r"\{\{\s*(title|date|author|slug)\s*\}\}").unwrap();
+    placeholder
+        .replace_all(text, |caps: &regex::Captures| {
+            let value = match &caps[1] {
+                "title" => variables.title,
+                "date" => variables.date,
+                "author" => variables.author,
+                _ => variables.slug,
+            };
+            escape(value)
+        })
+        .to_string()
+}
@@ -0,0 +1,122 @@
+use crate::check;
+use crate::config::TrailingSlash;
+use crate::images;
+use crate::markdown::{strip_code_spans, CodeFences};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+/// A post's reference to a local image, e.g. `![](/images/a.jpg)` or `<img src="a.png">`.
+#[derive(Debug, Clone)]
+pub struct AssetRef {
+    /// Post the reference is in
+    pub path: PathBuf,
+    pub line: usize,
+    /// Target as written
+    pub target: String,
+}
+
+impl fmt::Display for AssetRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: \"{}\" does not exist", self.path.display(), self.line, self.target)
+    }
+}
+
+/// Which images under the posts directory the posts use.
+#[derive(Debug, Default)]
+pub struct AssetReport {
+    /// Images (relative to the posts directory) with the slugs of the posts using them
+    pub used: BTreeMap<PathBuf, BTreeSet<String>>,
+    /// Images (relative to the posts directory) no post refers to
+    pub unused: Vec<PathBuf>,
+    /// References to images that are neither in the posts directory nor generated
+    pub missing: Vec<AssetRef>,
+}
+
+/// Find local image targets of Markdown links and images, link reference definitions and HTML
+/// `src`, `href` and `poster` attributes. Line numbers refer to the whole file, including
+/// frontmatter. Fenced and inline code is skipped.
+pub fn references(path: &Path, content: &str) -> Vec<AssetRef> {
+    let inline = Regex::new(r"\]\(\s*<?([^)\s>]+)").unwrap();
+    let definition = Regex::new(r"^\s{0,3}\[[^\]]+\]:\s*<?([^\s>]+)").unwrap();
+    let attr = Regex::new(r#"(?i)\b(?:src|href|poster)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+
+    let mut refs = Vec::new();
+    let mut fences = CodeFences::default();
+    for (index, line) in content.lines().enumerate() {
+        if fences.is_code(line) {
+            continue;
+        }
+        let text = strip_code_spans(line);
+        let targets = inline
+            .captures_iter(&text)
+            .chain(definition.captures_iter(&text))
+            .chain(attr.captures_iter(&text))
+            .filter_map(|c| c.get(1).or_else(|| c.get(2)).map(|m| m.as_str().to_string()));
+        for target in targets {
+            if !check::is_ignored(&target) && images::is_image(Path::new(strip_suffix(&target))) {
+                refs.push(AssetRef { path: path.to_path_buf(), line: index + 1, target });
+            }
+        }
+    }
+    refs
+}
+
+/// Path under the site root that `target` points at from a page in `dir`, resolving `.` and
+/// `..` like a browser. Root-relative targets may include `base_path` (e.g. `/blog/`).
+pub fn resolve(target: &str, dir: &Path, base_path: &str) -> PathBuf {
+    let path = strip_suffix(target);
+    let joined = match path.strip_prefix(base_path).or_else(|| path.strip_prefix('/')) {
+        Some(absolute) => PathBuf::from(absolute),
+        None => dir.join(path),
+    };
+    let mut normalized = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Normal(part) => normalized.push(part),
+            _ => {}
+        }
+    }
+    normalized
+}
+
+/// Match the `refs` of each post (by slug) against the images under `posts_dir`. A relative
+/// reference uses the image it finds from the post's page or, so that a mistake there doesn't
+/// get the image pruned, from the post's Markdown file. A reference counts as missing unless it
+/// finds an image or a generated file in `output_dir`, such as an illuminated initial.
+pub fn report(
+    posts_dir: &Path,
+    output_dir: &Path,
+    base_path: &str,
+    policy: TrailingSlash,
+    refs: &[(String, AssetRef)],
+) -> AssetReport {
+    let sources: BTreeSet<PathBuf> = images::sources(posts_dir, output_dir)
+        .into_iter()
+        .filter_map(|p| p.strip_prefix(posts_dir).ok().map(Path::to_path_buf))
+        .collect();
+
+    let mut report = AssetReport::default();
+    for (slug, reference) in refs {
+        let page_dir = policy.post_path(slug).parent().map(Path::to_path_buf).unwrap_or_default();
+        let source_dir = reference.path.parent().and_then(|d| d.strip_prefix(posts_dir).ok()).unwrap_or(Path::new(""));
+        let from_page = resolve(&reference.target, &page_dir, base_path);
+        let from_source = resolve(&reference.target, source_dir, base_path);
+        if let Some(image) = [from_page.clone(), from_source].into_iter().find(|p| sources.contains(p)) {
+            report.used.entry(image).or_default().insert(slug.clone());
+        } else if !output_dir.join(&from_page).is_file() {
+            report.missing.push(reference.clone());
+        }
+    }
+    report.unused = sources.into_iter().filter(|p| !report.used.contains_key(p)).collect();
+    report
+}
+
+/// `target` without its query string or fragment.
+fn strip_suffix(target: &str) -> &str {
+    target.split(['?', '#']).next().unwrap_or_default()
+}
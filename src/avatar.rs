@@ -0,0 +1,80 @@
+use crate::config::{Avatar, AvatarProvider, Config};
+use anyhow::{Context, Result};
+use colored::*;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// File name of the cached avatar inside the output directory.
+pub fn file_name(avatar: &Avatar) -> String {
+    let ext = avatar
+        .file
+        .as_deref()
+        .and_then(|f| Path::new(f).extension())
+        .and_then(|e| e.to_str())
+        .unwrap_or("jpg");
+    format!("avatar.{}", ext.to_lowercase())
+}
+
+/// Public URL of the avatar: absolute when the site URL is known, otherwise relative to `base`.
+pub fn public_url(config: &Config, base: &str) -> Option<String> {
+    let avatar = config.avatar.as_ref()?;
+    let name = file_name(avatar);
+    Some(match config.site_url() {
+        Some(url) => format!("{}{}", url, name),
+        None => format!("{}{}", base, name),
+    })
+}
+
+/// Gravatar/libravatar lookup URL for the configured email (SHA-256 of the trimmed, lowercased address).
+pub fn remote_url(avatar: &Avatar) -> Option<String> {
+    let email = avatar.email.as_deref()?.trim().to_lowercase();
+    if email.is_empty() {
+        return None;
+    }
+    let hash: String = Sha256::digest(email.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let host = match avatar.provider {
+        AvatarProvider::Gravatar => "https://gravatar.com/avatar",
+        AvatarProvider::Libravatar => "https://seccdn.libravatar.org/avatar",
+    };
+    Some(format!("{}/{}?s={}&d=identicon", host, hash, avatar.size))
+}
+
+/// Copy or download the avatar into `output_dir`, reusing a previously cached download.
+/// Nothing is downloaded when `offline`.
+pub async fn cache(avatar: &Avatar, output_dir: &Path, offline: bool) -> Result<()> {
+    let target = output_dir.join(file_name(avatar));
+
+    if let Some(file) = &avatar.file {
+        let bytes = fs::read(file).context(format!("Failed to copy avatar {}", file))?;
+        // Left alone when unchanged, so rebuilds don't touch its mtime
+        if fs::read(&target).ok().as_deref() != Some(bytes.as_slice()) {
+            fs::write(&target, bytes).context(format!("Failed to copy avatar {}", file))?;
+        }
+        return Ok(());
+    }
+
+    if target.exists() || offline {
+        return Ok(());
+    }
+
+    let Some(url) = remote_url(avatar) else {
+        warn!("{}", "Warning: avatar configured without `file` or `email`, skipping".yellow());
+        return Ok(());
+    };
+
+    let bytes = reqwest::get(&url)
+        .await
+        .and_then(|r| r.error_for_status())
+        .context("Failed to fetch avatar")?
+        .bytes()
+        .await?;
+    fs::write(&target, bytes)
+        .context("Failed to write avatar")?;
+    info!("{} {}", "Cached avatar".green(), target.display());
+    Ok(())
+}
@@ -0,0 +1,610 @@
+use crate::assets;
+use crate::config::Config;
+use crate::export::{self, escape, unescape};
+use crate::fonts;
+use crate::generator::{link_slug, series_members, Post};
+use crate::images::crc32;
+use crate::math;
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::warn;
+
+/// Stylesheet of books: the site's typography, set dark on light for paper and e-ink.
+const BOOK_CSS: &str = r#"body {
+  font-family: 'Crimson Text', Georgia, serif;
+  font-size: 1.1em;
+  line-height: 1.5;
+  color: #1a1a1a;
+  background: #ffffff;
+  margin: 0 5%;
+}
+
+h1, h2, h3, h4 {
+  font-family: 'Crimson Text', Georgia, serif;
+  font-weight: 600;
+  line-height: 1.2;
+  page-break-after: avoid;
+  break-after: avoid;
+}
+
+h1 { font-size: 2em; margin: 1.5em 0 0.25em; }
+h2 { font-size: 1.5em; margin: 1.5em 0 0.5em; }
+h3 { font-size: 1.25em; margin: 1.25em 0 0.5em; }
+
+p { margin: 0 0 1em; }
+
+a { color: inherit; }
+
+blockquote {
+  margin: 1em 0;
+  padding-left: 1em;
+  border-left: 3px solid #cccccc;
+  color: #444444;
+}
+
+pre {
+  font-family: 'SF Mono', Monaco, Consolas, 'Courier New', monospace;
+  font-size: 0.8em;
+  line-height: 1.4;
+  background: #f4f4f4;
+  padding: 0.75em;
+  white-space: pre-wrap;
+  page-break-inside: avoid;
+  break-inside: avoid;
+}
+
+code {
+  font-family: 'SF Mono', Monaco, Consolas, 'Courier New', monospace;
+  font-size: 0.9em;
+}
+
+img, svg {
+  max-width: 100%;
+  height: auto;
+}
+
+table {
+  border-collapse: collapse;
+  margin: 1em 0;
+  font-size: 0.9em;
+}
+
+th, td {
+  border: 1px solid #cccccc;
+  padding: 0.3em 0.6em;
+  text-align: left;
+}
+
+th { font-family: 'Inter', sans-serif; }
+
+figure { margin: 1em 0; }
+figcaption { font-size: 0.85em; color: #666666; }
+
+.cover {
+  text-align: center;
+  padding-top: 20%;
+}
+
+.cover img {
+  max-height: 60vh;
+  margin-bottom: 2em;
+}
+
+.book-title {
+  font-size: 2.5em;
+  letter-spacing: 0.05em;
+  margin: 0 0 0.5em;
+}
+
+.book-author, .book-dates, .chapter-date {
+  font-family: 'Inter', sans-serif;
+  font-size: 0.8em;
+  color: #666666;
+  letter-spacing: 0.05em;
+}
+
+.chapter-date { margin-bottom: 2em; }
+
+.toc ol {
+  list-style: none;
+  padding: 0;
+}
+
+.toc li { margin: 0.4em 0; }
+.toc a { text-decoration: none; }
+
+.missing-image { font-style: italic; color: #666666; }
+
+@media print {
+  .cover, .toc, .chapter { page-break-before: always; break-before: page; }
+}
+"#;
+
+/// Which posts go into a book. Filters combine; with none, every published post goes in.
+#[derive(Debug, Default, Clone)]
+pub struct Selection {
+    /// A single post, by slug
+    pub post: Option<String>,
+    pub tag: Option<String>,
+    pub series: Option<String>,
+}
+
+/// Posts compiled into one volume, in reading order.
+pub struct Book<'a> {
+    pub title: String,
+    pub author: String,
+    pub lang: String,
+    pub posts: Vec<&'a Post>,
+    /// Image shown on the cover page and by readers' libraries
+    pub cover: Option<PathBuf>,
+}
+
+impl<'a> Book<'a> {
+    /// The `selection` of `posts`: a series in its own order, anything else oldest first. The
+    /// title defaults to the series or tag name, or else the post's or site's title.
+    pub fn new(config: &Config, posts: &'a [Post], selection: &Selection, title: Option<String>) -> Self {
+        let mut chosen: Vec<&Post> = match &selection.series {
+            Some(series) => series_members(posts, series),
+            None => {
+                let mut all: Vec<&Post> = posts.iter().collect();
+                all.sort_by_key(|p| p.date);
+                all
+            }
+        };
+        if let Some(tag) = &selection.tag {
+            chosen.retain(|p| p.tags().iter().any(|t| t.eq_ignore_ascii_case(tag)));
+        }
+        if let Some(slug) = &selection.post {
+            chosen.retain(|p| &p.slug == slug || &p.original_slug == slug);
+        }
+
+        let title = title
+            .or_else(|| selection.series.clone())
+            .or_else(|| selection.tag.as_ref().map(|tag| format!("{}: {}", config.title, tag)))
+            .or_else(|| selection.post.as_ref().and(chosen.first()).map(|p| p.title.clone()))
+            .unwrap_or_else(|| config.title.clone());
+        Book { title, author: config.author.clone(), lang: config.languages.default.clone(), posts: chosen, cover: None }
+    }
+}
+
+/// The book as an EPUB 3 file: a cover, a table of contents and a chapter per post, with the
+/// local images the posts use (and the bundled fonts, when the site has them) packed inside.
+pub fn epub(config: &Config, book: &Book) -> Result<Vec<u8>> {
+    let assembly = assemble(config, book, Target::Epub)?;
+    let page = |title: &str, body: &str| {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" lang=\"{lang}\" xml:lang=\"{lang}\">\n<head>\n<meta charset=\"UTF-8\"/>\n<title>{}</title>\n<link rel=\"stylesheet\" type=\"text/css\" href=\"book.css\"/>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+            escape(title),
+            body,
+            lang = book.lang
+        )
+    };
+
+    let mut manifest = vec![
+        item("nav", "nav.xhtml", "application/xhtml+xml", Some("nav")),
+        item("cover", "cover.xhtml", "application/xhtml+xml", None),
+        item("css", "book.css", "text/css", None),
+    ];
+    let mut spine = vec!["<itemref idref=\"cover\"/>".to_string(), "<itemref idref=\"nav\"/>".to_string()];
+    let mut files: Vec<(String, Vec<u8>)> = vec![
+        ("mimetype".to_string(), b"application/epub+zip".to_vec()),
+        ("META-INF/container.xml".to_string(), CONTAINER_XML.as_bytes().to_vec()),
+        ("OEBPS/cover.xhtml".to_string(), page(&book.title, &assembly.cover).into_bytes()),
+        ("OEBPS/nav.xhtml".to_string(), page(&book.title, &assembly.toc).into_bytes()),
+        ("OEBPS/book.css".to_string(), assembly.css.into_bytes()),
+    ];
+    for (i, chapter) in assembly.chapters.iter().enumerate() {
+        let id = format!("chapter-{}", i + 1);
+        let properties: Vec<&str> = [(chapter.body.contains("<math"), "mathml"), (chapter.body.contains("<svg"), "svg")]
+            .into_iter()
+            .filter_map(|(has, property)| has.then_some(property))
+            .collect();
+        let properties = (!properties.is_empty()).then(|| properties.join(" "));
+        manifest.push(item(&id, &chapter_file(i), "application/xhtml+xml", properties.as_deref()));
+        spine.push(format!("<itemref idref=\"{}\"/>", id));
+        files.push((format!("OEBPS/{}", chapter_file(i)), page(&chapter.title, &chapter.body).into_bytes()));
+    }
+    for (i, resource) in assembly.resources.into_iter().enumerate() {
+        manifest.push(item(&format!("resource-{}", i + 1), &resource.path, resource.media_type, resource.properties));
+        files.push((format!("OEBPS/{}", resource.path), resource.bytes));
+    }
+
+    // Derived from the contents, so rebuilding an unchanged book gives the same file
+    let digest = Sha256::digest(format!("{}\n{}", book.title, book.posts.iter().map(|p| p.slug.as_str()).collect::<Vec<_>>().join("\n")));
+    let hex: String = digest.iter().take(16).map(|b| format!("{:02x}", b)).collect();
+    let uuid = format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..]);
+    let modified = book.posts.iter().map(|p| p.date).max().unwrap_or_default();
+    let opf = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" xml:lang="{lang}">
+<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+<dc:identifier id="book-id">urn:uuid:{uuid}</dc:identifier>
+<dc:title>{title}</dc:title>
+<dc:creator>{author}</dc:creator>
+<dc:language>{lang}</dc:language>
+<meta property="dcterms:modified">{modified}</meta>
+</metadata>
+<manifest>
+{manifest}
+</manifest>
+<spine>
+{spine}
+</spine>
+</package>
+"#,
+        lang = book.lang,
+        uuid = uuid,
+        title = escape(&book.title),
+        author = escape(&book.author),
+        modified = modified.format("%Y-%m-%dT%H:%M:%SZ"),
+        manifest = manifest.join("\n"),
+        spine = spine.join("\n"),
+    );
+    files.insert(2, ("OEBPS/content.opf".to_string(), opf.into_bytes()));
+    Ok(zip(&files))
+}
+
+/// Print the book to a PDF at `target` by rendering it as one HTML page with `renderer`, a
+/// Chromium-compatible browser command run headless.
+pub fn pdf(config: &Config, book: &Book, renderer: &str, target: &Path) -> Result<()> {
+    let assembly = assemble(config, book, Target::Pdf)?;
+    let scratch = std::env::temp_dir().join(format!("scribe-book-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&scratch);
+    fs::create_dir_all(&scratch).context("Failed to create a scratch directory")?;
+    for resource in &assembly.resources {
+        let path = scratch.join(&resource.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, &resource.bytes).context(format!("Failed to write {}", path.display()))?;
+    }
+    fs::write(scratch.join("book.css"), &assembly.css)?;
+    let chapters: String = assembly.chapters.iter().map(|c| c.body.as_str()).collect::<Vec<_>>().join("\n");
+    let html = format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"UTF-8\">\n<title>{}</title>\n<link rel=\"stylesheet\" href=\"book.css\">\n</head>\n<body>\n{}\n{}\n{}\n</body>\n</html>\n",
+        book.lang,
+        escape(&book.title),
+        assembly.cover,
+        assembly.toc,
+        chapters
+    );
+    let page = scratch.join("book.html");
+    fs::write(&page, html)?;
+
+    let target = std::path::absolute(target).context("Failed to resolve the PDF path")?;
+    let mut words = renderer.split_whitespace();
+    let program = words.next().context("renderer is empty")?;
+    let status = Command::new(program)
+        .args(words)
+        .args(["--headless", "--disable-gpu", "--no-pdf-header-footer"])
+        .arg(format!("--print-to-pdf={}", target.display()))
+        .arg(format!("file://{}", page.display()))
+        .status()
+        .context(format!("Failed to run {} (is Chromium installed? pass --renderer to use another)", program))?;
+    let _ = fs::remove_dir_all(&scratch);
+    if !status.success() {
+        bail!("{} exited with {}", program, status);
+    }
+    if !target.is_file() {
+        bail!("{} wrote no PDF", program);
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Epub,
+    Pdf,
+}
+
+/// A file packed alongside the pages, at `path` relative to them.
+struct Resource {
+    path: String,
+    media_type: &'static str,
+    properties: Option<&'static str>,
+    bytes: Vec<u8>,
+}
+
+struct Chapter {
+    title: String,
+    body: String,
+}
+
+/// Page contents shared by EPUB and PDF books.
+struct Assembly {
+    cover: String,
+    toc: String,
+    chapters: Vec<Chapter>,
+    resources: Vec<Resource>,
+    css: String,
+}
+
+fn assemble(config: &Config, book: &Book, target: Target) -> Result<Assembly> {
+    let mut resources = Vec::new();
+    let output_dir = Path::new(&config.output_dir);
+    let fonts: Vec<&fonts::Face> = fonts::FACES.iter().filter(|f| output_dir.join(f.path()).is_file()).collect();
+    let css = if fonts.len() == fonts::FACES.len() {
+        for face in fonts {
+            resources.push(Resource { path: face.path(), media_type: "font/woff2", properties: None, bytes: fs::read(output_dir.join(face.path()))? });
+        }
+        format!("{}{}", fonts::font_face_css(), BOOK_CSS)
+    } else {
+        BOOK_CSS.to_string()
+    };
+
+    let cover_image = match &book.cover {
+        Some(path) => {
+            let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+            let Some(media_type) = image_type(&extension) else {
+                bail!("{} is not a JPEG, PNG, GIF, WebP or SVG image", path.display());
+            };
+            let bytes = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+            let cover_path = format!("images/cover.{}", extension);
+            resources.push(Resource { path: cover_path.clone(), media_type, properties: Some("cover-image"), bytes });
+            format!("<img src=\"{}\" alt=\"\"/>\n", cover_path)
+        }
+        None => String::new(),
+    };
+    let years: Vec<String> = [book.posts.first(), book.posts.last()].into_iter().flatten().map(|p| p.date.format("%Y").to_string()).collect();
+    let dates = match years.as_slice() {
+        [first, last] if first != last => format!("{}–{}", first, last),
+        [first, ..] => first.clone(),
+        [] => String::new(),
+    };
+    let cover_type = if target == Target::Epub { " epub:type=\"cover\"" } else { "" };
+    let cover = format!(
+        "<section class=\"cover\"{}>\n{}<h1 class=\"book-title\">{}</h1>\n<p class=\"book-author\">{}</p>\n<p class=\"book-dates\">{}</p>\n</section>",
+        cover_type,
+        cover_image,
+        escape(&book.title),
+        escape(&book.author),
+        dates
+    );
+
+    let entries: String = book
+        .posts
+        .iter()
+        .enumerate()
+        .map(|(i, post)| format!("<li><a href=\"{}\">{}</a></li>\n", chapter_href(target, i, None), escape(&post.title)))
+        .collect();
+    let toc = match target {
+        Target::Epub => format!("<nav class=\"toc\" epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n<ol>\n{}</ol>\n</nav>", entries),
+        Target::Pdf => format!("<nav class=\"toc\">\n<h1>Contents</h1>\n<ol>\n{}</ol>\n</nav>", entries),
+    };
+
+    let mut chapters = Vec::new();
+    let mut images: BTreeMap<PathBuf, String> = BTreeMap::new();
+    for (i, post) in book.posts.iter().enumerate() {
+        let html = chapter_html(config, book, post, target, &mut images, &mut resources);
+        let body = format!(
+            "<section class=\"chapter\" id=\"chapter-{}\">\n<h1 class=\"chapter-title\">{}</h1>\n<p class=\"chapter-date\">{}</p>\n{}\n</section>",
+            i + 1,
+            escape(&post.title),
+            post.date.format("%B %-d, %Y"),
+            html
+        );
+        chapters.push(Chapter { title: post.title.clone(), body });
+    }
+    Ok(Assembly { cover, toc, chapters, resources, css })
+}
+
+/// A post's HTML made self-contained: math as MathML, links to posts in the book pointing at
+/// their chapters, other relative links at the live site, and local images packed in.
+fn chapter_html(
+    config: &Config,
+    book: &Book,
+    post: &Post,
+    target: Target,
+    images: &mut BTreeMap<PathBuf, String>,
+    resources: &mut Vec<Resource>,
+) -> String {
+    let post_url = config.site_url().map(|site| config.trailing_slash.post_href(&site, &post.slug));
+    let mut html = export::without_raw_blocks(&post.html_content, post_url.as_deref());
+
+    let math_span = Regex::new(r#"(?s)<(span|div) class="math math-(?:inline|display)">(.*?)</(?:span|div)>"#).unwrap();
+    html = math_span.replace_all(&html, |caps: &regex::Captures| math::to_mathml(&unescape(&caps[2]), &caps[1] == "div")).to_string();
+
+    let chapters: HashMap<&str, usize> = book
+        .posts
+        .iter()
+        .enumerate()
+        .flat_map(|(i, p)| [(p.slug.as_str(), i), (p.original_slug.as_str(), i)])
+        .collect();
+    let base = post_url.as_deref().and_then(|url| reqwest::Url::parse(url).ok());
+    let href = Regex::new(r#"\bhref="([^"]*)""#).unwrap();
+    html = href
+        .replace_all(&html, |caps: &regex::Captures| {
+            let link = &caps[1];
+            if link.starts_with('#') || reqwest::Url::parse(link).is_ok() {
+                return caps[0].to_string();
+            }
+            if let Some(&chapter) = link_slug(link).as_deref().and_then(|slug| chapters.get(slug)) {
+                let fragment = link.split_once('#').map(|(_, fragment)| fragment);
+                return format!("href=\"{}\"", chapter_href(target, chapter, fragment));
+            }
+            match base.as_ref().and_then(|base| base.join(link).ok()) {
+                Some(url) => format!("href=\"{}\"", url),
+                None => caps[0].to_string(),
+            }
+        })
+        .to_string();
+
+    let page_dir = config.trailing_slash.post_path(&post.slug).parent().map(Path::to_path_buf).unwrap_or_default();
+    let base_path = config.base_path();
+    let img = Regex::new(r#"<img\s[^>]*>"#).unwrap();
+    let src = Regex::new(r#"\bsrc="([^"]*)""#).unwrap();
+    let alt = Regex::new(r#"\balt="([^"]*)""#).unwrap();
+    html = img
+        .replace_all(&html, |caps: &regex::Captures| {
+            let tag = &caps[0];
+            let Some(source) = src.captures(tag).map(|c| c[1].to_string()) else {
+                return tag.to_string();
+            };
+            if source.starts_with("data:") || (target == Target::Pdf && reqwest::Url::parse(&source).is_ok()) {
+                return tag.to_string();
+            }
+            let packed = reqwest::Url::parse(&source).is_err().then(|| pack_image(config, &source, &page_dir, &base_path, images, resources)).flatten();
+            match packed {
+                Some(path) => src.replace(tag, format!("src=\"{}\"", path).as_str()).to_string(),
+                None => {
+                    warn!("Warning: image {} in '{}' left out of the book", source, post.slug);
+                    let text = alt.captures(tag).map(|c| c[1].to_string()).filter(|a| !a.is_empty()).unwrap_or_else(|| "image".to_string());
+                    format!("<span class=\"missing-image\">[{}]</span>", text)
+                }
+            }
+        })
+        .to_string();
+
+    if target == Target::Epub {
+        html = xhtml(&html);
+    }
+    html
+}
+
+/// Copy the local image `source` into the book, once, returning its path there. Images are
+/// looked up among the posts' images and then in the output directory, like the site serves them.
+fn pack_image(
+    config: &Config,
+    source: &str,
+    page_dir: &Path,
+    base_path: &str,
+    images: &mut BTreeMap<PathBuf, String>,
+    resources: &mut Vec<Resource>,
+) -> Option<String> {
+    let relative = assets::resolve(source, page_dir, base_path);
+    let file = [Path::new(&config.posts_dir).join(&relative), Path::new(&config.output_dir).join(&relative)]
+        .into_iter()
+        .find(|p| p.is_file())?;
+    if let Some(path) = images.get(&file) {
+        return Some(path.clone());
+    }
+    let extension = file.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    let media_type = image_type(&extension)?;
+    let bytes = fs::read(&file).ok()?;
+    let path = format!("images/{}.{}", images.len() + 1, extension);
+    resources.push(Resource { path: path.clone(), media_type, properties: None, bytes });
+    images.insert(file, path.clone());
+    Some(path)
+}
+
+fn image_type(extension: &str) -> Option<&'static str> {
+    match extension {
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "png" => Some("image/png"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "svg" => Some("image/svg+xml"),
+        _ => None,
+    }
+}
+
+fn chapter_file(index: usize) -> String {
+    format!("chapter-{}.xhtml", index + 1)
+}
+
+/// Link to a chapter, or an anchor in it: a file of its own in an EPUB, a section of the page in a PDF.
+fn chapter_href(target: Target, index: usize, fragment: Option<&str>) -> String {
+    match (target, fragment) {
+        (Target::Epub, Some(fragment)) => format!("{}#{}", chapter_file(index), fragment),
+        (Target::Epub, None) => chapter_file(index),
+        (Target::Pdf, Some(fragment)) => format!("#{}", fragment),
+        (Target::Pdf, None) => format!("#chapter-{}", index + 1),
+    }
+}
+
+fn item(id: &str, href: &str, media_type: &str, properties: Option<&str>) -> String {
+    match properties {
+        Some(properties) => format!("<item id=\"{}\" href=\"{}\" media-type=\"{}\" properties=\"{}\"/>", id, href, media_type, properties),
+        None => format!("<item id=\"{}\" href=\"{}\" media-type=\"{}\"/>", id, href, media_type),
+    }
+}
+
+/// Rendered HTML as XHTML, which EPUB readers parse strictly: every attribute given a quoted
+/// value (`checked` becomes `checked="checked"`), footnote ids turned into XML names by a `fn-`
+/// prefix, void elements closed and `&nbsp;`, the one named entity Markdown produces beyond
+/// XML's own, spelled numerically.
+fn xhtml(html: &str) -> String {
+    let tag = Regex::new(r#"<([a-zA-Z][\w-]*)((?:\s+[^\s"'=<>/]+(?:\s*=\s*(?:"[^"]*"|'[^']*'|[^\s"'=<>`]+))?)*)(\s*/?)>"#).unwrap();
+    let attribute = Regex::new(r#"([^\s"'=<>/]+)(?:\s*=\s*("[^"]*"|'[^']*'|[^\s"'=<>`]+))?"#).unwrap();
+    let html = tag.replace_all(html, |caps: &regex::Captures| {
+        let attributes: String = attribute
+            .captures_iter(&caps[2])
+            .map(|attr| match attr.get(2).map(|value| value.as_str()) {
+                Some(value) if value.starts_with(['"', '\'']) => format!(" {}={}", &attr[1], value),
+                Some(value) => format!(" {}=\"{}\"", &attr[1], value),
+                None => format!(" {}=\"{}\"", &attr[1], &attr[1]),
+            })
+            .collect();
+        format!("<{}{}{}>", &caps[1], attributes, &caps[3])
+    });
+    let footnote_id = Regex::new(r#"(<div class="footnote-definition" id=")([^"]*)""#).unwrap();
+    let footnote_href = Regex::new(r##"(<sup class="footnote-reference"><a href="#)([^"]*)""##).unwrap();
+    let html = footnote_id.replace_all(&html, "${1}fn-$2\"");
+    let html = footnote_href.replace_all(&html, "${1}fn-$2\"");
+    let void = Regex::new(r"<(br|hr|img|input|source|wbr|col)\b([^>]*?)\s*/?>").unwrap();
+    void.replace_all(&html, "<$1$2 />").replace("&nbsp;", "&#160;")
+}
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+<rootfiles>
+<rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+</rootfiles>
+</container>
+"#;
+
+/// A ZIP archive of `files`, stored without compression (as the EPUB `mimetype` entry must
+/// be) and dated 1980-01-01 so the same files always give the same archive.
+fn zip(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    const DOS_DATE: u16 = (1 << 5) | 1;
+    const UTF8_NAMES: u16 = 1 << 11;
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (name, bytes) in files {
+        let offset = archive.len() as u32;
+        let crc = crc32(bytes);
+        let size = bytes.len() as u32;
+
+        archive.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        for field in [20u16, UTF8_NAMES, 0, 0, DOS_DATE] {
+            archive.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            archive.extend_from_slice(&field.to_le_bytes());
+        }
+        archive.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&0u16.to_le_bytes());
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(bytes);
+
+        directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        for field in [20u16, 20, UTF8_NAMES, 0, 0, DOS_DATE] {
+            directory.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            directory.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [name.len() as u16, 0, 0, 0, 0] {
+            directory.extend_from_slice(&field.to_le_bytes());
+        }
+        directory.extend_from_slice(&0u32.to_le_bytes());
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&directory);
+    archive.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    for field in [0u16, 0, files.len() as u16, files.len() as u16] {
+        archive.extend_from_slice(&field.to_le_bytes());
+    }
+    archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&directory_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+    archive
+}
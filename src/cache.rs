@@ -0,0 +1,139 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Location of the URL metadata cache, relative to the project root.
+pub const URL_META_PATH: &str = ".scribe/url-meta.json";
+
+/// Location of the hit rates recorded by the last build.
+pub const STATS_PATH: &str = ".scribe/cache-stats.json";
+
+/// Cached URL metadata older than this is refetched.
+pub fn url_meta_ttl() -> Duration {
+    Duration::days(30)
+}
+
+/// Title/description fetched for an annotation link.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedMeta {
+    pub meta: serde_json::Value,
+    pub fetched: DateTime<Utc>,
+}
+
+impl CachedMeta {
+    pub fn is_stale(&self) -> bool {
+        Utc::now() - self.fetched > url_meta_ttl()
+    }
+}
+
+/// Metadata for annotation links, keyed by URL and stored in `.scribe/url-meta.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UrlMetaCache {
+    pub entries: BTreeMap<String, CachedMeta>,
+    /// Lookups served from / missed by the cache since loading
+    #[serde(skip)]
+    pub rate: HitRate,
+}
+
+impl UrlMetaCache {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        load_json(path.as_ref(), "URL metadata cache")
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        save_json(path.as_ref(), self, "URL metadata cache")
+    }
+
+    /// Fresh metadata for `url`, counting the lookup as a hit or miss.
+    pub fn get(&mut self, url: &str) -> Option<serde_json::Value> {
+        let meta = self.entries.get(url).filter(|e| !e.is_stale()).map(|e| e.meta.clone());
+        match meta {
+            Some(_) => self.rate.hits += 1,
+            None => self.rate.misses += 1,
+        }
+        meta
+    }
+
+    /// Whether `url` has metadata within its TTL, without counting a lookup.
+    pub fn is_fresh(&self, url: &str) -> bool {
+        self.entries.get(url).is_some_and(|e| !e.is_stale())
+    }
+
+    pub fn insert(&mut self, url: String, meta: serde_json::Value) {
+        self.entries.insert(url, CachedMeta { meta, fetched: Utc::now() });
+    }
+
+    pub fn stale_count(&self) -> usize {
+        self.entries.values().filter(|e| e.is_stale()).count()
+    }
+
+    /// Drop entries past their TTL, returning how many were removed.
+    pub fn prune_stale(&mut self) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, e| !e.is_stale());
+        before - self.entries.len()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct HitRate {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl std::fmt::Display for HitRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return write!(f, "no lookups");
+        }
+        write!(f, "{}/{} hits ({:.0}%)", self.hits, total, 100.0 * self.hits as f64 / total as f64)
+    }
+}
+
+/// Hit rates from the most recent build, stored in `.scribe/cache-stats.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url_meta: Option<HitRate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initials: Option<HitRate>,
+}
+
+impl CacheStats {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        load_json(path.as_ref(), "cache stats")
+    }
+
+    /// Load the stats at `path`, apply `update` and write them back.
+    pub fn record<P: AsRef<Path>>(path: P, update: impl FnOnce(&mut Self)) -> Result<()> {
+        let path = path.as_ref();
+        let mut stats = Self::load(path)?;
+        update(&mut stats);
+        save_json(path, &stats, "cache stats")
+    }
+}
+
+fn load_json<T: Default + for<'de> Deserialize<'de>>(path: &Path, what: &str) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let content = fs::read_to_string(path)
+        .context(format!("Failed to read {}", what))?;
+    serde_json::from_str(&content)
+        .context(format!("Failed to parse {}", what))
+}
+
+fn save_json<T: Serialize>(path: &Path, value: &T, what: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .context("Failed to create .scribe directory")?;
+    }
+    let content = serde_json::to_string_pretty(value)
+        .context(format!("Failed to serialize {}", what))?;
+    fs::write(path, content)
+        .context(format!("Failed to write {}", what))
+}
@@ -0,0 +1,380 @@
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+/// File chunk size, the same as `ipfs add`.
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// Links per file node in the balanced layout, the same as `ipfs add`.
+const MAX_LINKS: usize = 174;
+
+/// Size at which `ipfs add` shards a directory into a HAMT, estimated as the length of each
+/// entry's name plus its binary CID. Directories are never sharded here, so they stay below it.
+const HAMT_SHARDING_SIZE: usize = 256 * 1024;
+
+const DAG_PB: u64 = 0x70;
+const RAW: u64 = 0x55;
+const SHA2_256: u64 = 0x12;
+
+/// How files become blocks and blocks are addressed, following the `ipfs add` defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CidVersion {
+    /// `Qm...` CIDs with content in UnixFS leaves, as plain `ipfs add` and `scribe pin` make
+    V0,
+    /// `bafy...` CIDs with raw leaves, as `ipfs add --cid-version=1` and [`export`] make
+    V1,
+}
+
+impl CidVersion {
+    /// Layout that produced `cid`, judged by its encoding.
+    pub fn of(cid: &str) -> Option<Self> {
+        if cid.len() == 46 && cid.starts_with("Qm") {
+            Some(CidVersion::V0)
+        } else if cid.starts_with("bafy") || cid.starts_with("bafk") {
+            Some(CidVersion::V1)
+        } else {
+            None
+        }
+    }
+}
+
+/// What [`export`] wrote.
+#[derive(Debug)]
+pub struct CarSummary {
+    /// Root CID (CIDv1, base32), the address of the directory once imported
+    pub root: String,
+    pub files: usize,
+    pub blocks: usize,
+    /// Size of the CAR file
+    pub bytes: u64,
+}
+
+/// Pack `dir` into a CARv1 archive at `out`, laid out as `ipfs add -r --cid-version=1` would:
+/// 256 KiB chunks in raw leaves, balanced file DAGs and UnixFS directories.
+pub fn export(dir: &Path, out: &Path) -> Result<CarSummary> {
+    if !dir.is_dir() {
+        bail!("'{}' is not a directory", dir.display());
+    }
+    // The header names the root, which is only known at the end, so blocks go to a side file first
+    let body_path = out.with_extension("car.blocks");
+    let mut writer = BlockWriter {
+        version: CidVersion::V1,
+        out: Some(BufWriter::new(File::create(&body_path).context(format!("Failed to create {}", body_path.display()))?)),
+        seen: HashSet::new(),
+        files: 0,
+    };
+    let result = add_dir(&mut writer, dir).and_then(|root| {
+        writer.out.as_mut().map_or(Ok(()), |out| out.flush())?;
+        Ok(root)
+    });
+    let (files, blocks) = (writer.files, writer.seen.len());
+    drop(writer);
+    let result = result.and_then(|root| {
+        let mut car = BufWriter::new(File::create(out).context(format!("Failed to create {}", out.display()))?);
+        let header = header(&root.cid);
+        write_varint(&mut car, header.len() as u64)?;
+        car.write_all(&header)?;
+        io::copy(&mut File::open(&body_path)?, &mut car)?;
+        car.flush()?;
+        Ok(root)
+    });
+    let _ = fs::remove_file(&body_path);
+    let root = result?;
+
+    Ok(CarSummary { root: cid_string(CidVersion::V1, &root.cid), files, blocks, bytes: fs::metadata(out)?.len() })
+}
+
+/// CID that adding the file or directory at `path` to IPFS with `version` would give, computed
+/// locally without storing any blocks.
+pub fn hash(path: &Path, version: CidVersion) -> Result<String> {
+    let mut writer = BlockWriter { version, out: None, seen: HashSet::new(), files: 0 };
+    let root = if path.is_dir() { add_dir(&mut writer, path)? } else { add_file(&mut writer, path)? };
+    Ok(cid_string(version, &root.cid))
+}
+
+/// A block as seen from a link to it.
+struct Node {
+    cid: Vec<u8>,
+    /// Size of the block and everything it links to
+    tsize: u64,
+    /// Bytes of file content under it
+    filesize: u64,
+}
+
+struct BlockWriter {
+    version: CidVersion,
+    /// Where blocks are written, if anywhere
+    out: Option<BufWriter<File>>,
+    seen: HashSet<Vec<u8>>,
+    files: usize,
+}
+
+impl BlockWriter {
+    /// Write a block once, however often its content appears, and return its CID.
+    fn put(&mut self, codec: u64, data: &[u8]) -> Result<Vec<u8>> {
+        let cid = cid(self.version, codec, data);
+        if let Some(out) = self.out.as_mut().filter(|_| !self.seen.contains(&cid)) {
+            write_varint(out, (cid.len() + data.len()) as u64)?;
+            out.write_all(&cid)?;
+            out.write_all(data)?;
+        }
+        self.seen.insert(cid.clone());
+        Ok(cid)
+    }
+}
+
+fn add_dir(writer: &mut BlockWriter, dir: &Path) -> Result<Node> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .context(format!("Failed to read {}", dir.display()))?
+        .collect::<io::Result<_>>()?;
+    // UnixFS directories list their entries sorted by name
+    entries.sort_by_key(|e| e.file_name());
+
+    let mut links = Vec::new();
+    for entry in entries {
+        let path = entry.path();
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            bail!("'{}' is not a valid UTF-8 name", path.display());
+        };
+        let node = if path.is_dir() { add_dir(writer, &path)? } else { add_file(writer, &path)? };
+        links.push((name, node));
+    }
+
+    // A flat directory past this would get a different CID than `ipfs add` gives it
+    if links.iter().map(|(name, node)| name.len() + node.cid.len()).sum::<usize>() >= HAMT_SHARDING_SIZE {
+        bail!("'{}' has too many entries for one directory block; `ipfs add` would shard it", dir.display());
+    }
+    let block = pb_node(&links, &unixfs(1, None, None, &[]));
+    let tsize = block.len() as u64 + links.iter().map(|(_, n)| n.tsize).sum::<u64>();
+    let filesize = links.iter().map(|(_, n)| n.filesize).sum();
+    Ok(Node { cid: writer.put(DAG_PB, &block)?, tsize, filesize })
+}
+
+fn add_file(writer: &mut BlockWriter, path: &Path) -> Result<Node> {
+    writer.files += 1;
+    let mut file = File::open(path).context(format!("Failed to read {}", path.display()))?;
+    let mut leaves = Vec::new();
+    loop {
+        let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+        (&mut file).take(CHUNK_SIZE as u64).read_to_end(&mut chunk)?;
+        // An empty file is still one (empty) leaf
+        if chunk.is_empty() && !leaves.is_empty() {
+            break;
+        }
+        let size = chunk.len() as u64;
+        let leaf = match writer.version {
+            CidVersion::V0 => {
+                let block = pb_node(&[], &unixfs(2, Some(&chunk), Some(size), &[]));
+                Node { cid: writer.put(DAG_PB, &block)?, tsize: block.len() as u64, filesize: size }
+            }
+            CidVersion::V1 => Node { cid: writer.put(RAW, &chunk)?, tsize: size, filesize: size },
+        };
+        leaves.push(leaf);
+        if size < CHUNK_SIZE as u64 {
+            break;
+        }
+    }
+
+    // Group nodes level by level; a full subtree is always filled before the next begins
+    let mut level = leaves;
+    while level.len() > 1 {
+        let mut parents = Vec::new();
+        let mut children = level.into_iter().peekable();
+        while children.peek().is_some() {
+            let group: Vec<Node> = children.by_ref().take(MAX_LINKS).collect();
+            let filesize = group.iter().map(|n| n.filesize).sum();
+            let blocksizes: Vec<u64> = group.iter().map(|n| n.filesize).collect();
+            let links: Vec<(String, Node)> = group.into_iter().map(|n| (String::new(), n)).collect();
+            let block = pb_node(&links, &unixfs(2, None, Some(filesize), &blocksizes));
+            let tsize = block.len() as u64 + links.iter().map(|(_, n)| n.tsize).sum::<u64>();
+            parents.push(Node { cid: writer.put(DAG_PB, &block)?, tsize, filesize });
+        }
+        level = parents;
+    }
+    Ok(level.remove(0))
+}
+
+/// UnixFS `Data` message: `kind` 1 is a directory, 2 a file.
+fn unixfs(kind: u64, data: Option<&[u8]>, filesize: Option<u64>, blocksizes: &[u64]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_key(&mut out, 1, 0);
+    write_varint(&mut out, kind).unwrap();
+    if let Some(data) = data.filter(|d| !d.is_empty()) {
+        write_bytes(&mut out, 2, data);
+    }
+    if let Some(filesize) = filesize {
+        write_key(&mut out, 3, 0);
+        write_varint(&mut out, filesize).unwrap();
+    }
+    for size in blocksizes {
+        write_key(&mut out, 4, 0);
+        write_varint(&mut out, *size).unwrap();
+    }
+    out
+}
+
+/// dag-pb `PBNode`, with the links before the data as the codec requires.
+fn pb_node(links: &[(String, Node)], data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (name, node) in links {
+        let mut link = Vec::new();
+        write_bytes(&mut link, 1, &node.cid);
+        write_bytes(&mut link, 2, name.as_bytes());
+        write_key(&mut link, 3, 0);
+        write_varint(&mut link, node.tsize).unwrap();
+        write_bytes(&mut out, 2, &link);
+    }
+    write_bytes(&mut out, 1, data);
+    out
+}
+
+fn write_key(out: &mut Vec<u8>, field: u64, wire_type: u64) {
+    write_varint(out, field << 3 | wire_type).unwrap();
+}
+
+fn write_bytes(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_key(out, field, 2);
+    write_varint(out, bytes.len() as u64).unwrap();
+    out.extend_from_slice(bytes);
+}
+
+fn write_varint(out: &mut impl Write, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return out.write_all(&[byte]);
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+/// Binary CID of `data` with a SHA-256 multihash. CIDv0 is the bare multihash and always dag-pb.
+fn cid(version: CidVersion, codec: u64, data: &[u8]) -> Vec<u8> {
+    let mut cid = Vec::new();
+    if version == CidVersion::V1 {
+        write_varint(&mut cid, 1).unwrap();
+        write_varint(&mut cid, codec).unwrap();
+    }
+    write_varint(&mut cid, SHA2_256).unwrap();
+    write_varint(&mut cid, 32).unwrap();
+    cid.extend_from_slice(&Sha256::digest(data));
+    cid
+}
+
+/// Text form of a binary CID: base58btc for CIDv0 (`Qm...`), multibase base32 for CIDv1 (`bafy...`).
+fn cid_string(version: CidVersion, cid: &[u8]) -> String {
+    match version {
+        CidVersion::V0 => base58_encode(cid),
+        CidVersion::V1 => base32_encode(cid),
+    }
+}
+
+fn base58_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    // Little-endian base-58 digits of the number the bytes spell
+    let mut digits: Vec<u32> = Vec::new();
+    for byte in bytes {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut() {
+            carry += *digit << 8;
+            *digit = carry % 58;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push(carry % 58);
+            carry /= 58;
+        }
+    }
+    let zeros = bytes.iter().take_while(|b| **b == 0).count();
+    std::iter::repeat_n('1', zeros)
+        .chain(digits.iter().rev().map(|d| ALPHABET[*d as usize] as char))
+        .collect()
+}
+
+fn base32_encode(cid: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut out = String::from("b");
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in cid {
+        buffer = buffer << 8 | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[(buffer >> bits & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[(buffer << (5 - bits) & 31) as usize] as char);
+    }
+    out
+}
+
+/// Binary CIDv1 form of a `Qm...` or `b...` CID, as ENS contenthash and other CIDv1-only
+/// formats expect. `None` if it isn't a valid CID in either encoding.
+pub fn cid_v1_bytes(cid: &str) -> Option<Vec<u8>> {
+    if CidVersion::of(cid) == Some(CidVersion::V0) {
+        let multihash = base58_decode(cid)?;
+        if multihash.len() != 34 || multihash[..2] != [SHA2_256 as u8, 32] {
+            return None;
+        }
+        let mut v1 = Vec::new();
+        write_varint(&mut v1, 1).unwrap();
+        write_varint(&mut v1, DAG_PB).unwrap();
+        v1.extend(multihash);
+        return Some(v1);
+    }
+    let bytes = base32_decode(cid.strip_prefix('b')?)?;
+    (bytes.first() == Some(&1)).then_some(bytes)
+}
+
+fn base58_decode(text: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    // Little-endian bytes of the number the digits spell
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in text.bytes() {
+        let mut carry = ALPHABET.iter().position(|a| *a == c)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = text.bytes().take_while(|c| *c == b'1').count();
+    Some(std::iter::repeat_n(0, zeros).chain(bytes.into_iter().rev()).collect())
+}
+
+fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut out = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in text.bytes() {
+        buffer = buffer << 5 | ALPHABET.iter().position(|a| *a == c)? as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// DAG-CBOR `{"roots": [root], "version": 1}`.
+fn header(root: &[u8]) -> Vec<u8> {
+    let mut out = vec![0xa2, 0x65];
+    out.extend_from_slice(b"roots");
+    // Array of one CID: tag 42 around a byte string with the identity multibase prefix
+    out.extend_from_slice(&[0x81, 0xd8, 0x2a, 0x58, root.len() as u8 + 1, 0x00]);
+    out.extend_from_slice(root);
+    out.push(0x67);
+    out.extend_from_slice(b"version");
+    out.push(0x01);
+    out
+}
@@ -0,0 +1,167 @@
+use crate::frontmatter;
+use crate::generator::Post;
+use futures_util::stream::{self, StreamExt};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use walkdir::WalkDir;
+
+/// A problem found in the generated site or its sources.
+#[derive(Debug)]
+pub struct Issue {
+    /// Page (relative to the output directory) or post the problem was found in
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// Settings for fetching external links.
+pub struct ExternalCheck {
+    pub concurrency: usize,
+    pub timeout: Duration,
+}
+
+/// Validate internal links and images in every page under `dist`, post dates, and optionally
+/// external links. Root-relative links are resolved against `base_path` (e.g. `/blog/`).
+pub async fn check_site(dist: &Path, base_path: &str, posts: &[Post], external: Option<ExternalCheck>) -> Vec<Issue> {
+    let anchor = Regex::new(r"(?is)<a\b[^>]*>").unwrap();
+    let image = Regex::new(r"(?is)<img\b[^>]*>").unwrap();
+    let href = Regex::new(r#"(?i)\bhref\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    let src = Regex::new(r#"(?i)\bsrc\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    let attr = |re: &Regex, tag: &str| {
+        re.captures(tag)
+            .and_then(|c| c.get(1).or_else(|| c.get(2)))
+            .map(|m| m.as_str().to_string())
+    };
+
+    let mut issues = Vec::new();
+    // External URL -> pages linking to it
+    let mut external_links: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+
+    let pages = WalkDir::new(dist)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "html"));
+    for page in pages {
+        let Ok(html) = fs::read_to_string(page.path()) else { continue };
+        let relative = page.path().strip_prefix(dist).unwrap_or(page.path());
+        let location = relative.display().to_string();
+        let base = relative.parent().unwrap_or(Path::new(""));
+
+        let targets = anchor
+            .find_iter(&html)
+            .filter_map(|tag| attr(&href, tag.as_str()).map(|t| ("link", t)))
+            .chain(image.find_iter(&html).filter_map(|tag| attr(&src, tag.as_str()).map(|t| ("image", t))));
+        for (kind, target) in targets {
+            if target.starts_with("http://") || target.starts_with("https://") {
+                external_links.entry(target).or_default().insert(location.clone());
+            } else if !is_ignored(&target) && !resolves(dist, base_path, base, &target) {
+                issues.push(Issue { location: location.clone(), message: format!("broken {} {}", kind, target) });
+            }
+        }
+    }
+
+    for post in posts {
+        let dates = [("date", &post.frontmatter.date), ("updated", &post.frontmatter.updated)];
+        for (field, raw) in dates {
+            if let Some(raw) = raw.as_deref().filter(|raw| frontmatter::parse_date(raw).is_none()) {
+                issues.push(Issue {
+                    location: format!("post {}", post.original_slug),
+                    message: format!("{} \"{}\" is neither RFC 3339 nor YYYY-MM-DD", field, raw),
+                });
+            }
+        }
+    }
+
+    if let Some(settings) = external {
+        issues.extend(check_external(external_links, settings).await);
+    }
+    issues
+}
+
+/// Anchors, mail links, inline data and scripts aren't files to check.
+pub(crate) fn is_ignored(target: &str) -> bool {
+    target.is_empty()
+        || ["#", "mailto:", "tel:", "data:", "javascript:"].iter().any(|p| target.starts_with(p))
+        || target.starts_with("//")
+        || target.contains("://")
+}
+
+/// Whether `target`, linked from a page in `base`, points at a file in `dist`: the file itself,
+/// a directory with `index.html`, or an extensionless page with a `.html` file.
+fn resolves(dist: &Path, base_path: &str, base: &Path, target: &str) -> bool {
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+    if path.is_empty() {
+        return true;
+    }
+    let joined = match path.strip_prefix(base_path).or_else(|| path.strip_prefix('/')) {
+        Some(absolute) => PathBuf::from(absolute),
+        None => base.join(path),
+    };
+
+    // Resolve `.` and `..` like a browser does: `..` at the root stays at the root
+    let mut normalized = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Normal(part) => normalized.push(part),
+            _ => {}
+        }
+    }
+
+    let file = dist.join(&normalized);
+    file.is_file()
+        || file.join("index.html").is_file()
+        || (!path.ends_with('/') && dist.join(format!("{}.html", normalized.display())).is_file())
+}
+
+async fn check_external(links: BTreeMap<String, BTreeSet<String>>, settings: ExternalCheck) -> Vec<Issue> {
+    let client = match reqwest::Client::builder()
+        .timeout(settings.timeout)
+        .user_agent(concat!("scribe/", env!("CARGO_PKG_VERSION"), " (link checker)"))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            return vec![Issue { location: "external links".to_string(), message: format!("failed to create HTTP client: {}", e) }];
+        }
+    };
+
+    let results: Vec<(String, BTreeSet<String>, Option<String>)> = stream::iter(links)
+        .map(|(url, pages)| {
+            let client = client.clone();
+            async move {
+                let problem = match client.get(&url).send().await {
+                    Ok(response) if response.status().is_success() => None,
+                    Ok(response) => Some(format!("returned {}", response.status())),
+                    Err(e) if e.is_timeout() => Some("timed out".to_string()),
+                    Err(e) => Some(format!("failed: {}", e)),
+                };
+                (url, pages, problem)
+            }
+        })
+        .buffer_unordered(settings.concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut issues = Vec::new();
+    for (url, pages, problem) in results {
+        if let Some(problem) = problem {
+            for page in pages {
+                issues.push(Issue { location: page, message: format!("external link {} {}", url, problem) });
+            }
+        }
+    }
+    issues.sort_by(|a, b| a.location.cmp(&b.location).then(a.message.cmp(&b.message)));
+    issues
+}
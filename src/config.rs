@@ -1,7 +1,8 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -11,8 +12,806 @@ pub struct Config {
     pub url: Option<String>,
     pub posts_dir: String,
     pub output_dir: String,
+    /// Source formats read as posts besides Markdown (and HTML with frontmatter), e.g. `["org"]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub input_formats: Vec<InputFormat>,
     pub openai_api_key: Option<String>,
+    /// Passphrase `private: true` posts are encrypted with; also read from `SCRIBE_PASSPHRASE`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub private_passphrase: Option<String>,
     pub theme: Theme,
+    #[serde(default)]
+    pub notifications: Notifications,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<Avatar>,
+    #[serde(default)]
+    pub trailing_slash: TrailingSlash,
+    /// Path the site is served under, e.g. `/blog/` for `https://example.com/blog/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deploy: Option<DeployTarget>,
+    #[serde(default)]
+    pub publish: PublishOptions,
+    #[serde(default)]
+    pub markdown: MarkdownOptions,
+    #[serde(default)]
+    pub graph: GraphPage,
+    /// Build an `archive/` page listing every post by year and month, linked from the header
+    #[serde(default)]
+    pub archive: bool,
+    /// Write each post's data as JSON next to its page (`<slug>/index.json`), for other tools
+    #[serde(default)]
+    pub api: bool,
+    #[serde(default)]
+    pub pwa: Pwa,
+    /// Slugs no post may have, kept free for pages like tag indexes or search; scribe's own
+    /// generated pages are always reserved
+    #[serde(default = "default_reserved_slugs")]
+    pub reserved_slugs: Vec<String>,
+    /// YAML file of curated reading trails, each built as a `trails/<name>/` page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trails: Option<String>,
+    #[serde(default)]
+    pub fonts: Fonts,
+    /// TeX math between `$…$` and `$$…$$` in posts; off by default, so dollar signs stay text
+    #[serde(default)]
+    pub math: MathMode,
+    #[serde(default)]
+    pub mermaid: Mermaid,
+    /// Comment widget below each post
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comments: Option<Comments>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webmention: Option<Webmention>,
+
+    /// Analytics script loaded by every page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analytics: Option<Analytics>,
+    #[serde(default)]
+    pub initials: InitialsOptions,
+    #[serde(default)]
+    pub summaries: SummaryOptions,
+    #[serde(default)]
+    pub images: ImageOptions,
+    #[serde(default)]
+    pub watch: WatchOptions,
+    #[serde(default)]
+    pub trash: TrashOptions,
+    #[serde(default)]
+    pub languages: Languages,
+    /// Embed the scribe version, build time and post count in pages and `build.json`
+    #[serde(default)]
+    pub build_stamp: bool,
+    /// Output emitters to run, by name, with their options (see [`crate::emit::OutputEmitter`])
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub emitters: BTreeMap<String, serde_json::Value>,
+    /// Build without network access: no OpenAI, link metadata, favicon, avatar or web font requests
+    #[serde(default)]
+    pub offline: bool,
+    /// Annotate output with template comments and dump render contexts (set by `--debug-templates`)
+    #[serde(skip)]
+    pub debug_templates: bool,
+    /// Directory of the config file, set by `load`; project files like `scribe.lock` sit there
+    #[serde(skip)]
+    pub root: Option<PathBuf>,
+}
+
+/// Interactive link graph page and `graph.json`, both off by default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphPage {
+    pub enabled: bool,
+    /// Include tags as nodes connected to their posts
+    pub tags: bool,
+}
+
+/// Installable site that stays readable offline: `manifest.json` and a service worker
+/// precaching the build's pages and assets. Off by default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Pwa {
+    pub enabled: bool,
+    /// Name under the home screen icon; the site title when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short_name: Option<String>,
+    /// Files larger than this many KiB aren't precached, only kept once a reader fetches them
+    pub precache_max_kib: u64,
+}
+
+impl Default for Pwa {
+    fn default() -> Self {
+        Self { enabled: false, short_name: None, precache_max_kib: 512 }
+    }
+}
+
+/// How illuminated initials are made: requested from the OpenAI images API or drawn as SVG.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InitialsOptions {
+    pub mode: InitialsMode,
+    /// Prompt for each initial; `{letter}` and `{title}` (the post title) are filled in
+    pub prompt: String,
+    pub model: String,
+    pub size: String,
+    /// Image format: `png`, `jpeg` or `webp`
+    pub format: String,
+    /// Generate one initial per post, themed on its title, instead of sharing one per letter
+    pub per_post: bool,
+    /// Drop cap style for `mode: "svg"`
+    pub svg: SvgInitials,
+}
+
+/// AI-written excerpts and TL;DR blocks for posts without them, saved into their frontmatter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SummaryOptions {
+    /// Also fill in missing summaries during `scribe generate`, not only with `scribe summarize`
+    pub on_generate: bool,
+    /// OpenAI chat model
+    pub model: String,
+}
+
+impl Default for SummaryOptions {
+    fn default() -> Self {
+        Self {
+            on_generate: false,
+            model: "gpt-4o-mini".to_string(),
+        }
+    }
+}
+
+/// Images published from the posts directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageOptions {
+    /// Remove EXIF, XMP and IPTC metadata (camera details, GPS location) from published JPEG, PNG and WebP files
+    pub strip_metadata: bool,
+    /// EXIF fields to keep when stripping, e.g. `Model`; `Orientation` keeps photos upright
+    pub keep_exif: Vec<String>,
+}
+
+/// What `scribe serve --watch` watches besides the posts, config, stylesheet and trails file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchOptions {
+    /// Extra files or directories (watched recursively) whose changes rebuild the whole site,
+    /// e.g. templates, assets or included config
+    pub paths: Vec<String>,
+    /// Globs for changes to ignore, e.g. `*.swp` or `node_modules`; `*` stays within one path
+    /// segment and `**` crosses them
+    pub ignore: Vec<String>,
+}
+
+impl WatchOptions {
+    /// Whether `path` matches one of the `ignore` globs. A glob matches from the start of any
+    /// segment, and a match on a directory covers everything under it.
+    pub fn ignores(&self, path: &Path) -> bool {
+        let path = path.to_string_lossy().replace('\\', "/");
+        self.ignore.iter().any(|glob| {
+            let mut re = String::from("(^|/)");
+            let mut chars = glob.trim_matches('/').chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '*' if chars.peek() == Some(&'*') => {
+                        chars.next();
+                        re.push_str(".*");
+                    }
+                    '*' => re.push_str("[^/]*"),
+                    '?' => re.push_str("[^/]"),
+                    c => re.push_str(&regex::escape(&c.to_string())),
+                }
+            }
+            re.push_str("(/|$)");
+            regex::Regex::new(&re).is_ok_and(|r| r.is_match(&path))
+        })
+    }
+
+    /// Whether `path` is one of the extra watched `paths` or inside one.
+    pub fn covers(&self, path: &Path) -> bool {
+        self.paths.iter().any(|p| path.starts_with(p) || path.ends_with(p))
+    }
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            strip_metadata: true,
+            keep_exif: vec!["Orientation".to_string()],
+        }
+    }
+}
+
+/// Languages of a multilingual site. Posts in `posts/<code>/` or with `lang: <code>` are built
+/// under `/<code>/`; the default language stays at the root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Languages {
+    /// Code of the language at the site root, also used for `<html lang>`
+    pub default: String,
+    /// Name of each language by code, shown in the language switcher; empty for a single-language site
+    pub names: BTreeMap<String, String>,
+    /// Text direction of the default language; inferred from its code when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<Direction>,
+}
+
+impl Default for Languages {
+    fn default() -> Self {
+        Self {
+            default: "en".to_string(),
+            names: BTreeMap::new(),
+            dir: None,
+        }
+    }
+}
+
+impl Languages {
+    pub fn is_multilingual(&self) -> bool {
+        !self.names.is_empty()
+    }
+
+    /// Language codes in switcher order: the default first, then the rest alphabetically.
+    pub fn codes(&self) -> Vec<&str> {
+        std::iter::once(self.default.as_str())
+            .chain(self.names.keys().map(String::as_str).filter(|code| *code != self.default))
+            .collect()
+    }
+
+    /// Whether pages in `code` live under `/<code>/` rather than at the root.
+    pub fn is_prefixed(&self, code: &str) -> bool {
+        self.is_multilingual() && code != self.default && self.names.contains_key(code)
+    }
+
+    /// Path of a language's index relative to the site root: empty for the default, `<code>/` otherwise.
+    pub fn index_path(&self, code: &str) -> String {
+        if self.is_prefixed(code) { format!("{}/", code) } else { String::new() }
+    }
+
+    /// Text direction of the language `code`: `dir` for the default language, if set, and
+    /// otherwise right to left for Arabic, Hebrew, Persian, Urdu and other RTL scripts.
+    pub fn dir(&self, code: &str) -> Direction {
+        match self.dir {
+            Some(dir) if code == self.default => dir,
+            _ => {
+                let primary = code.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+                if RTL_LANGUAGES.contains(&primary.as_str()) { Direction::Rtl } else { Direction::Ltr }
+            }
+        }
+    }
+
+    /// Name of the language `code`, or the code itself.
+    pub fn name<'a>(&'a self, code: &'a str) -> &'a str {
+        self.names.get(code).map_or(code, String::as_str)
+    }
+}
+
+/// A source format posts can be written in besides Markdown, converted to Markdown when read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputFormat {
+    /// `.adoc` and `.asciidoc` files
+    Asciidoc,
+    /// Emacs Org-mode `.org` files
+    Org,
+}
+
+impl InputFormat {
+    pub const ALL: [InputFormat; 2] = [InputFormat::Asciidoc, InputFormat::Org];
+
+    /// The format of a file with `path`'s extension, if it is one of these.
+    pub fn of(path: &Path) -> Option<InputFormat> {
+        match path.extension()?.to_str()? {
+            "adoc" | "asciidoc" => Some(InputFormat::Asciidoc),
+            "org" => Some(InputFormat::Org),
+            _ => None,
+        }
+    }
+}
+
+/// Direction text runs in, for `<html dir>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Ltr => "ltr",
+            Direction::Rtl => "rtl",
+        }
+    }
+}
+
+/// Languages written right to left, by primary subtag.
+const RTL_LANGUAGES: &[&str] = &["ar", "arc", "ckb", "dv", "fa", "he", "ks", "ku", "ps", "sd", "syr", "ug", "ur", "yi"];
+
+/// What the build leaves at the URL of a post moved to the trash with `scribe rm`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrashOptions {
+    pub page: TrashedPage,
+    /// Slug of the post `redirect` forwards to; the home page if unset
+    pub redirect_to: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrashedPage {
+    /// Delete the page
+    #[default]
+    Remove,
+    /// Replace it with a "gone" notice
+    Gone,
+    /// Forward to `redirect_to`
+    Redirect,
+}
+
+/// Source of illuminated initials.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InitialsMode {
+    /// Generated images, when an OpenAI API key is set
+    #[default]
+    Openai,
+    /// Drop caps drawn locally as SVG, without network calls
+    Svg,
+    /// No initials
+    None,
+}
+
+/// Look of the SVG drop caps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SvgInitials {
+    /// CSS font family for the letter; images can't load web fonts, so it must be installed locally
+    pub font: String,
+    pub ornament: Ornament,
+    pub color: String,
+    pub background: String,
+    pub ornament_color: String,
+}
+
+impl Default for SvgInitials {
+    fn default() -> Self {
+        Self {
+            font: "'Crimson Text', Georgia, serif".to_string(),
+            ornament: Ornament::default(),
+            color: "#f5f5f5".to_string(),
+            background: "#0a0a0a".to_string(),
+            ornament_color: "#8b8b8b".to_string(),
+        }
+    }
+}
+
+/// Border drawn around an SVG drop cap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ornament {
+    None,
+    /// A single rule
+    #[default]
+    Frame,
+    /// Two nested rules
+    Double,
+    /// Corner brackets with a diamond on each side
+    Corners,
+}
+
+impl Default for InitialsOptions {
+    fn default() -> Self {
+        Self {
+            mode: InitialsMode::default(),
+            prompt: "A black background with white ink drawing featuring an illuminated initial '{letter}' in the Italian Futurist style, with geometric and abstract forms, swirling lines, and dynamic composition reminiscent of early 20th-century avant-garde art. The background should be pure black with white forms and lines.".to_string(),
+            model: "gpt-image-1".to_string(),
+            size: "1024x1024".to_string(),
+            format: "png".to_string(),
+            per_post: false,
+            svg: SvgInitials::default(),
+        }
+    }
+}
+
+impl InitialsOptions {
+    /// The prompt for `letter` in a post titled `title`.
+    ///
+    /// Per-post initials are themed on the title even when the prompt doesn't mention `{title}`.
+    pub fn prompt_for(&self, letter: char, title: &str) -> String {
+        let prompt = if self.per_post && !self.prompt.contains("{title}") {
+            format!("{} Work in imagery that evokes the title \"{{title}}\".", self.prompt)
+        } else {
+            self.prompt.clone()
+        };
+        prompt.replace("{letter}", &letter.to_string()).replace("{title}", title)
+    }
+}
+
+/// Web fonts served with the site instead of from the Google Fonts CDN, off by default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Fonts {
+    /// Download Crimson Text and Inter into `fonts/` and reference them from `style.css`
+    pub bundle: bool,
+}
+
+/// How `$…$` and `$$…$$` math in posts is published.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MathMode {
+    /// Dollar signs are ordinary text
+    #[default]
+    Off,
+    /// TeX typeset in the browser by KaTeX, bundled into `katex/` so pages work from IPFS
+    Katex,
+    /// MathML rendered at build time, for pages without JavaScript
+    Mathml,
+}
+
+/// Diagrams from ```` ```mermaid ```` code blocks, which stay code blocks unless `mode` is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mermaid {
+    pub mode: MermaidMode,
+    /// Renderer for `mode: "svg"`, run with mermaid-cli's `-i`, `-o`, `-t`, `-b` and `-I` flags,
+    /// e.g. `npx -p @mermaid-js/mermaid-cli mmdc`
+    pub command: String,
+    /// Mermaid theme: `dark`, `default`, `neutral` or `forest`
+    pub theme: String,
+}
+
+impl Default for Mermaid {
+    fn default() -> Self {
+        Self {
+            mode: MermaidMode::Off,
+            command: "mmdc".to_string(),
+            theme: "dark".to_string(),
+        }
+    }
+}
+
+/// How `mermaid` code blocks are published.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MermaidMode {
+    /// Shown as code
+    #[default]
+    Off,
+    /// Drawn in the browser by mermaid.js, bundled into `mermaid/`
+    Script,
+    /// Rendered to inline SVG at build time by `command`, for pages without JavaScript
+    Svg,
+}
+
+/// Markdown extensions; everything except smart punctuation is on by default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkdownOptions {
+    pub footnotes: bool,
+    pub tables: bool,
+    pub strikethrough: bool,
+    pub tasklists: bool,
+    pub definition_lists: bool,
+    pub smart_punctuation: bool,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            footnotes: true,
+            tables: true,
+            strikethrough: true,
+            tasklists: true,
+            definition_lists: true,
+            smart_punctuation: false,
+        }
+    }
+}
+
+/// Hosting target for `scribe deploy`. Each target may override the reported `url`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "target", rename_all = "kebab-case")]
+pub enum DeployTarget {
+    /// Force-push the site to a branch (e.g. `gh-pages`)
+    GithubPages {
+        #[serde(default = "default_deploy_remote")]
+        remote: String,
+        #[serde(default = "default_deploy_branch")]
+        branch: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+    /// rsync over SSH to `user@host:/path`
+    Rsync {
+        destination: String,
+        /// Extra arguments passed to ssh (e.g. "-p 2222")
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ssh_args: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+    /// S3-compatible bucket, synced with the `aws` CLI
+    S3 {
+        bucket: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        prefix: Option<String>,
+        /// Custom endpoint for S3-compatible providers (R2, B2, MinIO...)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        endpoint: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        region: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+}
+
+/// Where `scribe publish` pins the site and which names it points at the new root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PublishOptions {
+    /// IPFS API endpoint
+    pub ipfs_api: String,
+    /// IPNS key to publish under; `null` skips the IPNS update
+    pub ipns_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dnslink: Option<DnsLink>,
+    /// More places `scribe pin` and `scribe publish` pin the site, all at once
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<PinNode>,
+    /// Further attempts for each of `nodes` after a failure, with exponential backoff
+    pub retries: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ens: Option<Ens>,
+}
+
+impl Default for PublishOptions {
+    fn default() -> Self {
+        Self {
+            ipfs_api: "http://127.0.0.1:5001".to_string(),
+            ipns_key: Some("self".to_string()),
+            dnslink: None,
+            nodes: Vec::new(),
+            retries: 3,
+            ens: None,
+        }
+    }
+}
+
+/// ENS name whose contenthash is pointed at each new root, through Foundry's `cast`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ens {
+    /// e.g. `myblog.eth`
+    pub name: String,
+    /// Ethereum JSON-RPC endpoint; `cast` falls back to `ETH_RPC_URL`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpc_url: Option<String>,
+    /// Account that manages the name and signs the update
+    pub wallet: EnsWallet,
+}
+
+/// How the contenthash transaction is signed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnsWallet {
+    Ledger,
+    Trezor,
+    /// Encrypted keystore file; `cast` asks for its password unless `ETH_PASSWORD` is set
+    Keystore(String),
+    /// Environment variable holding a raw private key
+    PrivateKeyEnv(String),
+}
+
+/// An extra node the site is pinned on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PinNode {
+    /// IPFS node RPC API (e.g. on a VPS) the site is uploaded to and pinned on
+    Api { api: String },
+    /// IPFS Pinning Service API (Pinata, Filebase...) asked to fetch and pin the root, with an
+    /// access token read from `token_env`
+    Service { service: String, token_env: String },
+}
+
+impl PinNode {
+    /// Endpoint URL, naming the node in reports.
+    pub fn endpoint(&self) -> &str {
+        match self {
+            PinNode::Api { api } => api,
+            PinNode::Service { service, .. } => service,
+        }
+    }
+}
+
+/// DNS provider holding the `_dnslink` TXT record of `domain`, updated after every publish.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "kebab-case")]
+pub enum DnsLink {
+    /// Cloudflare DNS API, authenticated with an API token read from `token_env`
+    Cloudflare {
+        zone_id: String,
+        domain: String,
+        #[serde(default = "default_cloudflare_token_env")]
+        token_env: String,
+    },
+    /// Route 53, updated with the `aws` CLI and its usual credentials
+    Route53 {
+        hosted_zone_id: String,
+        domain: String,
+    },
+}
+
+impl DnsLink {
+    /// Name of the TXT record, e.g. `_dnslink.example.com`.
+    pub fn record_name(&self) -> String {
+        let domain = match self {
+            DnsLink::Cloudflare { domain, .. } | DnsLink::Route53 { domain, .. } => domain.trim_end_matches('.'),
+        };
+        if domain.starts_with("_dnslink.") {
+            domain.to_string()
+        } else {
+            format!("_dnslink.{}", domain)
+        }
+    }
+}
+
+/// Hosted comment threads embedded below posts, loaded by the reader's browser.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "kebab-case")]
+pub enum Comments {
+    /// GitHub Discussions through giscus; the IDs come from the form at https://giscus.app
+    Giscus {
+        repo: String,
+        repo_id: String,
+        category: String,
+        category_id: String,
+        /// How pages map to discussions: `pathname`, `url`, `title` or `og:title`
+        #[serde(default = "default_comments_mapping")]
+        mapping: String,
+        #[serde(default = "default_giscus_theme")]
+        theme: String,
+    },
+    /// GitHub issues through utterances
+    Utterances {
+        repo: String,
+        /// How pages map to issues: `pathname`, `url`, `title` or `og:title`
+        #[serde(default = "default_comments_mapping")]
+        issue_term: String,
+        #[serde(default = "default_utterances_theme")]
+        theme: String,
+        /// Label added to the issues utterances opens
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+    },
+}
+
+/// Privacy-friendly analytics whose script every page loads, hosted or on a self-hosted
+/// instance at `host`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "kebab-case")]
+pub enum Analytics {
+    /// Plausible, counting the site registered as `domain`
+    Plausible {
+        /// Site as added to Plausible; the host of `url` when unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        domain: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    /// GoatCounter, counting into `<code>.goatcounter.com` or the instance at `host`
+    Goatcounter {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        code: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    /// Umami, counting into the website with ID `website_id`
+    Umami {
+        website_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+}
+
+fn default_comments_mapping() -> String {
+    "pathname".to_string()
+}
+
+fn default_giscus_theme() -> String {
+    "dark".to_string()
+}
+
+fn default_utterances_theme() -> String {
+    "github-dark".to_string()
+}
+
+/// Webmentions received through webmention.io, advertised on post pages and shown under them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webmention {
+    /// Domain registered with webmention.io, e.g. `example.com`
+    pub domain: String,
+    /// Also advertise webmention.io's pingback endpoint
+    #[serde(default)]
+    pub pingback: bool,
+    /// Fetch received mentions at build time and render them under each post (needs `url`)
+    #[serde(default = "default_true")]
+    pub show: bool,
+}
+
+impl Webmention {
+    /// Endpoint senders post webmentions to.
+    pub fn endpoint(&self) -> String {
+        format!("https://webmention.io/{}/webmention", self.domain)
+    }
+
+    /// Endpoint accepting pingbacks, converted to webmentions.
+    pub fn pingback_endpoint(&self) -> String {
+        format!("https://webmention.io/{}/xmlrpc", self.domain)
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_cloudflare_token_env() -> String {
+    "CLOUDFLARE_API_TOKEN".to_string()
+}
+
+fn default_deploy_remote() -> String {
+    "origin".to_string()
+}
+
+fn default_deploy_branch() -> String {
+    "gh-pages".to_string()
+}
+
+/// Canonical URL form for post pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrailingSlash {
+    /// `/slug/`, written to `slug/index.html`
+    #[default]
+    Always,
+    /// `/slug`, written to `slug.html`
+    Never,
+}
+
+impl TrailingSlash {
+    /// Link to a post from a page whose relative site root is `root` (e.g. `./`, `../` or `/`).
+    pub fn post_href(self, root: &str, slug: &str) -> String {
+        match self {
+            TrailingSlash::Always => format!("{}{}/", root, slug),
+            TrailingSlash::Never => format!("{}{}", root, slug),
+        }
+    }
+
+    /// Output file for a post, relative to the output directory.
+    pub fn post_path(self, slug: &str) -> PathBuf {
+        match self {
+            TrailingSlash::Always => Path::new(slug).join("index.html"),
+            TrailingSlash::Never => PathBuf::from(format!("{}.html", slug)),
+        }
+    }
+
+    /// Relative path back to the site root from the page for `path` (e.g. `series/intro`).
+    pub fn page_root(self, path: &str) -> String {
+        let depth = path.split('/').count();
+        let depth = match self {
+            TrailingSlash::Always => depth,
+            TrailingSlash::Never => depth - 1,
+        };
+        if depth == 0 { "./".to_string() } else { "../".repeat(depth) }
+    }
+
+    /// Relative path from a post page back to the site root.
+    pub fn post_root(self) -> &'static str {
+        match self {
+            TrailingSlash::Always => "../",
+            TrailingSlash::Never => "./",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +820,74 @@ pub struct Theme {
     pub background_color: String,
     pub text_color: String,
     pub accent_color: String,
+    /// Show Home → series or primary tag → post at the top of posts in a series or with tags
+    #[serde(default = "default_breadcrumbs")]
+    pub breadcrumbs: bool,
+    #[serde(default = "default_breadcrumb_separator")]
+    pub breadcrumb_separator: String,
+    /// Stylesheet appended to the generated `style.css`, e.g. `theme.css`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stylesheet: Option<String>,
+    /// Show an estimated reading time under post titles
+    #[serde(default)]
+    pub reading_time: bool,
+}
+
+fn default_breadcrumbs() -> bool {
+    true
+}
+
+fn default_breadcrumb_separator() -> String {
+    "→".to_string()
+}
+
+/// Where to report build completion and failure.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Notifications {
+    /// Show a native desktop notification after watch-mode rebuilds
+    #[serde(default)]
+    pub desktop: bool,
+    /// Generic webhook receiving a JSON payload
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+    /// Slack incoming webhook URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slack: Option<String>,
+    /// ntfy topic URL (e.g. https://ntfy.sh/my-blog)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ntfy: Option<String>,
+}
+
+/// Author avatar, either a local image or looked up by email hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Avatar {
+    /// Email used to look up the image on Gravatar/libravatar
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Local image file; takes precedence over `email`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub provider: AvatarProvider,
+    /// Requested size in pixels for remote avatars
+    #[serde(default = "default_avatar_size")]
+    pub size: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AvatarProvider {
+    #[default]
+    Gravatar,
+    Libravatar,
+}
+
+fn default_reserved_slugs() -> Vec<String> {
+    ["tags", "feed", "search", "assets"].map(String::from).to_vec()
+}
+
+fn default_avatar_size() -> u32 {
+    160
 }
 
 impl Default for Config {
@@ -32,8 +899,40 @@ impl Default for Config {
             url: None,
             posts_dir: "posts".to_string(),
             output_dir: "dist".to_string(),
+            input_formats: Vec::new(),
             openai_api_key: None,
+            private_passphrase: None,
             theme: Theme::default(),
+            notifications: Notifications::default(),
+            avatar: None,
+            trailing_slash: TrailingSlash::default(),
+            base_path: None,
+            deploy: None,
+            publish: PublishOptions::default(),
+            markdown: MarkdownOptions::default(),
+            graph: GraphPage::default(),
+            archive: false,
+            api: false,
+            pwa: Pwa::default(),
+            reserved_slugs: default_reserved_slugs(),
+            trails: None,
+            fonts: Fonts::default(),
+            math: MathMode::default(),
+            mermaid: Mermaid::default(),
+            comments: None,
+            webmention: None,
+            analytics: None,
+            initials: InitialsOptions::default(),
+            summaries: SummaryOptions::default(),
+            images: ImageOptions::default(),
+            watch: WatchOptions::default(),
+            trash: TrashOptions::default(),
+            languages: Languages::default(),
+            build_stamp: false,
+            emitters: BTreeMap::new(),
+            offline: false,
+            debug_templates: false,
+            root: None,
         }
     }
 }
@@ -45,12 +944,17 @@ impl Default for Theme {
             background_color: "#0a0a0a".to_string(),
             text_color: "#f5f5f5".to_string(),
             accent_color: "#8b8b8b".to_string(),
+            breadcrumbs: default_breadcrumbs(),
+            breadcrumb_separator: default_breadcrumb_separator(),
+            stylesheet: None,
+            reading_time: false,
         }
     }
 }
 
 impl Config {
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let root = path.as_ref().parent().unwrap_or(Path::new("")).to_path_buf();
         let mut config = if path.as_ref().exists() {
             let content = fs::read_to_string(path)
                 .context("Failed to read config file")?;
@@ -71,7 +975,53 @@ impl Config {
         if let Ok(api_key) = std::env::var("OPENAI_API_KEY") {
             config.openai_api_key = Some(api_key);
         }
+        if let Ok(passphrase) = std::env::var("SCRIBE_PASSPHRASE") {
+            config.private_passphrase = Some(passphrase);
+        }
+        
+        // Set by the global `--offline` flag
+        if std::env::var("SCRIBE_OFFLINE").is_ok_and(|v| !v.is_empty() && v != "0") {
+            config.offline = true;
+        }
+        config.root = Some(root);
         
         Ok(config)
     }
+
+    /// Directory project files such as `scribe.lock` sit in: the config file's, or for a config
+    /// built in code, the one holding `posts_dir`.
+    pub fn project_dir(&self) -> PathBuf {
+        self.root.clone().unwrap_or_else(|| Path::new(&self.posts_dir).parent().unwrap_or(Path::new("")).to_path_buf())
+    }
+
+    /// `base_path` with a leading and trailing slash; `/` when unset.
+    pub fn base_path(&self) -> String {
+        let trimmed = self.base_path.as_deref().unwrap_or_default().trim_matches('/');
+        if trimmed.is_empty() { "/".to_string() } else { format!("/{}/", trimmed) }
+    }
+
+    /// Public address of the site root (with a trailing slash), including `base_path` unless
+    /// `url` already ends with it.
+    pub fn site_url(&self) -> Option<String> {
+        let url = self.url.as_deref()?.trim_end_matches('/');
+        let base = self.base_path();
+        let base = base.trim_end_matches('/');
+        if url.ends_with(base) {
+            Some(format!("{}/", url))
+        } else {
+            Some(format!("{}{}/", url, base))
+        }
+    }
+
+    /// Copy with API keys and webhook URLs masked, safe to write into the output directory.
+    pub fn redacted(&self) -> Config {
+        let mask = |value: &Option<String>| value.as_ref().map(|_| "<redacted>".to_string());
+        let mut config = self.clone();
+        config.openai_api_key = mask(&self.openai_api_key);
+        config.private_passphrase = mask(&self.private_passphrase);
+        config.notifications.webhook = mask(&self.notifications.webhook);
+        config.notifications.slack = mask(&self.notifications.slack);
+        config.notifications.ntfy = mask(&self.notifications.ntfy);
+        config
+    }
 } 
\ No newline at end of file
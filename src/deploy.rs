@@ -0,0 +1,125 @@
+use crate::config::{Config, DeployTarget};
+use anyhow::{bail, Context, Result};
+use colored::*;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tracing::info;
+use walkdir::WalkDir;
+
+/// Publish the output directory to the configured target and return the public URL.
+pub fn deploy(config: &Config, target: &DeployTarget) -> Result<String> {
+    let dist = Path::new(&config.output_dir);
+    if !dist.is_dir() {
+        bail!("Output directory '{}' does not exist", dist.display());
+    }
+
+    match target {
+        DeployTarget::GithubPages { remote, branch, url } => {
+            deploy_github_pages(dist, remote, branch)?;
+            published_url(url, config).or_else(|_| Ok(format!("{}@{}", remote, branch)))
+        }
+        DeployTarget::Rsync { destination, ssh_args, url } => {
+            let mut cmd = Command::new("rsync");
+            cmd.arg("-avz").arg("--delete");
+            if let Some(ssh) = ssh_args {
+                cmd.arg("-e").arg(format!("ssh {}", ssh));
+            }
+            // Trailing slash copies the directory contents rather than the directory itself
+            cmd.arg(format!("{}/", dist.display())).arg(destination);
+            run(&mut cmd)?;
+            published_url(url, config).or_else(|_| Ok(destination.clone()))
+        }
+        DeployTarget::S3 { bucket, prefix, endpoint, region, url } => {
+            let destination = match prefix {
+                Some(p) => format!("s3://{}/{}", bucket, p.trim_matches('/')),
+                None => format!("s3://{}", bucket),
+            };
+            let mut cmd = Command::new("aws");
+            cmd.arg("s3").arg("sync").arg(dist).arg(&destination).arg("--delete");
+            if let Some(endpoint) = endpoint {
+                cmd.arg("--endpoint-url").arg(endpoint);
+            }
+            if let Some(region) = region {
+                cmd.arg("--region").arg(region);
+            }
+            run(&mut cmd)?;
+            published_url(url, config).or_else(|_| {
+                Ok(match endpoint {
+                    Some(endpoint) => format!("{}/{}/", endpoint.trim_end_matches('/'), bucket),
+                    None => format!("https://{}.s3.amazonaws.com/", bucket),
+                })
+            })
+        }
+    }
+}
+
+/// Force-push the output directory as the sole commit of `branch` on `remote`.
+fn deploy_github_pages(dist: &Path, remote: &str, branch: &str) -> Result<()> {
+    let remote_url = String::from_utf8(
+        Command::new("git")
+            .args(["remote", "get-url", remote])
+            .output()
+            .context("Failed to run git")?
+            .stdout,
+    )?
+    .trim()
+    .to_string();
+    if remote_url.is_empty() {
+        bail!("Git remote '{}' not found", remote);
+    }
+
+    // Stage the site in a throwaway repository so the project history is untouched
+    let staging = std::env::temp_dir().join(format!("scribe-deploy-{}", std::process::id()));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    copy_dir(dist, &staging)?;
+    // Serve files and directories starting with '_' as-is
+    fs::write(staging.join(".nojekyll"), "")?;
+
+    let git = |args: &[&str]| {
+        let mut cmd = Command::new("git");
+        cmd.current_dir(&staging).args(args);
+        run(&mut cmd)
+    };
+    let result = git(&["init", "--quiet"])
+        .and_then(|_| git(&["add", "--all"]))
+        .and_then(|_| git(&["-c", "user.name=scribe", "-c", "user.email=scribe@localhost", "commit", "--quiet", "-m", "Deploy site"]))
+        .and_then(|_| git(&["push", "--force", "--quiet", &remote_url, &format!("HEAD:refs/heads/{}", branch)]));
+
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    for entry in WalkDir::new(from).into_iter().filter_map(|e| e.ok()) {
+        let relative = entry.path().strip_prefix(from)?;
+        let target = to.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+fn published_url(target_url: &Option<String>, config: &Config) -> Result<String> {
+    target_url
+        .clone()
+        .or_else(|| config.site_url())
+        .context("No published URL configured")
+}
+
+fn run(cmd: &mut Command) -> Result<()> {
+    let program = cmd.get_program().to_string_lossy().to_string();
+    info!("{} {}", "Running".cyan(), program);
+    let status = cmd
+        .status()
+        .context(format!("Failed to run {} (is it installed?)", program))?;
+    if !status.success() {
+        bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
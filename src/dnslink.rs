@@ -0,0 +1,99 @@
+use crate::config::DnsLink;
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use std::process::Command;
+
+const CLOUDFLARE_API: &str = "https://api.cloudflare.com/client/v4";
+
+/// TTL of the TXT record, short so a new publish is picked up quickly.
+const TTL: u32 = 60;
+
+/// Point the `_dnslink` TXT record at `path` (e.g. `/ipfs/<cid>`), creating it if needed.
+pub async fn update(dnslink: &DnsLink, path: &str) -> Result<()> {
+    let name = dnslink.record_name();
+    let content = format!("dnslink={}", path);
+    match dnslink {
+        DnsLink::Cloudflare { zone_id, token_env, .. } => {
+            let token = std::env::var(token_env)
+                .ok()
+                .filter(|t| !t.is_empty())
+                .context(format!("Set {} to a Cloudflare API token with DNS edit access", token_env))?;
+            update_cloudflare(zone_id, &token, &name, &content).await
+        }
+        DnsLink::Route53 { hosted_zone_id, .. } => update_route53(hosted_zone_id, &name, &content),
+    }
+}
+
+async fn update_cloudflare(zone_id: &str, token: &str, name: &str, content: &str) -> Result<()> {
+    let client = reqwest::Client::new();
+    let records_url = format!("{}/zones/{}/dns_records", CLOUDFLARE_API, zone_id);
+
+    let existing: serde_json::Value = client
+        .get(&records_url)
+        .bearer_auth(token)
+        .query(&[("type", "TXT"), ("name", name)])
+        .send()
+        .await
+        .context("Failed to reach the Cloudflare API")?
+        .json()
+        .await?;
+    check_cloudflare(&existing)?;
+    // Only replace a record that is a DNSLink, leaving other TXT records on the name alone
+    let record_id = existing["result"].as_array().and_then(|records| {
+        records
+            .iter()
+            .find(|r| r["content"].as_str().is_some_and(|c| c.trim_matches('"').starts_with("dnslink=")))
+            .and_then(|r| r["id"].as_str())
+    });
+
+    let record = json!({ "type": "TXT", "name": name, "content": content, "ttl": TTL });
+    let request = match record_id {
+        Some(id) => client.put(format!("{}/{}", records_url, id)),
+        None => client.post(&records_url),
+    };
+    let response: serde_json::Value = request
+        .bearer_auth(token)
+        .json(&record)
+        .send()
+        .await
+        .context("Failed to reach the Cloudflare API")?
+        .json()
+        .await?;
+    check_cloudflare(&response)
+}
+
+fn check_cloudflare(response: &serde_json::Value) -> Result<()> {
+    if response["success"].as_bool() == Some(true) {
+        return Ok(());
+    }
+    let errors: Vec<String> = response["errors"]
+        .as_array()
+        .map(|errors| errors.iter().filter_map(|e| e["message"].as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+    bail!("Cloudflare API error: {}", if errors.is_empty() { "unknown error".to_string() } else { errors.join("; ") })
+}
+
+fn update_route53(hosted_zone_id: &str, name: &str, content: &str) -> Result<()> {
+    // Route 53 expects TXT values in their quoted zone file form
+    let change_batch = json!({
+        "Comment": "scribe publish",
+        "Changes": [{
+            "Action": "UPSERT",
+            "ResourceRecordSet": {
+                "Name": name,
+                "Type": "TXT",
+                "TTL": TTL,
+                "ResourceRecords": [{ "Value": format!("\"{}\"", content) }],
+            },
+        }],
+    });
+    let status = Command::new("aws")
+        .args(["route53", "change-resource-record-sets", "--hosted-zone-id", hosted_zone_id, "--change-batch"])
+        .arg(change_batch.to_string())
+        .status()
+        .context("Failed to run aws (is it installed?)")?;
+    if !status.success() {
+        bail!("aws exited with {}", status);
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::config::Config;
+use crate::generator::Post;
+use crate::trails::Trail;
+use anyhow::{bail, Result};
+use std::path::{Component, Path, PathBuf};
+
+/// What an emitter sees of the site being built.
+#[derive(Debug, Clone, Copy)]
+pub struct SiteContext<'a> {
+    pub config: &'a Config,
+    /// Every published post, newest first
+    pub posts: &'a [Post],
+    pub trails: &'a [Trail],
+}
+
+/// A file an emitter adds to the site, at `path` relative to the output directory.
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+impl Output {
+    pub fn new(path: impl Into<PathBuf>, content: impl Into<String>) -> Self {
+        Output { path: path.into(), content: content.into() }
+    }
+}
+
+/// Extra outputs written alongside the generated site, e.g. a custom JSON API or another
+/// format. Add emitters with [`SiteGenerator::add_emitter`](crate::generator::SiteGenerator::add_emitter);
+/// one only runs when its name is a key of `emitters` in the config, and gets that key's value
+/// as its options.
+pub trait OutputEmitter: Send + Sync {
+    /// The key enabling the emitter under `emitters` in the config.
+    fn name(&self) -> &str;
+
+    /// Files to write for `site`. Like every output they are only rewritten when their content
+    /// changes.
+    fn emit(&self, site: &SiteContext, options: &serde_json::Value) -> Result<Vec<Output>>;
+}
+
+impl std::fmt::Debug for dyn OutputEmitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OutputEmitter({})", self.name())
+    }
+}
+
+/// Check that an emitted `path` stays inside the output directory.
+pub(crate) fn check_path(emitter: &str, path: &Path) -> Result<()> {
+    if path.as_os_str().is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        bail!("Emitter '{}' wrote to {}, which is not a path inside the output directory", emitter, path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use crate::car;
+use crate::config::{Ens, EnsWallet};
+use anyhow::{bail, Context, Result};
+use colored::*;
+use std::process::Command;
+use tracing::info;
+
+/// ENS registry, at the same address on mainnet and the testnets.
+const REGISTRY: &str = "0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e";
+
+const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+
+/// EIP-1577 contenthash of an IPFS CID: the `ipfs-ns` multicodec followed by the CIDv1.
+pub fn contenthash(cid: &str) -> Result<String> {
+    let cid_bytes = car::cid_v1_bytes(cid).context(format!("'{}' is not a valid CID", cid))?;
+    let hex: String = cid_bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("0xe301{}", hex))
+}
+
+/// Point the contenthash of `ens.name` at `cid` by sending a `setContenthash` transaction to
+/// the name's resolver. Returns false when it already pointed there and nothing was sent.
+pub fn update(ens: &Ens, cid: &str) -> Result<bool> {
+    let contenthash = contenthash(cid)?;
+    let node = cast(&["namehash", &ens.name], None)?;
+
+    let resolver = cast(&["call", REGISTRY, "resolver(bytes32)(address)", &node], ens.rpc_url.as_deref())?;
+    if resolver.is_empty() || resolver == ZERO_ADDRESS {
+        bail!("{} has no resolver; set one in the ENS app first", ens.name);
+    }
+    let current = cast(&["call", &resolver, "contenthash(bytes32)(bytes)", &node], ens.rpc_url.as_deref())?;
+    if current.eq_ignore_ascii_case(&contenthash) {
+        return Ok(false);
+    }
+
+    let mut send = Command::new("cast");
+    send.args(["send", &resolver, "setContenthash(bytes32,bytes)", &node, &contenthash]);
+    if let Some(rpc_url) = &ens.rpc_url {
+        send.args(["--rpc-url", rpc_url]);
+    }
+    match &ens.wallet {
+        EnsWallet::Ledger => {
+            send.arg("--ledger");
+        }
+        EnsWallet::Trezor => {
+            send.arg("--trezor");
+        }
+        EnsWallet::Keystore(path) => {
+            send.args(["--keystore", path]);
+        }
+        EnsWallet::PrivateKeyEnv(var) => {
+            let key = std::env::var(var)
+                .ok()
+                .filter(|k| !k.is_empty())
+                .context(format!("Set {} to the private key of the account managing {}", var, ens.name))?;
+            // Through the environment, which cast reads, so the key never shows up in `ps`
+            send.env("ETH_PRIVATE_KEY", key.trim());
+        }
+    }
+    info!("{} cast send (setContenthash on {})", "Running".cyan(), resolver);
+    // Hardware wallets and keystore passwords prompt on the terminal
+    let status = send.status().context("Failed to run cast (is Foundry installed?)")?;
+    if !status.success() {
+        bail!("cast exited with {}", status);
+    }
+    Ok(true)
+}
+
+/// Run a read-only `cast` command and return its trimmed output.
+fn cast(args: &[&str], rpc_url: Option<&str>) -> Result<String> {
+    let mut cmd = Command::new("cast");
+    cmd.args(args);
+    if let Some(rpc_url) = rpc_url {
+        cmd.args(["--rpc-url", rpc_url]);
+    }
+    let output = cmd.output().context("Failed to run cast (is Foundry installed?)")?;
+    if !output.status.success() {
+        bail!("cast {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
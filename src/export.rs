@@ -0,0 +1,347 @@
+use crate::config::Config;
+use crate::generator::Post;
+use clap::ValueEnum;
+use regex::Regex;
+
+/// Formats `scribe export` writes a post in.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// HTML email with inline styles, plus a plain-text alternative
+    Email,
+    /// EPUB ebook of the selected posts
+    Epub,
+    /// PDF book of the selected posts, printed by a headless browser
+    Pdf,
+}
+
+/// A post as a newsletter: HTML with every style inlined, for pasting into Buttondown,
+/// Mailchimp and the like, and the plain-text part sent alongside it.
+pub struct Email {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+/// Styles for each element of a post body, inlined because email clients drop stylesheets.
+const STYLES: &[(&str, &str)] = &[
+    ("p", "margin: 0 0 20px; font-size: 18px; line-height: 1.6;"),
+    ("h1", "margin: 36px 0 16px; font-size: 28px; line-height: 1.25; font-weight: 600;"),
+    ("h2", "margin: 36px 0 16px; font-size: 24px; line-height: 1.3; font-weight: 600;"),
+    ("h3", "margin: 28px 0 12px; font-size: 20px; line-height: 1.3; font-weight: 600;"),
+    ("h4", "margin: 24px 0 12px; font-size: 18px; line-height: 1.3; font-weight: 600;"),
+    ("a", "color: #1a1a1a; text-decoration: underline;"),
+    ("blockquote", "margin: 0 0 20px; padding: 0 0 0 16px; border-left: 3px solid #d0d0d0; color: #555555;"),
+    ("pre", "margin: 0 0 20px; padding: 16px; background-color: #f4f4f4; border-radius: 4px; overflow-x: auto; font-size: 14px; line-height: 1.5; white-space: pre-wrap;"),
+    ("code", "font-family: Menlo, Consolas, 'Courier New', monospace; font-size: 0.9em;"),
+    ("img", "display: block; max-width: 100%; height: auto; border: 0;"),
+    ("ul", "margin: 0 0 20px; padding-left: 24px;"),
+    ("ol", "margin: 0 0 20px; padding-left: 24px;"),
+    ("li", "margin: 0 0 8px; font-size: 18px; line-height: 1.6;"),
+    ("hr", "border: 0; border-top: 1px solid #e0e0e0; margin: 32px 0;"),
+    ("table", "border-collapse: collapse; margin: 0 0 20px; width: 100%;"),
+    ("th", "border: 1px solid #e0e0e0; padding: 8px; text-align: left;"),
+    ("td", "border: 1px solid #e0e0e0; padding: 8px;"),
+    ("figure", "margin: 0 0 20px;"),
+    ("figcaption", "margin-top: 8px; font-size: 14px; color: #777777;"),
+];
+
+/// `post` as an email. Links and images point at the live site when `url` is set, and
+/// `::: raw` blocks, which can't run in a mail client, link to the post instead.
+pub fn email(config: &Config, post: &Post) -> Email {
+    let post_url = config.site_url().map(|site| config.trailing_slash.post_href(&site, &post.slug));
+    let mut content = without_raw_blocks(&post.html_content, post_url.as_deref());
+    if let Some(url) = &post_url {
+        content = absolute_links(&content, url);
+    }
+
+    let date = post.date.format("%B %-d, %Y").to_string();
+    let tldr = post.frontmatter.tldr.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    let mut body = format!(
+        "<h1 style=\"margin: 0 0 8px; font-size: 32px; line-height: 1.2; font-weight: 600;\">{}</h1>\n<p style=\"margin: 0 0 32px; font-family: Helvetica, Arial, sans-serif; font-size: 14px; color: #777777;\">{}</p>\n",
+        post.title, date
+    );
+    if let Some(tldr) = tldr {
+        body.push_str(&format!(
+            "<p style=\"margin: 0 0 28px; padding: 12px 16px; background-color: #f4f4f4; font-size: 16px; line-height: 1.5;\"><strong>TL;DR</strong> {}</p>\n",
+            escape(tldr)
+        ));
+    }
+    body.push_str(&inline_styles(&content));
+    if let Some(url) = &post_url {
+        body.push_str(&format!(
+            "\n<hr style=\"border: 0; border-top: 1px solid #e0e0e0; margin: 32px 0;\">\n<p style=\"margin: 0; font-family: Helvetica, Arial, sans-serif; font-size: 14px; color: #777777;\">Read this post on <a href=\"{}\" style=\"color: #777777; text-decoration: underline;\">{}</a>.</p>",
+            url,
+            escape(&config.title)
+        ));
+    }
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="{}">
+<head>
+<meta charset="UTF-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>{}</title>
+</head>
+<body style="margin: 0; padding: 0; background-color: #f4f4f4;">
+<table role="presentation" width="100%" cellpadding="0" cellspacing="0" border="0" style="background-color: #f4f4f4;">
+<tr><td align="center" style="padding: 24px 12px;">
+<table role="presentation" width="600" cellpadding="0" cellspacing="0" border="0" style="max-width: 600px; width: 100%; background-color: #ffffff;">
+<tr><td style="padding: 40px 32px; font-family: Georgia, 'Times New Roman', serif; color: #1a1a1a;">
+{}
+</td></tr>
+</table>
+</td></tr>
+</table>
+</body>
+</html>
+"#,
+        post.lang, post.title, body
+    );
+
+    let mut text = format!("{}\n{}\n\n{}\n\n", post.title, "=".repeat(post.title.chars().count()), date);
+    if let Some(tldr) = tldr {
+        text.push_str(&format!("TL;DR {}\n\n", tldr));
+    }
+    text.push_str(html_to_text(&content).trim());
+    if let Some(url) = &post_url {
+        text.push_str(&format!("\n\n---\nRead this post on {}: {}", config.title, url));
+    }
+    text.push('\n');
+
+    Email { subject: post.title.clone(), html, text }
+}
+
+/// `html` with each `::: raw` block placeholder replaced by a note pointing readers at the
+/// post on the web, at `post_url` when known.
+pub(crate) fn without_raw_blocks(html: &str, post_url: Option<&str>) -> String {
+    let placeholder = Regex::new(r#"<div data-raw-block="\d+"></div>"#).unwrap();
+    let note = match post_url {
+        Some(url) => format!("<p><em>This part of the post is interactive; <a href=\"{}\">see it on the web</a>.</em></p>", url),
+        None => "<p><em>This part of the post is interactive; see it on the web.</em></p>".to_string(),
+    };
+    placeholder.replace_all(html, note.as_str()).to_string()
+}
+
+/// Resolve relative `href` and `src` attributes against the page at `base`.
+pub(crate) fn absolute_links(html: &str, base: &str) -> String {
+    let Ok(base) = reqwest::Url::parse(base) else {
+        return html.to_string();
+    };
+    let attribute = Regex::new(r#"\b(href|src)="([^"]*)""#).unwrap();
+    attribute
+        .replace_all(html, |caps: &regex::Captures| {
+            let target = &caps[2];
+            // Absolute URLs, `mailto:` links and fragments (footnotes) stay as they are
+            if target.starts_with('#') || reqwest::Url::parse(target).is_ok() {
+                return caps[0].to_string();
+            }
+            match base.join(target) {
+                Ok(url) => format!("{}=\"{}\"", &caps[1], url),
+                Err(_) => caps[0].to_string(),
+            }
+        })
+        .to_string()
+}
+
+/// Add the email style of each element in `html` as its `style` attribute, unless it has one.
+fn inline_styles(html: &str) -> String {
+    let names: Vec<&str> = STYLES.iter().map(|(name, _)| *name).collect();
+    let tag = Regex::new(&format!(r"<({})(\s[^>]*)?>", names.join("|"))).unwrap();
+    tag.replace_all(html, |caps: &regex::Captures| {
+        let attributes = caps.get(2).map_or("", |m| m.as_str());
+        if attributes.contains("style=") {
+            return caps[0].to_string();
+        }
+        let style = STYLES.iter().find(|(name, _)| *name == &caps[1]).map_or("", |(_, style)| *style);
+        format!("<{} style=\"{}\"{}>", &caps[1], style, attributes)
+    })
+    .to_string()
+}
+
+/// Readable plain text of rendered post `html`: blank lines between blocks, underlined
+/// headings, bulleted and numbered lists, quoted blockquotes and link targets in brackets.
+pub fn html_to_text(html: &str) -> String {
+    let mut out = String::new();
+    // Open links (target and where their text starts), blockquotes and lists
+    let mut links: Vec<(String, usize)> = Vec::new();
+    let mut quotes: Vec<usize> = Vec::new();
+    let mut lists: Vec<Option<usize>> = Vec::new();
+    let mut heading_start = 0;
+    let mut pre = false;
+    let attribute = |tag: &str, name: &str| {
+        Regex::new(&format!(r#"\b{}="([^"]*)""#, name)).unwrap().captures(tag).map(|c| unescape(&c[1]))
+    };
+
+    let tex = Regex::new(r"(?s)<annotation[^>]*>(.*?)</annotation>").unwrap();
+
+    let mut rest = html;
+    while !rest.is_empty() {
+        let Some(tag_start) = rest.find('<') else {
+            push_text(&mut out, rest, pre);
+            break;
+        };
+        push_text(&mut out, &rest[..tag_start], pre);
+        let Some(tag_len) = rest[tag_start..].find('>') else {
+            push_text(&mut out, &rest[tag_start..], pre);
+            break;
+        };
+        let tag = &rest[tag_start + 1..tag_start + tag_len];
+        rest = &rest[tag_start + tag_len + 1..];
+        let closing = tag.starts_with('/');
+        let name: String = tag.trim_start_matches('/').chars().take_while(|c| c.is_ascii_alphanumeric()).collect::<String>().to_ascii_lowercase();
+
+        match (name.as_str(), closing) {
+            ("p" | "div" | "figure" | "table" | "section", _) => blank_line(&mut out),
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                blank_line(&mut out);
+                heading_start = out.len();
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => {
+                let length = out[heading_start..].trim().chars().count();
+                let underline = if name == "h1" || name == "h2" { "=" } else { "-" };
+                out.push('\n');
+                out.push_str(&underline.repeat(length));
+                blank_line(&mut out);
+            }
+            ("br", _) => out.push('\n'),
+            ("hr", _) => {
+                blank_line(&mut out);
+                out.push_str("---");
+                blank_line(&mut out);
+            }
+            ("ul", false) => {
+                new_line(&mut out);
+                lists.push(None);
+            }
+            ("ol", false) => {
+                new_line(&mut out);
+                let start = attribute(tag, "start").and_then(|s| s.parse().ok()).unwrap_or(1);
+                lists.push(Some(start));
+            }
+            ("ul" | "ol", true) => {
+                lists.pop();
+                if lists.is_empty() {
+                    blank_line(&mut out);
+                }
+            }
+            ("li", false) => {
+                new_line(&mut out);
+                out.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(number)) => {
+                        out.push_str(&format!("{}. ", number));
+                        *number += 1;
+                    }
+                    _ => out.push_str("- "),
+                }
+            }
+            ("tr", true) => new_line(&mut out),
+            ("td" | "th", true) => out.push_str("  "),
+            ("pre", false) => {
+                blank_line(&mut out);
+                pre = true;
+            }
+            ("pre", true) => {
+                pre = false;
+                blank_line(&mut out);
+            }
+            ("blockquote", false) => {
+                blank_line(&mut out);
+                quotes.push(out.len());
+            }
+            ("blockquote", true) => {
+                if let Some(start) = quotes.pop() {
+                    let quoted: Vec<String> = out[start..].trim().lines().map(|line| format!("> {}", line).trim_end().to_string()).collect();
+                    out.truncate(start);
+                    out.push_str(&quoted.join("\n"));
+                }
+                blank_line(&mut out);
+            }
+            ("a", false) => links.push((attribute(tag, "href").unwrap_or_default(), out.len())),
+            ("a", true) => {
+                if let Some((href, start)) = links.pop() {
+                    let text = out[start..].trim();
+                    if !href.is_empty() && !href.starts_with('#') && text != href {
+                        out.push_str(&format!(" ({})", href));
+                    }
+                }
+            }
+            ("img", _) => {
+                if let Some(alt) = attribute(tag, "alt").filter(|a| !a.is_empty()) {
+                    out.push_str(&format!("[{}]", alt));
+                }
+            }
+            // MathML reads as the TeX it came from
+            ("math", false) => {
+                let end = rest.find("</math>").unwrap_or(rest.len());
+                if let Some(caps) = tex.captures(&rest[..end]) {
+                    out.push_str(&unescape(&caps[1]));
+                }
+                rest = &rest[(end + "</math>".len()).min(rest.len())..];
+            }
+            ("script" | "style" | "iframe" | "svg", false) => {
+                let close = format!("</{}>", name);
+                let end = rest.find(&close).map_or(rest.len(), |end| end + close.len());
+                rest = &rest[end..];
+            }
+            _ => {}
+        }
+    }
+
+    let blank_lines = Regex::new(r"\n{3,}").unwrap();
+    blank_lines.replace_all(&out, "\n\n").trim().to_string()
+}
+
+/// Text between tags; outside `<pre>`, whitespace runs collapse to a space, and none starts a line.
+fn push_text(out: &mut String, text: &str, pre: bool) {
+    let text = unescape(text);
+    if pre {
+        out.push_str(&text);
+        return;
+    }
+    for (i, word) in text.split_whitespace().enumerate() {
+        let at_line_start = out.is_empty() || out.ends_with('\n');
+        let needs_space = (i > 0 || text.starts_with(char::is_whitespace)) && !at_line_start && !out.ends_with(' ');
+        if needs_space {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    if text.ends_with(char::is_whitespace) && !text.trim().is_empty() {
+        out.push(' ');
+    }
+}
+
+fn new_line(out: &mut String) {
+    let trimmed = out.trim_end_matches(' ').len();
+    out.truncate(trimmed);
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+fn blank_line(out: &mut String) {
+    new_line(out);
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+}
+
+/// `text` escaped for HTML and XML text and attribute values.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// `text` with the entities rendered HTML uses decoded, numeric ones included.
+pub(crate) fn unescape(text: &str) -> String {
+    let numeric = Regex::new(r"&#(x[0-9a-fA-F]+|[0-9]+);").unwrap();
+    let text = numeric.replace_all(text, |caps: &regex::Captures| {
+        let code = match caps[1].strip_prefix('x') {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => caps[1].parse().ok(),
+        };
+        code.and_then(char::from_u32).map(String::from).unwrap_or_default()
+    });
+    text.replace("&nbsp;", " ").replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&amp;", "&")
+}
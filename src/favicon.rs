@@ -0,0 +1,70 @@
+use futures_util::stream::{self, StreamExt};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Directory inside the output directory holding self-hosted favicons.
+pub const DIR: &str = "favicons";
+
+/// Path of the favicon for `host`, relative to the output directory.
+pub fn path(host: &str) -> String {
+    format!("{}/{}.ico", DIR, host)
+}
+
+/// Where the favicon for `host` is downloaded from. DuckDuckGo's icon service resolves
+/// `<link rel="icon">` and falls back to `/favicon.ico`.
+pub fn url(host: &str) -> String {
+    format!("https://icons.duckduckgo.com/ip3/{}.ico", host)
+}
+
+/// Host part of an absolute http(s) URL.
+pub fn host(url: &str) -> Option<&str> {
+    url.strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .and_then(|rest| rest.split(['/', '?', '#', ':']).next())
+        .filter(|host| !host.is_empty() && !host.contains(['\\', '"', '\'', '<', '>']))
+}
+
+/// Download favicons for `hosts` missing from `output_dir/favicons` (unless `offline`) and
+/// return the output-relative path of every host that has one.
+pub async fn cache(hosts: BTreeSet<String>, output_dir: &Path, offline: bool) -> BTreeMap<String, String> {
+    let dir = output_dir.join(DIR);
+    let missing: Vec<String> = hosts.iter().filter(|h| !output_dir.join(path(h)).exists()).cloned().collect();
+
+    if !offline && !missing.is_empty() && fs::create_dir_all(&dir).is_ok() {
+        let client = reqwest::Client::builder().timeout(Duration::from_secs(5)).build().unwrap_or_default();
+        stream::iter(missing)
+            .map(|host| {
+                let client = client.clone();
+                let target = output_dir.join(path(&host));
+                async move {
+                    let url = url(&host);
+                    let response = match client.get(&url).send().await {
+                        Ok(r) if r.status().is_success() => r,
+                        _ => return,
+                    };
+                    let is_image = response
+                        .headers()
+                        .get(reqwest::header::CONTENT_TYPE)
+                        .and_then(|v| v.to_str().ok())
+                        .is_some_and(|t| t.starts_with("image/"));
+                    if let (true, Ok(bytes)) = (is_image, response.bytes().await) {
+                        let _ = fs::write(target, bytes);
+                    }
+                }
+            })
+            .buffer_unordered(8)
+            .collect::<Vec<()>>()
+            .await;
+    }
+
+    hosts
+        .into_iter()
+        .filter(|h| output_dir.join(path(h)).exists())
+        .map(|h| {
+            let p = path(&h);
+            (h, p)
+        })
+        .collect()
+}
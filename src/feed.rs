@@ -0,0 +1,92 @@
+use crate::config::Config;
+use crate::export;
+use crate::generator::Post;
+
+/// RSS feed of each language's index, e.g. `feed.xml` and `nl/feed.xml`.
+pub const FILE: &str = "feed.xml";
+
+/// JSON Feed next to each RSS feed, e.g. `feed.json` and `nl/feed.json`.
+pub const JSON_FILE: &str = "feed.json";
+
+/// Newest posts a feed carries.
+const ITEMS: usize = 20;
+
+/// RSS 2.0 feed of the newest of `posts` (newest first) in the language `lang`, with absolute
+/// links under `site_url`. Nothing in it depends on when it was built, so an unchanged site
+/// produces the same bytes.
+pub fn render(config: &Config, posts: &[&Post], lang: &str, site_url: &str) -> String {
+    let home = format!("{}{}", site_url, config.languages.index_path(lang));
+    let posts = &posts[..posts.len().min(ITEMS)];
+    let last_build = posts.iter().map(|p| p.frontmatter.parsed_updated().unwrap_or(p.date)).max();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\">\n  <channel>\n");
+    xml.push_str(&format!("    <title>{}</title>\n", escape(&config.title)));
+    xml.push_str(&format!("    <link>{}</link>\n", escape(&home)));
+    xml.push_str(&format!("    <atom:link href=\"{}{}\" rel=\"self\" type=\"application/rss+xml\"/>\n", escape(&home), FILE));
+    xml.push_str(&format!("    <description>{}</description>\n", escape(config.description.as_deref().unwrap_or(&config.title))));
+    xml.push_str(&format!("    <language>{}</language>\n", escape(lang)));
+    if let Some(last_build) = last_build {
+        xml.push_str(&format!("    <lastBuildDate>{}</lastBuildDate>\n", last_build.to_rfc2822()));
+    }
+    for post in posts {
+        let link = config.trailing_slash.post_href(site_url, &post.slug);
+        xml.push_str("    <item>\n");
+        xml.push_str(&format!("      <title>{}</title>\n", escape(&post.title)));
+        xml.push_str(&format!("      <link>{}</link>\n", escape(&link)));
+        xml.push_str(&format!("      <guid isPermaLink=\"true\">{}</guid>\n", escape(&link)));
+        xml.push_str(&format!("      <pubDate>{}</pubDate>\n", post.date.to_rfc2822()));
+        if let Some(excerpt) = post.excerpt.as_deref().filter(|e| !e.trim().is_empty()) {
+            xml.push_str(&format!("      <description>{}</description>\n", escape(excerpt.trim())));
+        }
+        xml.push_str("    </item>\n");
+    }
+    xml.push_str("  </channel>\n</rss>\n");
+    xml
+}
+
+/// JSON Feed 1.1 of the same posts as [`render`], carrying each post's full HTML with links
+/// made absolute, so reader apps can show posts without visiting the site.
+pub fn render_json(config: &Config, posts: &[&Post], lang: &str, site_url: &str) -> String {
+    let home = format!("{}{}", site_url, config.languages.index_path(lang));
+    let posts = &posts[..posts.len().min(ITEMS)];
+    let items: Vec<serde_json::Value> = posts
+        .iter()
+        .map(|post| {
+            let link = config.trailing_slash.post_href(site_url, &post.slug);
+            let html = export::absolute_links(&export::without_raw_blocks(&post.html_content, Some(&link)), &link);
+            let mut item = serde_json::json!({
+                "id": link,
+                "url": link,
+                "title": post.title,
+                "content_html": html,
+                "date_published": post.date.to_rfc3339(),
+                "language": post.lang,
+            });
+            if let Some(updated) = post.frontmatter.parsed_updated() {
+                item["date_modified"] = updated.to_rfc3339().into();
+            }
+            if let Some(excerpt) = post.excerpt.as_deref().map(str::trim).filter(|e| !e.is_empty()) {
+                item["summary"] = excerpt.into();
+            }
+            if !post.tags().is_empty() {
+                item["tags"] = post.tags().into();
+            }
+            item
+        })
+        .collect();
+    let feed = serde_json::json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": config.title,
+        "home_page_url": home,
+        "feed_url": format!("{}{}", home, JSON_FILE),
+        "description": config.description.as_deref().unwrap_or(&config.title),
+        "language": lang,
+        "authors": [{ "name": config.author }],
+        "items": items,
+    });
+    format!("{}\n", serde_json::to_string_pretty(&feed).unwrap_or_default())
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,90 @@
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+/// Directory inside the output directory holding bundled fonts.
+pub const DIR: &str = "fonts";
+
+/// A web font face used by the stylesheet.
+pub struct Face {
+    pub family: &'static str,
+    /// Fontsource package name, also used for the file name
+    pub id: &'static str,
+    pub style: &'static str,
+    pub weight: u16,
+}
+
+/// Every face the stylesheet uses; the same set the Google Fonts links load.
+pub const FACES: &[Face] = &[
+    Face { family: "Crimson Text", id: "crimson-text", style: "normal", weight: 400 },
+    Face { family: "Crimson Text", id: "crimson-text", style: "italic", weight: 400 },
+    Face { family: "Crimson Text", id: "crimson-text", style: "normal", weight: 600 },
+    Face { family: "Inter", id: "inter", style: "normal", weight: 400 },
+    Face { family: "Inter", id: "inter", style: "normal", weight: 600 },
+    Face { family: "Inter", id: "inter", style: "normal", weight: 700 },
+];
+
+impl Face {
+    /// Path of the woff2 file, relative to the output directory.
+    pub fn path(&self) -> String {
+        format!("{}/{}-{}-{}.woff2", DIR, self.id, self.weight, self.style)
+    }
+
+    /// Latin subset from the Fontsource CDN.
+    pub fn url(&self) -> String {
+        format!(
+            "https://cdn.jsdelivr.net/fontsource/fonts/{}@latest/latin-{}-{}.woff2",
+            self.id, self.weight, self.style
+        )
+    }
+}
+
+/// Download the faces missing from `output_dir/fonts`. Nothing is downloaded when `offline`;
+/// missing faces then fall back to the stylesheet's local fonts.
+pub async fn bundle(output_dir: &Path, offline: bool) -> Result<()> {
+    let missing: Vec<&Face> = FACES.iter().filter(|f| !output_dir.join(f.path()).exists()).collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if offline {
+        anyhow::bail!("{} bundled font(s) not downloaded yet; build once without --offline", missing.len());
+    }
+
+    fs::create_dir_all(output_dir.join(DIR))
+        .context("Failed to create fonts directory")?;
+    let client = reqwest::Client::new();
+    for face in missing {
+        let url = face.url();
+        let bytes = client
+            .get(&url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .context(format!("Failed to fetch {}", url))?
+            .bytes()
+            .await?;
+        let target = output_dir.join(face.path());
+        fs::write(&target, bytes)
+            .context(format!("Failed to write {}", target.display()))?;
+        info!("{} {}", "Bundled font".green(), target.display());
+    }
+    Ok(())
+}
+
+/// `@font-face` rules for the bundled faces. URLs are relative to `style.css` at the site root.
+pub fn font_face_css() -> String {
+    FACES
+        .iter()
+        .map(|face| {
+            format!(
+                "@font-face {{\n  font-family: '{}';\n  font-style: {};\n  font-weight: {};\n  font-display: swap;\n  src: url('{}') format('woff2');\n}}\n\n",
+                face.family,
+                face.style,
+                face.weight,
+                face.path()
+            )
+        })
+        .collect()
+}
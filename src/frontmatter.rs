@@ -0,0 +1,494 @@
+use crate::config::Direction;
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Post frontmatter: the fields scribe understands, plus everything else in `extra`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Frontmatter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Raw date as authored; see [`Frontmatter::parsed_date`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    /// Raw last-updated date as authored; see [`Frontmatter::parsed_updated`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+    /// Short summary shown above the post
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tldr: Option<String>,
+    #[serde(default, deserialize_with = "string_or_list", skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Name of the series this post belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series: Option<String>,
+    /// Position within the series; posts without one follow, ordered by date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series_order: Option<i64>,
+    /// Language code of the post on a multilingual site, e.g. `nl`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// Text direction (`ltr` or `rtl`), overriding the one of the post's language
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<Direction>,
+    /// Collections the post is also listed in, each with a hub page at `/<collection>/`
+    #[serde(default, deserialize_with = "string_or_list", skip_serializing_if = "Vec::is_empty")]
+    pub also_in: Vec<String>,
+    /// Former slugs, which get pages redirecting to the post
+    #[serde(default, deserialize_with = "string_or_list", skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Set to false to leave the comment widget off this post
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comments: Option<bool>,
+    /// Page layout: `wide` to let the content span the window, `bare` for the content alone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<PageLayout>,
+    /// Stylesheets for this post only, relative to its file, copied next to its page
+    #[serde(default, deserialize_with = "string_or_list", skip_serializing_if = "Vec::is_empty")]
+    pub css: Vec<String>,
+    /// Scripts for this post only, relative to its file, copied next to its page
+    #[serde(default, deserialize_with = "string_or_list", skip_serializing_if = "Vec::is_empty")]
+    pub js: Vec<String>,
+    /// Unfinished post, left out of the site
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub draft: bool,
+    /// Published encrypted with the site's passphrase, behind a prompt to unlock it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub private: bool,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl Frontmatter {
+    /// Build from a parsed key/value map, reading each field on its own: known fields with
+    /// unexpected types are kept in `extra`, leaving the rest readable, and [`validate`] reports
+    /// them by key.
+    pub fn from_map(map: serde_json::Map<String, serde_json::Value>) -> Self {
+        let mut valid = serde_json::Map::new();
+        let mut invalid = Vec::new();
+        for (key, value) in map {
+            if field_error(&key, &value).is_some() {
+                invalid.push((key, value));
+            } else {
+                valid.insert(key, value);
+            }
+        }
+        let mut frontmatter: Frontmatter = serde_json::from_value(serde_json::Value::Object(valid)).unwrap_or_default();
+        frontmatter.extra.extend(invalid);
+        frontmatter
+    }
+
+    /// The `date` field as RFC 3339 or a plain `YYYY-MM-DD` day (midnight UTC).
+    pub fn parsed_date(&self) -> Option<DateTime<Utc>> {
+        parse_date(self.date.as_deref()?)
+    }
+
+    /// The `updated` field, parsed like `date`.
+    pub fn parsed_updated(&self) -> Option<DateTime<Utc>> {
+        parse_date(self.updated.as_deref()?)
+    }
+}
+
+/// How a post's page is laid out, from its `layout:` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageLayout {
+    /// The site's usual page
+    Default,
+    /// The usual page with the content spanning the window
+    Wide,
+    /// Only the post's content, without the site's header, footer and navigation
+    Bare,
+}
+
+/// A problem with the frontmatter of a post, at a line of the whole file.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub path: PathBuf,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.path.display(), self.line, self.message)
+    }
+}
+
+/// Check the frontmatter of `content` (read from `path`): a block that doesn't parse, known
+/// fields of the wrong type, dates that are neither RFC 3339 nor `YYYY-MM-DD`, and a missing
+/// title. Content opening with `---` followed by prose is a horizontal rule, not a broken block.
+pub fn validate(path: &Path, content: &str) -> Vec<Problem> {
+    let problem = |line: usize, message: String| Problem { path: path.to_path_buf(), line, message };
+    let lines: Vec<&str> = content.lines().collect();
+    let Some(block) = find_block(&lines) else {
+        let Some(format) = opening_format(&lines) else {
+            return Vec::new();
+        };
+        let (start, closing) = match format {
+            Format::Yaml => (1, (1..lines.len()).find(|&i| matches!(lines[i].trim_end(), "---" | "..."))),
+            Format::Toml => (1, (1..lines.len()).find(|&i| lines[i].trim_end() == "+++")),
+            Format::Json => (0, (0..lines.len()).find(|&i| lines[i].trim_end() == "}").map(|i| i + 1)),
+        };
+        let Some(end) = closing else {
+            return vec![problem(1, format!("frontmatter is never closed with {}", format.closing()))];
+        };
+        let message = match parse(format, &between(&lines, start, end)) {
+            Err(e) => {
+                // Positions in the message count from the start of the block, like `line`
+                let position = Regex::new(r"line (\d+) column").unwrap();
+                let message = position.replace_all(&e.message, |c: &regex::Captures| {
+                    format!("line {} column", c[1].parse::<usize>().map_or(0, |n| n + start))
+                });
+                return vec![problem(e.line.map_or(1, |l| l + start), format!("frontmatter is not valid {}: {}", format.name(), message))];
+            }
+            Ok(_) => format!("frontmatter must be a {} of fields", format.table()),
+        };
+        return vec![problem(1, message)];
+    };
+
+    let map = match parse(block.format, &block.text(&lines)) {
+        Ok(serde_json::Value::Object(map)) => map,
+        Ok(_) => serde_json::Map::new(),
+        Err(e) => return vec![problem(1, format!("frontmatter can't be read: {}", e.message))],
+    };
+    let line_of = |key: &str| block.field_line(&lines, key).map_or(1, |i| i + 1);
+    let mut problems: Vec<Problem> = map
+        .iter()
+        .filter_map(|(key, value)| field_error(key, value).map(|e| problem(line_of(key), format!("`{}`: {}", key, e))))
+        .collect();
+    for key in ["date", "updated"] {
+        if let Some(raw) = map.get(key).and_then(|v| v.as_str()).filter(|raw| parse_date(raw).is_none()) {
+            problems.push(problem(line_of(key), format!("`{}`: \"{}\" is neither RFC 3339 nor YYYY-MM-DD", key, raw)));
+        }
+    }
+    if map.get("title").is_none_or(|t| t.is_null() || t.as_str().is_some_and(|t| t.trim().is_empty())) {
+        problems.push(problem(1, "missing `title`; the file name is used instead".to_string()));
+    }
+    problems.sort_by_key(|p| p.line);
+    problems
+}
+
+/// Format of the block `lines` seem meant to open with, even if it doesn't parse: `+++`, a
+/// JSON object, or `---` followed by what reads as a YAML field rather than prose.
+fn opening_format(lines: &[&str]) -> Option<Format> {
+    let first = lines.first()?.trim_start_matches('\u{feff}').trim_end();
+    let field = Regex::new(r"^[A-Za-z_][\w-]*\s*:(\s|$)").unwrap();
+    match first {
+        "+++" => Some(Format::Toml),
+        "---" => lines[1..].iter().find(|l| !l.trim().is_empty()).filter(|l| field.is_match(l)).map(|_| Format::Yaml),
+        _ if is_json_opening(first) => Some(Format::Json),
+        _ => None,
+    }
+}
+
+/// `{` alone or followed by a quoted key, unlike template syntax such as `{{< note >}}`.
+fn is_json_opening(line: &str) -> bool {
+    line == "{" || line.strip_prefix('{').is_some_and(|rest| rest.trim_start().starts_with('"'))
+}
+
+/// Why `value` can't be the frontmatter field `key`, if it can't.
+fn field_error(key: &str, value: &serde_json::Value) -> Option<String> {
+    let single = serde_json::Map::from_iter([(key.to_string(), value.clone())]);
+    serde_json::from_value::<Frontmatter>(serde_json::Value::Object(single)).err().map(|e| e.to_string())
+}
+
+/// An RFC 3339 timestamp or a plain `YYYY-MM-DD` day (midnight UTC).
+pub fn parse_date(raw: &str) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+    DateTime::parse_from_rfc3339(raw)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|dt| dt.and_utc())
+        })
+}
+
+/// Syntax of a frontmatter block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// Between `---` fences
+    Yaml,
+    /// Between `+++` fences
+    Toml,
+    /// A JSON object opening the file
+    Json,
+}
+
+impl Format {
+    fn name(self) -> &'static str {
+        match self {
+            Format::Yaml => "YAML",
+            Format::Toml => "TOML",
+            Format::Json => "JSON",
+        }
+    }
+
+    fn table(self) -> &'static str {
+        match self {
+            Format::Yaml => "mapping",
+            Format::Toml => "table",
+            Format::Json => "object",
+        }
+    }
+
+    fn closing(self) -> &'static str {
+        match self {
+            Format::Yaml => "---",
+            Format::Toml => "+++",
+            Format::Json => "}",
+        }
+    }
+}
+
+/// Where a frontmatter block sits among the lines of a file.
+struct Block {
+    format: Format,
+    /// First line of the block's text: after the opening fence, or the JSON object's `{`
+    start: usize,
+    /// Line after the block's text: the closing fence, or the line after the JSON object
+    end: usize,
+    /// First line of the body
+    body: usize,
+}
+
+impl Block {
+    fn text(&self, lines: &[&str]) -> String {
+        between(lines, self.start, self.end)
+    }
+
+    /// Index of the line setting the top-level `key`.
+    fn field_line(&self, lines: &[&str], key: &str) -> Option<usize> {
+        (self.start..self.fields_end(lines)).find(|&i| field_value(self.format, lines[i], key).is_some())
+    }
+
+    /// Line after the top-level fields: in TOML, later fields belong to the first `[table]`.
+    fn fields_end(&self, lines: &[&str]) -> usize {
+        match self.format {
+            Format::Toml => (self.start..self.end).find(|&i| lines[i].trim_start().starts_with('[')).unwrap_or(self.end),
+            _ => self.end,
+        }
+    }
+}
+
+/// The frontmatter block `lines` start with, if any.
+///
+/// A YAML block must open on the first line with `---` and closes at the first `---` or `...`
+/// line (at column 0, so indented ones inside block scalars don't count) after which the lines
+/// in between parse as a YAML mapping. A document that merely opens with a horizontal rule has
+/// prose there instead, so it has no frontmatter. TOML blocks are fenced by `+++` lines the same
+/// way, and a JSON block is the object opening the file, up to the first line that completes it.
+fn find_block(lines: &[&str]) -> Option<Block> {
+    let first = lines.first()?.trim_start_matches('\u{feff}').trim_end();
+    let fenced = |format: Format, fences: &[&str]| {
+        (1..lines.len())
+            .filter(|&i| fences.contains(&lines[i].trim_end()))
+            .find(|&i| is_table(format, &between(lines, 1, i)))
+            .map(|end| Block { format, start: 1, end, body: end + 1 })
+    };
+    match first {
+        "---" => fenced(Format::Yaml, &["---", "..."]),
+        "+++" => fenced(Format::Toml, &["+++"]),
+        _ if is_json_opening(first) => (0..lines.len())
+            .filter(|&i| lines[i].trim_end().ends_with('}'))
+            .find(|&i| is_table(Format::Json, &between(lines, 0, i + 1)))
+            .map(|i| Block { format: Format::Json, start: 0, end: i + 1, body: i + 1 }),
+        _ => None,
+    }
+}
+
+/// Lines `start..end` as one text, without the byte order mark.
+fn between(lines: &[&str], start: usize, end: usize) -> String {
+    lines[start..end]
+        .iter()
+        .map(|l| l.trim_start_matches('\u{feff}').trim_end_matches(['\r', '\n']))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_table(format: Format, text: &str) -> bool {
+    matches!(parse(format, text), Ok(serde_json::Value::Object(_) | serde_json::Value::Null))
+}
+
+/// Why a block's text doesn't parse, at a 1-based line of the text when known.
+struct ParseError {
+    line: Option<usize>,
+    message: String,
+}
+
+/// The block's text as a JSON value, so every format reads into the same map. TOML dates
+/// become strings like YAML's.
+fn parse(format: Format, text: &str) -> Result<serde_json::Value, ParseError> {
+    match format {
+        Format::Yaml => serde_yaml::from_str(text).map_err(|e| ParseError { line: e.location().map(|l| l.line()), message: e.to_string() }),
+        Format::Toml => text
+            .parse::<toml::Value>()
+            .map(toml_to_json)
+            .map_err(|e| ParseError { line: e.line_col().map(|(line, _)| line + 1), message: e.to_string() }),
+        Format::Json => serde_json::from_str(text).map_err(|e| ParseError { line: Some(e.line()), message: e.to_string() }),
+    }
+}
+
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => serde_json::Value::String(s),
+        toml::Value::Integer(i) => i.into(),
+        toml::Value::Float(f) => f.into(),
+        toml::Value::Boolean(b) => b.into(),
+        toml::Value::Datetime(d) => serde_json::Value::String(d.to_string()),
+        toml::Value::Array(items) => items.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => serde_json::Value::Object(table.into_iter().map(|(k, v)| (k, toml_to_json(v))).collect()),
+    }
+}
+
+/// The value text of `line` if it sets the top-level `key` in a block of `format`.
+fn field_value<'a>(format: Format, line: &'a str, key: &str) -> Option<&'a str> {
+    match format {
+        Format::Yaml => line.strip_prefix(key)?.strip_prefix(':'),
+        Format::Toml => line.strip_prefix(key)?.trim_start().strip_prefix('='),
+        Format::Json => line.trim_start().strip_prefix(&format!("\"{}\"", key))?.trim_start().strip_prefix(':'),
+    }
+}
+
+/// Whether `content` opens with a frontmatter block.
+pub fn has_block(content: &str) -> bool {
+    find_block(&content.lines().collect::<Vec<_>>()).is_some()
+}
+
+/// Separate the frontmatter of `content` from its Markdown body. Without a frontmatter block
+/// the whole of `content` is the body. YAML, TOML and JSON blocks read into the same fields;
+/// those that can't be read are left at their defaults.
+pub fn split(content: &str) -> (Option<Frontmatter>, String) {
+    let (map, body) = split_fields(content);
+    (map.map(Frontmatter::from_map), body)
+}
+
+/// Like [`split`], but with the frontmatter's fields as they were written, whatever their names
+/// and types; a block that can't be read has none.
+pub fn split_fields(content: &str) -> (Option<serde_json::Map<String, serde_json::Value>>, String) {
+    let lines: Vec<&str> = content.lines().collect();
+    let Some(block) = find_block(&lines) else {
+        return (None, content.to_string());
+    };
+    let map = match parse(block.format, &block.text(&lines)) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    (Some(map), lines[block.body..].join("\n"))
+}
+
+/// Raw value of the top-level `key` in the frontmatter of `content`, without quotes.
+pub fn raw_field<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+    let lines: Vec<&str> = content.lines().collect();
+    let block = find_block(&lines)?;
+    let line = lines[block.field_line(&lines, key)?];
+    field_value(block.format, line, key).map(|v| v.trim().trim_end_matches(',').trim_end().trim_matches(['"', '\'']))
+}
+
+/// Set the top-level `key` to `value` in the frontmatter of `content`. Only that line changes
+/// (keeping its quote style, with `value` escaped to match), or a new line is added at the end
+/// of the block's top-level fields; the rest of the file is returned byte for byte.
+pub fn set_field(content: &str, key: &str, value: &str) -> Result<String> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let Some(block) = find_block(&lines) else {
+        anyhow::bail!("no frontmatter block");
+    };
+    if block.format == Format::Json {
+        return set_raw(content, key, &serde_json::Value::String(value.to_string()).to_string());
+    }
+    let single_quoted = block
+        .field_line(&lines, key)
+        .and_then(|i| field_value(block.format, lines[i], key))
+        .is_some_and(|v| v.trim_start().starts_with('\''));
+    let quoted = match block.format {
+        Format::Yaml if single_quoted => format!("'{}'", value.replace('\'', "''")),
+        // TOML literal strings can't hold a `'` at all
+        Format::Toml if single_quoted && !value.contains(['\'', '\n']) => format!("'{}'", value),
+        _ => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+    };
+    set_raw(content, key, &quoted)
+}
+
+/// Like `set_field`, but with `raw` written as it is, e.g. `true` or `["a", "b"]` (which read
+/// the same in YAML, TOML and JSON).
+pub fn set_raw(content: &str, key: &str, raw: &str) -> Result<String> {
+    let mut lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let Some(block) = find_block(&lines) else {
+        anyhow::bail!("no frontmatter block");
+    };
+    let newline = if lines[0].ends_with("\r\n") { "\r\n" } else { "\n" };
+    let existing = block.field_line(&lines, key);
+    let (index, line) = match block.format {
+        Format::Yaml => (existing.unwrap_or(block.end), format!("{}: {}{}", key, raw, newline)),
+        // Before the blank lines that set off the first `[table]`, if any
+        Format::Toml => {
+            let mut end = block.fields_end(&lines);
+            while end > block.start && lines[end - 1].trim().is_empty() {
+                end -= 1;
+            }
+            (existing.unwrap_or(end), format!("{} = {}{}", key, raw, newline))
+        }
+        Format::Json => match existing {
+            Some(i) => {
+                let indent = &lines[i][..lines[i].len() - lines[i].trim_start().len()];
+                let comma = if lines[i].trim_end().ends_with(',') { "," } else { "" };
+                (i, format!("{}\"{}\": {}{}{}", indent, key, raw, comma, newline))
+            }
+            // Added first in the object, so a comma follows unless the object was empty
+            None => {
+                let empty = matches!(parse(Format::Json, &block.text(&lines)), Ok(serde_json::Value::Object(map)) if map.is_empty());
+                let indent = lines.get(1).map_or("  ", |l| &l[..l.len() - l.trim_start().len()]);
+                let indent = if indent.is_empty() { "  " } else { indent };
+                (1, format!("{}\"{}\": {}{}{}", indent, key, raw, if empty { "" } else { "," }, newline))
+            }
+        },
+    };
+    match existing {
+        Some(_) => lines[index] = &line,
+        None => lines.insert(index, &line),
+    }
+
+    let updated = lines.concat();
+    let expected_body = if existing.is_some() { block.body } else { block.body + 1 };
+    let updated_lines: Vec<&str> = updated.split_inclusive('\n').collect();
+    match find_block(&updated_lines) {
+        Some(b) if b.format == block.format && b.body == expected_body => Ok(updated),
+        _ => {
+            let end = if block.format == Format::Json { expected_body } else { expected_body - 1 };
+            let error = parse(block.format, &between(&updated_lines, block.start, end.min(updated_lines.len())))
+                .err()
+                .map_or(format!("not a {}", block.format.table()), |e| e.message);
+            anyhow::bail!("frontmatter would not be valid {}: {}", block.format.name(), error);
+        }
+    }
+}
+
+/// Accept tags as a YAML list or a comma-separated string.
+fn string_or_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tags {
+        List(Vec<String>),
+        Joined(String),
+    }
+
+    let tags = match Option::<Tags>::deserialize(deserializer)? {
+        Some(Tags::List(items)) => items,
+        Some(Tags::Joined(list)) => list.split(',').map(str::to_string).collect(),
+        None => Vec::new(),
+    };
+    Ok(tags
+        .into_iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect())
+}
@@ -0,0 +1,101 @@
+use crate::config::{Config, TrailingSlash};
+use crate::generator::NOT_FOUND_PAGE;
+use regex::Regex;
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Redirect rules for hosts that read them: IPFS subdomain and DNSLink gateways, Netlify and
+/// Cloudflare Pages.
+pub const FILE: &str = "_redirects";
+
+/// An `href` or `src` attribute, compiled once for every page [`relativize`] rewrites.
+static ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"\b(href|src)="([^"]*)""#).unwrap());
+
+/// A line of `_redirects`: requests for `from` answered with `to` and `status`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub from: String,
+    pub to: String,
+    pub status: u16,
+}
+
+/// Rules for a site's moved and removed pages: each `(alias, slug)` in `moved` answers with a
+/// 301 to the post (the home page for an empty slug) and each of `gone` with a 410. Without
+/// trailing slashes, `pages` are rewritten to their `.html` files. Anything else gets the 404
+/// page. Paths start at the site's base path.
+pub fn rules(config: &Config, moved: &[(String, String)], gone: &[String], pages: &[String]) -> Vec<Rule> {
+    let base = config.base_path();
+    let policy = config.trailing_slash;
+    let mut rules: Vec<Rule> = moved
+        .iter()
+        .map(|(alias, slug)| {
+            // Without a post to go to, the home page
+            let to = if slug.is_empty() { base.clone() } else { policy.post_href(&base, slug) };
+            Rule { from: policy.post_href(&base, alias), to, status: 301 }
+        })
+        .collect();
+    rules.extend(gone.iter().map(|slug| Rule { from: policy.post_href(&base, slug), to: format!("{}{}", base, NOT_FOUND_PAGE), status: 410 }));
+    if policy == TrailingSlash::Never {
+        rules.extend(pages.iter().map(|page| Rule { from: format!("{}{}", base, page), to: format!("{}{}.html", base, page), status: 200 }));
+    }
+    rules.push(Rule { from: format!("{}*", base), to: format!("{}{}", base, NOT_FOUND_PAGE), status: 404 });
+    rules
+}
+
+/// `rules` as a `_redirects` file; hosts apply the first rule that matches.
+pub fn render(rules: &[Rule]) -> String {
+    let width = rules.iter().map(|r| r.from.len()).max().unwrap_or(0);
+    rules.iter().map(|r| format!("{:width$}  {}  {}\n", r.from, r.to, r.status, width = width)).collect()
+}
+
+/// Rewrite the `href` and `src` attributes of the page at `page` (relative to the output
+/// directory) so that links within the site are relative to the page, and links to directory
+/// pages (those `is_dir` says have an `index.html`) end in a slash. The site then works the same
+/// from a domain root, a subdirectory or a path gateway's `/ipfs/<cid>/`.
+pub fn relativize(html: &str, page: &Path, base_path: &str, is_dir: impl Fn(&Path) -> bool) -> String {
+    let page_dir = page.parent().unwrap_or(Path::new(""));
+    let up = "../".repeat(page_dir.components().count());
+    ATTRIBUTE
+        .replace_all(html, |caps: &regex::Captures| {
+            let value = &caps[2];
+            if value.is_empty() || value.starts_with('#') || value.starts_with("//") || has_scheme(value) {
+                return caps[0].to_string();
+            }
+            let split = value.find(['?', '#']).unwrap_or(value.len());
+            let (path, rest) = value.split_at(split);
+            let mut path = match path.strip_prefix('/') {
+                Some(site_path) => {
+                    let site_path = format!("/{}", site_path);
+                    let site_path = site_path.strip_prefix(base_path).or_else(|| (site_path == base_path.trim_end_matches('/')).then_some("")).unwrap_or(&site_path[1..]);
+                    let relative = format!("{}{}", up, site_path);
+                    if relative.is_empty() { "./".to_string() } else { relative }
+                }
+                None => path.to_string(),
+            };
+            if !path.is_empty() && !path.ends_with('/') && is_dir(&normalize(&page_dir.join(&path))) {
+                path.push('/');
+            }
+            format!("{}=\"{}{}\"", &caps[1], path, rest)
+        })
+        .to_string()
+}
+
+/// `mailto:`, `https:`, `data:` and the like.
+fn has_scheme(value: &str) -> bool {
+    value.split_once(':').is_some_and(|(scheme, _)| !scheme.is_empty() && !scheme.contains('/') && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c)))
+}
+
+/// `path` with `.` and `..` resolved, as a browser does.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::Normal(part) => out.push(part),
+            _ => {}
+        }
+    }
+    out
+}
@@ -1,15 +1,48 @@
-use crate::config::Config;
+use crate::annotations;
+use crate::api::{self, BuildFormat};
+use crate::assets::{self, AssetRef, AssetReport};
+use crate::avatar;
+use crate::cache::{self, CacheStats, HitRate, UrlMetaCache};
+use crate::config::{Config, InitialsMode, InputFormat, InitialsOptions, MathMode, MermaidMode, TrashedPage};
+use crate::emit::{self, OutputEmitter, SiteContext};
+use crate::favicon;
+use crate::feed;
+use crate::fonts;
+use crate::gateway;
+use crate::frontmatter::{self, Frontmatter};
+use crate::graph::{GraphFormat, LinkGraph};
+use crate::images;
+use crate::initials;
+use crate::lint::{self, MissingAlt};
+use crate::lock::{self, Lockfile, Resource, ResourceKind};
+use crate::markdown::{self, CodeFences};
+use crate::markup;
+use crate::math;
+use crate::mermaid;
+use crate::minify;
+use crate::private;
+use crate::progress::Progress;
+use crate::pwa;
+use crate::quoteback;
+use crate::sitemap;
+use crate::reading;
+use crate::summarize;
 use crate::templates;
+use crate::trails::{self, Trail};
+use crate::trash;
+use crate::webmention;
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, Utc};
 use colored::*;
-use markdown::to_html;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use serde_yaml;
-use std::collections::{HashMap, HashSet};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fs;
-use std::path::{Path};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, error, info, warn};
 use walkdir::WalkDir;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,13 +55,470 @@ pub struct Post {
     pub content: String,
     pub html_content: String,
     pub first_letter: Option<char>,
-    pub frontmatter: HashMap<String, serde_json::Value>,
+    pub frontmatter: Frontmatter,
+    /// Language code, from `lang:` or the language directory the post is in
+    #[serde(default)]
+    pub lang: String,
+    /// Markdown file the post was read from
+    #[serde(skip)]
+    pub source: PathBuf,
+    /// `::: raw` blocks, in the order of their placeholders in `html_content`
+    #[serde(default)]
+    pub raw_blocks: Vec<markdown::RawBlock>,
+    /// Words in the post, counting characters for Chinese and Japanese
+    #[serde(default)]
+    pub words: usize,
+    /// Links in the post body, used for backlinks and the link graph
+    #[serde(default)]
+    pub links: Vec<markdown::Link>,
+    /// Sanitized slugs of the posts `links` point at, so backlink lookups skip re-parsing hrefs
+    #[serde(skip)]
+    linked_slugs: HashSet<String>,
+    /// Webmentions received for the post, filled in by full builds when `webmention` is set
+    #[serde(default)]
+    pub webmentions: Vec<webmention::Mention>,
+    /// A private post's content, set aside by [`Post::hide`] until its page is rendered
+    #[serde(skip)]
+    hidden: Option<Arc<Post>>,
+}
+
+impl Post {
+    /// The first link in this post that points at `other`, by its sanitized or original slug.
+    pub fn link_to(&self, other: &Post) -> Option<&markdown::Link> {
+        if self.slug == other.slug || !self.linked_slugs.contains(&other.slug) {
+            return None;
+        }
+        self.links.iter().find(|link| link_slug(&link.href).is_some_and(|slug| slug == other.slug))
+    }
+
+    /// Whether this post links to `other`.
+    pub fn links_to(&self, other: &Post) -> bool {
+        self.link_to(other).is_some()
+    }
+
+    /// Whether the post is published encrypted, by `private: true`. A `private:` that isn't a
+    /// boolean counts too, so a mistyped value doesn't publish the post in the clear.
+    pub fn is_private(&self) -> bool {
+        self.frontmatter.private || self.frontmatter.extra.contains_key("private")
+    }
+
+    /// Set the content aside, leaving the title, date and other public fields, so nothing but
+    /// the sealed page carries it: not listings, feeds, the API, backlinks or the link graph. An
+    /// excerpt is kept only when written in the frontmatter.
+    pub fn hide(&mut self) {
+        if self.hidden.is_some() {
+            return;
+        }
+        self.hidden = Some(Arc::new(self.clone()));
+        self.content.clear();
+        self.html_content.clear();
+        self.raw_blocks.clear();
+        self.links.clear();
+        self.linked_slugs.clear();
+        self.excerpt = self.frontmatter.excerpt.clone();
+    }
+
+    /// The post with any content set aside by [`Post::hide`] back in place.
+    pub fn revealed(&self) -> Post {
+        let Some(hidden) = &self.hidden else {
+            return self.clone();
+        };
+        Post {
+            excerpt: hidden.excerpt.clone(),
+            content: hidden.content.clone(),
+            html_content: hidden.html_content.clone(),
+            raw_blocks: hidden.raw_blocks.clone(),
+            links: hidden.links.clone(),
+            linked_slugs: hidden.linked_slugs.clone(),
+            hidden: None,
+            ..self.clone()
+        }
+    }
+
+    /// The post's own stylesheets and scripts, from its `css:` and `js:` fields.
+    pub fn assets(&self) -> impl Iterator<Item = &String> {
+        self.frontmatter.css.iter().chain(&self.frontmatter.js)
+    }
+
+    /// Where a local `css:` or `js:` file of this post is written, relative to the output
+    /// directory: under the post's slug, at its path from the post's file. None for remote
+    /// addresses and for paths leaving the post's directory.
+    pub fn asset_path(&self, asset: &str) -> Option<String> {
+        if is_remote_asset(asset) {
+            return None;
+        }
+        let parts: Vec<&str> = asset.trim_start_matches("./").split('/').collect();
+        if parts.iter().any(|part| matches!(*part, "" | "." | "..")) {
+            return None;
+        }
+        Some(format!("{}/{}", self.slug, parts.join("/")))
+    }
+
+    /// Tags from frontmatter, given either as a list or a comma-separated string.
+    pub fn tags(&self) -> &[String] {
+        &self.frontmatter.tags
+    }
+
+    /// Slug without the language prefix, shared by translations of the same post.
+    pub fn translation_key(&self) -> &str {
+        self.slug.split_once('/').map_or(&self.slug, |(_, slug)| slug)
+    }
+
+    /// Estimated minutes to read the post.
+    pub fn reading_minutes(&self) -> usize {
+        reading::minutes(self.words, &self.lang)
+    }
+
+    /// Collections from `also_in`, as written.
+    pub fn collections(&self) -> impl Iterator<Item = &str> {
+        self.frontmatter.also_in.iter().map(|name| name.trim()).filter(|name| !name.is_empty())
+    }
+
+    /// Name of the series this post belongs to, if any.
+    pub fn series(&self) -> Option<&str> {
+        self.frontmatter.series.as_deref().map(str::trim).filter(|s| !s.is_empty())
+    }
+}
+
+/// Whether a `css:` or `js:` entry is an address to link as it is rather than a file to copy.
+pub fn is_remote_asset(asset: &str) -> bool {
+    asset.starts_with("//") || asset.contains("://")
+}
+
+/// Posts in the series `name`, ordered by `series_order` and then by date (oldest first).
+pub fn series_members<'a>(posts: &'a [Post], name: &str) -> Vec<&'a Post> {
+    let mut members: Vec<&Post> = posts.iter().filter(|p| p.series() == Some(name)).collect();
+    members.sort_by_key(|p| (p.frontmatter.series_order.is_none(), p.frontmatter.series_order, p.date));
+    members
+}
+
+/// Sanitized post slug an internal `href` points at, or `None` for external links and anchors.
+/// Accepts `/slug/`, `./slug`, `../slug.html`, `slug.md`, `../nl/slug/` and similar.
+pub(crate) fn link_slug(href: &str) -> Option<String> {
+    if href.contains("://") || href.starts_with('#') || href.starts_with("mailto:") || href.starts_with("data:") {
+        return None;
+    }
+    let mut path = href.split(['?', '#']).next().unwrap_or_default();
+    while let Some(rest) = path.strip_prefix("../").or_else(|| path.strip_prefix("./")).or_else(|| path.strip_prefix('/')) {
+        path = rest;
+    }
+    let path = path.trim_end_matches('/');
+    let path = ["/index.html", ".html", ".md"].iter().find_map(|ext| path.strip_suffix(ext)).unwrap_or(path);
+    if path.is_empty() {
+        return None;
+    }
+    Some(path.split('/').map(sanitize_slug).collect::<Vec<_>>().join("/"))
+}
+
+/// Whether `path` is a post: a Markdown file, a file in one of the enabled `formats`, or an
+/// HTML file opening with frontmatter (so demo pages and other HTML kept beside the posts
+/// aren't published as posts).
+pub fn is_post_file(path: &Path, formats: &[InputFormat]) -> bool {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("md") => true,
+        Some("html") => fs::read_to_string(path).is_ok_and(|content| frontmatter::has_block(&content)),
+        _ => InputFormat::of(path).is_some_and(|format| formats.contains(&format)),
+    }
+}
+
+/// Whether the post at `path` is hand-written HTML rather than Markdown.
+pub fn is_html_post(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "html")
+}
+
+/// Post files under `posts_dir` (see [`is_post_file`]), in path order, leaving out the trash.
+pub fn post_files(posts_dir: &Path, formats: &[InputFormat]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = WalkDir::new(posts_dir)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != trash::DIR)
+        .filter_map(|e| e.ok())
+        .map(|e| e.into_path())
+        .filter(|p| is_post_file(p, formats))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Pages in `output_dir` (relative to it) that aren't among the `produced` outputs. Only HTML,
+/// post JSON beside an HTML page, sitemap chunks, the (fingerprinted) stylesheet and the files
+/// one build format writes and the other doesn't (feeds, sitemap, redirects and the content
+/// JSON) count, so the initials cache, fonts, images and other assets are never stale.
+fn stale_pages(output_dir: &Path, produced: &HashSet<PathBuf>) -> Vec<PathBuf> {
+    const GENERATED: [&str; 5] = [sitemap::FILE, gateway::FILE, api::POSTS_FILE, api::TAGS_FILE, api::GRAPH_FILE];
+    let is_feed = |relative: &Path| relative.components().count() <= 2 && relative.file_name().is_some_and(|name| name == feed::FILE || name == feed::JSON_FILE);
+    let is_page = |relative: &Path| {
+        relative.extension().is_some_and(|ext| ext == "html")
+            || is_feed(relative)
+            || (relative.parent() == Some(Path::new("")) && relative.to_str().is_some_and(|name| GENERATED.contains(&name)))
+            || (relative.extension().is_some_and(|ext| ext == "json") && output_dir.join(relative.with_extension("html")).is_file())
+            || (relative.parent() == Some(Path::new("")) && relative.to_str().is_some_and(sitemap::is_chunk_file))
+            || (relative.parent() == Some(Path::new("")) && relative.to_str().is_some_and(is_stylesheet))
+    };
+    let mut stale: Vec<PathBuf> = WalkDir::new(output_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.path().strip_prefix(output_dir).ok().map(Path::to_path_buf))
+        .filter(|relative| is_page(relative) && !produced.contains(relative))
+        .collect();
+    stale.sort();
+    stale
+}
+
+/// Source file of the post `slug` (original or sanitized) under `posts_dir`.
+pub fn find_post_file(posts_dir: &Path, formats: &[InputFormat], slug: &str) -> Option<PathBuf> {
+    let wanted = sanitize_slug(slug);
+    post_files(posts_dir, formats)
+        .into_iter()
+        .find(|p| p.file_stem().and_then(|s| s.to_str()).is_some_and(|stem| stem == slug || sanitize_slug(stem) == wanted))
+}
+
+/// Point wiki links and Markdown/HTML links at the post `old` (sanitized slug) to `new` instead,
+/// keeping each link's form (`/old/`, `../old.html`, `[[Old|text]]`...). Fenced and inline code
+/// is left alone. Returns the new text and the number of links changed.
+pub fn retarget_links(markdown: &str, old: &str, new: &str) -> (String, usize) {
+    let wiki_link = Regex::new(r"\[\[([^\[\]|]+)(\|[^\[\]]+)?\]\]").unwrap();
+    let href = Regex::new(r#"(\]\(\s*<?|href=")([^)\s">]+)"#).unwrap();
+    let mut changed = 0;
+    let mut fences = CodeFences::default();
+    let lines: Vec<String> = markdown
+        .split_inclusive('\n')
+        .map(|line| {
+            if fences.is_code(line) {
+                return line.to_string();
+            }
+            markdown::replace_outside_code(line, |text| {
+                let text = wiki_link.replace_all(text, |caps: &regex::Captures| {
+                    if sanitize_slug(caps[1].trim()) != old {
+                        return caps[0].to_string();
+                    }
+                    changed += 1;
+                    format!("[[{}{}]]", new, caps.get(2).map_or("", |m| m.as_str()))
+                });
+                href.replace_all(&text, |caps: &regex::Captures| match retarget_href(&caps[2], old, new) {
+                    Some(target) => {
+                        changed += 1;
+                        format!("{}{}", &caps[1], target)
+                    }
+                    None => caps[0].to_string(),
+                })
+                .into_owned()
+            })
+        })
+        .collect();
+    (lines.concat(), changed)
+}
+
+/// `href` with its slug swapped for `new`, if it is an internal link to `old` (see [`link_slug`]).
+fn retarget_href(href: &str, old: &str, new: &str) -> Option<String> {
+    if link_slug(href).as_deref() != Some(old) {
+        return None;
+    }
+    let path_end = href.find(['?', '#']).unwrap_or(href.len());
+    let path = &href[..path_end];
+    let mut start = 0;
+    while let Some(prefix) = ["../", "./", "/"].iter().find(|p| path[start..].starts_with(*p)) {
+        start += prefix.len();
+    }
+    let trimmed = path.trim_end_matches('/');
+    let stem = ["/index.html", ".html", ".md"].iter().find_map(|ext| trimmed.strip_suffix(ext)).unwrap_or(trimmed);
+    Some(format!("{}{}{}", &href[..start], new, &href[stem.len()..]))
+}
+
+/// Output path (without extension or trailing slash) of the link graph page.
+pub const GRAPH_PAGE: &str = "graph";
+
+/// Output path (without extension or trailing slash) of the archive page.
+pub const ARCHIVE_PAGE: &str = "archive";
+
+/// File served for URLs that match no page.
+pub const NOT_FOUND_PAGE: &str = "404.html";
+
+/// The site's stylesheet, at the root of the output directory.
+pub const STYLESHEET: &str = "style.css";
+
+/// Project stylesheet appended to `style.css` after the built-in and theme styles.
+pub const CUSTOM_STYLESHEET: &str = "static/custom.css";
+
+/// Project script, copied to `custom.js` at the root of the output directory and loaded by
+/// every page.
+pub const CUSTOM_SCRIPT: &str = "static/custom.js";
+
+/// The project script in the output directory.
+pub const CUSTOM_SCRIPT_FILE: &str = "custom.js";
+
+/// Name of the stylesheet with `css` in a release build: `style.<hash>.css`, after the first
+/// eight hex digits of its SHA-256.
+fn fingerprinted_stylesheet(css: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(css.as_bytes()));
+    format!("style.{}.css", &hash[..8])
+}
+
+/// Whether `name` is the stylesheet of a build, fingerprinted or not.
+fn is_stylesheet(name: &str) -> bool {
+    name == STYLESHEET || name.strip_prefix("style.").and_then(|n| n.strip_suffix(".css")).is_some_and(|hash| hash.len() == 8 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Slugs a post can't have: the config's `reserved_slugs` plus the pages and directories scribe
+/// generates itself, whether or not they are enabled.
+pub fn reserved_slugs(config: &Config) -> BTreeSet<String> {
+    let mut reserved: BTreeSet<String> = config.reserved_slugs.iter().map(|s| sanitize_slug(s)).collect();
+    reserved.extend(["series", trails::DIR, ARCHIVE_PAGE, GRAPH_PAGE, initials::DIR, fonts::DIR, math::DIR, mermaid::DIR, favicon::DIR].map(String::from));
+    reserved
+}
+
+/// Output path (without extension or trailing slash) of the landing page for series `name`.
+pub fn series_path(name: &str) -> String {
+    format!("series/{}", sanitize_slug(name))
 }
 
 #[derive(Debug)]
 pub struct SiteGenerator {
     config: Config,
     posts: Vec<Post>,
+    dry_run: bool,
+    strict: bool,
+    /// Slugs, globs or tags selecting the posts to render; empty renders all
+    only: Vec<String>,
+    missing_alt: Vec<MissingAlt>,
+    /// Frontmatter that doesn't parse or has fields of the wrong type
+    frontmatter_problems: Vec<frontmatter::Problem>,
+    /// Images each post (by slug) refers to, drafts included
+    asset_refs: Vec<(String, AssetRef)>,
+    /// Curated reading trails from the `trails` file
+    trails: Vec<Trail>,
+    /// Posts that failed to load or render; the rest of the site is still built
+    failures: Mutex<Vec<PostFailure>>,
+    /// Leave pages that are no longer generated in the output directory
+    keep_stale: bool,
+    /// What the last `generate` did to each output file
+    summary: BuildSummary,
+    /// Extra outputs, run when enabled under `emitters` in the config
+    emitters: Vec<Box<dyn OutputEmitter>>,
+    /// Build only from the resources in `scribe.lock`, fetching nothing
+    frozen: bool,
+    /// The lockfile a frozen build checked, whose link metadata it uses
+    locked: Lockfile,
+    /// Build posts dated in the future too
+    future: bool,
+    /// Build the site as of the end of this day instead of now
+    until: Option<NaiveDate>,
+    format: BuildFormat,
+    /// Minify pages and the stylesheet, and fingerprint the stylesheet's name
+    release: bool,
+}
+
+/// A parsed post along with what `load_posts` needs to finish it on the main thread.
+struct LoadedPost {
+    post: Post,
+    content: String,
+    had_frontmatter: bool,
+    missing_alt: Vec<MissingAlt>,
+    frontmatter_problems: Vec<frontmatter::Problem>,
+    asset_refs: Vec<AssetRef>,
+}
+
+/// A post that failed to build, pointing at the line responsible when it is known.
+#[derive(Debug, Clone)]
+pub struct PostFailure {
+    /// The post's Markdown file, or its annotations sidecar when that is at fault
+    pub path: PathBuf,
+    /// 1-based line in `path`
+    pub line: Option<usize>,
+    /// The error and its causes, outermost first
+    pub errors: Vec<String>,
+}
+
+impl PostFailure {
+    fn new(path: &Path, error: &anyhow::Error) -> Self {
+        let mut path = path.to_path_buf();
+        let mut line = None;
+        if let Some(yaml) = error.chain().find_map(|e| e.downcast_ref::<serde_yaml::Error>()) {
+            // Only annotation sidecars are parsed strictly as YAML
+            path = annotations::sidecar_path(&path);
+            line = yaml.location().map(|l| l.line());
+        } else if let Some(source) = error.chain().find_map(|e| e.downcast_ref::<SourceError>()) {
+            line = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| content.find(&source.text).map(|i| content[..i].matches('\n').count() + 1));
+        }
+        PostFailure { path, line, errors: error.chain().map(|e| e.to_string()).collect() }
+    }
+}
+
+impl std::fmt::Display for PostFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.errors.join(": ")),
+            None => write!(f, "{}: {}", self.path.display(), self.errors.join(": ")),
+        }
+    }
+}
+
+/// An error caused by `text` in a post's source, located by searching for it when reported.
+#[derive(Debug)]
+struct SourceError {
+    text: String,
+    message: String,
+}
+
+impl std::fmt::Display for SourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+/// Output file recording the build stamp, relative to the output directory.
+pub const BUILD_STAMP_FILE: &str = "build.json";
+
+/// What produced a build, embedded in every page and `build.json` when `build_stamp` is set.
+#[derive(Debug, Serialize)]
+pub struct BuildStamp {
+    pub version: &'static str,
+    pub built: DateTime<Utc>,
+    pub posts: usize,
+}
+
+impl BuildStamp {
+    /// `html` with the stamp as a comment right after the doctype.
+    fn annotate(&self, html: &str) -> String {
+        let comment = format!(
+            "<!-- Built by scribe {} on {} from {} posts -->",
+            self.version,
+            self.built.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            self.posts
+        );
+        match html.split_once('\n') {
+            Some((doctype, rest)) => format!("{}\n{}\n{}", doctype, comment, rest),
+            None => format!("{}\n{}", comment, html),
+        }
+    }
+}
+
+/// Files the last generation run wrote or left alone, relative to the output directory.
+#[derive(Debug, Default)]
+pub struct BuildSummary {
+    pub created: Vec<PathBuf>,
+    pub updated: Vec<PathBuf>,
+    pub unchanged: Vec<PathBuf>,
+    /// Stale pages deleted after the build
+    pub removed: Vec<PathBuf>,
+}
+
+/// Files a generation run would touch, relative to the output directory.
+#[derive(Debug, Default)]
+pub struct ChangePlan {
+    pub created: Vec<PathBuf>,
+    pub updated: Vec<PathBuf>,
+    pub unchanged: Vec<PathBuf>,
+    /// Pages present in the output directory that would no longer be generated, and would be removed
+    pub stale: Vec<PathBuf>,
+    /// Illuminated initials that would be generated, one OpenAI image request each
+    pub initials: Vec<char>,
+    /// Annotation URLs without fresh cached metadata, fetched during a real build
+    pub uncached_urls: usize,
 }
 
 impl SiteGenerator {
@@ -36,11 +526,109 @@ impl SiteGenerator {
         Self {
             config,
             posts: Vec::new(),
+            dry_run: false,
+            strict: false,
+            only: Vec::new(),
+            missing_alt: Vec::new(),
+            frontmatter_problems: Vec::new(),
+            asset_refs: Vec::new(),
+            trails: Vec::new(),
+            failures: Mutex::new(Vec::new()),
+            keep_stale: false,
+            summary: BuildSummary::default(),
+            emitters: Vec::new(),
+            frozen: false,
+            locked: Lockfile::default(),
+            future: false,
+            until: None,
+            format: BuildFormat::Html,
+            release: false,
         }
     }
 
+    /// Fail instead of fetching anything missing from, or differing from, `scribe.lock`.
+    /// Frozen builds are offline and leave the lockfile alone.
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+        self.config.offline |= frozen;
+    }
+
+    /// Register an emitter of extra outputs. It runs on each build whose config lists its name
+    /// under `emitters`.
+    pub fn add_emitter(&mut self, emitter: impl OutputEmitter + 'static) {
+        self.emitters.push(Box::new(emitter));
+    }
+
+    /// Also build posts dated after now (or after the `set_until` day), which are otherwise
+    /// left out until a build after their date.
+    pub fn set_future(&mut self, future: bool) {
+        self.future = future;
+    }
+
+    /// Build the site as it was at the end of `day` (UTC), leaving out posts dated later.
+    pub fn set_until(&mut self, day: Option<NaiveDate>) {
+        self.until = day;
+    }
+
+    /// Build the website, or only its content as JSON (`posts.json`, `tags.json` and
+    /// `graph.json`) for another frontend to render.
+    pub fn set_format(&mut self, format: BuildFormat) {
+        self.format = format;
+    }
+
+    /// Minify the HTML of every page and `style.css`, and name the stylesheet after a hash of
+    /// its content (`style.<hash>.css`) so it can be cached for good.
+    pub fn set_release(&mut self, release: bool) {
+        self.release = release;
+    }
+
+    /// Render only posts matching one of `filters` (slug, glob or tag) plus the index.
+    pub fn set_only(&mut self, filters: Vec<String>) {
+        self.only = filters;
+    }
+
+    /// Keep pages the build no longer produces (e.g. of renamed posts) instead of deleting them.
+    pub fn set_keep_stale(&mut self, keep: bool) {
+        self.keep_stale = keep;
+    }
+
+    /// Images under the posts directory that the posts loaded by the last `generate` or
+    /// `read_posts` use, those they don't, and references to images that don't exist.
+    pub fn asset_report(&self) -> AssetReport {
+        assets::report(
+            Path::new(&self.config.posts_dir),
+            Path::new(&self.config.output_dir),
+            &self.config.base_path(),
+            self.config.trailing_slash,
+            &self.asset_refs,
+        )
+    }
+
+    /// Fail the build on lint findings (e.g. invalid frontmatter or images without alt text)
+    /// instead of warning.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Files created, updated and left unchanged by the last `generate`.
+    pub fn into_summary(self) -> BuildSummary {
+        self.summary
+    }
+
+    /// Mark template regions with HTML comments and write each page's render context as JSON.
+    pub fn set_debug_templates(&mut self, debug: bool) {
+        self.config.debug_templates = debug;
+    }
+
     pub async fn generate(&mut self) -> Result<()> {
-        println!("{}", "Generating site...".cyan());
+        info!("{}", "Generating site...".cyan());
+        if self.config.analytics.is_some() && templates::analytics_script(&self.config).is_empty() {
+            warn!("{}", "Warning: analytics needs a domain (or the site's url) for Plausible, or a code or host for GoatCounter; no script added".yellow());
+        }
         
         // Create output directory
         fs::create_dir_all(&self.config.output_dir)
@@ -48,65 +636,568 @@ impl SiteGenerator {
         
         // Load posts
         self.load_posts().await?;
+        self.report_lints()?;
+        
+        // A frozen build must find everything it would otherwise fetch exactly as locked
+        if self.frozen {
+            self.locked = Lockfile::load(lock::path(&self.config))?;
+            let mut problems = self.locked.differences(&self.resources());
+            problems.extend(self.uncached_initials().into_iter().map(|l| format!("illuminated initial '{}' would be generated", l)));
+            if !problems.is_empty() {
+                anyhow::bail!("{} is out of date; build without --frozen to update it:\n  {}", lock::FILE, problems.join("\n  "));
+            }
+        }
+        
+        // Fill in missing excerpts and TL;DRs, then pick them up from the rewritten frontmatter
+        if self.config.summaries.on_generate && self.posts.iter().any(summarize::needs_summary) {
+            if self.config.offline {
+                info!("{}", "Offline: skipping summaries".yellow());
+            } else if self.config.openai_api_key.is_none() {
+                info!("{}", "OPENAI_API_KEY not set, skipping summaries".yellow());
+            } else if summarize::summarize_posts(&self.config, self.selected_posts()).await? > 0 {
+                self.load_posts().await?;
+            }
+        }
+        
+        // Initials from older versions were data-URL text files; turn them into images
+        match initials::migrate(&Path::new(&self.config.output_dir).join(initials::DIR)) {
+            Ok(0) => {}
+            Ok(n) => info!("{}", format!("Migrated {} illuminated initial(s) to image files", n).cyan()),
+            Err(e) => warn!("{}", format!("Warning: {:#}", e).yellow()),
+        }
+        
+        // Pages and their assets are left out of headless builds, which only write the content
+        let pages = self.format == BuildFormat::Html;
         
         // Generate illuminated initials if needed
-        if let Some(_api_key) = &self.config.openai_api_key {
-            self.generate_initials().await?;
+        if pages && self.config.initials.mode == InitialsMode::Openai && self.config.openai_api_key.is_some() {
+            if self.config.offline {
+                info!("{}", "Offline: skipping illuminated initials".yellow());
+            } else {
+                self.generate_initials().await?;
+            }
+        }
+        
+        // Draw diagrams as SVG once; later builds reuse them from the cache
+        if self.config.mermaid.mode == MermaidMode::Svg {
+            let rendered = mermaid::render_missing(&self.selected_posts(), &self.config.mermaid);
+            if rendered > 0 {
+                info!("{}", format!("Rendered {} diagram(s)", rendered).cyan());
+            }
+        }
+        
+        // Mentions from webmention.io, shown under the posts they mention
+        if pages && self.config.webmention.as_ref().is_some_and(|w| w.show) {
+            self.attach_webmentions().await?;
+        }
+        
+        // Cache the author avatar alongside the site
+        if let Some(avatar) = self.config.avatar.as_ref().filter(|_| pages) {
+            if let Err(e) = avatar::cache(avatar, Path::new(&self.config.output_dir), self.config.offline).await {
+                warn!("{}", format!("Warning: {:#}", e).yellow());
+            }
+        }
+        
+        // Publish images kept alongside the posts, without their metadata
+        let published = images::publish(Path::new(&self.config.posts_dir), Path::new(&self.config.output_dir), &self.config.images)?;
+        if published > 0 {
+            info!("{}", format!("Published {} image(s)", published).cyan());
+        }
+        let missing = self.asset_report().missing;
+        if !missing.is_empty() {
+            let mut report = format!("{} reference(s) to missing images:", missing.len());
+            for reference in &missing {
+                report.push_str(&format!("\n  {}", reference));
+            }
+            warn!("{}", report.yellow());
+        }
+        
+        // Vendor web fonts instead of linking the Google Fonts CDN
+        if pages && self.config.fonts.bundle {
+            if let Err(e) = fonts::bundle(Path::new(&self.config.output_dir), self.config.offline).await {
+                warn!("{}", format!("Warning: {:#}", e).yellow());
+            }
+        }
+        // KaTeX goes alongside, for math typeset in the browser
+        if pages && self.config.math == MathMode::Katex {
+            if let Err(e) = math::bundle(Path::new(&self.config.output_dir), self.config.offline).await {
+                warn!("{}", format!("Warning: {:#}", e).yellow());
+            }
+        }
+        // and mermaid.js, for diagrams drawn there
+        if pages && self.config.mermaid.mode == MermaidMode::Script {
+            if let Err(e) = mermaid::bundle(Path::new(&self.config.output_dir), self.config.offline).await {
+                warn!("{}", format!("Warning: {:#}", e).yellow());
+            }
+        }
+        
+        // Render post pages, index and assets, then write out the ones that changed
+        let mut summary = BuildSummary::default();
+        let mut produced = HashSet::new();
+        for (path, content) in self.render_outputs().await? {
+            let relative = path.strip_prefix(&self.config.output_dir).unwrap_or(&path).to_path_buf();
+            produced.insert(relative.clone());
+            match fs::read(&path) {
+                Ok(existing) if existing == content.as_bytes() => {
+                    summary.unchanged.push(relative);
+                    continue;
+                }
+                Ok(_) => summary.updated.push(relative),
+                Err(_) => summary.created.push(relative),
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, content)
+                .context(format!("Failed to write {}", path.display()))?;
+            debug!("  {} {}", "wrote".green(), path.display());
+        }
+        
+        // Delete pages left behind by renamed or removed posts; a partial build can't tell which
+        // those are, and posts that failed keep their last good page
+        let failures = self.failures();
+        if !self.keep_stale && self.only.is_empty() && failures.is_empty() {
+            let output_dir = Path::new(&self.config.output_dir);
+            for relative in stale_pages(output_dir, &produced) {
+                let path = output_dir.join(&relative);
+                fs::remove_file(&path).context(format!("Failed to remove {}", path.display()))?;
+                // Drop directories the page leaves empty, e.g. `old-slug/`
+                let mut dir = path.parent();
+                while let Some(d) = dir.filter(|d| *d != output_dir) {
+                    if fs::remove_dir(d).is_err() {
+                        break;
+                    }
+                    dir = d.parent();
+                }
+                debug!("  {} {}", "removed".red(), path.display());
+                summary.removed.push(relative);
+            }
+            if !summary.removed.is_empty() {
+                info!("{}", format!("Removed {} stale page(s)", summary.removed.len()).cyan());
+            }
         }
+        self.summary = summary;
         
-        // Generate individual post pages
-        self.generate_posts().await?;
+        // Trashed posts are gone unless a notice or redirect took their place
+        if self.config.trash.page == TrashedPage::Remove {
+            for slug in self.trashed_slugs() {
+                let path = Path::new(&self.config.output_dir).join(self.config.trailing_slash.post_path(&slug));
+                if path.exists() {
+                    fs::remove_file(&path).context(format!("Failed to remove {}", path.display()))?;
+                    // Directory-style pages leave an empty `slug/` behind
+                    if let Some(dir) = path.parent().filter(|d| *d != Path::new(&self.config.output_dir)) {
+                        let _ = fs::remove_dir(dir);
+                    }
+                    info!("  {} {}", "removed".red(), path.display());
+                }
+            }
+        }
         
-        // Generate index page
-        self.generate_index().await?;
+        // Record what was fetched; partial builds only fetch for some posts, offline ones for none
+        if !self.frozen && !self.config.offline && self.only.is_empty() {
+            let resources = self.resources().fetched();
+            if !resources.resources.is_empty() && resources.save(lock::path(&self.config))? {
+                info!("{}", format!("Updated {}", lock::FILE).cyan());
+            }
+        }
         
-        // Copy assets
-        self.copy_assets().await?;
+        info!("{}", format!("Generated {} posts", self.posts.len()).green());
         
-        println!("{}", format!("Generated {} posts", self.posts.len()).green());
+        if !failures.is_empty() {
+            error!("{}", format!("{} post(s) failed to build:", failures.len()).red());
+            for failure in &failures {
+                error!("  {}", failure);
+            }
+            let details: Vec<String> = failures.iter().map(ToString::to_string).collect();
+            return Err(anyhow::anyhow!(details.join("\n")).context(format!("{} post(s) failed to build", failures.len())));
+        }
         
         Ok(())
     }
 
+    /// Posts that failed to load or render in the last `generate`, `plan` or `read_posts`.
+    pub fn failures(&self) -> Vec<PostFailure> {
+        self.failures.lock().unwrap().clone()
+    }
+
+    fn report_lints(&self) -> Result<()> {
+        // Each finding: its report and the summary a strict build fails with
+        let mut findings = Vec::new();
+        if !self.frontmatter_problems.is_empty() {
+            let summary = format!("{} frontmatter problem(s)", self.frontmatter_problems.len());
+            let mut report = format!("{}:", summary);
+            for problem in &self.frontmatter_problems {
+                report.push_str(&format!("\n  {}", problem));
+            }
+            findings.push((report, summary));
+        }
+        if !self.missing_alt.is_empty() {
+            let summary = format!("{} image(s) without alt text", self.missing_alt.len());
+            let mut report = format!("{}:", summary);
+            for issue in &self.missing_alt {
+                report.push_str(&format!("\n  {}", issue));
+            }
+            report.push_str(&format!("\n  Add alt text, or mark decorative images with {}", lint::ALT_OPT_OUT));
+            findings.push((report, summary));
+        }
+        for (report, _) in &findings {
+            if self.strict {
+                error!("{}", report.red());
+            } else {
+                warn!("{}", report.yellow());
+            }
+        }
+        if self.strict && !findings.is_empty() {
+            let summaries: Vec<&str> = findings.iter().map(|(_, summary)| summary.as_str()).collect();
+            anyhow::bail!("{}", summaries.join(" and "));
+        }
+        Ok(())
+    }
+
+    /// Render a single Markdown file (inside or outside the posts directory) with the site's
+    /// template and stylesheet into `dir`, returning the path of the rendered page.
+    pub async fn preview(&self, source: &Path, dir: &Path) -> Result<PathBuf> {
+        let content = fs::read_to_string(source)
+            .context(format!("Failed to read {}", source.display()))?;
+        let mut known_slugs = self.post_slugs();
+        known_slugs.insert(self.post_language(source, &frontmatter::split(&content).0.unwrap_or_default()).1);
+        let post = self.parse_post(&content, source, &known_slugs)?;
+        
+        let url_meta = load_url_meta();
+        let fetched = Progress::new(uncached_urls(&[&post], &url_meta) as u64, "Fetching");
+        let url_meta = Mutex::new(url_meta);
+        let annotation_meta_json = build_annotation_meta_json(&post, &url_meta, &BTreeMap::new(), false, &fetched).await;
+        drop(fetched);
+        url_meta.into_inner().unwrap().save(cache::URL_META_PATH)?;
+        let html = templates::render_post(&self.config, &post, std::slice::from_ref(&post), &[], annotation_meta_json)?;
+        
+        let page_path = dir.join(self.config.trailing_slash.post_path(&post.slug));
+        if let Some(parent) = page_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&page_path, html)?;
+        fs::write(dir.join(STYLESHEET), self.stylesheet()?)?;
+        if Path::new(CUSTOM_SCRIPT).is_file() {
+            fs::copy(CUSTOM_SCRIPT, dir.join(CUSTOM_SCRIPT_FILE))?;
+        }
+        // The page links its initial relatively, so bring the image along
+        let output_dir = Path::new(&self.config.output_dir);
+        if let Some(image) = post.first_letter.and_then(|l| initials::find(&initials::dir(output_dir, &self.config.initials, &post.slug), l)) {
+            let copy = dir.join(image.strip_prefix(output_dir)?);
+            fs::create_dir_all(copy.parent().unwrap())?;
+            fs::copy(&image, copy)?;
+        }
+        
+        Ok(page_path)
+    }
+
+    /// Rewrite only `style.css`, for stylesheet edits that leave every page as it is.
+    pub fn write_stylesheet(&self) -> Result<PathBuf> {
+        let path = Path::new(&self.config.output_dir).join(STYLESHEET);
+        fs::create_dir_all(&self.config.output_dir)
+            .context("Failed to create output directory")?;
+        fs::write(&path, self.stylesheet()?)
+            .context(format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// The built-in styles followed by the theme's own stylesheet and `static/custom.css`, if any.
+    fn stylesheet(&self) -> Result<String> {
+        let mut css = templates::generate_css(&self.config);
+        if let Some(path) = &self.config.theme.stylesheet {
+            let custom = fs::read_to_string(path)
+                .context(format!("Failed to read stylesheet {}", path))?;
+            css.push_str("\n/* Theme stylesheet */\n");
+            css.push_str(&custom);
+        }
+        if let Ok(custom) = fs::read_to_string(CUSTOM_STYLESHEET) {
+            css.push_str(&format!("\n/* {} */\n", CUSTOM_STYLESHEET));
+            css.push_str(&custom);
+        }
+        Ok(css)
+    }
+
+    /// Load and parse all posts without touching the filesystem.
+    pub async fn read_posts(&mut self) -> Result<&[Post]> {
+        let dry_run = std::mem::replace(&mut self.dry_run, true);
+        let loaded = self.load_posts().await;
+        self.dry_run = dry_run;
+        loaded?;
+        for failure in self.failures() {
+            warn!("{}", format!("Warning: skipping {}", failure).yellow());
+        }
+        Ok(&self.posts)
+    }
+
+    /// Posts loaded by the last `generate`, `plan` or `read_posts`, newest first.
+    pub fn posts(&self) -> &[Post] {
+        &self.posts
+    }
+
+    /// Render the site in memory and compare it against the output directory without writing anything.
+    pub async fn plan(&mut self) -> Result<ChangePlan> {
+        // Offline, and without saving caches, like a real build's loading in `read_posts`
+        let dry_run = std::mem::replace(&mut self.dry_run, true);
+        let plan = self.plan_changes().await;
+        self.dry_run = dry_run;
+        plan
+    }
+
+    async fn plan_changes(&mut self) -> Result<ChangePlan> {
+        self.read_posts().await?;
+
+        let output_dir = Path::new(&self.config.output_dir);
+        let mut plan = ChangePlan::default();
+        let mut produced = HashSet::new();
+
+        for (path, content) in self.render_outputs().await? {
+            let relative = path.strip_prefix(output_dir).unwrap_or(&path).to_path_buf();
+            match fs::read(&path) {
+                Ok(existing) if existing == content.as_bytes() => plan.unchanged.push(relative.clone()),
+                Ok(_) => plan.updated.push(relative.clone()),
+                Err(_) => plan.created.push(relative.clone()),
+            }
+            produced.insert(relative);
+        }
+
+        // A partial build leaves other pages alone, and one with failed posts keeps them, so nothing is stale
+        if self.only.is_empty() && self.failures.lock().unwrap().is_empty() {
+            plan.stale = stale_pages(output_dir, &produced);
+        }
+
+        // API work a real build would do
+        if !self.config.offline {
+            plan.initials = self.uncached_initials();
+        }
+        if !self.config.offline {
+            let url_meta = load_url_meta();
+            let urls: HashSet<String> = self.posts.iter().filter(|p| self.is_selected(p)).flat_map(annotation_urls).collect();
+            plan.uncached_urls = urls.iter().filter(|u| !url_meta.is_fresh(u)).count();
+        }
+
+        Ok(plan)
+    }
+
+    /// Letters of illuminated initials the OpenAI API would be asked for.
+    fn uncached_initials(&self) -> Vec<char> {
+        if self.config.initials.mode != InitialsMode::Openai || self.config.openai_api_key.is_none() {
+            return Vec::new();
+        }
+        let output_dir = Path::new(&self.config.output_dir);
+        let wanted: BTreeSet<(PathBuf, char)> = self.posts
+            .iter()
+            .filter_map(|p| p.first_letter.map(|l| (initials::dir(output_dir, &self.config.initials, &p.slug), l)))
+            .collect();
+        wanted
+            .into_iter()
+            .filter(|(dir, l)| !initials::is_cached(dir, *l))
+            .map(|(_, l)| l)
+            .collect()
+    }
+
+    /// Remote resources the site uses as they are now: downloads in the output directory and
+    /// `.scribe/`, and cached link metadata.
+    fn resources(&self) -> Lockfile {
+        let output_dir = Path::new(&self.config.output_dir);
+        let mut resources = BTreeMap::new();
+        if self.config.fonts.bundle {
+            for face in fonts::FACES {
+                resources.insert(face.url(), Resource::file(ResourceKind::Font, output_dir, face.path()));
+            }
+        }
+        if let Some(avatar) = self.config.avatar.as_ref().filter(|a| a.file.is_none()) {
+            if let Some(url) = avatar::remote_url(avatar) {
+                resources.insert(url, Resource::file(ResourceKind::Avatar, output_dir, avatar::file_name(avatar)));
+            }
+        }
+        let urls: BTreeSet<String> = self.posts.iter().flat_map(annotation_urls).collect();
+        for host in urls.iter().filter_map(|url| favicon::host(url)) {
+            resources.insert(favicon::url(host), Resource::file(ResourceKind::Favicon, output_dir, favicon::path(host)));
+        }
+        let url_meta = load_url_meta();
+        for url in urls {
+            let meta = url_meta.entries.get(&url).map(|e| e.meta.clone());
+            resources.insert(url, Resource::metadata(meta));
+        }
+        if self.config.math == MathMode::Katex {
+            for file in math::files() {
+                let path = format!("{}/{}", math::VENDOR_DIR, file);
+                resources.insert(math::url(&file), Resource::file(ResourceKind::Katex, Path::new(""), path));
+            }
+        }
+        if self.config.mermaid.mode == MermaidMode::Script {
+            resources.insert(mermaid::url(), Resource::file(ResourceKind::Mermaid, Path::new(""), mermaid::VENDOR_PATH.to_string()));
+        }
+        if let Some(site_url) = self.config.site_url().filter(|_| self.config.webmention.as_ref().is_some_and(|w| w.show)) {
+            let cache = webmention::Cache::load(webmention::CACHE_PATH).unwrap_or_default();
+            for post in &self.posts {
+                let target = self.config.trailing_slash.post_href(&site_url, &post.slug);
+                let mentions = cache.fetched(&target).and_then(|m| serde_json::to_value(m).ok());
+                resources.insert(webmention::feed_url(&target), Resource::webmentions(mentions));
+            }
+        }
+        Lockfile { resources }
+    }
+
     async fn load_posts(&mut self) -> Result<()> {
+        // Read alongside the posts so watch-mode rebuilds pick up trail edits too
+        self.trails = match &self.config.trails {
+            Some(path) => trails::load(Path::new(path))?,
+            None => Vec::new(),
+        };
+        
         let posts_dir = Path::new(&self.config.posts_dir);
         if !posts_dir.exists() {
-            fs::create_dir_all(posts_dir)
-                .context("Failed to create posts directory")?;
+            if !self.dry_run {
+                fs::create_dir_all(posts_dir)
+                    .context("Failed to create posts directory")?;
+            }
             return Ok(());
         }
 
-        let mut posts = Vec::new();
+        self.missing_alt.clear();
+        self.frontmatter_problems.clear();
+        self.asset_refs.clear();
+        let known_slugs = self.post_slugs();
+        let reserved = reserved_slugs(&self.config);
+        let paths = post_files(posts_dir, &self.config.input_formats);
+
+        // Reading, linting and rendering each post is independent, so spread it over all cores. It
+        // runs off the runtime, by a generator of its own, so aborting the build doesn't wait for it
+        let loader = SiteGenerator::new(self.config.clone());
+        let (paths, loaded) = tokio::task::spawn_blocking(move || {
+            let workers = std::thread::available_parallelism().map_or(1, |n| n.get()).min(paths.len().max(1));
+            let chunk_size = paths.len().div_ceil(workers).max(1);
+            let loaded: Vec<Result<LoadedPost>> = std::thread::scope(|scope| {
+                let handles: Vec<_> = paths
+                    .chunks(chunk_size)
+                    .map(|chunk| scope.spawn(|| chunk.iter().map(|path| loader.load_post(path, &known_slugs, &reserved)).collect::<Vec<_>>()))
+                    .collect();
+                handles.into_iter().flat_map(|h| h.join().expect("post loading thread panicked")).collect()
+            });
+            (paths, loaded)
+        })
+        .await
+        .context("Failed to load posts")?;
+
+        // Posts dated after this are scheduled, and wait for a later build
+        let cutoff = match self.until {
+            Some(day) => day.and_hms_opt(23, 59, 59).map_or_else(Utc::now, |end| end.and_utc()),
+            None => Utc::now(),
+        };
+        let mut scheduled = 0;
         
-        for entry in WalkDir::new(posts_dir)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path().extension().map_or(false, |ext| ext == "md"))
-        {
-            let content = fs::read_to_string(entry.path())
-                .context(format!("Failed to read {}", entry.path().display()))?;
-            let had_frontmatter = content.lines().next().map(|l| l.trim() == "---").unwrap_or(false);
-            
-            let post = self.parse_post(&content, entry.path())?;
+        // Apply results in path order so errors, lints and rewrites are deterministic
+        let mut posts = Vec::with_capacity(loaded.len());
+        let mut failures = Vec::new();
+        for (path, result) in paths.iter().zip(loaded) {
+            let LoadedPost { mut post, content, had_frontmatter, missing_alt, frontmatter_problems, asset_refs } = match result {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    failures.push(PostFailure::new(path, &e));
+                    continue;
+                }
+            };
+            // Reported for drafts too, whose `draft: true` may be what can't be read
+            self.frontmatter_problems.extend(frontmatter_problems);
+            // A draft's images are still in use, just not published yet
+            self.asset_refs.extend(asset_refs.into_iter().map(|r| (post.slug.clone(), r)));
+            if post.frontmatter.draft {
+                debug!("Skipping draft {}", path.display());
+                continue;
+            }
+            if !self.future && post.date > cutoff {
+                debug!("Skipping {}, scheduled for {}", path.display(), post.date.format("%Y-%m-%d %H:%M"));
+                scheduled += 1;
+                continue;
+            }
+            self.missing_alt.extend(missing_alt);
+            if post.is_private() {
+                if self.config.private_passphrase.is_none() {
+                    warn!("{}", format!("Warning: {} is private but no private_passphrase is set; leaving it out", path.display()).yellow());
+                    continue;
+                }
+                post.hide();
+            }
 
             // If there was no frontmatter, write one in-place using derived values
-            if !had_frontmatter {
-                if let Err(e) = Self::write_frontmatter_in_place(entry.path(), &post, &content) {
-                    eprintln!("Warning: failed to write frontmatter for {}: {}", entry.path().display(), e);
+            if !had_frontmatter && !self.dry_run {
+                if let Err(e) = Self::write_frontmatter_in_place(path, &post, &content) {
+                    warn!("Warning: failed to write frontmatter for {}: {}", path.display(), e);
                 } else {
-                    println!("{} {}", "Annotated".green(), entry.path().display());
+                    info!("{} {}", "Annotated".green(), path.display());
                 }
             }
             posts.push(post);
         }
         
+        if scheduled > 0 {
+            let hint = if self.until.is_none() { "; build with --future to include them" } else { "" };
+            info!("{}", format!("Left out {} post(s) dated after {}{}", scheduled, cutoff.format("%Y-%m-%d %H:%M UTC"), hint).cyan());
+        }
+        
         // Sort by date (newest first)
-        posts.sort_by(|a, b| b.date.cmp(&a.date));
+        posts.sort_by_key(|p| std::cmp::Reverse(p.date));
         
         self.posts = posts;
+        *self.failures.lock().unwrap() = failures;
+        Ok(())
+    }
+
+    /// Fetch webmentions of every post not fetched recently (none when offline) and put the
+    /// cached ones on their posts.
+    async fn attach_webmentions(&mut self) -> Result<()> {
+        let Some(site_url) = self.config.site_url() else {
+            warn!("{}", "Warning: webmentions need the site's url to look up posts by; set url in the config".yellow());
+            return Ok(());
+        };
+        let mut cache = webmention::Cache::load(webmention::CACHE_PATH)?;
+        let targets: Vec<String> = self.posts.iter().map(|p| self.config.trailing_slash.post_href(&site_url, &p.slug)).collect();
+        if self.frozen {
+            // Frozen builds show the locked mentions, however old
+            let locked: BTreeMap<&str, &serde_json::Value> = self.locked.webmentions().collect();
+            for (post, target) in self.posts.iter_mut().zip(&targets) {
+                let mentions = locked.get(webmention::feed_url(target).as_str());
+                post.webmentions = mentions.and_then(|m| serde_json::from_value((*m).clone()).ok()).unwrap_or_default();
+            }
+            return Ok(());
+        }
+        if self.config.offline {
+            info!("{}", "Offline: using cached webmentions".yellow());
+        } else {
+            let (fetched, errors) = webmention::refresh(&mut cache, &targets).await;
+            for error in &errors {
+                warn!("{}", format!("Warning: webmentions for {}", error).yellow());
+            }
+            if fetched > 0 {
+                cache.save(webmention::CACHE_PATH)?;
+                info!("{}", format!("Fetched webmentions for {} post(s)", fetched).cyan());
+            }
+        }
+        for (post, target) in self.posts.iter_mut().zip(&targets) {
+            post.webmentions = cache.mentions(target).to_vec();
+        }
         Ok(())
     }
 
+    /// Read, lint and parse one post file. Runs on a worker thread, so it only reads the filesystem.
+    fn load_post(&self, path: &Path, known_slugs: &HashSet<String>, reserved: &BTreeSet<String>) -> Result<LoadedPost> {
+        let content = markup::read(path)
+            .context(format!("Failed to read {}", path.display()))?;
+        let stem = sanitize_slug(path.file_stem().and_then(|s| s.to_str()).unwrap_or("untitled"));
+        if reserved.contains(&stem) {
+            anyhow::bail!("the slug '{}' is reserved for a generated page; rename the file (or edit reserved_slugs in the config)", stem);
+        }
+        let frontmatter_problems = frontmatter::validate(path, &content);
+        // A block that doesn't parse is still the author's, so no frontmatter gets written over
+        // it; AsciiDoc and Org posts keep their own headers
+        let had_frontmatter = frontmatter::has_block(&content) || !frontmatter_problems.is_empty() || InputFormat::of(path).is_some();
+        let missing_alt = lint::missing_alt_text(path, &content);
+        let asset_refs = assets::references(path, &content);
+        let post = self.parse_post(&content, path, known_slugs)?;
+        Ok(LoadedPost { post, content, had_frontmatter, missing_alt, frontmatter_problems, asset_refs })
+    }
+
     fn write_frontmatter_in_place(path: &Path, post: &Post, original_content: &str) -> Result<()> {
         // Build YAML frontmatter
         let title = post.title.replace('"', "\\\"");
@@ -132,18 +1223,100 @@ impl SiteGenerator {
         Ok(())
     }
 
-    fn parse_post(&self, content: &str, path: &Path) -> Result<Post> {
+    /// Slugs of trashed posts that no live post or alias has taken over.
+    fn trashed_slugs(&self) -> Vec<String> {
+        let taken: HashSet<String> = self.posts
+            .iter()
+            .flat_map(|p| std::iter::once(p.slug.clone()).chain(p.frontmatter.aliases.iter().map(|a| sanitize_slug(a))))
+            .collect();
+        trash::slugs(Path::new(&self.config.posts_dir)).into_iter().filter(|s| !taken.contains(s)).collect()
+    }
+
+    /// Sanitized slugs of every post in the posts directory, for resolving `[[slug]]` links.
+    fn post_slugs(&self) -> HashSet<String> {
+        post_files(Path::new(&self.config.posts_dir), &self.config.input_formats)
+            .iter()
+            .map(|path| {
+                // Only a multilingual site needs the frontmatter, for `lang:`
+                let frontmatter = if self.config.languages.is_multilingual() {
+                    markup::read(path).ok().and_then(|content| frontmatter::split(&content).0)
+                } else {
+                    None
+                };
+                self.post_language(path, &frontmatter.unwrap_or_default()).1
+            })
+            .collect()
+    }
+
+    /// Language and slug of the post at `path`. Posts in a language other than the default get
+    /// slugs under its code, e.g. `nl/hello`.
+    fn post_language(&self, path: &Path, frontmatter: &Frontmatter) -> (String, String) {
+        let languages = &self.config.languages;
+        let stem = sanitize_slug(path.file_stem().and_then(|s| s.to_str()).unwrap_or("untitled"));
+        let directory = path
+            .strip_prefix(&self.config.posts_dir)
+            .ok()
+            .filter(|relative| relative.components().count() > 1)
+            .and_then(|relative| relative.components().next())
+            .and_then(|c| c.as_os_str().to_str())
+            .filter(|dir| languages.names.contains_key(*dir));
+        let lang = frontmatter
+            .lang
+            .as_deref()
+            .map(str::trim)
+            .filter(|lang| !lang.is_empty())
+            .or(directory)
+            .unwrap_or(&languages.default)
+            .to_string();
+        let slug = if languages.is_prefixed(&lang) { format!("{}/{}", lang, stem) } else { stem };
+        (lang, slug)
+    }
+
+    fn parse_post(&self, content: &str, path: &Path, known_slugs: &HashSet<String>) -> Result<Post> {
         // Parse frontmatter using serde_yaml
-        let (frontmatter, markdown) = self.parse_frontmatter(content);
+        let (frontmatter, markdown) = frontmatter::split(content);
+        let frontmatter = frontmatter.unwrap_or_default();
+        
+        // Raw HTML blocks skip every pass below and go back in when the page is rendered; a
+        // hand-written HTML post is one such block
+        let html_post = is_html_post(path);
+        let body = markdown;
+        let (markdown, raw_blocks) = if html_post { markdown::raw_document(&body) } else { markdown::extract_raw_blocks(&body) };
+        
+        // Merge annotations kept in a `slug.annotations.yaml` sidecar
+        let (markdown, unmatched) = annotations::merge_sidecar(path, &markdown, &self.config.markdown)?;
+        for key in unmatched {
+            warn!("{}", format!("Warning: {}: annotation key {} matches no paragraph or list", annotations::sidecar_path(path).display(), key).yellow());
+        }
+        
+        // Math is kept from every Markdown pass too, and typeset once the post is rendered
+        let (markdown, math_spans) = if self.config.math == MathMode::Off {
+            (markdown, Vec::new())
+        } else {
+            math::extract(&markdown)
+        };
         
-        // Convert markdown to HTML (autolink raw URLs first)
-        let autolinked_markdown = Self::autolink_markdown(&markdown);
-        let html_content = to_html(&autolinked_markdown);
+        // Convert markdown to HTML (wiki links and raw URLs become Markdown links first)
+        let (lang, slug) = self.post_language(path, &frontmatter);
+        if self.config.languages.is_multilingual() && !self.config.languages.names.contains_key(&lang) {
+            warn!("{}", format!("Warning: {}: language '{}' is not in languages.names; building it at the root", path.display(), lang).yellow());
+        }
+        let wiki_linked_markdown = self.resolve_wiki_links(&markdown, known_slugs, &slug, &lang)?;
+        let autolinked_markdown = Self::autolink_markdown(&wiki_linked_markdown);
+        let rendered = quoteback::render(&markdown::render(&autolinked_markdown, &self.config.markdown));
+        let mut html_content = math::restore(&rendered, &math_spans, self.config.math);
+        if self.config.mermaid.mode != MermaidMode::Off {
+            html_content = mermaid::mark(&html_content);
+        }
+        let links = if html_post { markdown::html_links(&body) } else { markdown::links(&wiki_linked_markdown, &self.config.markdown) };
+        let linked_slugs = links.iter().filter_map(|link| link_slug(&link.href)).collect();
         
         // Extract first paragraph for illuminated initial
         let first_paragraph_match = Regex::new(r"<p>(.*?)</p>").unwrap();
+        // Math reads as its TeX in descriptions, not as the text of MathML elements
+        let summary_html = if html_post { body.clone() } else { math::restore(&rendered, &math_spans, MathMode::Katex) };
         let first_paragraph = first_paragraph_match
-            .captures(&html_content)
+            .captures(&summary_html)
             .and_then(|caps| caps.get(1))
             .map(|m| m.as_str().to_string())
             .unwrap_or_default();
@@ -158,39 +1331,37 @@ impl SiteGenerator {
             html_unescape(line1)
         };
         
-        // Extract first letter from first paragraph
+        // Extract first letter from first paragraph; hand-written HTML keeps its own
         let first_letter = first_paragraph
             .chars()
+            .filter(|_| !html_post)
             .find(|c| c.is_alphabetic())
             .map(|c| c.to_uppercase().next().unwrap());
         
         // Extract title from frontmatter or filename
         let title = frontmatter
-            .get("title")
-            .and_then(|v| v.as_str())
+            .title
+            .clone()
             .unwrap_or_else(|| {
                 path.file_stem()
                     .and_then(|s| s.to_str())
                     .unwrap_or("Untitled")
-            })
-            .to_string();
+                    .to_string()
+            });
         
         // Extract date from frontmatter or file creation time (fallbacks to modified, then now)
         let date = frontmatter
-            .get("date")
-            .and_then(|v| v.as_str())
-            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-            .map(|dt| dt.with_timezone(&Utc))
+            .parsed_date()
             .unwrap_or_else(|| {
                 let meta_result = fs::metadata(path);
                 if let Ok(meta) = meta_result {
                     // Try created() first
                     if let Ok(ct) = meta.created() {
                         let secs = ct.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
-                        DateTime::from_timestamp(secs, 0).unwrap_or_else(|| Utc::now())
+                        DateTime::from_timestamp(secs, 0).unwrap_or_else(Utc::now)
                     } else if let Ok(mt) = meta.modified() {
                         let secs = mt.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
-                        DateTime::from_timestamp(secs, 0).unwrap_or_else(|| Utc::now())
+                        DateTime::from_timestamp(secs, 0).unwrap_or_else(Utc::now)
                     } else {
                         Utc::now()
                     }
@@ -199,22 +1370,23 @@ impl SiteGenerator {
                 }
             });
         
-        // Extract description/excerpt from frontmatter or first line of first paragraph
+        // Extract description/excerpt from frontmatter or first line of first paragraph, cut to
+        // excerpt length in the post's language
         let excerpt = frontmatter
-            .get("excerpt")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
+            .excerpt
+            .clone()
             .or_else(|| {
                 let desc = first_paragraph_text_line1.trim();
-                if desc.is_empty() { None } else { Some(desc.to_string()) }
+                if desc.is_empty() { None } else { Some(reading::excerpt(desc, &lang)) }
             });
+        let text = if html_post { markdown::html_text(&body) } else { markdown::plain_text(&markdown, &self.config.markdown) };
+        let words = reading::word_count(&text, &lang);
         
         let original_slug = path
             .file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("untitled")
             .to_string();
-        let slug = sanitize_slug(&original_slug);
         
         Ok(Post {
             slug,
@@ -222,16 +1394,69 @@ impl SiteGenerator {
             title,
             date,
             excerpt,
-            content: markdown,
+            content: if html_post { body } else { markdown },
             html_content,
             first_letter,
             frontmatter,
+            lang,
+            source: path.to_path_buf(),
+            raw_blocks,
+            words,
+            links,
+            linked_slugs,
+            webmentions: Vec::new(),
+            hidden: None,
         })
     }
 
+    /// Replace `[[slug]]` and `[[slug|Display Text]]` outside fenced and inline code with Markdown
+    /// links to the post's URL, relative to the linking post `from`. Targets may use the original
+    /// or sanitized slug, and resolve to the post in the linking post's language `lang` when
+    /// there is one; unknown targets are an error.
+    fn resolve_wiki_links(&self, markdown: &str, known_slugs: &HashSet<String>, from: &str, lang: &str) -> Result<String> {
+        let wiki_link = Regex::new(r"\[\[([^\[\]|]+)(?:\|([^\[\]]+))?\]\]").unwrap();
+        let policy = self.config.trailing_slash;
+        // Raw text and target of each link to an unknown post
+        let mut unknown: Vec<(String, String)> = Vec::new();
+        let mut fences = CodeFences::default();
+
+        let lines: Vec<String> = markdown
+            .lines()
+            .map(|line| {
+                if fences.is_code(line) {
+                    return line.to_string();
+                }
+                markdown::replace_outside_code(line, |text| {
+                    wiki_link
+                        .replace_all(text, |caps: &regex::Captures| {
+                            let target = caps[1].trim();
+                            let slug = target.split('/').map(sanitize_slug).collect::<Vec<_>>().join("/");
+                            let translated = format!("{}/{}", lang, slug);
+                            let slug = if self.config.languages.is_prefixed(lang) && known_slugs.contains(&translated) { translated } else { slug };
+                            if !known_slugs.contains(&slug) {
+                                unknown.push((caps[0].to_string(), target.to_string()));
+                                return caps[0].to_string();
+                            }
+                            let text = caps.get(2).map_or(target, |m| m.as_str().trim());
+                            format!("[{}]({})", text, policy.post_href(&policy.page_root(from), &slug))
+                        })
+                        .to_string()
+                })
+            })
+            .collect();
+
+        if let Some((text, _)) = unknown.first() {
+            let targets: Vec<&str> = unknown.iter().map(|(_, target)| target.as_str()).collect();
+            return Err(SourceError { text: text.clone(), message: format!("wiki link to unknown post {}", targets.join(", ")) }.into());
+        }
+        Ok(lines.join("\n"))
+    }
+
     /// Convert bare URLs in Markdown text to autolink format `<url>` while avoiding
     /// fenced code blocks and inline code spans.
     fn autolink_markdown(markdown: &str) -> String {
+        // Match a conservative URL, we'll handle trailing punctuation separately
+        let url = Regex::new(r"https?://[^\s<>()]+").unwrap();
         let mut result_lines: Vec<String> = Vec::new();
         let mut in_code_block = false;
         for line in markdown.lines() {
@@ -262,7 +1487,7 @@ impl SiteGenerator {
                         in_inline_code = false;
                     } else {
                         // Flush buffer with autolinking (outside code)
-                        processed_line.push_str(&Self::autolink_text(&buffer));
+                        processed_line.push_str(&Self::autolink_text(&buffer, &url));
                         buffer.clear();
                         processed_line.push('`');
                         in_inline_code = true;
@@ -275,7 +1500,7 @@ impl SiteGenerator {
                 // Remaining buffer is inside code
                 processed_line.push_str(&buffer);
             } else {
-                processed_line.push_str(&Self::autolink_text(&buffer));
+                processed_line.push_str(&Self::autolink_text(&buffer, &url));
             }
 
             result_lines.push(processed_line);
@@ -285,12 +1510,10 @@ impl SiteGenerator {
     }
 
     /// Autolink bare http/https URLs in a plain text segment (no inline/fenced code).
-    fn autolink_text(text: &str) -> String {
-        // Match a conservative URL, we'll handle trailing punctuation separately
-        let re = Regex::new(r"https?://[^\s<>()]+").unwrap();
+    fn autolink_text(text: &str, url: &Regex) -> String {
         let mut result = String::with_capacity(text.len());
         let mut last_end = 0;
-        for m in re.find_iter(text) {
+        for m in url.find_iter(text) {
             // Avoid transforming URLs that are part of a Markdown link: "](" immediately before
             let start = m.start();
             let end = m.end();
@@ -341,41 +1564,6 @@ impl SiteGenerator {
         (s[..end].to_string(), s[end..].to_string())
     }
 
-    fn parse_frontmatter(&self, content: &str) -> (HashMap<String, serde_json::Value>, String) {
-        let mut frontmatter = HashMap::new();
-        let mut lines = content.lines();
-        
-        // Check if content starts with frontmatter
-        if let Some(first_line) = lines.next() {
-            if first_line.trim() == "---" {
-                let mut frontmatter_lines = Vec::new();
-                
-                // Collect frontmatter lines
-                while let Some(line) = lines.next() {
-                    if line.trim() == "---" {
-                        break;
-                    }
-                    frontmatter_lines.push(line);
-                }
-                
-                // Parse frontmatter using serde_yaml
-                if !frontmatter_lines.is_empty() {
-                    let yaml_content = frontmatter_lines.join("\n");
-                    if let Ok(parsed) = serde_yaml::from_str::<serde_json::Value>(&yaml_content) {
-                        if let serde_json::Value::Object(map) = parsed {
-                            frontmatter = map.into_iter().collect();
-                        }
-                    }
-                }
-                
-                return (frontmatter, lines.collect::<Vec<_>>().join("\n"));
-            }
-        }
-        
-        // No frontmatter found
-        (frontmatter, content.to_string())
-    }
-
     async fn generate_initials(&self) -> Result<()> {
         let posts_with_initials: Vec<_> = self.posts
             .iter()
@@ -383,70 +1571,95 @@ impl SiteGenerator {
             .collect();
         
         if !posts_with_initials.is_empty() {
-            println!("{}", format!("Generating {} illuminated initials...", posts_with_initials.len()).cyan());
+            info!("{}", format!("Generating {} illuminated initials...", posts_with_initials.len()).cyan());
             
-            let initials_dir = Path::new(&self.config.output_dir).join("initials");
-            fs::create_dir_all(&initials_dir)?;
+            let output_dir = Path::new(&self.config.output_dir);
             
             // Generate initials using OpenAI if API key is available
             if let Some(api_key) = &self.config.openai_api_key {
                 let mut tasks = Vec::new();
+                let mut rate = HitRate::default();
+                // Posts sharing a letter share its initial unless initials are per post
+                let mut requested = HashSet::new();
                 
+                let mut missing = Vec::new();
                 for post in posts_with_initials {
                     if let Some(letter) = post.first_letter {
-                        let initial_path = initials_dir.join(format!("{}.txt", letter));
-                        if !initial_path.exists() {
-                            println!("Generating illuminated initial '{}'", letter.to_uppercase());
-                            let api_key = api_key.clone();
-                            let title = post.title.clone();
-                            let task = tokio::spawn(async move {
-                                Self::generate_illuminated_initial_static(letter, &title, &api_key).await
-                            });
-                            tasks.push((task, initial_path, letter));
+                        let initials_dir = initials::dir(output_dir, &self.config.initials, &post.slug);
+                        if !requested.insert((initials_dir.clone(), letter)) {
+                            continue;
+                        }
+                        if !initials::is_cached(&initials_dir, letter) {
+                            rate.misses += 1;
+                            missing.push((post, initials_dir, letter));
+                        } else {
+                            rate.hits += 1;
+                            info!("Illuminated initial for '{}' already exists, skipping", letter);
+                        }
+                    }
+                }
+                CacheStats::record(cache::STATS_PATH, |stats| stats.initials = Some(rate))?;
+                
+                let illuminated = Progress::new(missing.len() as u64, "Illuminating");
+                for (post, initials_dir, letter) in missing {
+                    // With a bar, it counts them instead
+                    if !illuminated.is_shown() {
+                        if self.config.initials.per_post {
+                            info!("Generating illuminated initial '{}' for {}", letter.to_uppercase(), post.slug);
                         } else {
-                            println!("Illuminated initial for '{}' already exists, skipping", letter);
+                            info!("Generating illuminated initial '{}'", letter.to_uppercase());
                         }
                     }
+                    let api_key = api_key.clone();
+                    let title = post.title.clone();
+                    let options = self.config.initials.clone();
+                    let task = tokio::spawn(async move {
+                        Self::generate_illuminated_initial_static(letter, &title, &options, &api_key).await
+                    });
+                    tasks.push((task, initials_dir, letter));
                 }
                 
                 // Wait for all tasks to complete
-                for (task, initial_path, letter) in tasks {
+                for (task, initials_dir, letter) in tasks {
                     match task.await {
-                        Ok(Ok(image_url)) => {
-                            println!("Successfully generated illuminated initial for '{}'", letter);
-                            fs::write(initial_path, image_url)?;
+                        Ok(Ok(image)) => {
+                            illuminated.inc_or_log(format!("Successfully generated illuminated initial for '{}'", letter));
+                            fs::create_dir_all(&initials_dir)?;
+                            image.save(&initials_dir, letter)?;
                         }
                         Ok(Err(e)) => {
-                            eprintln!("Failed to generate illuminated initial for '{}': {}", letter, e);
+                            error!("Failed to generate illuminated initial for '{}': {}", letter, e);
                         }
                         Err(e) => {
-                            eprintln!("Task failed for illuminated initial '{}': {}", letter, e);
+                            error!("Task failed for illuminated initial '{}': {}", letter, e);
                         }
                     }
                 }
             } else {
-                println!("{}", "Warning: OPENAI_API_KEY not found in environment. Skipping illuminated initials.".yellow());
+                warn!("{}", "Warning: OPENAI_API_KEY not found in environment. Skipping illuminated initials.".yellow());
             }
         }
         
         Ok(())
     }
 
-    pub async fn generate_illuminated_initial_static(letter: char, _title: &str, api_key: &str) -> Result<String> {
+    /// Request an illuminated initial for `letter` and return the decoded image.
+    pub async fn generate_illuminated_initial_static(letter: char, title: &str, options: &InitialsOptions, api_key: &str) -> Result<initials::Image> {
         let client = reqwest::Client::new();
         
-        let prompt = format!(
-            "A black background with white ink drawing featuring an illuminated initial '{}' in the Italian Futurist style, with geometric and abstract forms, swirling lines, and dynamic composition reminiscent of early 20th-century avant-garde art. The background should be pure black with white forms and lines.",
-            letter
-        );
-        
-        // Use the DALL-E API endpoint with gpt-image-1 model
-        let request_body = serde_json::json!({
-            "model": "gpt-image-1",
-            "prompt": prompt,
+        let mut request_body = serde_json::json!({
+            "model": options.model,
+            "prompt": options.prompt_for(letter, title),
             "n": 1,
-            "size": "1024x1024"
+            "size": options.size
         });
+        // DALL-E models only return base64 when asked and always produce PNG; GPT image models
+        // always return base64 and take the format
+        if options.model.starts_with("dall-e") {
+            request_body["response_format"] = "b64_json".into();
+        } else {
+            request_body["output_format"] = options.format.as_str().into();
+        }
         
         let response = client
             .post("https://api.openai.com/v1/images/generations")
@@ -469,7 +1682,11 @@ impl SiteGenerator {
         if let Some(data_array) = response_json.get("data").and_then(|d| d.as_array()) {
             if let Some(first_image) = data_array.first() {
                 if let Some(b64_json) = first_image.get("b64_json").and_then(|b| b.as_str()) {
-                    return Ok(format!("data:image/png;base64,{}", b64_json));
+                    let format = if options.model.starts_with("dall-e") { "png" } else { options.format.as_str() };
+                    return Ok(initials::Image {
+                        bytes: base64::engine::general_purpose::STANDARD.decode(b64_json).context("Invalid base64 image data")?,
+                        extension: format.to_string(),
+                    });
                 }
             }
         }
@@ -477,71 +1694,553 @@ impl SiteGenerator {
         Err(anyhow::anyhow!("Could not extract image data from API response"))
     }
 
-    async fn generate_posts(&self) -> Result<()> {
-        let mut tasks = Vec::new();
+    /// Render every output file (post pages, index, stylesheet) as `(path, content)` pairs.
+    async fn render_outputs(&self) -> Result<Vec<(PathBuf, String)>> {
+        if self.format == BuildFormat::Json {
+            let output_dir = Path::new(&self.config.output_dir);
+            // Private posts have no page there to seal, so they stay out
+            let posts: Vec<Post> = self.posts.iter().filter(|p| !p.is_private()).cloned().collect();
+            return Ok(api::content_bundle(&self.config, &posts).into_iter().map(|(path, json)| (output_dir.join(path), json)).collect());
+        }
+        let mut outputs = self.render_posts().await?;
+        let output_dir = Path::new(&self.config.output_dir);
         
+        // Private posts' pages are set aside for prompt pages, finished along with the rest and
+        // sealed into them at the end
+        let mut private_pages = Vec::new();
+        let mut private_slugs = Vec::new();
+        for post in self.selected_posts().into_iter().filter(|p| p.is_private()) {
+            let path = output_dir.join(self.config.trailing_slash.post_path(&post.slug));
+            if let Some((_, page)) = outputs.iter_mut().find(|(p, _)| *p == path) {
+                let prompt = templates::render_private(&self.config, post)?;
+                private_pages.push((path, std::mem::replace(page, prompt)));
+                private_slugs.push(post.slug.clone());
+            }
+        }
+        
+        // Index page, one per language on a multilingual site
+        let languages = &self.config.languages;
+        for lang in languages.codes() {
+            let posts: Vec<&Post> = self.posts.iter().filter(|p| !languages.is_multilingual() || p.lang == lang).collect();
+            let index_path = output_dir.join(languages.index_path(lang)).join("index.html");
+            if self.config.debug_templates {
+                let fields = serde_json::json!({ "lang": lang, "posts": &posts });
+                outputs.push((debug_context_path(&index_path), templates::debug_context(&self.config, "index", fields)));
+            }
+            outputs.push((index_path, templates::render_index(&self.config, &posts, lang)?));
+        }
+        
+        // Series landing pages (skipped for partial builds)
+        let series: BTreeSet<&str> = self.posts.iter().filter_map(|p| p.series()).collect();
+        for name in series.into_iter().filter(|_| self.only.is_empty()) {
+            let members = series_members(&self.posts, name);
+            let path = output_dir.join(self.config.trailing_slash.post_path(&series_path(name)));
+            if self.config.debug_templates {
+                let fields = serde_json::json!({ "name": name, "posts": &members });
+                outputs.push((debug_context_path(&path), templates::debug_context(&self.config, "series", fields)));
+            }
+            outputs.push((path, templates::render_series(&self.config, name, &members)?));
+        }
+        
+        // Curated trail pages (skipped for partial builds)
+        for trail in self.trails.iter().filter(|_| self.only.is_empty()) {
+            for slug in trail.unknown_slugs(&self.posts) {
+                warn!("{}", format!("Warning: trail '{}' lists '{}', which is not a post", trail.name, slug).yellow());
+            }
+            let members = trail.members(&self.posts);
+            let path = output_dir.join(self.config.trailing_slash.post_path(&trail.path()));
+            if self.config.debug_templates {
+                let steps: Vec<_> = members.iter().map(|(post, blurb)| serde_json::json!({ "post": post, "blurb": blurb })).collect();
+                let fields = serde_json::json!({ "name": &trail.name, "description": &trail.description, "steps": steps });
+                outputs.push((debug_context_path(&path), templates::debug_context(&self.config, "trail", fields)));
+            }
+            outputs.push((path, templates::render_trail(&self.config, trail, &members)?));
+        }
+        
+        // Pages at former slugs forwarding to the post
+        let slugs: HashSet<&str> = self.posts.iter().map(|p| p.slug.as_str()).collect();
+        let mut moved: Vec<(String, String)> = Vec::new();
+        let mut gone: Vec<String> = Vec::new();
         for post in &self.posts {
-            let config = self.config.clone();
+            for alias in post.frontmatter.aliases.iter().map(|a| sanitize_slug(a)) {
+                if slugs.contains(alias.as_str()) {
+                    warn!("{}", format!("Warning: alias '{}' of '{}' is taken by another post; no redirect written", alias, post.slug).yellow());
+                    continue;
+                }
+                let path = output_dir.join(self.config.trailing_slash.post_path(&alias));
+                outputs.push((path, templates::render_redirect(&self.config, &alias, post)));
+                moved.push((alias, post.slug.clone()));
+            }
+        }
+        
+        // Collection hubs, plus a page per member pointing at its canonical URL rather than a copy
+        // (skipped for partial builds)
+        let mut collections: BTreeMap<String, (&str, Vec<&Post>)> = BTreeMap::new();
+        for post in &self.posts {
+            for name in post.collections() {
+                collections.entry(sanitize_slug(name)).or_insert((name, Vec::new())).1.push(post);
+            }
+        }
+        let reserved = reserved_slugs(&self.config);
+        for (path, (name, members)) in collections.into_iter().filter(|_| self.only.is_empty()) {
+            if slugs.contains(path.as_str()) || reserved.contains(&path) || self.config.languages.names.contains_key(&path) {
+                warn!("{}", format!("Warning: collection '{}' clashes with an existing page; no hub written", name).yellow());
+                continue;
+            }
+            let hub = output_dir.join(self.config.trailing_slash.post_path(&path));
+            if self.config.debug_templates {
+                let fields = serde_json::json!({ "name": name, "posts": &members });
+                outputs.push((debug_context_path(&hub), templates::debug_context(&self.config, "collection", fields)));
+            }
+            outputs.push((hub, templates::render_collection(&self.config, name, &path, &members)?));
+            for post in members {
+                let alias = format!("{}/{}", path, post.slug);
+                let page = output_dir.join(self.config.trailing_slash.post_path(&alias));
+                outputs.push((page, templates::render_collection_alias(&self.config, &alias, post)));
+            }
+        }
+
+        // Notices or redirects at the URLs of trashed posts
+        if self.config.trash.page != TrashedPage::Remove {
+            let redirect_to = self.config.trash.redirect_to.as_deref().map(sanitize_slug);
+            let target = redirect_to.as_ref().and_then(|slug| self.posts.iter().find(|p| &p.slug == slug));
+            if self.config.trash.page == TrashedPage::Redirect && redirect_to.is_some() && target.is_none() {
+                warn!("{}", format!("Warning: trash.redirect_to '{}' is not a post; redirecting to the home page", redirect_to.as_deref().unwrap_or_default()).yellow());
+            }
+            for slug in self.trashed_slugs() {
+                let path = output_dir.join(self.config.trailing_slash.post_path(&slug));
+                let page = match self.config.trash.page {
+                    TrashedPage::Redirect => {
+                        moved.push((slug.clone(), target.map(|p| p.slug.clone()).unwrap_or_default()));
+                        templates::render_trashed_redirect(&self.config, &slug, target)
+                    }
+                    _ => {
+                        gone.push(slug.clone());
+                        templates::render_gone(&self.config, &slug)?
+                    }
+                };
+                outputs.push((path, page));
+            }
+        }
+        
+        // Every post by year and month
+        if self.config.archive {
+            let path = output_dir.join(self.config.trailing_slash.post_path(ARCHIVE_PAGE));
+            if self.config.debug_templates {
+                let fields = serde_json::json!({ "posts": &self.posts });
+                outputs.push((debug_context_path(&path), templates::debug_context(&self.config, "archive", fields)));
+            }
+            outputs.push((path, templates::render_archive(&self.config, &self.posts)?));
+        }
+        
+        // Link graph data and explorer page
+        if self.config.graph.enabled {
+            let graph = LinkGraph::build(&self.posts, self.config.graph.tags, None, None, self.config.trailing_slash);
+            let graph_json = graph.render(GraphFormat::Json);
+            let path = output_dir.join(self.config.trailing_slash.post_path(GRAPH_PAGE));
+            outputs.push((path, templates::render_graph(&self.config, &graph_json)?));
+            outputs.push((output_dir.join(api::GRAPH_FILE), graph_json));
+        }
+        
+        // Error page for unknown URLs, used by `scribe serve`, IPFS gateways and most static hosts
+        outputs.push((output_dir.join(NOT_FOUND_PAGE), templates::render_not_found(&self.config)?));
+        
+        // Feeds and the sitemap need absolute URLs, so only sites with a `url` get them. Neither
+        // depends on the build time, so a file is only rewritten when its entries change
+        if let Some(site_url) = self.config.site_url() {
+            for lang in languages.codes() {
+                let posts: Vec<&Post> =
+                    self.posts.iter().filter(|p| !p.is_private() && (!languages.is_multilingual() || p.lang == lang)).collect();
+                let path = output_dir.join(languages.index_path(lang)).join(feed::FILE);
+                outputs.push((path, feed::render(&self.config, &posts, lang, &site_url)));
+                let path = output_dir.join(languages.index_path(lang)).join(feed::JSON_FILE);
+                outputs.push((path, feed::render_json(&self.config, &posts, lang, &site_url)));
+            }
+            for (name, xml) in sitemap::render(&self.sitemap_entries(&site_url), &site_url) {
+                outputs.push((output_dir.join(name), xml));
+            }
+        }
+        
+        // Assets
+        outputs.push((output_dir.join(STYLESHEET), self.stylesheet()?));
+        if let Ok(script) = fs::read_to_string(CUSTOM_SCRIPT) {
+            outputs.push((output_dir.join(CUSTOM_SCRIPT_FILE), script));
+        }
+        
+        // Outputs of registered emitters, which may not replace any of the site's own files
+        for name in self.config.emitters.keys().filter(|name| !self.emitters.iter().any(|e| e.name() == name.as_str())) {
+            warn!("{}", format!("Warning: no emitter named '{}' is registered; skipping it", name).yellow());
+        }
+        let site = SiteContext { config: &self.config, posts: &self.posts, trails: &self.trails };
+        let mut taken: HashSet<PathBuf> = outputs.iter().map(|(path, _)| path.clone()).collect();
+        for emitter in &self.emitters {
+            let Some(options) = self.config.emitters.get(emitter.name()) else { continue };
+            let files = emitter.emit(&site, options).context(format!("Emitter '{}' failed", emitter.name()))?;
+            for file in files {
+                emit::check_path(emitter.name(), &file.path)?;
+                let path = output_dir.join(&file.path);
+                if file.path == Path::new(BUILD_STAMP_FILE) || !taken.insert(path.clone()) {
+                    anyhow::bail!("Emitter '{}' wrote to {}, which another output already uses", emitter.name(), file.path.display());
+                }
+                outputs.push((path, file.content));
+            }
+        }
+        
+        // Links within the site relative to each page, so it works from any root: a domain, a
+        // subdirectory or an IPFS path gateway. The 404 page is served at any depth, so it keeps
+        // links from the base path
+        let dirs: HashSet<PathBuf> = outputs
+            .iter()
+            .filter(|(path, _)| path.file_name().is_some_and(|name| name == "index.html"))
+            .filter_map(|(path, _)| path.parent()?.strip_prefix(output_dir).ok().map(Path::to_path_buf))
+            .collect();
+        let base_path = self.config.base_path();
+        for (path, content) in outputs.iter_mut().chain(private_pages.iter_mut()) {
+            let Ok(page) = path.strip_prefix(output_dir) else { continue };
+            if path.extension().is_some_and(|ext| ext == "html") && page != Path::new(NOT_FOUND_PAGE) {
+                *content = gateway::relativize(content, page, &base_path, |dir| dirs.contains(dir) || output_dir.join(dir).join("index.html").is_file());
+            }
+        }
+        
+        // Redirects for hosts that read them; path gateways fall back on the redirect pages. A
+        // partial build doesn't know every page, so it keeps the last file
+        let pages: Vec<String> = outputs
+            .iter()
+            .filter_map(|(path, _)| path.strip_prefix(output_dir).ok()?.to_str()?.strip_suffix(".html").map(str::to_string))
+            .filter(|page| page != "index" && !page.ends_with("/index") && format!("{}.html", page) != NOT_FOUND_PAGE)
+            .collect();
+        if self.only.is_empty() {
+            outputs.push((output_dir.join(gateway::FILE), gateway::render(&gateway::rules(&self.config, &moved, &gone, &pages))));
+        }
+        
+        // Smaller pages, and a stylesheet whose name changes with its content
+        if self.release {
+            let stylesheet = output_dir.join(STYLESHEET);
+            for (path, content) in outputs.iter_mut().chain(private_pages.iter_mut()) {
+                if path.extension().is_some_and(|ext| ext == "html") {
+                    *content = minify::html(content);
+                } else if *path == stylesheet {
+                    *content = minify::css(content);
+                }
+            }
+            if let Some(index) = outputs.iter().position(|(path, _)| *path == stylesheet) {
+                let name = fingerprinted_stylesheet(&outputs[index].1);
+                outputs[index].0 = output_dir.join(&name);
+                let link = Regex::new(&format!(r#"(href="(?:[^"]*/)?){}""#, regex::escape(STYLESHEET))).unwrap();
+                for (path, content) in outputs.iter_mut().chain(private_pages.iter_mut()) {
+                    if path.extension().is_some_and(|ext| ext == "html") {
+                        *content = link.replace_all(content, format!("${{1}}{}\"", name)).to_string();
+                    }
+                }
+            }
+        }
+        
+        // Which scribe build produced the site
+        if self.config.build_stamp {
+            let stamp = BuildStamp { version: env!("CARGO_PKG_VERSION"), built: self.content_time(), posts: self.posts.len() };
+            for (path, content) in outputs.iter_mut().chain(private_pages.iter_mut()) {
+                if path.extension().is_some_and(|ext| ext == "html") {
+                    *content = stamp.annotate(content);
+                }
+            }
+            outputs.push((output_dir.join(BUILD_STAMP_FILE), serde_json::to_string_pretty(&stamp)?));
+        }
+        
+        // Private pages sealed into their prompt pages, now that they are final
+        if let Some(passphrase) = &self.config.private_passphrase {
+            for ((path, page), slug) in private_pages.into_iter().zip(&private_slugs) {
+                if let Some((_, prompt)) = outputs.iter_mut().find(|(p, _)| *p == path) {
+                    *prompt = private::fill(prompt, &page, passphrase, slug)?;
+                }
+            }
+        }
+        
+        // A service worker precaching what the build produced, or one retiring the worker of an
+        // earlier build. Partial builds don't know every page, so they leave it alone
+        if self.only.is_empty() {
+            let worker = output_dir.join(pwa::WORKER_FILE);
+            if self.config.pwa.enabled {
+                outputs.push((output_dir.join(pwa::MANIFEST_FILE), pwa::manifest(&self.config)));
+                let files = self.precache(&outputs);
+                let files: Vec<(String, &[u8])> = files.iter().map(|(path, content)| (path.clone(), content.as_slice())).collect();
+                outputs.push((worker, pwa::worker(&files)));
+            } else if worker.is_file() {
+                outputs.push((worker, pwa::RETIRED_WORKER.to_string()));
+            }
+        }
+        
+        Ok(outputs)
+    }
+
+    /// Time the site's content is as of, for the build stamp, so unchanged content builds the
+    /// same: `SOURCE_DATE_EPOCH` (seconds) when set, as reproducible builds expect, otherwise the
+    /// newest post's `updated` or `date`.
+    fn content_time(&self) -> DateTime<Utc> {
+        let epoch = std::env::var("SOURCE_DATE_EPOCH").ok().and_then(|s| s.trim().parse::<i64>().ok());
+        match epoch.and_then(|secs| DateTime::from_timestamp(secs, 0)) {
+            Some(time) => time,
+            None => self.posts.iter().map(|p| p.frontmatter.parsed_updated().unwrap_or(p.date)).max().unwrap_or_default(),
+        }
+    }
+
+    /// Files a service worker precaches, by request path with their content: the pages and
+    /// assets among `outputs`, and the images, fonts and scripts already in the output directory,
+    /// leaving out those over the size limit.
+    fn precache(&self, outputs: &[(PathBuf, String)]) -> Vec<(String, Vec<u8>)> {
+        let output_dir = Path::new(&self.config.output_dir);
+        let limit = self.config.pwa.precache_max_kib * 1024;
+        let relative = |path: &Path| path.strip_prefix(output_dir).ok().and_then(|p| p.to_str()).map(|p| p.replace('\\', "/"));
+        let mut files: BTreeMap<String, Vec<u8>> = outputs
+            .iter()
+            .filter(|(_, content)| content.len() as u64 <= limit)
+            .filter_map(|(path, content)| Some((relative(path)?, content.as_bytes().to_vec())))
+            .filter(|(path, _)| pwa::is_precached(path))
+            .collect();
+        // Pages and stylesheets on disk that weren't produced are stale, so only assets are
+        // picked up there
+        let on_disk: Vec<PathBuf> = WalkDir::new(output_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && e.metadata().is_ok_and(|m| m.len() <= limit))
+            .map(|e| e.into_path())
+            .collect();
+        for path in on_disk {
+            let Some(name) = relative(&path) else { continue };
+            if files.contains_key(&name) || name.ends_with(".html") || is_stylesheet(&name) || name == pwa::WORKER_FILE || !pwa::is_precached(&name) {
+                continue;
+            }
+            if let Ok(content) = fs::read(&path) {
+                files.insert(name, content);
+            }
+        }
+        files.into_iter().map(|(path, content)| (pwa::request_path(self.config.trailing_slash, &path), content)).collect()
+    }
+
+    /// Every indexable page: posts oldest first, so new posts land in the last sitemap chunk, then
+    /// the home pages and listings.
+    fn sitemap_entries(&self, site_url: &str) -> Vec<sitemap::Entry> {
+        let policy = self.config.trailing_slash;
+        let languages = &self.config.languages;
+        let modified = |post: &Post| post.frontmatter.parsed_updated().unwrap_or(post.date);
+
+        let mut posts: Vec<&Post> = self.posts.iter().filter(|p| !p.is_private()).collect();
+        posts.sort_by(|a, b| (a.date, &a.slug).cmp(&(b.date, &b.slug)));
+        let mut entries: Vec<sitemap::Entry> = posts
+            .iter()
+            .map(|post| sitemap::Entry { loc: policy.post_href(site_url, &post.slug), lastmod: Some(modified(post)) })
+            .collect();
+
+        for lang in languages.codes() {
+            let newest = self.posts.iter().filter(|p| !languages.is_multilingual() || p.lang == lang).map(modified).max();
+            entries.push(sitemap::Entry { loc: format!("{}{}", site_url, languages.index_path(lang)), lastmod: newest });
+        }
+        let series: BTreeSet<&str> = self.posts.iter().filter_map(|p| p.series()).collect();
+        let mut pages: Vec<String> = series.into_iter().map(series_path).collect();
+        pages.extend(self.trails.iter().map(Trail::path));
+        if self.config.archive {
+            pages.push(ARCHIVE_PAGE.to_string());
+        }
+        if self.config.graph.enabled {
+            pages.push(GRAPH_PAGE.to_string());
+        }
+        entries.extend(pages.iter().map(|page| sitemap::Entry { loc: policy.post_href(site_url, page), lastmod: None }));
+        entries
+    }
+
+    /// Posts picked by [`SiteGenerator::set_only`], or all of them.
+    pub fn selected_posts(&self) -> Vec<&Post> {
+        self.posts.iter().filter(|p| self.is_selected(p)).collect()
+    }
+
+    fn is_selected(&self, post: &Post) -> bool {
+        self.only.is_empty()
+            || self.only.iter().any(|filter| {
+                post.slug == *filter
+                    || post.original_slug == *filter
+                    || post.tags().iter().any(|t| t == filter)
+                    || glob_matches(filter, &post.slug)
+                    || glob_matches(filter, &post.original_slug)
+            })
+    }
+
+    async fn render_posts(&self) -> Result<Vec<(PathBuf, String)>> {
+        let mut tasks = Vec::new();
+        // Private posts' pages are rendered with their content, to be sealed in `render_outputs`
+        let revealed: Vec<Post> = self.selected_posts().into_iter().map(Post::revealed).collect();
+        let selected: Vec<&Post> = revealed.iter().collect();
+        if !self.only.is_empty() {
+            info!("{}", format!("Rendering {} of {} posts", selected.len(), self.posts.len()).cyan());
+        }
+        let mut url_meta = load_url_meta();
+        // Frozen builds use the locked metadata, however old
+        for (url, meta) in self.locked.metadata() {
+            url_meta.insert(url.to_string(), meta.clone());
+        }
+        let offline = self.dry_run || self.config.offline;
+        let fetches = if offline { 0 } else { uncached_urls(&selected, &url_meta) };
+        let url_meta = Arc::new(Mutex::new(url_meta));
+        
+        // Self-host favicons for the linked sites shown in annotation panels and quotebacks
+        let hosts: BTreeSet<String> = selected
+            .iter()
+            .flat_map(|p| annotation_urls(p))
+            .filter_map(|url| favicon::host(&url).map(str::to_string))
+            .collect();
+        let favicons = Arc::new(favicon::cache(hosts, Path::new(&self.config.output_dir), offline).await);
+        
+        // Shared by every task rather than copied into each, which is quadratic in the post count
+        let config = Arc::new(self.config.clone());
+        let all_posts: Arc<[Post]> = self.posts.clone().into();
+        let trails: Arc<[Trail]> = self.trails.clone().into();
+        let rendered = Arc::new(Progress::new(selected.len() as u64, "Rendering"));
+        let fetched = Arc::new(Progress::new(fetches as u64, "Fetching"));
+        for post in selected {
+            let rendered = rendered.clone();
+            let fetched = fetched.clone();
+            let config = config.clone();
             let post = post.clone();
-            let all_posts = self.posts.clone();
+            let all_posts = all_posts.clone();
+            let trails = trails.clone();
+            let url_meta = url_meta.clone();
+            let favicons = favicons.clone();
+            let source = post.source.clone();
             
             let task = tokio::spawn(async move {
-                let post_dir = Path::new(&config.output_dir).join(&post.slug);
-                fs::create_dir_all(&post_dir)?;
+                let output_path = Path::new(&config.output_dir).join(config.trailing_slash.post_path(&post.slug));
                 
                 // Build annotation metadata JSON (URL -> { title, description })
-                let annotation_meta_json = build_annotation_meta_json(&post).await;
+                let annotation_meta_json = build_annotation_meta_json(&post, &url_meta, &favicons, offline, &fetched).await;
 
-                let html = templates::render_post(&config, &post, &all_posts, annotation_meta_json)?;
-                let output_path = post_dir.join("index.html");
-                fs::write(output_path, html)?;
-                Ok::<(), anyhow::Error>(())
+                let html = templates::render_post(&config, &post, &all_posts, &trails, annotation_meta_json);
+                rendered.inc(&post.slug);
+                let html = html?;
+                let mut outputs = Vec::new();
+                if config.debug_templates && !post.is_private() {
+                    let fields = templates::post_context(&config, &post, &all_posts);
+                    outputs.push((debug_context_path(&output_path), templates::debug_context(&config, "post", fields)));
+                }
+                outputs.push((output_path, html));
+                if config.api && !post.is_private() {
+                    let path = Path::new(&config.output_dir).join(api::post_path(config.trailing_slash, &post.slug));
+                    outputs.push((path, api::render_post(&config, &post)));
+                }
+                // The post's own stylesheets and scripts, next to its page
+                for asset in post.assets().filter(|a| !is_remote_asset(a)) {
+                    let Some(path) = post.asset_path(asset) else {
+                        warn!("{}", format!("Warning: '{}' in {} leaves the post's directory; not copied", asset, post.source.display()).yellow());
+                        continue;
+                    };
+                    let from = post.source.parent().unwrap_or(Path::new("")).join(asset);
+                    match fs::read_to_string(&from) {
+                        Ok(content) => outputs.push((Path::new(&config.output_dir).join(path), content)),
+                        Err(e) => warn!("{}", format!("Warning: can't read '{}' for {}: {}", asset, post.source.display(), e).yellow()),
+                    }
+                }
+                Ok::<Vec<(PathBuf, String)>, anyhow::Error>(outputs)
             });
             
-            tasks.push(task);
+            tasks.push((task, source));
         }
         
-        // Wait for all tasks to complete
-        for task in tasks {
+        // The tasks hold the other references, so the bars clear once the last post is done
+        drop((rendered, fetched));
+        
+        // Wait for all tasks to complete; a post that fails to render doesn't stop the others
+        let mut outputs = Vec::with_capacity(tasks.len());
+        for (task, source) in tasks {
             match task.await {
-                Ok(Ok(())) => {
-                    // Task completed successfully
+                Ok(Ok(output)) => {
+                    outputs.extend(output);
                 }
                 Ok(Err(e)) => {
-                    return Err(e);
+                    self.failures.lock().unwrap().push(PostFailure::new(&source, &e));
                 }
                 Err(e) => {
-                    return Err(anyhow::anyhow!("Task failed: {}", e));
+                    self.failures.lock().unwrap().push(PostFailure::new(&source, &anyhow::anyhow!("Task failed: {}", e)));
                 }
             }
         }
         
-        Ok(())
-    }
-
-    async fn generate_index(&self) -> Result<()> {
-        let html = templates::render_index(&self.config, &self.posts)?;
-        let output_path = Path::new(&self.config.output_dir).join("index.html");
-        fs::write(output_path, html)?;
+        if !self.dry_run && !self.frozen {
+            let url_meta = url_meta.lock().unwrap();
+            url_meta.save(cache::URL_META_PATH)?;
+            let rate = url_meta.rate;
+            CacheStats::record(cache::STATS_PATH, |stats| stats.url_meta = Some(rate))?;
+        }
         
-        Ok(())
+        Ok(outputs)
     }
 
-    async fn copy_assets(&self) -> Result<()> {
-        // Copy CSS file
-        let css_content = templates::generate_css(&self.config);
-        let css_path = Path::new(&self.config.output_dir).join("style.css");
-        fs::write(css_path, css_content)?;
+    /// Fill the illuminated-initial and URL-metadata caches for every post without writing pages.
+    pub async fn warm_caches(&mut self) -> Result<()> {
+        if self.config.offline {
+            anyhow::bail!("Warming caches needs network access; drop --offline");
+        }
+        self.read_posts().await?;
+        // SVG initials are drawn at render time, so only OpenAI ones are fetched ahead
+        if self.config.initials.mode == InitialsMode::Openai {
+            if self.config.openai_api_key.is_some() {
+                self.generate_initials().await?;
+            } else {
+                info!("{}", "OPENAI_API_KEY not set, skipping illuminated initials".yellow());
+            }
+        }
+        
+        let url_meta = load_url_meta();
+        let fetched = Progress::new(uncached_urls(&self.posts.iter().collect::<Vec<_>>(), &url_meta) as u64, "Fetching");
+        let url_meta = Mutex::new(url_meta);
+        for post in &self.posts {
+            build_annotation_meta_json(post, &url_meta, &BTreeMap::new(), false, &fetched).await;
+        }
+        drop(fetched);
+        let url_meta = url_meta.into_inner().unwrap();
+        url_meta.save(cache::URL_META_PATH)?;
+        info!("{}", format!("URL metadata: {} cached, {} fetched", url_meta.rate.hits, url_meta.rate.misses).green());
         
+        let hosts: BTreeSet<String> = self
+            .posts
+            .iter()
+            .flat_map(annotation_urls)
+            .filter_map(|url| favicon::host(&url).map(str::to_string))
+            .collect();
+        let wanted = hosts.len();
+        let cached = favicon::cache(hosts, Path::new(&self.config.output_dir), false).await;
+        info!("{}", format!("Favicons: {} of {} sites", cached.len(), wanted).green());
         Ok(())
     }
 } 
 
-/// Extract external URLs from annotation sections in raw markdown and fetch metadata.
-async fn build_annotation_meta_json(post: &Post) -> Option<String> {
+/// `page.html` -> `page.context.json`, written alongside the page by `--debug-templates`.
+fn debug_context_path(page: &Path) -> PathBuf {
+    page.with_extension("context.json")
+}
+
+/// Links per post whose metadata is fetched.
+const MAX_FETCHES_PER_POST: usize = 32;
+
+/// How many distinct links of `posts` have no cached metadata yet.
+fn uncached_urls(posts: &[&Post], url_meta: &UrlMetaCache) -> usize {
+    posts
+        .iter()
+        .flat_map(|p| annotation_urls(p).into_iter().take(MAX_FETCHES_PER_POST))
+        .filter(|url| !url_meta.is_fresh(url))
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// The URL metadata cache, starting empty if it can't be read.
+fn load_url_meta() -> UrlMetaCache {
+    UrlMetaCache::load(cache::URL_META_PATH).unwrap_or_else(|e| {
+        warn!("{}", format!("Warning: {}; starting with an empty cache", e).yellow());
+        UrlMetaCache::default()
+    })
+}
+
+/// External URLs from annotation sections and links in the post, whose metadata the page embeds.
+fn annotation_urls(post: &Post) -> BTreeSet<String> {
     let markdown = &post.content;
     // Collect URLs from fenced blocks ```links/```anno and from a 'Links:' marker followed by list
-    let mut urls: HashSet<String> = HashSet::new();
+    let mut urls: BTreeSet<String> = BTreeSet::new();
 
     // Simple stateful parse for fenced blocks
     let mut in_links_block = false;
@@ -567,13 +2266,14 @@ async fn build_annotation_meta_json(post: &Post) -> Option<String> {
 
     // Also collect any anchors from rendered HTML content as a fallback
     let html = &post.html_content;
-    if let Some(re) = Regex::new(r#"(?is)<a[^>]+href\s*=\s*([\"'])(https?://[^\"'>\s]+)\1"#).ok() {
+    if let Ok(re) = Regex::new(r#"(?is)<a[^>]+href\s*=\s*[\"'](https?://[^\"'>\s]+)[\"']"#) {
         for cap in re.captures_iter(html) {
-            if let Some(m) = cap.get(2) { urls.insert(m.as_str().to_string()); }
+            if let Some(m) = cap.get(1) { urls.insert(m.as_str().to_string()); }
         }
     }
 
     // Parse list after 'Links:' marker
+    let ordered_item = Regex::new(r"^\d+[\.)]\s+").unwrap();
     let mut lines_iter = markdown.lines().peekable();
     while let Some(line) = lines_iter.next() {
         let t = line.trim();
@@ -581,11 +2281,11 @@ async fn build_annotation_meta_json(post: &Post) -> Option<String> {
             // Consume subsequent list items
             while let Some(next) = lines_iter.peek() {
                 let nt = next.trim();
-                if nt.starts_with("-") || nt.starts_with("*") || Regex::new(r"^\d+[\.)]\s+").unwrap().is_match(nt) { // bullet or ordered list
+                if nt.starts_with("-") || nt.starts_with("*") || ordered_item.is_match(nt) { // bullet or ordered list
                     let content = if nt.starts_with("-") || nt.starts_with("*") {
                         nt.trim_start_matches(|c: char| c == '-' || c == '*' || c.is_whitespace()).trim().to_string()
                     } else {
-                        Regex::new(r"^\d+[\.)]\s+").unwrap().replace(nt, "").to_string()
+                        ordered_item.replace(nt, "").to_string()
                     };
                     if let Some(u) = extract_url_from_line(content.trim()) {
                         urls.insert(u);
@@ -598,37 +2298,75 @@ async fn build_annotation_meta_json(post: &Post) -> Option<String> {
         }
     }
 
+    urls
+}
+
+/// Build the annotation metadata JSON for a post, fetching metadata missing from the cache
+/// unless `offline` (dry runs and offline builds), in which case only cached metadata is used. Entries whose host
+/// is in `favicons` get a `favicon` path relative to the site root.
+async fn build_annotation_meta_json(
+    post: &Post,
+    url_meta: &Mutex<UrlMetaCache>,
+    favicons: &BTreeMap<String, String>,
+    offline: bool,
+    fetched: &Progress,
+) -> Option<String> {
+    let urls = annotation_urls(post);
     if urls.is_empty() { return None; }
 
-    // Fetch metadata concurrently with a simple cap
+    // Fetch metadata missing from the cache concurrently with a simple cap
     let client = reqwest::Client::new();
+    let mut found = Vec::new();
     let mut tasks = Vec::new();
-    for url in urls.into_iter().take(32) { // limit to 32 per post
+    for url in urls.into_iter().take(MAX_FETCHES_PER_POST) {
+        if let Some(meta) = url_meta.lock().unwrap().get(&url) {
+            found.push((url, meta));
+            continue;
+        }
+        if offline {
+            continue;
+        }
         let client = client.clone();
         tasks.push(tokio::spawn(async move {
-            let meta = fetch_url_metadata(&client, &url).await.unwrap_or_default();
+            let meta = fetch_url_metadata(&client, &url).await;
             (url, meta)
         }));
     }
-
-    let mut map: HashMap<String, serde_json::Value> = HashMap::new();
     for t in tasks {
         if let Ok((url, meta)) = t.await {
-            let key_main = canonicalize_url(&url);
-            map.insert(key_main.clone(), meta.clone());
-            // also insert with/without trailing slash variants to maximize client hits
-            if key_main.ends_with('/') {
-                let no_slash = key_main.trim_end_matches('/').to_string();
-                map.insert(no_slash, meta.clone());
-            } else {
-                let with_slash = format!("{}/", key_main);
-                map.insert(with_slash, meta.clone());
+            fetched.inc(&url);
+            // Only successful fetches are cached so failures are retried next build
+            if let Ok(meta) = &meta {
+                url_meta.lock().unwrap().insert(url.clone(), meta.clone());
             }
-            // also insert the raw URL that was authored
-            map.insert(url, meta);
+            found.push((url, meta.unwrap_or_default()));
+        }
+    }
+    for (url, meta) in &mut found {
+        if let Some(path) = favicon::host(url).and_then(|host| favicons.get(host)) {
+            if !meta.is_object() {
+                *meta = serde_json::json!({});
+            }
+            meta["favicon"] = serde_json::Value::String(path.clone());
         }
     }
 
+    let mut map: BTreeMap<String, serde_json::Value> = BTreeMap::new();
+    for (url, meta) in found {
+        let key_main = canonicalize_url(&url);
+        map.insert(key_main.clone(), meta.clone());
+        // also insert with/without trailing slash variants to maximize client hits
+        if key_main.ends_with('/') {
+            let no_slash = key_main.trim_end_matches('/').to_string();
+            map.insert(no_slash, meta.clone());
+        } else {
+            let with_slash = format!("{}/", key_main);
+            map.insert(with_slash, meta.clone());
+        }
+        // also insert the raw URL that was authored
+        map.insert(url, meta);
+    }
+
     if map.is_empty() { return None; }
     Some(serde_json::to_string(&map).unwrap_or_else(|_| String::new()))
 }
@@ -652,24 +2390,24 @@ async fn fetch_url_metadata(client: &reqwest::Client, url: &str) -> Result<serde
     let title_tag = Regex::new(r"(?is)<title[^>]*>(.*?)</title>")
         .ok()
         .and_then(|re| re.captures(&text).and_then(|c| c.get(1)).map(|m| html_unescape(m.as_str())));
-    let og_title = Regex::new(r#"(?is)<meta[^>]*\bproperty\s*=\s*([\"'])og:title\1[^>]*\bcontent\s*=\s*([\"'])(.*?)\2|<meta[^>]*\bcontent\s*=\s*([\"'])(.*?)\4[^>]*\bproperty\s*=\s*([\"'])og:title\6"#)
+    let og_title = Regex::new(r#"(?is)<meta[^>]*\bproperty\s*=\s*[\"']og:title[\"'][^>]*\bcontent\s*=\s*[\"'](.*?)[\"']|<meta[^>]*\bcontent\s*=\s*[\"'](.*?)[\"'][^>]*\bproperty\s*=\s*[\"']og:title[\"']"#)
         .ok()
-        .and_then(|re| re.captures(&text)).and_then(|c| c.get(3).or_else(|| c.get(5))).map(|m| html_unescape(m.as_str()));
-    let tw_title = Regex::new(r#"(?is)<meta[^>]*\bname\s*=\s*([\"'])twitter:title\1[^>]*\bcontent\s*=\s*([\"'])(.*?)\2|<meta[^>]*\bproperty\s*=\s*([\"'])twitter:title\4[^>]*\bcontent\s*=\s*([\"'])(.*?)\5|<meta[^>]*\bcontent\s*=\s*([\"'])(.*?)\7[^>]*\bname\s*=\s*([\"'])twitter:title\8|<meta[^>]*\bcontent\s*=\s*([\"'])(.*?)\10[^>]*\bproperty\s*=\s*([\"'])twitter:title\11"#)
+        .and_then(|re| re.captures(&text)).and_then(|c| c.get(1).or_else(|| c.get(2))).map(|m| html_unescape(m.as_str()));
+    let tw_title = Regex::new(r#"(?is)<meta[^>]*\bname\s*=\s*[\"']twitter:title[\"'][^>]*\bcontent\s*=\s*[\"'](.*?)[\"']|<meta[^>]*\bproperty\s*=\s*[\"']twitter:title[\"'][^>]*\bcontent\s*=\s*[\"'](.*?)[\"']|<meta[^>]*\bcontent\s*=\s*[\"'](.*?)[\"'][^>]*\bname\s*=\s*[\"']twitter:title[\"']|<meta[^>]*\bcontent\s*=\s*[\"'](.*?)[\"'][^>]*\bproperty\s*=\s*[\"']twitter:title[\"']"#)
         .ok()
         .and_then(|re| re.captures(&text))
-        .and_then(|c| c.get(3).or_else(|| c.get(6)).or_else(|| c.get(8)).or_else(|| c.get(11)))
+        .and_then(|c| c.get(1).or_else(|| c.get(2)).or_else(|| c.get(3)).or_else(|| c.get(4)))
         .map(|m| html_unescape(m.as_str()));
-    let name_desc_any = Regex::new(r#"(?is)<meta[^>]*\bname\s*=\s*([\"'])description\1[^>]*\bcontent\s*=\s*([\"'])(.*?)\2|<meta[^>]*\bcontent\s*=\s*([\"'])(.*?)\4[^>]*\bname\s*=\s*([\"'])description\6"#)
+    let name_desc_any = Regex::new(r#"(?is)<meta[^>]*\bname\s*=\s*[\"']description[\"'][^>]*\bcontent\s*=\s*[\"'](.*?)[\"']|<meta[^>]*\bcontent\s*=\s*[\"'](.*?)[\"'][^>]*\bname\s*=\s*[\"']description[\"']"#)
         .ok()
-        .and_then(|re| re.captures(&text)).and_then(|c| c.get(3).or_else(|| c.get(5))).map(|m| html_unescape(m.as_str()));
-    let og_desc = Regex::new(r#"(?is)<meta[^>]*\bproperty\s*=\s*([\"'])og:description\1[^>]*\bcontent\s*=\s*([\"'])(.*?)\2|<meta[^>]*\bcontent\s*=\s*([\"'])(.*?)\4[^>]*\bproperty\s*=\s*([\"'])og:description\6"#)
+        .and_then(|re| re.captures(&text)).and_then(|c| c.get(1).or_else(|| c.get(2))).map(|m| html_unescape(m.as_str()));
+    let og_desc = Regex::new(r#"(?is)<meta[^>]*\bproperty\s*=\s*[\"']og:description[\"'][^>]*\bcontent\s*=\s*[\"'](.*?)[\"']|<meta[^>]*\bcontent\s*=\s*[\"'](.*?)[\"'][^>]*\bproperty\s*=\s*[\"']og:description[\"']"#)
         .ok()
-        .and_then(|re| re.captures(&text)).and_then(|c| c.get(3).or_else(|| c.get(5))).map(|m| html_unescape(m.as_str()));
-    let tw_desc = Regex::new(r#"(?is)<meta[^>]*\bname\s*=\s*([\"'])twitter:description\1[^>]*\bcontent\s*=\s*([\"'])(.*?)\2|<meta[^>]*\bproperty\s*=\s*([\"'])twitter:description\4[^>]*\bcontent\s*=\s*([\"'])(.*?)\5|<meta[^>]*\bcontent\s*=\s*([\"'])(.*?)\7[^>]*\bname\s*=\s*([\"'])twitter:description\8|<meta[^>]*\bcontent\s*=\s*([\"'])(.*?)\10[^>]*\bproperty\s*=\s*([\"'])twitter:description\11"#)
+        .and_then(|re| re.captures(&text)).and_then(|c| c.get(1).or_else(|| c.get(2))).map(|m| html_unescape(m.as_str()));
+    let tw_desc = Regex::new(r#"(?is)<meta[^>]*\bname\s*=\s*[\"']twitter:description[\"'][^>]*\bcontent\s*=\s*[\"'](.*?)[\"']|<meta[^>]*\bproperty\s*=\s*[\"']twitter:description[\"'][^>]*\bcontent\s*=\s*[\"'](.*?)[\"']|<meta[^>]*\bcontent\s*=\s*[\"'](.*?)[\"'][^>]*\bname\s*=\s*[\"']twitter:description[\"']|<meta[^>]*\bcontent\s*=\s*[\"'](.*?)[\"'][^>]*\bproperty\s*=\s*[\"']twitter:description[\"']"#)
         .ok()
         .and_then(|re| re.captures(&text))
-        .and_then(|c| c.get(3).or_else(|| c.get(6)).or_else(|| c.get(8)).or_else(|| c.get(11)))
+        .and_then(|c| c.get(1).or_else(|| c.get(2)).or_else(|| c.get(3)).or_else(|| c.get(4)))
         .map(|m| html_unescape(m.as_str()));
 
     let title = tw_title.or(og_title).or(title_tag);
@@ -733,7 +2471,25 @@ fn canonicalize_url(url: &str) -> String {
     }
 }
 
-fn sanitize_slug(input: &str) -> String {
+/// Match `text` against a shell-style glob (`*`, `?`); patterns without wildcards never match.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    if !pattern.contains(['*', '?']) {
+        return false;
+    }
+    let mut re = String::from("^");
+    for ch in pattern.chars() {
+        match ch {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    Regex::new(&re).map(|r| r.is_match(text)).unwrap_or(false)
+}
+
+/// Lowercase `input` and collapse every run of non-alphanumeric characters into one `-`.
+pub fn sanitize_slug(input: &str) -> String {
     // Lowercase and replace any non-alphanumeric with '-'
     let lowered = input.to_lowercase();
     let provisional: String = lowered
@@ -0,0 +1,148 @@
+use crate::config::TrailingSlash;
+use crate::generator::Post;
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+    Json,
+    Dot,
+    Graphml,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Node {
+    pub id: String,
+    pub label: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    /// Page URL relative to the site root (posts only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Edge {
+    pub source: String,
+    pub target: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+}
+
+/// Internal link network between posts, optionally with tags as extra nodes.
+#[derive(Debug, Default, Serialize)]
+pub struct LinkGraph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl LinkGraph {
+    /// Build the graph from posts dated within `[since, until]`; links to filtered-out posts are dropped.
+    /// Post URLs follow `policy`.
+    pub fn build(posts: &[Post], include_tags: bool, since: Option<NaiveDate>, until: Option<NaiveDate>, policy: TrailingSlash) -> Self {
+        let selected: Vec<&Post> = posts
+            .iter()
+            .filter(|p| since.is_none_or(|d| p.date.date_naive() >= d))
+            .filter(|p| until.is_none_or(|d| p.date.date_naive() <= d))
+            .collect();
+
+        let mut graph = LinkGraph::default();
+        for post in &selected {
+            graph.nodes.push(Node {
+                id: post.slug.clone(),
+                label: post.title.clone(),
+                kind: "post",
+                date: Some(post.date.format("%Y-%m-%d").to_string()),
+                url: Some(policy.post_href("", &post.slug)),
+            });
+        }
+
+        for from in &selected {
+            for to in &selected {
+                if from.links_to(to) {
+                    graph.edges.push(Edge { source: from.slug.clone(), target: to.slug.clone(), kind: "link" });
+                }
+            }
+        }
+
+        if include_tags {
+            let mut tags = BTreeSet::new();
+            for post in &selected {
+                for tag in post.tags() {
+                    graph.edges.push(Edge { source: post.slug.clone(), target: format!("tag:{}", tag), kind: "tag" });
+                    tags.insert(tag.clone());
+                }
+            }
+            for tag in tags {
+                graph.nodes.push(Node { id: format!("tag:{}", tag), label: tag, kind: "tag", date: None, url: None });
+            }
+        }
+
+        graph
+    }
+
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Json => serde_json::to_string_pretty(self).unwrap_or_default(),
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Graphml => self.to_graphml(),
+        }
+    }
+
+    fn to_dot(&self) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut out = String::from("digraph links {\n    node [shape=ellipse];\n");
+        for node in &self.nodes {
+            let shape = if node.kind == "tag" { ", shape=box" } else { "" };
+            out.push_str(&format!("    {} [label={}{}];\n", quote(&node.id), quote(&node.label), shape));
+        }
+        for edge in &self.edges {
+            let style = if edge.kind == "tag" { " [style=dashed]" } else { "" };
+            out.push_str(&format!("    {} -> {}{};\n", quote(&edge.source), quote(&edge.target), style));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn to_graphml(&self) -> String {
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+            "  <key id=\"type\" for=\"all\" attr.name=\"type\" attr.type=\"string\"/>\n",
+            "  <key id=\"date\" for=\"node\" attr.name=\"date\" attr.type=\"string\"/>\n",
+            "  <graph id=\"links\" edgedefault=\"directed\">\n",
+        ));
+        for node in &self.nodes {
+            out.push_str(&format!("    <node id=\"{}\">\n", xml_escape(&node.id)));
+            out.push_str(&format!("      <data key=\"label\">{}</data>\n", xml_escape(&node.label)));
+            out.push_str(&format!("      <data key=\"type\">{}</data>\n", node.kind));
+            if let Some(date) = &node.date {
+                out.push_str(&format!("      <data key=\"date\">{}</data>\n", date));
+            }
+            out.push_str("    </node>\n");
+        }
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\"><data key=\"type\">{}</data></edge>\n",
+                xml_escape(&edge.source),
+                xml_escape(&edge.target),
+                edge.kind
+            ));
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,382 @@
+use crate::config::ImageOptions;
+use crate::trash;
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+use walkdir::WalkDir;
+
+/// Files published from the posts directory; the first four have their metadata stripped.
+const EXTENSIONS: [&str; 7] = ["jpg", "jpeg", "png", "webp", "gif", "svg", "avif"];
+
+/// EXIF fields that `images.keep_exif` can name, with their tag numbers.
+const KEEPABLE_TAGS: [(&str, u16); 8] = [
+    ("ImageDescription", 0x010E),
+    ("Make", 0x010F),
+    ("Model", 0x0110),
+    ("Orientation", 0x0112),
+    ("Software", 0x0131),
+    ("DateTime", 0x0132),
+    ("Artist", 0x013B),
+    ("Copyright", 0x8298),
+];
+
+/// Tag of the pointer to the GPS IFD.
+const GPS_IFD_TAG: u16 = 0x8825;
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const XMP_HEADERS: [&[u8]; 2] = [b"http://ns.adobe.com/xap/1.0/\0", b"http://ns.adobe.com/xmp/extension/\0"];
+
+/// An image with its metadata removed.
+pub struct Stripped {
+    pub bytes: Vec<u8>,
+    /// The original carried GPS coordinates in EXIF or XMP
+    pub had_location: bool,
+}
+
+/// Copy images under `posts_dir` into `output_dir` at the same relative path (so `/images/a.jpg`
+/// in a post finds `posts/images/a.jpg`), stripping metadata unless disabled. Warns about every
+/// original with location data. Returns the number of files written.
+pub fn publish(posts_dir: &Path, output_dir: &Path, options: &ImageOptions) -> Result<usize> {
+    let keep = tag_ids(&options.keep_exif)?;
+    let mut written = 0;
+    for path in sources(posts_dir, output_dir) {
+        let path = path.as_path();
+        let ext = extension(path).unwrap_or_default();
+        let original = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+        let bytes = match strip_metadata(&original, &ext, &keep) {
+            Ok(Some(stripped)) => {
+                if stripped.had_location {
+                    let what = if options.strip_metadata { "removed from the published copy" } else { "published with it" };
+                    warn!("{}", format!("Warning: {} contains GPS location data; {}", path.display(), what).yellow());
+                }
+                if options.strip_metadata { stripped.bytes } else { original }
+            }
+            Ok(None) => original,
+            Err(e) => {
+                warn!("{}", format!("Warning: {} not published: {:#}", path.display(), e).yellow());
+                continue;
+            }
+        };
+
+        let target = output_dir.join(path.strip_prefix(posts_dir).unwrap_or(path));
+        if fs::read(&target).is_ok_and(|existing| existing == bytes) {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target, bytes).context(format!("Failed to write {}", target.display()))?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// Images under `posts_dir` that `publish` copies, leaving out the trash and `output_dir`.
+pub fn sources(posts_dir: &Path, output_dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = WalkDir::new(posts_dir)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != trash::DIR)
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_image(e.path()) && !e.path().starts_with(output_dir))
+        .map(|e| e.into_path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Whether `path` has the extension of an image `publish` copies.
+pub fn is_image(path: &Path) -> bool {
+    extension(path).is_some_and(|ext| EXTENSIONS.contains(&ext.as_str()))
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension().and_then(|e| e.to_str()).map(str::to_lowercase)
+}
+
+/// Tag numbers for EXIF field names, rejecting names that can't be kept.
+pub fn tag_ids(names: &[String]) -> Result<Vec<u16>> {
+    names
+        .iter()
+        .map(|name| {
+            KEEPABLE_TAGS
+                .iter()
+                .find(|(known, _)| known.eq_ignore_ascii_case(name))
+                .map(|(_, id)| *id)
+                .with_context(|| {
+                    let known: Vec<&str> = KEEPABLE_TAGS.iter().map(|(n, _)| *n).collect();
+                    format!("images.keep_exif: unknown EXIF field '{}' (expected one of {})", name, known.join(", "))
+                })
+        })
+        .collect()
+}
+
+/// Remove EXIF (except the `keep` tags), XMP and IPTC metadata from a JPEG, PNG or WebP image.
+/// `None` for formats that aren't stripped.
+pub fn strip_metadata(bytes: &[u8], extension: &str, keep: &[u16]) -> Result<Option<Stripped>> {
+    match extension {
+        "jpg" | "jpeg" => strip_jpeg(bytes, keep).map(Some),
+        "png" => strip_png(bytes, keep).map(Some),
+        "webp" => strip_webp(bytes, keep).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn strip_jpeg(bytes: &[u8], keep: &[u16]) -> Result<Stripped> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        anyhow::bail!("not a JPEG file");
+    }
+    let mut out = bytes[..2].to_vec();
+    let mut had_location = false;
+    let mut pos = 2;
+    while pos < bytes.len() {
+        if bytes[pos] != 0xFF {
+            anyhow::bail!("corrupt JPEG segment at byte {}", pos);
+        }
+        let marker = *bytes.get(pos + 1).context("truncated JPEG")?;
+        // Start of scan or end of image: the rest is image data
+        if marker == 0xDA || marker == 0xD9 {
+            out.extend_from_slice(&bytes[pos..]);
+            break;
+        }
+        // Fill byte before a marker
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+        // Markers without a length
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            out.extend_from_slice(&bytes[pos..pos + 2]);
+            pos += 2;
+            continue;
+        }
+        let length = u16::from_be_bytes([*bytes.get(pos + 2).context("truncated JPEG")?, *bytes.get(pos + 3).context("truncated JPEG")?]) as usize;
+        let end = pos + 2 + length;
+        let data = bytes.get(pos + 4..end).context("truncated JPEG")?;
+        match marker {
+            0xE1 if data.starts_with(EXIF_HEADER) => {
+                let tiff = &data[EXIF_HEADER.len()..];
+                had_location |= tiff_has_gps(tiff);
+                if let Some(kept) = rebuild_tiff(tiff, keep) {
+                    let segment = [EXIF_HEADER, &kept].concat();
+                    out.extend_from_slice(&[0xFF, 0xE1]);
+                    out.extend_from_slice(&((segment.len() + 2) as u16).to_be_bytes());
+                    out.extend_from_slice(&segment);
+                }
+            }
+            0xE1 if XMP_HEADERS.iter().any(|h| data.starts_with(h)) => had_location |= xmp_has_gps(data),
+            // Photoshop resources, including IPTC
+            0xED => {}
+            _ => out.extend_from_slice(&bytes[pos..end]),
+        }
+        pos = end;
+    }
+    Ok(Stripped { bytes: out, had_location })
+}
+
+fn strip_png(bytes: &[u8], keep: &[u16]) -> Result<Stripped> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if !bytes.starts_with(SIGNATURE) {
+        anyhow::bail!("not a PNG file");
+    }
+    let mut out = SIGNATURE.to_vec();
+    let mut had_location = false;
+    let mut pos = SIGNATURE.len();
+    while pos + 8 <= bytes.len() {
+        let length = u32::from_be_bytes(bytes[pos..pos + 4].try_into()?) as usize;
+        let kind = &bytes[pos + 4..pos + 8];
+        let end = pos + 12 + length;
+        let data = bytes.get(pos + 8..pos + 8 + length).context("truncated PNG")?;
+        match kind {
+            b"eXIf" => {
+                had_location |= tiff_has_gps(data);
+                if let Some(kept) = rebuild_tiff(data, keep) {
+                    out.extend_from_slice(&png_chunk(b"eXIf", &kept));
+                }
+            }
+            b"iTXt" | b"tEXt" | b"zTXt" if data.starts_with(b"XML:com.adobe.xmp\0") => had_location |= xmp_has_gps(data),
+            _ => out.extend_from_slice(bytes.get(pos..end).context("truncated PNG")?),
+        }
+        pos = end;
+    }
+    Ok(Stripped { bytes: out, had_location })
+}
+
+fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(data);
+    chunk.extend_from_slice(&crc32(&chunk[4..]).to_be_bytes());
+    chunk
+}
+
+/// The CRC-32 checksum PNG chunks and ZIP entries carry.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn strip_webp(bytes: &[u8], keep: &[u16]) -> Result<Stripped> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WEBP" {
+        anyhow::bail!("not a WebP file");
+    }
+    let mut out = bytes[..12].to_vec();
+    let mut had_location = false;
+    let mut kept_exif = false;
+    let mut vp8x = None;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let kind = &bytes[pos..pos + 4];
+        let length = u32::from_le_bytes(bytes[pos + 4..pos + 8].try_into()?) as usize;
+        let end = (pos + 8 + length + length % 2).min(bytes.len());
+        let data = bytes.get(pos + 8..pos + 8 + length).context("truncated WebP")?;
+        match kind {
+            b"EXIF" => {
+                let tiff = data.strip_prefix(EXIF_HEADER).unwrap_or(data);
+                had_location |= tiff_has_gps(tiff);
+                if let Some(kept) = rebuild_tiff(tiff, keep) {
+                    out.extend_from_slice(b"EXIF");
+                    out.extend_from_slice(&(kept.len() as u32).to_le_bytes());
+                    out.extend_from_slice(&kept);
+                    if kept.len() % 2 == 1 {
+                        out.push(0);
+                    }
+                    kept_exif = true;
+                }
+            }
+            b"XMP " => had_location |= xmp_has_gps(data),
+            _ => {
+                if kind == b"VP8X" {
+                    vp8x = Some(out.len() + 8);
+                }
+                out.extend_from_slice(&bytes[pos..end]);
+            }
+        }
+        pos = end;
+    }
+    // The extended header flags which metadata chunks follow
+    if let Some(flags) = vp8x.filter(|&i| i < out.len()) {
+        out[flags] &= !0x0C;
+        if kept_exif {
+            out[flags] |= 0x08;
+        }
+    }
+    let riff_size = (out.len() - 8) as u32;
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Ok(Stripped { bytes: out, had_location })
+}
+
+fn xmp_has_gps(data: &[u8]) -> bool {
+    String::from_utf8_lossy(data).contains("GPSLatitude")
+}
+
+/// Byte order and first IFD entries of a TIFF structure (the body of an EXIF block).
+struct Tiff<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let little_endian = match bytes.get(..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        Some(Tiff { bytes, little_endian })
+    }
+
+    fn u16(&self, at: usize) -> Option<u16> {
+        let b: [u8; 2] = self.bytes.get(at..at + 2)?.try_into().ok()?;
+        Some(if self.little_endian { u16::from_le_bytes(b) } else { u16::from_be_bytes(b) })
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        let b: [u8; 4] = self.bytes.get(at..at + 4)?.try_into().ok()?;
+        Some(if self.little_endian { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    }
+
+    /// `(tag, type, count, raw value bytes)` for each entry of IFD0.
+    fn ifd0(&self) -> Vec<(u16, u16, u32, &'a [u8])> {
+        let Some(ifd) = self.u32(4).map(|o| o as usize) else {
+            return Vec::new();
+        };
+        let count = self.u16(ifd).unwrap_or(0) as usize;
+        (0..count)
+            .filter_map(|i| {
+                let entry = ifd + 2 + i * 12;
+                let (tag, kind, n) = (self.u16(entry)?, self.u16(entry + 2)?, self.u32(entry + 4)?);
+                let size = type_size(kind)?.checked_mul(n as usize)?;
+                let value = if size <= 4 {
+                    self.bytes.get(entry + 8..entry + 8 + size)?
+                } else {
+                    let offset = self.u32(entry + 8)? as usize;
+                    self.bytes.get(offset..offset.checked_add(size)?)?
+                };
+                Some((tag, kind, n, value))
+            })
+            .collect()
+    }
+}
+
+fn type_size(kind: u16) -> Option<usize> {
+    match kind {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    }
+}
+
+fn tiff_has_gps(bytes: &[u8]) -> bool {
+    Tiff::parse(bytes).is_some_and(|tiff| tiff.ifd0().iter().any(|(tag, ..)| *tag == GPS_IFD_TAG))
+}
+
+/// A new TIFF holding only the `keep` tags of IFD0, or `None` if none of them are present.
+fn rebuild_tiff(bytes: &[u8], keep: &[u16]) -> Option<Vec<u8>> {
+    let tiff = Tiff::parse(bytes)?;
+    let mut entries: Vec<_> = tiff.ifd0().into_iter().filter(|(tag, ..)| keep.contains(tag)).collect();
+    if entries.is_empty() {
+        return None;
+    }
+    entries.sort_by_key(|(tag, ..)| *tag);
+
+    let le = tiff.little_endian;
+    let u16b = |v: u16| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+    let u32b = |v: u32| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+    let mut out = bytes[..2].to_vec();
+    out.extend_from_slice(&u16b(42));
+    out.extend_from_slice(&u32b(8));
+    out.extend_from_slice(&u16b(entries.len() as u16));
+
+    let mut data_offset = 8 + 2 + entries.len() * 12 + 4;
+    let mut data = Vec::new();
+    for (tag, kind, count, value) in &entries {
+        out.extend_from_slice(&u16b(*tag));
+        out.extend_from_slice(&u16b(*kind));
+        out.extend_from_slice(&u32b(*count));
+        if value.len() <= 4 {
+            let mut inline = value.to_vec();
+            inline.resize(4, 0);
+            out.extend_from_slice(&inline);
+        } else {
+            out.extend_from_slice(&u32b(data_offset as u32));
+            data.extend_from_slice(value);
+            if value.len() % 2 == 1 {
+                data.push(0);
+            }
+            data_offset = 8 + 2 + entries.len() * 12 + 4 + data.len();
+        }
+    }
+    out.extend_from_slice(&u32b(0));
+    out.extend_from_slice(&data);
+    Some(out)
+}
@@ -0,0 +1,624 @@
+use crate::frontmatter;
+use crate::generator::sanitize_slug;
+use crate::images;
+use crate::markdown::{code_spans, CodeFences};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use clap::ValueEnum;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Generators `scribe import` converts content from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Source {
+    /// `_posts/` and `_drafts/` of a Jekyll site, with Liquid tags
+    Jekyll,
+    /// `content/` of a Hugo site, with shortcodes, page bundles and `static/` files
+    Hugo,
+    /// Notes of an Obsidian vault, with wiki links and embeds
+    Obsidian,
+}
+
+/// A post converted to scribe's conventions, to be written at `path` under the posts directory.
+#[derive(Debug)]
+pub struct Converted {
+    pub source: PathBuf,
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// A file a converted post refers to, copied to `to` under the posts directory so it is served
+/// at the address the post uses.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Asset {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Something the import couldn't carry over as it was.
+#[derive(Debug)]
+pub struct Issue {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+/// Everything an import would write, and what it couldn't map.
+#[derive(Debug, Default)]
+pub struct Import {
+    pub posts: Vec<Converted>,
+    pub assets: Vec<Asset>,
+    pub issues: Vec<Issue>,
+}
+
+/// Frontmatter fields that are translated (or only matter to the other generator) rather than
+/// carried over as they are.
+const HANDLED_FIELDS: &[&str] = &[
+    "title", "date", "updated", "lastmod", "last_modified_at", "modified", "created", "excerpt", "description", "summary", "tags",
+    "categories", "series", "lang", "language", "aliases", "redirect_from", "permalink", "url", "slug", "draft", "published", "layout",
+];
+
+/// Convert the content of the `from` site or vault at `root` without writing anything.
+pub fn convert(from: Source, root: &Path) -> Result<Import> {
+    if !root.is_dir() {
+        bail!("{} is not a directory", root.display());
+    }
+    let sources = discover(from, root)?;
+    let notes = Notes::index(from, root, &sources);
+    let mut import = Import::default();
+    let mut taken: HashSet<PathBuf> = HashSet::new();
+    let mut assets: BTreeMap<PathBuf, PathBuf> = BTreeMap::new();
+    for (path, in_drafts) in sources {
+        let mut issues = Vec::new();
+        let content = fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?;
+        let post = convert_post(from, root, &path, &content, in_drafts, &notes, &mut assets, &mut issues);
+        let mut target = post.path.clone();
+        let mut n = 2;
+        while !taken.insert(target.clone()) {
+            target = post.path.with_file_name(format!("{}-{}.md", post.slug, n));
+            n += 1;
+        }
+        if target != post.path {
+            issues.push(format!("slug '{}' is taken by another post; written as {}", post.slug, target.display()));
+        }
+        let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+        import.issues.extend(issues.into_iter().map(|message| Issue { path: relative.clone(), message }));
+        import.posts.push(Converted { source: path, path: target, content: post.content });
+    }
+    import.assets = assets.into_iter().map(|(from, to)| Asset { from, to }).collect();
+    Ok(import)
+}
+
+impl Import {
+    /// Write the posts and copy the assets into `posts_dir`, leaving files that already exist
+    /// alone. Returns the paths (relative to `posts_dir`) that were skipped for existing.
+    pub fn write(&self, posts_dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut skipped = Vec::new();
+        let files = self.posts.iter().map(|p| (&p.path, None, Some(&p.content))).chain(self.assets.iter().map(|a| (&a.to, Some(&a.from), None)));
+        for (relative, from, content) in files {
+            let target = posts_dir.join(relative);
+            if target.exists() {
+                skipped.push(relative.clone());
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).context(format!("Failed to create {}", parent.display()))?;
+            }
+            match (from, content) {
+                (Some(from), _) => fs::copy(from, &target).map(|_| ()),
+                (_, Some(content)) => fs::write(&target, content),
+                _ => Ok(()),
+            }
+            .context(format!("Failed to write {}", target.display()))?;
+        }
+        Ok(skipped)
+    }
+}
+
+/// Markdown files to convert, each with whether it sits among Jekyll's drafts.
+fn discover(from: Source, root: &Path) -> Result<Vec<(PathBuf, bool)>> {
+    let markdown = |dir: &Path| -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = WalkDir::new(dir)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+            .filter_map(|e| e.ok())
+            .map(|e| e.into_path())
+            .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "md" || ext == "markdown"))
+            .collect();
+        files.sort();
+        files
+    };
+    let sources: Vec<(PathBuf, bool)> = match from {
+        Source::Jekyll => {
+            if !root.join("_posts").is_dir() {
+                bail!("{} has no _posts directory; is it a Jekyll site?", root.display());
+            }
+            let posts = markdown(&root.join("_posts")).into_iter().map(|p| (p, false));
+            posts.chain(markdown(&root.join("_drafts")).into_iter().map(|p| (p, true))).collect()
+        }
+        Source::Hugo => {
+            if !root.join("content").is_dir() {
+                bail!("{} has no content directory; is it a Hugo site?", root.display());
+            }
+            // Section and home pages list posts rather than being posts
+            markdown(&root.join("content"))
+                .into_iter()
+                .filter(|p| p.file_stem().is_none_or(|stem| !stem.to_string_lossy().starts_with("_index")))
+                .map(|p| (p, false))
+                .collect()
+        }
+        Source::Obsidian => markdown(root).into_iter().map(|p| (p, false)).collect(),
+    };
+    Ok(sources)
+}
+
+/// Slugs of the notes and posts being imported by the names links use for them, and the
+/// attachments of a vault by file name.
+#[derive(Default)]
+struct Notes {
+    root: PathBuf,
+    slugs: HashMap<String, String>,
+    attachments: HashMap<String, PathBuf>,
+}
+
+impl Notes {
+    fn index(from: Source, root: &Path, sources: &[(PathBuf, bool)]) -> Self {
+        let mut notes = Notes { root: root.to_path_buf(), ..Default::default() };
+        for (path, _) in sources {
+            let name = source_name(from, path);
+            let slug = file_slug(from, path).0;
+            if let Ok(content) = fs::read_to_string(path) {
+                if let (Some(fields), _) = frontmatter::split_fields(&content) {
+                    // An Obsidian alias is another name links use for the note
+                    if from == Source::Obsidian {
+                        for alias in strings(fields.get("aliases"), ',') {
+                            notes.slugs.insert(alias.to_lowercase(), slug.clone());
+                        }
+                    }
+                    if let Some(own) = fields.get("slug").and_then(|s| s.as_str()) {
+                        notes.slugs.insert(name.to_lowercase(), sanitize_slug(own));
+                        continue;
+                    }
+                }
+            }
+            notes.slugs.insert(name.to_lowercase(), slug);
+        }
+        if from == Source::Obsidian {
+            for entry in WalkDir::new(root)
+                .into_iter()
+                .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file() && e.path().extension().is_none_or(|ext| ext != "md"))
+            {
+                notes.attachments.entry(entry.file_name().to_string_lossy().to_lowercase()).or_insert_with(|| entry.into_path());
+            }
+        }
+        notes
+    }
+
+    fn slug(&self, name: &str) -> Option<&str> {
+        let name = name.trim().trim_end_matches(".md");
+        let name = name.rsplit('/').next().unwrap_or(name);
+        self.slugs.get(&name.to_lowercase()).map(String::as_str)
+    }
+}
+
+/// Name other content refers to a source file by: the Jekyll file name (date included), a
+/// Hugo bundle's directory or file name, or an Obsidian note's name.
+fn source_name(from: Source, path: &Path) -> String {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    match from {
+        Source::Hugo if stem == "index" => path.parent().and_then(|p| p.file_name()).map(|n| n.to_string_lossy().to_string()).unwrap_or(stem),
+        _ => stem,
+    }
+}
+
+/// Slug a source file gets from its name, with the date of a Jekyll file name and the language
+/// of a Hugo file name (`post.nl.md`) taken off.
+fn file_slug(from: Source, path: &Path) -> (String, Option<NaiveDate>, Option<String>) {
+    let name = source_name(from, path);
+    match from {
+        Source::Jekyll => {
+            let dated = Regex::new(r"^(\d{4}-\d{2}-\d{2})-(.+)$").unwrap();
+            match dated.captures(&name) {
+                Some(caps) => (sanitize_slug(&caps[2]), NaiveDate::parse_from_str(&caps[1], "%Y-%m-%d").ok(), None),
+                None => (sanitize_slug(&name), None, None),
+            }
+        }
+        Source::Hugo => {
+            let translated = Regex::new(r"^(.+)\.([a-z]{2}(?:-[A-Za-z]{2})?)$").unwrap();
+            match translated.captures(&name) {
+                Some(caps) => (sanitize_slug(&caps[1]), None, Some(caps[2].to_string())),
+                None => (sanitize_slug(&name), None, None),
+            }
+        }
+        Source::Obsidian => (sanitize_slug(&name), None, None),
+    }
+}
+
+struct Post {
+    slug: String,
+    path: PathBuf,
+    content: String,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn convert_post(
+    from: Source,
+    root: &Path,
+    path: &Path,
+    content: &str,
+    in_drafts: bool,
+    notes: &Notes,
+    assets: &mut BTreeMap<PathBuf, PathBuf>,
+    issues: &mut Vec<String>,
+) -> Post {
+    let (fields, body) = frontmatter::split_fields(content);
+    let fields = fields.unwrap_or_default();
+    let (file_slug, file_date, file_lang) = file_slug(from, path);
+    let slug = fields.get("slug").and_then(|s| s.as_str()).map(sanitize_slug).unwrap_or(file_slug);
+    let text = |key: &str| fields.get(key).and_then(|v| v.as_str()).map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+
+    let mut out: Vec<(String, serde_json::Value)> = Vec::new();
+    let title = text("title").unwrap_or_else(|| {
+        let name = source_name(from, path);
+        if from != Source::Obsidian {
+            issues.push("no title; the file name is used".to_string());
+        }
+        name
+    });
+    out.push(("title".to_string(), title.into()));
+
+    let date = ["date", "created"]
+        .iter()
+        .find_map(|key| fields.get(*key).and_then(parse_date))
+        .or_else(|| file_date.and_then(|d| d.and_hms_opt(0, 0, 0)).map(|d| d.and_utc()))
+        .unwrap_or_else(|| {
+            issues.push("no date; the file's modification time is used".to_string());
+            fs::metadata(path).and_then(|m| m.modified()).map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now())
+        });
+    out.push(("date".to_string(), format_date(date).into()));
+    if let Some(updated) = ["updated", "lastmod", "last_modified_at", "modified"].iter().find_map(|key| fields.get(*key).and_then(parse_date)) {
+        out.push(("updated".to_string(), format_date(updated).into()));
+    }
+    if let Some(excerpt) = ["excerpt", "description", "summary"].iter().find_map(|key| text(key)) {
+        out.push(("excerpt".to_string(), excerpt.into()));
+    }
+
+    // Jekyll separates tags with spaces; categories become tags too, as scribe has only those
+    let separator = if from == Source::Jekyll { ' ' } else { ',' };
+    let mut tags: Vec<String> = strings(fields.get("tags"), separator);
+    tags.extend(strings(fields.get("categories"), separator));
+    let body = match from {
+        Source::Jekyll => liquid(&body, issues),
+        Source::Hugo => shortcodes(&body, issues),
+        Source::Obsidian => {
+            let inline_tag = Regex::new(r"(?:^|\s)#([A-Za-z][\w/-]*)").unwrap();
+            outside_code(&body, |text| {
+                tags.extend(inline_tag.captures_iter(text).map(|c| c[1].to_string()));
+                text.to_string()
+            });
+            obsidian(&body, notes, assets, issues)
+        }
+    };
+    let mut seen = HashSet::new();
+    tags = tags.into_iter().map(|t| t.trim_start_matches('#').to_string()).filter(|t| seen.insert(t.to_lowercase())).collect();
+    if !tags.is_empty() {
+        out.push(("tags".to_string(), tags.into()));
+    }
+    if let Some(series) = strings(fields.get("series"), ',').into_iter().next() {
+        out.push(("series".to_string(), series.into()));
+    }
+    let lang = text("lang").or_else(|| text("language")).or(file_lang);
+    if let Some(lang) = &lang {
+        out.push(("lang".to_string(), lang.clone().into()));
+    }
+
+    // Old addresses that were a single path segment keep working through aliases
+    let mut aliases = Vec::new();
+    let mut old_urls: Vec<String> = strings(fields.get("redirect_from"), ',');
+    old_urls.extend(text("permalink").or_else(|| text("url")));
+    if from == Source::Hugo {
+        old_urls.extend(strings(fields.get("aliases"), ','));
+    } else if from == Source::Jekyll && text("permalink").is_none() {
+        // Jekyll's default permalink: /:categories/:year/:month/:day/:title.html
+        let mut segments: Vec<String> = strings(fields.get("categories"), ' ').iter().map(|c| sanitize_slug(c)).collect();
+        segments.push(date.format("%Y/%m/%d").to_string());
+        old_urls.push(format!("/{}/{}.html", segments.join("/"), slug));
+    }
+    for url in old_urls {
+        let path = url.trim().trim_matches('/');
+        let path = path.strip_suffix(".html").unwrap_or(path);
+        if path.is_empty() || sanitize_slug(path) == slug {
+            continue;
+        }
+        if path.contains('/') {
+            issues.push(format!("old address {} gets no redirect; aliases are single path segments", url.trim()));
+        } else {
+            aliases.push(sanitize_slug(path));
+        }
+    }
+    if !aliases.is_empty() {
+        out.push(("aliases".to_string(), aliases.into()));
+    }
+
+    let draft = in_drafts || fields.get("draft").and_then(|d| d.as_bool()) == Some(true) || fields.get("published").and_then(|p| p.as_bool()) == Some(false);
+    if draft {
+        out.push(("draft".to_string(), true.into()));
+    }
+    let kept: Vec<&String> = fields.keys().filter(|key| !HANDLED_FIELDS.contains(&key.as_str())).collect();
+    if !kept.is_empty() {
+        let names: Vec<&str> = kept.iter().map(|k| k.as_str()).collect();
+        issues.push(format!("kept field(s) scribe doesn't use: {}", names.join(", ")));
+    }
+    for key in kept {
+        out.push((key.clone(), fields[key].clone()));
+    }
+
+    let body = match from {
+        Source::Obsidian => body,
+        _ => copy_assets(from, root, path, &slug, &body, assets, issues),
+    };
+    let mut content = String::from("---\n");
+    for (key, value) in out {
+        content.push_str(&format!("{}: {}\n", key, yaml_value(&value)));
+    }
+    content.push_str("---\n\n");
+    content.push_str(body.trim_start_matches('\n').trim_end());
+    content.push('\n');
+
+    let path = match &lang {
+        Some(lang) if from == Source::Hugo && path.file_stem().is_some_and(|s| s.to_string_lossy().ends_with(&format!(".{}", lang))) => Path::new(lang).join(format!("{}.md", slug)),
+        _ => PathBuf::from(format!("{}.md", slug)),
+    };
+    Post { slug, path, content }
+}
+
+/// A frontmatter value as YAML on one line: strings as YAML scalars, anything else in JSON's
+/// flow syntax, which YAML reads the same.
+fn yaml_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => serde_yaml::to_string(s).map(|y| y.trim_end().to_string()).unwrap_or_else(|_| format!("{:?}", s)),
+        other => other.to_string(),
+    }
+}
+
+/// A list field, or a string field split at `separator`.
+fn strings(value: Option<&serde_json::Value>, separator: char) -> Vec<String> {
+    let items: Vec<String> = match value {
+        Some(serde_json::Value::Array(items)) => items.iter().filter_map(|i| i.as_str().map(str::to_string)).collect(),
+        Some(serde_json::Value::String(s)) => s.split(separator).map(str::to_string).collect(),
+        _ => Vec::new(),
+    };
+    items.into_iter().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
+}
+
+/// Dates as Jekyll, Hugo and Obsidian write them: RFC 3339, `YYYY-MM-DD HH:MM:SS +ZZZZ`, with
+/// or without a time or zone.
+fn parse_date(value: &serde_json::Value) -> Option<DateTime<Utc>> {
+    let raw = value.as_str()?.trim();
+    if let Some(date) = frontmatter::parse_date(raw) {
+        return Some(date);
+    }
+    for format in ["%Y-%m-%d %H:%M:%S %z", "%Y-%m-%d %H:%M %z", "%Y-%m-%dT%H:%M:%S%z"] {
+        if let Ok(date) = DateTime::parse_from_str(raw, format) {
+            return Some(date.with_timezone(&Utc));
+        }
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())
+        .map(|date| date.and_utc())
+}
+
+/// A plain day for midnight, RFC 3339 otherwise.
+fn format_date(date: DateTime<Utc>) -> String {
+    if date.time() == chrono::NaiveTime::MIN {
+        date.format("%Y-%m-%d").to_string()
+    } else {
+        date.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    }
+}
+
+/// Apply `convert` to the parts of `markdown` outside fenced code blocks and inline code spans.
+fn outside_code(markdown: &str, mut convert: impl FnMut(&str) -> String) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut prose = String::new();
+    let mut fences = CodeFences::default();
+    for line in markdown.split_inclusive('\n') {
+        if fences.is_code(line) {
+            out.push_str(&convert(&std::mem::take(&mut prose)));
+            out.push_str(line);
+            continue;
+        }
+        for (piece, code) in code_spans(line) {
+            if code {
+                out.push_str(&convert(&std::mem::take(&mut prose)));
+                out.push_str(piece);
+            } else {
+                prose.push_str(piece);
+            }
+        }
+    }
+    out.push_str(&convert(&prose));
+    out
+}
+
+/// Jekyll's Liquid tags in `body` as Markdown: highlight blocks as fenced code, `post_url` and
+/// `link` as links to the imported posts, and `site.baseurl` and URL filters as plain paths.
+fn liquid(body: &str, issues: &mut Vec<String>) -> String {
+    let highlight = Regex::new(r"(?s)\{%-?\s*highlight\s+(\w+)[^%]*%\}\n?(.*?)\{%-?\s*endhighlight\s*-?%\}").unwrap();
+    let body = highlight.replace_all(body, |c: &regex::Captures| format!("```{}\n{}\n```", &c[1], c[2].trim_end_matches('\n')));
+    let raw = Regex::new(r"\{%-?\s*(?:end)?raw\s*-?%\}").unwrap();
+    let body = raw.replace_all(&body, "").to_string();
+    outside_code(&body, |text| {
+        let post_url = Regex::new(r"\{%-?\s*post_url\s+(?:[\w/-]*/)?\d{4}-\d{2}-\d{2}-([^\s%]+)\s*-?%\}").unwrap();
+        let text = post_url.replace_all(text, |c: &regex::Captures| format!("../{}/", sanitize_slug(&c[1])));
+        let link = Regex::new(r"\{%-?\s*link\s+_posts/(?:[\w/-]*/)?\d{4}-\d{2}-\d{2}-([^\s%]+?)\.(?:md|markdown)\s*-?%\}").unwrap();
+        let text = link.replace_all(&text, |c: &regex::Captures| format!("../{}/", sanitize_slug(&c[1])));
+        let filtered = Regex::new(r#"\{\{-?\s*["']([^"']*)["']\s*\|\s*(?:relative_url|absolute_url)\s*-?\}\}"#).unwrap();
+        let text = filtered.replace_all(&text, "$1");
+        let site = Regex::new(r"\{\{-?\s*site\.(?:url|baseurl)\s*-?\}\}").unwrap();
+        let text = site.replace_all(&text, "");
+        let more = Regex::new(r"<!--\s*more\s*-->\n?").unwrap();
+        let text = more.replace_all(&text, "").to_string();
+        let leftover = Regex::new(r"\{%.*?%\}|\{\{.*?\}\}").unwrap();
+        for tag in leftover.find_iter(&text) {
+            issues.push(format!("Liquid left as it is: {}", tag.as_str()));
+        }
+        text
+    })
+}
+
+/// Hugo shortcodes in `body` as Markdown: `highlight` as fenced code, `ref` and `relref` as links
+/// to the imported posts, `figure` as an image and `youtube` as a link.
+fn shortcodes(body: &str, issues: &mut Vec<String>) -> String {
+    let highlight = Regex::new(r"(?s)\{\{[<%]\s*highlight\s+(\w+)[^>%]*[>%]\}\}\n?(.*?)\{\{[<%]\s*/highlight\s*[>%]\}\}").unwrap();
+    let body = highlight.replace_all(body, |c: &regex::Captures| format!("```{}\n{}\n```", &c[1], c[2].trim_end_matches('\n'))).to_string();
+    outside_code(&body, |text| {
+        let reference = Regex::new(r#"\{\{[<%]\s*(?:rel)?ref\s+"?([^">%]+?)"?\s*[>%]\}\}"#).unwrap();
+        let text = reference.replace_all(text, |c: &regex::Captures| {
+            let target = c[1].split('#').next().unwrap_or_default().trim_end_matches('/');
+            let target = target.trim_end_matches(".md").trim_end_matches("/index").trim_end_matches("/_index");
+            format!("../{}/", sanitize_slug(target.rsplit('/').next().unwrap_or(target)))
+        });
+        let figure = Regex::new(r"\{\{[<%]\s*figure\s+([^>%]*?)\s*/?[>%]\}\}").unwrap();
+        let attribute = |attributes: &str, name: &str| {
+            Regex::new(&format!(r#"\b{}="([^"]*)""#, name)).unwrap().captures(attributes).map(|c| c[1].to_string())
+        };
+        let text = figure.replace_all(&text, |c: &regex::Captures| {
+            let alt = attribute(&c[1], "alt").or_else(|| attribute(&c[1], "caption")).unwrap_or_default();
+            format!("![{}]({})", alt, attribute(&c[1], "src").unwrap_or_default())
+        });
+        let youtube = Regex::new(r#"\{\{[<%]\s*youtube\s+(?:id=)?"?([\w-]+)"?\s*[>%]\}\}"#).unwrap();
+        let text = youtube.replace_all(&text, "[Video on YouTube](https://www.youtube.com/watch?v=$1)");
+        let more = Regex::new(r"<!--\s*more\s*-->\n?").unwrap();
+        let text = more.replace_all(&text, "").to_string();
+        let leftover = Regex::new(r"\{\{[<%].*?[>%]\}\}").unwrap();
+        for shortcode in leftover.find_iter(&text) {
+            issues.push(format!("shortcode left as it is: {}", shortcode.as_str()));
+        }
+        text
+    })
+}
+
+/// Obsidian syntax in `body` as scribe's: wiki links by note name (or alias) to the imported
+/// posts' slugs, embedded attachments as images served from the same vault path, highlights as
+/// `<mark>` and comments removed.
+fn obsidian(body: &str, notes: &Notes, assets: &mut BTreeMap<PathBuf, PathBuf>, issues: &mut Vec<String>) -> String {
+    let comment = Regex::new(r"(?s)%%.*?%%").unwrap();
+    let body = comment.replace_all(body, "").to_string();
+    outside_code(&body, |text| {
+        let highlight = Regex::new(r"==([^=\n]+)==").unwrap();
+        let text = highlight.replace_all(text, "<mark>$1</mark>");
+        let wiki = Regex::new(r"(!?)\[\[([^\]|#]*)(#[^\]|]*)?(?:\|([^\]]*))?\]\]").unwrap();
+        let text = wiki.replace_all(&text, |c: &regex::Captures| {
+            let (embed, name) = (!c[1].is_empty(), c[2].trim());
+            let label = c.get(4).map(|m| m.as_str().trim());
+            if let Some(slug) = notes.slug(name) {
+                if embed {
+                    issues.push(format!("embedded note [[{}]] became a link", name));
+                }
+                if c.get(3).is_some() {
+                    issues.push(format!("link to a heading in [[{}]] now links to the whole post", name));
+                }
+                return format!("[[{}|{}]]", slug, label.unwrap_or(name));
+            }
+            if let Some(file) = notes.attachments.get(&name.rsplit('/').next().unwrap_or(name).to_lowercase()) {
+                let relative = vault_path(notes, file);
+                assets.insert(file.clone(), relative.clone());
+                let url = format!("/{}", relative.to_string_lossy().replace(' ', "%20"));
+                // A number after the `|` is a display width, not a caption
+                let alt = label.filter(|l| !l.chars().all(|ch| ch.is_ascii_digit() || ch == 'x')).unwrap_or_default();
+                return if embed && images::is_image(file) { format!("![{}]({})", alt, url) } else { format!("[{}]({})", label.unwrap_or(name), url) };
+            }
+            issues.push(format!("no note or attachment named '{}'; the link became text", name));
+            label.unwrap_or(name).to_string()
+        });
+        // Markdown links and images pointing at notes or attachments by path
+        let link = Regex::new(r"(!?)\[([^\]]*)\]\(([^)\s]+)\)").unwrap();
+        link.replace_all(&text, |c: &regex::Captures| {
+            let target = c[3].replace("%20", " ");
+            if target.contains("://") || target.starts_with('#') || target.starts_with("mailto:") {
+                return c[0].to_string();
+            }
+            if target.ends_with(".md") {
+                if let Some(slug) = notes.slug(&target) {
+                    return format!("[[{}|{}]]", slug, &c[2]);
+                }
+            }
+            match notes.attachments.get(&target.rsplit('/').next().unwrap_or(&target).to_lowercase()) {
+                Some(file) => {
+                    let relative = vault_path(notes, file);
+                    assets.insert(file.clone(), relative.clone());
+                    format!("{}[{}](/{})", &c[1], &c[2], relative.to_string_lossy().replace(' ', "%20"))
+                }
+                None => c[0].to_string(),
+            }
+        })
+        .to_string()
+    })
+}
+
+/// Path of a vault file relative to the vault, where the import puts it under the posts directory.
+fn vault_path(notes: &Notes, file: &Path) -> PathBuf {
+    file.strip_prefix(&notes.root).unwrap_or(file).to_path_buf()
+}
+
+/// Copy the local images and files `body` refers to from a Jekyll or Hugo site to where scribe
+/// serves them at the same address: site-root paths from the site (Hugo's `static/`), paths
+/// relative to a Hugo page bundle into a directory named after the post.
+fn copy_assets(
+    from: Source,
+    root: &Path,
+    path: &Path,
+    slug: &str,
+    body: &str,
+    assets: &mut BTreeMap<PathBuf, PathBuf>,
+    issues: &mut Vec<String>,
+) -> String {
+    let bundle = from == Source::Hugo && path.file_stem().is_some_and(|s| s == "index");
+    let reference = Regex::new(r#"(!?\[[^\]]*\]\()([^)\s]+)(\))|(\bsrc=")([^"]+)(")"#).unwrap();
+    outside_code(body, |text| {
+        reference
+            .replace_all(text, |c: &regex::Captures| {
+                let (open, target, close) = match c.get(2) {
+                    Some(target) => (&c[1], target.as_str(), &c[3]),
+                    None => (&c[4], &c[5], &c[6]),
+                };
+                let file_part = target.split(['?', '#']).next().unwrap_or_default();
+                if target.contains("://") || target.starts_with('#') || target.starts_with("mailto:") || target.starts_with("../") && !bundle {
+                    return c[0].to_string();
+                }
+                let decoded = file_part.replace("%20", " ");
+                let (source, to) = if let Some(site_path) = decoded.strip_prefix('/') {
+                    let base = if from == Source::Hugo { root.join("static") } else { root.to_path_buf() };
+                    (base.join(site_path), PathBuf::from(site_path))
+                } else if bundle {
+                    (path.parent().unwrap_or(root).join(&decoded), Path::new(slug).join(&decoded))
+                } else {
+                    return c[0].to_string();
+                };
+                // Links to pages (e.g. `/about/`) aren't files to copy
+                if source.is_dir() || (!source.exists() && Path::new(&decoded).extension().is_none()) {
+                    return c[0].to_string();
+                }
+                if !source.is_file() {
+                    issues.push(format!("{} not found in the site; the reference is kept", target));
+                    return c[0].to_string();
+                }
+                assets.insert(source, to);
+                format!("{}{}{}", open, target, close)
+            })
+            .to_string()
+    })
+}
@@ -0,0 +1,122 @@
+use crate::config::{InitialsOptions, Ornament, SvgInitials};
+use anyhow::{Context, Result};
+use base64::Engine;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory under the output directory that holds generated initials.
+pub const DIR: &str = "initials";
+
+/// Extensions a generated initial may be saved under, in lookup order.
+const EXTENSIONS: [&str; 3] = ["png", "webp", "jpeg"];
+
+/// A generated initial, decoded from the API's base64 response.
+pub struct Image {
+    pub bytes: Vec<u8>,
+    /// File extension matching the image format: `png`, `jpeg` or `webp`
+    pub extension: String,
+}
+
+impl Image {
+    /// Decode a `data:image/<format>;base64,...` URL.
+    pub fn from_data_url(data_url: &str) -> Result<Self> {
+        let (header, data) = data_url.trim().split_once(',').context("Not a data URL")?;
+        let extension = header
+            .strip_prefix("data:image/")
+            .and_then(|h| h.strip_suffix(";base64"))
+            .context("Not a base64 image data URL")?;
+        Ok(Image {
+            bytes: base64::engine::general_purpose::STANDARD.decode(data).context("Invalid base64 image data")?,
+            extension: extension.to_string(),
+        })
+    }
+
+    /// Write the image to `<dir>/<letter>.<extension>`.
+    pub fn save(&self, dir: &Path, letter: char) -> Result<PathBuf> {
+        let path = dir.join(format!("{}.{}", letter, self.extension));
+        fs::write(&path, &self.bytes).context(format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// Directory holding the initial of the post `slug`: shared per letter, or one per post with `per_post`.
+pub fn dir(output_dir: &Path, options: &InitialsOptions, slug: &str) -> PathBuf {
+    let dir = output_dir.join(DIR);
+    if options.per_post { dir.join(slug) } else { dir }
+}
+
+/// The saved image for `letter` in `dir`, if one has been generated.
+pub fn find(dir: &Path, letter: char) -> Option<PathBuf> {
+    EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", letter, ext)))
+        .find(|path| path.is_file())
+}
+
+/// Whether `letter` needs no API call: it has an image, or a legacy `.txt` that [`migrate`] will convert.
+pub fn is_cached(dir: &Path, letter: char) -> bool {
+    find(dir, letter).is_some() || dir.join(format!("{}.txt", letter)).is_file()
+}
+
+/// Convert initials saved by older versions as data-URL `.txt` files into image files.
+///
+/// Returns the number of initials migrated.
+pub fn migrate(dir: &Path) -> Result<usize> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(0);
+    };
+    let mut migrated = 0;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "txt") {
+            continue;
+        }
+        let Some(letter) = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.chars().next()) else {
+            continue;
+        };
+        let data_url = fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?;
+        let image = Image::from_data_url(&data_url).context(format!("Failed to migrate {}", path.display()))?;
+        image.save(dir, letter)?;
+        fs::remove_file(&path)?;
+        migrated += 1;
+    }
+    Ok(migrated)
+}
+
+/// A square drop cap for `letter` as a standalone SVG document.
+pub fn svg(letter: char, style: &SvgInitials) -> String {
+    let ornament = match style.ornament {
+        Ornament::None => String::new(),
+        Ornament::Frame => r#"<rect x="6" y="6" width="88" height="88" stroke-width="2"/>"#.to_string(),
+        Ornament::Double => {
+            r#"<rect x="4" y="4" width="92" height="92" stroke-width="2"/><rect x="9" y="9" width="82" height="82" stroke-width="1"/>"#.to_string()
+        }
+        Ornament::Corners => {
+            let corners = r#"<path d="M5 22V5h17M78 5h17v17M95 78v17H78M22 95H5V78" stroke-width="2"/>"#;
+            let diamonds: String = [(50.0, 5.0), (95.0, 50.0), (50.0, 95.0), (5.0, 50.0)]
+                .iter()
+                .map(|(x, y)| format!(r#"<path d="M{} {}l3 3-3 3-3-3z" fill="{}"/>"#, x, y - 3.0, xml_escape(&style.ornament_color)))
+                .collect();
+            format!("{}{}", corners, diamonds)
+        }
+    };
+
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100"><rect width="100" height="100" fill="{}"/><g fill="none" stroke="{}">{}</g><text x="50" y="53" text-anchor="middle" dominant-baseline="middle" font-family="{}" font-size="64" fill="{}">{}</text></svg>"#,
+        xml_escape(&style.background),
+        xml_escape(&style.ornament_color),
+        ornament,
+        xml_escape(&style.font),
+        xml_escape(&style.color),
+        xml_escape(&letter.to_string())
+    )
+}
+
+/// [`svg`] as a data URL for an `<img src>`.
+pub fn svg_data_url(letter: char, style: &SvgInitials) -> String {
+    format!("data:image/svg+xml;base64,{}", base64::engine::general_purpose::STANDARD.encode(svg(letter, style)))
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,73 @@
+//! scribe: a minimal static site generator for typography-focused sites. The `scribe` binary
+//! is a thin CLI over these modules; [`serve::start`] runs the preview server in-process.
+//!
+//! Building a site from another program:
+//!
+//! ```no_run
+//! # async fn build() -> anyhow::Result<()> {
+//! let config = scribe::Config { posts_dir: "posts".into(), output_dir: "dist".into(), ..Default::default() };
+//! let mut generator = scribe::SiteGenerator::new(config);
+//! generator.generate().await?;
+//! for post in generator.posts() {
+//!     println!("{} ({} words)", post.title, post.words);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`SiteGenerator::read_posts`] parses posts without building, and the [`templates`] functions
+//! render single pages from them. Caches and the pin ledger live in `.scribe/` under the
+//! current directory.
+
+pub mod access;
+pub mod annotations;
+pub mod api;
+pub mod archetypes;
+pub mod assets;
+pub mod avatar;
+pub mod book;
+pub mod cache;
+pub mod car;
+pub mod check;
+pub mod config;
+pub mod deploy;
+pub mod dnslink;
+pub mod emit;
+pub mod ens;
+pub mod export;
+pub mod favicon;
+pub mod feed;
+pub mod fonts;
+pub mod frontmatter;
+pub mod gateway;
+pub mod generator;
+pub mod graph;
+pub mod images;
+pub mod import;
+pub mod initials;
+pub mod lint;
+pub mod lock;
+pub mod logging;
+pub mod markdown;
+pub mod markup;
+pub mod math;
+pub mod mermaid;
+pub mod minify;
+pub mod notifications;
+pub mod pins;
+pub mod private;
+pub mod progress;
+pub mod pwa;
+pub mod quoteback;
+pub mod reading;
+pub mod serve;
+pub mod sitemap;
+pub mod summarize;
+pub mod templates;
+pub mod tls;
+pub mod trails;
+pub mod trash;
+pub mod webmention;
+
+pub use config::Config;
+pub use generator::{BuildSummary, Post, SiteGenerator};
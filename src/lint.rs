@@ -0,0 +1,68 @@
+use crate::markdown::{strip_code_spans, CodeFences};
+use regex::Regex;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Marker that opts a single image out of the alt-text check (same or previous line).
+pub const ALT_OPT_OUT: &str = "<!-- alt-ok -->";
+
+/// An image without alt text.
+#[derive(Debug, Clone)]
+pub struct MissingAlt {
+    pub path: PathBuf,
+    pub line: usize,
+    pub src: String,
+}
+
+impl fmt::Display for MissingAlt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: image \"{}\" has no alt text", self.path.display(), self.line, self.src)
+    }
+}
+
+/// Find Markdown (`![](src)`) and HTML (`<img>`) images with empty or missing alt text.
+/// Line numbers refer to the whole file, including frontmatter. Fenced and inline code is skipped.
+pub fn missing_alt_text(path: &Path, content: &str) -> Vec<MissingAlt> {
+    let markdown_image = Regex::new(r"!\[([^\]]*)\]\(\s*<?([^)\s>]*)").unwrap();
+    let html_image = Regex::new(r"(?i)<img\b[^>]*>").unwrap();
+    let alt_attr = Regex::new(r#"(?i)\balt\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    let src_attr = Regex::new(r#"(?i)\bsrc\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+
+    let mut issues = Vec::new();
+    let mut fences = CodeFences::default();
+    let mut previous_line = "";
+
+    for (index, line) in content.lines().enumerate() {
+        if fences.is_code(line) || line.contains(ALT_OPT_OUT) || previous_line.contains(ALT_OPT_OUT) {
+            previous_line = line;
+            continue;
+        }
+
+        let text = strip_code_spans(line);
+        for caps in markdown_image.captures_iter(&text) {
+            if caps[1].trim().is_empty() {
+                issues.push(MissingAlt { path: path.to_path_buf(), line: index + 1, src: caps[2].to_string() });
+            }
+        }
+
+        for tag in html_image.find_iter(&text) {
+            let alt = alt_attr
+                .captures(tag.as_str())
+                .and_then(|c| c.get(1).or_else(|| c.get(2)))
+                .map(|m| m.as_str().trim().to_string())
+                .unwrap_or_default();
+            if alt.is_empty() {
+                let src = src_attr
+                    .captures(tag.as_str())
+                    .and_then(|c| c.get(1).or_else(|| c.get(2)))
+                    .map(|m| m.as_str().to_string())
+                    .unwrap_or_default();
+                issues.push(MissingAlt { path: path.to_path_buf(), line: index + 1, src });
+            }
+        }
+
+        previous_line = line;
+    }
+
+    issues
+}
@@ -0,0 +1,171 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the lockfile, kept in the project directory next to the config.
+pub const FILE: &str = "scribe.lock";
+
+/// The lockfile of the project `config` belongs to.
+pub fn path(config: &Config) -> PathBuf {
+    config.project_dir().join(FILE)
+}
+
+/// Remote resources a build used, keyed by the URL they came from, with hashes so a `--frozen`
+/// build can prove it uses exactly the same ones.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub resources: BTreeMap<String, Resource>,
+}
+
+/// One fetched resource.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Resource {
+    pub kind: ResourceKind,
+    /// Where the download is kept, relative to the output directory, or to the project for
+    /// downloads kept under `.scribe/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// SHA-256 of the downloaded file or metadata; none when the fetch found nothing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// The fetched link metadata or webmentions, used as-is by `--frozen` builds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceKind {
+    Font,
+    Favicon,
+    Avatar,
+    /// Title and description of an annotation link
+    Metadata,
+    /// A file of the bundled KaTeX release: stylesheet, script or font
+    Katex,
+    /// The bundled mermaid.js runtime
+    Mermaid,
+    /// Mentions of a post, as fetched from webmention.io
+    Webmentions,
+}
+
+impl ResourceKind {
+    /// Downloads a build always fetches when missing, as opposed to lookups that may come up empty.
+    fn required(self) -> bool {
+        matches!(self, ResourceKind::Font | ResourceKind::Avatar | ResourceKind::Katex | ResourceKind::Mermaid)
+    }
+
+    /// Fetched data a frozen build takes from the lockfile, so only has to be locked.
+    fn used_as_locked(self) -> bool {
+        matches!(self, ResourceKind::Metadata | ResourceKind::Webmentions)
+    }
+}
+
+impl Resource {
+    /// A file kept at `path` inside `dir` (the output directory, or the project for `.scribe/`),
+    /// hashed if it exists.
+    pub fn file(kind: ResourceKind, dir: &Path, path: String) -> Self {
+        let sha256 = fs::read(dir.join(&path)).ok().map(|bytes| sha256(&bytes));
+        Resource { kind, path: Some(path), sha256, meta: None }
+    }
+
+    /// Link metadata, or none when it couldn't be fetched.
+    pub fn metadata(meta: Option<serde_json::Value>) -> Self {
+        Self::data(ResourceKind::Metadata, meta)
+    }
+
+    /// Webmentions of a post, or none when they haven't been fetched.
+    pub fn webmentions(mentions: Option<serde_json::Value>) -> Self {
+        Self::data(ResourceKind::Webmentions, mentions)
+    }
+
+    fn data(kind: ResourceKind, meta: Option<serde_json::Value>) -> Self {
+        let sha256 = meta.as_ref().map(|m| sha256(m.to_string().as_bytes()));
+        Resource { kind, path: None, sha256, meta }
+    }
+}
+
+impl Lockfile {
+    /// Read the lockfile at `path`; a missing one is empty.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).context(format!("Failed to parse {}", path.display()))
+    }
+
+    /// Write the lockfile to `path` unless it already holds the same, returning whether it changed.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        let path = path.as_ref();
+        if Self::load(path).is_ok_and(|existing| existing == *self) {
+            return Ok(false);
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content + "\n").context(format!("Failed to write {}", path.display()))?;
+        Ok(true)
+    }
+
+    /// What a build that fetched should lock: files on disk, fetched data, and lookups that came
+    /// up empty (a site without a favicon, a link without metadata). Downloads that failed are
+    /// left out, to be fetched again, and nothing points at a file that isn't there.
+    pub fn fetched(mut self) -> Self {
+        self.resources.retain(|_, r| r.sha256.is_some() || !r.kind.required());
+        for resource in self.resources.values_mut().filter(|r| r.sha256.is_none()) {
+            resource.path = None;
+        }
+        self
+    }
+
+    /// How the `current` resources differ from the locked ones, one line per resource that
+    /// would have to be fetched again. Link metadata and webmentions only have to be locked,
+    /// since frozen builds take them from the lockfile.
+    pub fn differences(&self, current: &Lockfile) -> Vec<String> {
+        current
+            .resources
+            .iter()
+            .filter_map(|(url, resource)| {
+                let path = resource.path.as_deref().unwrap_or(url);
+                // Fonts, the avatar and the scripts are always downloaded when missing; a favicon
+                // may be locked as not found, since many sites have none
+                if resource.sha256.is_none() && resource.kind.required() {
+                    return Some(format!("{} is missing (from {})", path, url));
+                }
+                let Some(locked) = self.resources.get(url) else {
+                    return Some(format!("{} is not locked", url));
+                };
+                if resource.kind.used_as_locked() || locked.sha256 == resource.sha256 {
+                    return None;
+                }
+                Some(match (&locked.sha256, &resource.sha256) {
+                    (Some(_), None) => format!("{} is missing (from {})", path, url),
+                    (None, Some(_)) => format!("{} was not found when locked (from {})", path, url),
+                    _ => format!("{} does not match its locked hash (from {})", path, url),
+                })
+            })
+            .collect()
+    }
+
+    /// Metadata of every locked link.
+    pub fn metadata(&self) -> impl Iterator<Item = (&str, &serde_json::Value)> {
+        self.data(ResourceKind::Metadata)
+    }
+
+    /// Locked webmentions, by the webmention.io URL they were fetched from.
+    pub fn webmentions(&self) -> impl Iterator<Item = (&str, &serde_json::Value)> {
+        self.data(ResourceKind::Webmentions)
+    }
+
+    fn data(&self, kind: ResourceKind) -> impl Iterator<Item = (&str, &serde_json::Value)> {
+        self.resources.iter().filter(move |(_, r)| r.kind == kind).filter_map(|(url, r)| Some((url.as_str(), r.meta.as_ref()?)))
+    }
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
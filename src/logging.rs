@@ -0,0 +1,117 @@
+use std::fmt::Write as _;
+use std::io::Write as _;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// How log events are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The message alone, as the CLI has always printed it: info and debug on stdout, warnings
+    /// and errors on stderr
+    Human,
+    /// One JSON object per event on stderr, e.g.
+    /// `{"level":"info","target":"scribe::generator","message":"Generated 5 posts"}`
+    Json,
+}
+
+/// Send scribe's log events to the terminal, showing those at `level` and more severe. JSON
+/// output has no colors. Does nothing if a subscriber is already installed.
+pub fn init(level: Level, format: Format) {
+    if format == Format::Json {
+        colored::control::set_override(false);
+    }
+    let _ = tracing::subscriber::set_global_default(Logger { level, format });
+}
+
+struct Logger {
+    level: Level,
+    format: Format,
+}
+
+impl Subscriber for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        // Dependencies (hyper, reqwest, warp) log through tracing too; only scribe's events are shown
+        *metadata.level() <= self.level && metadata.target().starts_with("scribe")
+    }
+
+    // Scribe logs events only, so spans need no bookkeeping
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        match self.format {
+            Format::Human => {
+                let mut line = fields.message;
+                for (name, value) in &fields.others {
+                    let _ = write!(line, " {}={}", name, value);
+                }
+                // Printed above any progress bars rather than through them
+                crate::progress::suspend(|| {
+                    if *metadata.level() <= Level::WARN {
+                        let _ = writeln!(std::io::stderr(), "{}", line);
+                    } else {
+                        let _ = writeln!(std::io::stdout(), "{}", line);
+                    }
+                });
+            }
+            Format::Json => {
+                let mut object = serde_json::Map::new();
+                object.insert("level".into(), metadata.level().as_str().to_lowercase().into());
+                object.insert("target".into(), metadata.target().into());
+                object.insert("message".into(), fields.message.trim().into());
+                for (name, value) in fields.others {
+                    object.insert(name, value);
+                }
+                let _ = writeln!(std::io::stderr(), "{}", serde_json::Value::Object(object));
+            }
+        }
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+/// The message and any structured fields of an event.
+#[derive(Default)]
+struct Fields {
+    message: String,
+    others: Vec<(String, serde_json::Value)>,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message = value.to_string(),
+            name => self.others.push((name.to_string(), value.into())),
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.others.push((field.name().to_string(), value.into()));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.others.push((field.name().to_string(), value.into()));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.others.push((field.name().to_string(), value.into()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{:?}", value),
+            name => self.others.push((name.to_string(), format!("{:?}", value).into())),
+        }
+    }
+}
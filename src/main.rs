@@ -1,24 +1,21 @@
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use colored::*;
-use std::path::PathBuf;
+use serde::Deserialize;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use std::process;
-use warp::Filter;
 use ipfs_api_backend_hyper::{IpfsApi, IpfsClient, TryFromUri};
-use notify::{RecursiveMode, Watcher, PollWatcher};
-use std::sync::mpsc;
 use std::time::Duration;
-use warp::ws::{Message, WebSocket};
-use futures_util::sink::SinkExt;
-use std::sync::Arc;
-use tokio::sync::{broadcast, RwLock};
 
-mod config;
-mod generator;
-mod templates;
-
-use config::Config;
-use generator::SiteGenerator;
+use scribe::{access, annotations, api, archetypes, book, cache, car, check, config, deploy, dnslink, ens, export, favicon, frontmatter, graph, import, initials, notifications, pins, progress, serve, summarize, trash};
+use scribe::config::Config;
+use scribe::generator::{find_post_file, post_files, reserved_slugs, retarget_links, sanitize_slug, SiteGenerator};
+use scribe::notifications::BuildOutcome;
+use scribe::serve::{DeployOnChange, ServeOptions};
+use scribe::logging::{self, Format};
+use scribe::tls::Tls;
+use tracing::{error, info, warn, Level};
 
 #[derive(Parser)]
 #[command(name = "scribe")]
@@ -27,6 +24,22 @@ use generator::SiteGenerator;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    
+    /// Disable all network access while building (same as `"offline": true` in the config)
+    #[arg(long, global = true)]
+    offline: bool,
+    
+    /// Only print warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    
+    /// Also print debug output, such as every file written (with generate --dry-run: every page, unchanged ones included, and planned API calls)
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    
+    /// Print log output as one JSON object per line on stderr
+    #[arg(long, global = true)]
+    log_json: bool,
 }
 
 #[derive(Subcommand)]
@@ -36,6 +49,60 @@ enum Commands {
         /// Path to config file
         #[arg(short, long, default_value = "config.json")]
         config: PathBuf,
+        
+        /// Print which pages would be created, updated or left stale without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        
+        /// Fail the build on lint findings such as invalid frontmatter or images without alt text
+        #[arg(long)]
+        strict: bool,
+        
+        /// Only render posts matching a slug, glob (e.g. "rust-*") or tag; repeatable
+        #[arg(long)]
+        only: Vec<String>,
+        
+        /// Annotate HTML with the template/partial behind each region and write render contexts as JSON
+        #[arg(long)]
+        debug_templates: bool,
+        
+        /// Leave pages that are no longer generated (e.g. of renamed posts) in the output directory
+        #[arg(long)]
+        keep_stale: bool,
+        
+        /// Build offline from the resources recorded in scribe.lock, failing if any would need fetching
+        #[arg(long)]
+        frozen: bool,
+        
+        /// Include posts dated in the future, which are left out until a build after their date
+        #[arg(long)]
+        future: bool,
+        
+        /// Build the site as of the end of this day (YYYY-MM-DD), leaving out posts dated later
+        #[arg(long, conflicts_with = "future")]
+        until: Option<chrono::NaiveDate>,
+        
+        /// Build the website, or only its content as JSON for a frontend of your own
+        #[arg(long, value_enum, default_value = "html")]
+        format: api::BuildFormat,
+        
+        /// Subdirectory the site is served from, e.g. /blog/ (overrides base_path in the config)
+        #[arg(long)]
+        base_path: Option<String>,
+        
+        /// Minify pages and the stylesheet, and fingerprint the stylesheet for long-lived caching
+        #[arg(long)]
+        release: bool,
+    },
+    /// Delete the output directory
+    Clean {
+        /// Keep generated illuminated initials, which cost an API call each to regenerate
+        #[arg(long)]
+        keep_initials: bool,
+        
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
     },
     /// Serve the generated site locally
     Serve {
@@ -58,6 +125,42 @@ enum Commands {
         /// Watch for changes and regenerate automatically
         #[arg(short, long, default_value = "true")]
         watch: bool,
+        
+        /// Pin the site to IPFS after each successful regeneration
+        #[arg(long)]
+        deploy_on_change: bool,
+        
+        /// IPFS API endpoint used by --deploy-on-change
+        #[arg(long, default_value = "http://127.0.0.1:5001")]
+        ipfs_api: String,
+        
+        /// Seconds to wait for further changes before deploying
+        #[arg(long, default_value = "30")]
+        deploy_debounce: u64,
+        
+        /// Serve the existing build instead of generating the site first
+        #[arg(long)]
+        no_generate: bool,
+        
+        /// Open the site in the default browser once the server is up
+        #[arg(long)]
+        open: bool,
+        
+        /// Serve over HTTPS with this PEM certificate (needs --tls-key)
+        #[arg(long, requires = "tls_key", conflicts_with = "tls_self_signed")]
+        tls_cert: Option<PathBuf>,
+        
+        /// PKCS#8 PEM private key for --tls-cert
+        #[arg(long, requires = "tls_cert")]
+        tls_key: Option<PathBuf>,
+        
+        /// Serve over HTTPS with a self-signed certificate for localhost, kept in .scribe/tls/
+        #[arg(long)]
+        tls_self_signed: bool,
+        
+        /// Log requests to .scribe/access.log, for `scribe stats`
+        #[arg(long)]
+        access_log: bool,
     },
     /// Generate illuminated initials for specific letters
     Initials {
@@ -77,6 +180,9 @@ enum Commands {
     Create {
         /// Directory to create the project in
         directory: PathBuf,
+        /// Read the answers from a TOML file instead of prompting
+        #[arg(long, value_name = "ANSWERS")]
+        from: Option<PathBuf>,
     },
     /// Pin generated site content to IPFS
     Pin {
@@ -95,17 +201,224 @@ enum Commands {
         /// Recursive pin (pin all referenced content)
         #[arg(short, long, default_value = "true")]
         recursive: bool,
+        
+        /// Update an IPNS name to point at the new root hash
+        #[arg(long)]
+        publish_ipns: bool,
+        
+        /// IPNS key to publish under
+        #[arg(long, default_value = "self")]
+        ipns_key: String,
+        
+        /// Pack the directory into a CAR file instead of adding it to an IPFS node
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["name", "publish_ipns", "update_ens"])]
+        car: Option<PathBuf>,
+        
+        /// Check that the directory hashes to this CID instead of adding it to an IPFS node
+        #[arg(long, value_name = "CID", conflicts_with_all = ["name", "publish_ipns", "car", "update_ens"])]
+        verify: Option<String>,
+        
+        /// Point the ENS name configured under `publish.ens` at the new root hash
+        #[arg(long)]
+        update_ens: bool,
+        
+        /// Config file listing extra nodes to pin on (`publish.nodes`), if it exists
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+    },
+    /// Generate the site and publish it to the host configured under `deploy`
+    Deploy {
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+        
+        /// Publish the existing output directory without regenerating
+        #[arg(long)]
+        skip_generate: bool,
+    },
+    /// Generate the site, pin it to IPFS, update IPNS and the DNSLink record and ENS name configured under `publish`
+    Publish {
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+        
+        /// Publish the existing output directory without regenerating
+        #[arg(long)]
+        skip_generate: bool,
+        
+        /// Pin name/description
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+    /// Export the internal link graph between posts
+    Graph {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "json")]
+        format: graph::GraphFormat,
+        
+        /// Include tags as nodes linked to their posts
+        #[arg(long)]
+        tags: bool,
+        
+        /// Only include posts dated on or after this day (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<chrono::NaiveDate>,
+        
+        /// Only include posts dated on or before this day (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<chrono::NaiveDate>,
+        
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+    },
+    /// Render a post for another medium, such as a newsletter email
+    Export {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "email")]
+        format: export::ExportFormat,
+        
+        /// Slug of the post to export (required for email)
+        #[arg(short, long)]
+        post: Option<String>,
+        
+        /// Only put posts with this tag in the book
+        #[arg(long)]
+        tag: Option<String>,
+        
+        /// Put this series in the book, in series order
+        #[arg(long)]
+        series: Option<String>,
+        
+        /// Book title (default: the series or tag, or the site title)
+        #[arg(long)]
+        title: Option<String>,
+        
+        /// Cover image for the book
+        #[arg(long)]
+        cover: Option<PathBuf>,
+        
+        /// Headless browser command printing PDFs
+        #[arg(long, default_value = "chromium")]
+        renderer: String,
+        
+        /// Directory to write the exported files to
+        #[arg(short, long, default_value = ".")]
+        output: PathBuf,
+        
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+    },
+    /// Convert the posts of a Jekyll or Hugo site or an Obsidian vault into the posts directory
+    Import {
+        /// Generator the content comes from
+        #[arg(long, value_enum)]
+        from: import::Source,
+        
+        /// Root of the site or vault to import
+        path: PathBuf,
+        
+        /// Report what would be written and what can't be mapped, without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+    },
+    /// Render a single Markdown file with the site's template and open it in the browser
+    Preview {
+        /// Markdown file to render (may live outside the posts directory)
+        file: PathBuf,
+        
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+        
+        /// Only render; don't open a browser
+        #[arg(long)]
+        no_open: bool,
+    },
+    /// Validate the generated site: internal links, images and post dates
+    Check {
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+        
+        /// Also fetch external links and report those not returning 2xx
+        #[arg(long)]
+        external: bool,
+        
+        /// Maximum simultaneous external requests
+        #[arg(long, default_value = "8")]
+        concurrency: usize,
+        
+        /// Timeout per external request, in seconds
+        #[arg(long, default_value = "10")]
+        timeout: u64,
+    },
+    /// Report images in the posts directory that no post uses, and references to missing ones
+    Assets {
+        /// Delete the unused images
+        #[arg(long)]
+        prune: bool,
+        
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+    },
+    /// Inspect and maintain the URL-metadata, illuminated-initial and favicon caches
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+        
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json", global = true)]
+        config: PathBuf,
     },
     /// Create a new blog post
     New {
-        /// Title of the new post
+        /// Title of the new post; prompted for when missing
         #[arg(short, long)]
-        title: String,
+        title: Option<String>,
         
         /// Excerpt/description of the post
         #[arg(short, long)]
         excerpt: Option<String>,
         
+        /// Tags of the post, comma-separated or repeated
+        #[arg(long, value_delimiter = ',')]
+        tags: Vec<String>,
+        
+        /// Series the post belongs to
+        #[arg(long)]
+        series: Option<String>,
+        
+        /// Slug (and file name) to use instead of one derived from the title
+        #[arg(long)]
+        slug: Option<String>,
+        
+        /// Mark the post as a draft, which builds leave out
+        #[arg(long)]
+        draft: bool,
+        
+        /// Start from archetypes/<KIND>.md instead of archetypes/default.md or the built-in post
+        #[arg(short, long)]
+        kind: Option<String>,
+        
+        /// Prompt for the post's details, using the flags given as defaults
+        #[arg(short, long)]
+        interactive: bool,
+        
+        /// Open the new post in $EDITOR
+        #[arg(long)]
+        edit: bool,
+        
         /// Path to config file
         #[arg(short, long, default_value = "config.json")]
         config: PathBuf,
@@ -114,163 +427,371 @@ enum Commands {
         #[arg(short, long)]
         posts_dir: Option<PathBuf>,
     },
+    /// Write AI-generated excerpts and TL;DRs into the frontmatter of posts missing them
+    Summarize {
+        /// Only summarize posts matching a slug, glob (e.g. "rust-*") or tag; repeatable
+        #[arg(long)]
+        only: Vec<String>,
+        
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+    },
+    /// Set a post's `updated` (default) or `date` field to now, leaving the rest of the file as is
+    Touch {
+        /// Slug of the post (original or sanitized)
+        slug: String,
+        
+        /// Bump the publication `date` instead
+        #[arg(long, conflicts_with = "updated")]
+        published: bool,
+        
+        /// Bump the `updated` field (the default)
+        #[arg(long)]
+        updated: bool,
+        
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+    },
+    /// Rename a post: move its source file, update links to it and redirect its old URL
+    Mv {
+        /// Current slug of the post (original or sanitized)
+        old: String,
+        
+        /// New slug
+        new: String,
+        
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+    },
+    /// Move a post to the trash; the next build takes its page down
+    Rm {
+        /// Slug of the post (original or sanitized)
+        slug: String,
+        
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+    },
+    /// Bring a post back from the trash, or list the trash without a slug
+    Restore {
+        /// Slug of the trashed post
+        slug: Option<String>,
+        
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+    },
+    /// Summarize the access log of `serve --access-log`: views per post, referrers and browsers
+    Stats {
+        /// Access log to read
+        #[arg(long, default_value = access::LOG_PATH)]
+        log: PathBuf,
+        
+        /// Only count requests on or after this day (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<chrono::NaiveDate>,
+        
+        /// Rows to show in each list
+        #[arg(long, default_value = "10")]
+        top: usize,
+        
+        /// Path to config file
+        #[arg(short, long, default_value = "config.json")]
+        config: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Show cache sizes, stale entries and hit rates from the last build
+    Stats,
+    /// Delete cached data
+    Clear {
+        /// Only drop URL metadata past its 30-day lifetime
+        #[arg(long)]
+        stale: bool,
+    },
+    /// Fetch everything the next build would need
+    Warm,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
     
-    // Print ASCII art
-    println!(
-        r#"
+    let level = if cli.quiet { Level::WARN } else if cli.verbose { Level::DEBUG } else { Level::INFO };
+    logging::init(level, if cli.log_json { Format::Json } else { Format::Human });
+    progress::enable(!cli.quiet && !cli.log_json && std::io::stderr().is_terminal());
+    
+    // Print ASCII art (unless quiet, or stdout carries machine-readable output)
+    if !cli.quiet && !cli.log_json && !matches!(cli.command, Commands::Graph { output: None, .. }) {
+        println!(
+            r#"
    ◜ s c r i b e ◝
     ink • eternal
                                  
 "#
-    );
+        );
+    }
+    
+    // Read by Config::load in every command, including watch-mode rebuilds
+    if cli.offline {
+        std::env::set_var("SCRIBE_OFFLINE", "1");
+    }
     
     match cli.command {
-        Commands::Generate { config } => {
+        Commands::Generate { config, dry_run, strict, only, debug_templates, keep_stale, frozen, future, until, format, base_path, release } => {
             // Load configuration
-            let config = Config::load(&config)
+            let mut config = Config::load(&config)
                 .context("Failed to load configuration")?;
+            if base_path.is_some() {
+                config.base_path = base_path;
+            }
+            
+            // Webhooks are network requests too
+            let notify_config = if config.offline { Default::default() } else { config.notifications.clone() };
+            let site_title = config.title.clone();
             
             // Create generator
             let mut generator = SiteGenerator::new(config);
+            generator.set_strict(strict);
+            generator.set_only(only);
+            generator.set_debug_templates(debug_templates);
+            generator.set_keep_stale(keep_stale);
+            generator.set_frozen(frozen);
+            generator.set_future(future);
+            generator.set_until(until);
+            generator.set_format(format);
+            generator.set_release(release);
+            
+            if dry_run {
+                print_change_plan(generator, cli.verbose).await?;
+                return Ok(());
+            }
             
             // Generate site
             if let Err(e) = generator.generate().await {
-                eprintln!("{}", format!("Error: {}", e).red());
+                // `{:#}` keeps the causes, such as where a data file failed to parse
+                let message = format!("{:#}", e);
+                error!("{}", format!("Error: {}", message).red());
+                notifications::notify_webhooks(&notify_config, &site_title, &BuildOutcome::Failure(message)).await;
                 process::exit(1);
             }
+            notifications::notify_webhooks(&notify_config, &site_title, &BuildOutcome::Success).await;
         }
-        Commands::Serve { dist, port, host, config, watch } => {
-            serve_site(dist, host, port, config, watch).await?;
+        Commands::Clean { keep_initials, config } => {
+            clean_output(keep_initials, config)?;
+        }
+        Commands::Serve { dist, port, host, config, watch, deploy_on_change, ipfs_api, deploy_debounce, no_generate, open, tls_cert, tls_key, tls_self_signed, access_log } => {
+            if !no_generate {
+                generate_before_serving(&config, &dist).await;
+            }
+            let deploy = deploy_on_change.then(|| DeployOnChange {
+                dist: dist.clone(),
+                ipfs_api,
+                debounce: Duration::from_secs(deploy_debounce),
+            });
+            let tls = match (tls_cert, tls_key) {
+                (Some(cert), Some(key)) => Some(Tls::Files { cert, key }),
+                _ => tls_self_signed.then_some(Tls::SelfSigned),
+            };
+            let access_log = access_log.then(|| PathBuf::from(access::LOG_PATH));
+            serve_site(ServeOptions { dist, host, port, config, watch, deploy, tls, access_log }, open).await;
         }
         Commands::Initials { letters, config, output } => {
             generate_initials_command(letters, config, output).await?;
         }
-        Commands::Create { directory } => {
-            create_project(directory).await?;
+        Commands::Create { directory, from } => {
+            create_project(directory, from).await?;
+        }
+        Commands::Pin { dist, verify: Some(cid), .. } => {
+            verify_pin(dist, cid)?;
+        }
+        Commands::Pin { dist, car: Some(car), .. } => {
+            export_car(dist, car)?;
+        }
+        Commands::Pin { dist, ipfs_api, name, recursive, publish_ipns, ipns_key, car: None, verify: None, update_ens, config } => {
+            let ipns_key = publish_ipns.then_some(ipns_key);
+            pin_to_ipfs(config, dist, ipfs_api, name, recursive, ipns_key, update_ens).await?;
+        }
+        Commands::Deploy { config, skip_generate } => {
+            deploy_site(config, skip_generate).await?;
+        }
+        Commands::Publish { config, skip_generate, name } => {
+            publish_site(config, skip_generate, name).await?;
+        }
+        Commands::Graph { format, tags, since, until, output, config } => {
+            export_graph(config, format, tags, since, until, output).await?;
+        }
+        Commands::Export { format, post, tag, series, title, cover, renderer, output, config } => {
+            let selection = book::Selection { post, tag, series };
+            export_posts(config, format, selection, title, cover, renderer, output).await?;
+        }
+        Commands::Import { from, path, dry_run, config } => {
+            import_content(from, path, dry_run, config).await?;
+        }
+        Commands::Preview { file, config, no_open } => {
+            preview_post(file, config, no_open).await?;
         }
-        Commands::Pin { dist, ipfs_api, name, recursive } => {
-            pin_to_ipfs(dist, ipfs_api, name, recursive).await?;
+        Commands::Check { config, external, concurrency, timeout } => {
+            let external = external.then(|| check::ExternalCheck { concurrency, timeout: Duration::from_secs(timeout) });
+            check_site(config, external).await?;
         }
-        Commands::New { title, excerpt, config, posts_dir } => {
-            create_new_post(title, excerpt, config, posts_dir).await?;
+        Commands::Assets { prune, config } => {
+            report_assets(prune, config).await?;
+        }
+        Commands::Cache { action, config } => {
+            manage_cache(action, config).await?;
+        }
+        Commands::New { title, excerpt, tags, series, slug, draft, kind, interactive, edit, config, posts_dir } => {
+            let post = NewPost { title: title.unwrap_or_default(), excerpt, tags, series, slug, draft, kind };
+            let post = if interactive || post.title.is_empty() {
+                match prompt_new_post(post)? {
+                    Some(post) => post,
+                    None => return Ok(()),
+                }
+            } else {
+                post
+            };
+            create_new_post(post, edit, config, posts_dir).await?;
+        }
+        Commands::Summarize { only, config } => {
+            summarize_posts(only, config).await?;
+        }
+        Commands::Touch { slug, published, updated: _, config } => {
+            touch_post(slug, published, config)?;
+        }
+        Commands::Mv { old, new, config } => {
+            move_post(old, new, config)?;
+        }
+        Commands::Rm { slug, config } => {
+            trash_post(slug, config)?;
+        }
+        Commands::Restore { slug, config } => {
+            restore_post(slug, config)?;
+        }
+        Commands::Stats { log, since, top, config } => {
+            show_stats(log, since, top, config).await?;
         }
     }
     
     Ok(())
 }
 
-// Global hot reload broadcaster
-type HotReloadSender = Arc<RwLock<Option<broadcast::Sender<String>>>>;
-
-async fn serve_site(dist_path: PathBuf, host: String, port: u16, config_path: PathBuf, watch: bool) -> Result<()> {
-    // Check if dist directory exists
-    if !dist_path.exists() {
-        eprintln!("{}", format!("Error: Directory '{}' does not exist. Run 'scribe generate' first.", dist_path.display()).red());
-        process::exit(1);
+async fn print_change_plan(mut generator: SiteGenerator, verbose: bool) -> Result<()> {
+    info!("{}", "Dry run: nothing will be written and no APIs will be called".cyan());
+    let output_dir = PathBuf::from(&generator.config().output_dir);
+    let has_api_key = generator.config().openai_api_key.is_some();
+    let initials_mode = generator.config().initials.mode;
+    let offline = generator.config().offline;
+    let plan = generator.plan().await?;
+    
+    if verbose {
+        info!("Output directory: {}", output_dir.display());
     }
-
-    if !dist_path.is_dir() {
-        eprintln!("{}", format!("Error: '{}' is not a directory.", dist_path.display()).red());
-        process::exit(1);
+    for path in &plan.created {
+        info!("  {} {}", "create".green(), path.display());
+    }
+    for path in &plan.updated {
+        info!("  {} {}", "update".yellow(), path.display());
+    }
+    if verbose {
+        for path in &plan.unchanged {
+            info!("  {} {}", "same  ".dimmed(), path.display());
+        }
+    }
+    for path in &plan.stale {
+        info!("  {} {} (no longer generated, removed on generate)", "stale ".red(), path.display());
     }
-
-    println!("{}", format!("Starting server...").green().bold());
-    println!("{}", format!("Serving: {}", dist_path.display()).blue());
-    println!("{}", format!("URL: http://{}:{}", host, port).blue());
     
-    // Create hot reload broadcaster
-    let hot_reload_tx: HotReloadSender = Arc::new(RwLock::new(None));
+    info!(
+        "\n{} to create, {} to update, {} unchanged, {} stale",
+        plan.created.len().to_string().green(),
+        plan.updated.len().to_string().yellow(),
+        plan.unchanged.len(),
+        plan.stale.len().to_string().red()
+    );
     
-    // Setup file watching if enabled
-    let _watcher_handle = if watch {
-        println!("{}", "File watching enabled - changes will trigger regeneration".yellow());
-        // Create broadcast channel for hot reload
-        let (reload_tx, _) = broadcast::channel(100);
-        *hot_reload_tx.write().await = Some(reload_tx.clone());
-        Some(setup_file_watcher(config_path.clone(), Some(reload_tx)).await?)
+    if offline {
+        info!("Illuminated initials and link metadata: skipped (offline)");
+        return Ok(());
+    }
+    if initials_mode != config::InitialsMode::Openai {
+        info!("Illuminated initials: no API calls (initials.mode is not \"openai\")");
+    } else if !has_api_key {
+        info!("Illuminated initials: skipped (OPENAI_API_KEY not set)");
+    } else if plan.initials.is_empty() {
+        info!("Illuminated initials: all cached");
     } else {
-        None
-    };
+        let letters: String = plan.initials.iter().collect();
+        info!("Illuminated initials: {} to generate ({}), {} OpenAI image request(s)", plan.initials.len(), letters, plan.initials.len());
+    }
+    info!("Link metadata: {} URL(s) to fetch", plan.uncached_urls);
     
-    println!("{}", format!("Press Ctrl+C to stop").yellow());
-
-    // Create static file serving route
-    let static_files = warp::fs::dir(dist_path.clone())
-        .or(warp::path::end().and(warp::fs::file(dist_path.join("index.html"))));
-
-    // Redirect route: map unsanitized single-segment paths to sanitized directories
-    let redirect_dist = dist_path.clone();
-    let sanitize_redirect = warp::path::param::<String>()
-        .and(warp::path::end())
-        .and_then(move |slug: String| {
-            let redirect_dist = redirect_dist.clone();
-            async move {
-                let sanitized = sanitize_slug(&slug);
-                let sanitized_dir = redirect_dist.join(&sanitized);
-                // Only redirect if a generated directory exists for the sanitized slug
-                if sanitized != slug && sanitized_dir.is_dir() {
-                    let uri: warp::http::Uri = format!("/{}/", sanitized).parse().unwrap();
-                    Ok::<_, warp::Rejection>(warp::redirect::see_other(uri))
-                } else {
-                    Err(warp::reject::not_found())
-                }
-            }
-        });
-
-    let cors = warp::cors()
-        .allow_any_origin()
-        .allow_headers(vec!["content-type"])
-        .allow_methods(vec!["GET", "POST", "DELETE"]);
-
-    // Create routes with optional WebSocket for hot reload
-    let routes = if watch {
-        let hot_reload_tx_clone = hot_reload_tx.clone();
-        let ws_route = warp::path("__hot_reload__")
-            .and(warp::ws())
-            .and(warp::any().map(move || hot_reload_tx_clone.clone()))
-            .and_then(|ws: warp::ws::Ws, hot_reload_tx: HotReloadSender| async move {
-                Ok::<_, warp::Rejection>(ws.on_upgrade(move |socket| handle_websocket(socket, hot_reload_tx)))
-            });
-        
-        ws_route
-            .or(sanitize_redirect)
-            .or(static_files)
-            .with(cors)
-            .with(warp::log("scribe"))
-            .boxed()
-    } else {
-        sanitize_redirect
-            .or(static_files)
-            .with(cors)
-            .with(warp::log("scribe"))
-            .boxed()
-    };
-
-    // Parse the host address
-    let addr: std::net::IpAddr = host.parse()
-        .context("Invalid host address")?;
+    Ok(())
+}
 
-    // Start the server
-    warp::serve(routes)
-        .run((addr, port))
-        .await;
+/// Build the site so `serve` works straight after `create`. A failed build still serves the last
+/// good one when there is one.
+async fn generate_before_serving(config_path: &Path, dist: &Path) {
+    if !config_path.exists() {
+        warn!("{}", format!("Warning: {} not found; serving {} as it is", config_path.display(), dist.display()).yellow());
+        return;
+    }
+    let result = match Config::load(config_path) {
+        Ok(config) => SiteGenerator::new(config).generate().await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        if !dist.is_dir() {
+            error!("{}", format!("Error: {:#}", e).red());
+            process::exit(1);
+        }
+        warn!("{}", format!("Warning: {:#}; serving the previous build", e).yellow());
+    }
+}
 
-    Ok(())
-} 
+async fn serve_site(options: ServeOptions, open: bool) {
+    info!("{}", "Starting server...".green().bold());
+    info!("{}", format!("Serving: {}", options.dist.display()).blue());
+    let server = match serve::start(options).await {
+        Ok(server) => server,
+        Err(e) => {
+            error!("{}", format!("Error: {:#}", e).red());
+            process::exit(1);
+        }
+    };
+    info!("{}", format!("URL: {}", server.url()).blue());
+    for url in server.network_urls() {
+        info!("{}", format!("Network: {}", url).blue());
+    }
+    if open {
+        open_in_browser(&server.url());
+    }
+    info!("{}", "Press Ctrl+C to stop".yellow());
+    
+    if let Err(e) = server.wait().await {
+        error!("{}", format!("Error: {:#}", e).red());
+        process::exit(1);
+    }
+}
 
 async fn generate_initials_command(letters: String, config_path: PathBuf, output_dir: PathBuf) -> Result<()> {
     // Load configuration
     let config = Config::load(&config_path)
         .context("Failed to load configuration")?;
     
+    if config.offline {
+        error!("{}", "Error: generating illuminated initials needs network access; drop --offline.".red());
+        process::exit(1);
+    }
+    
     // Check if OpenAI API key is available
     if config.openai_api_key.is_none() {
-        eprintln!("{}", "Error: OPENAI_API_KEY not found in environment or config. Cannot generate illuminated initials.".red());
+        error!("{}", "Error: OPENAI_API_KEY not found in environment or config. Cannot generate illuminated initials.".red());
         process::exit(1);
     }
     
@@ -292,7 +813,7 @@ async fn generate_initials_command(letters: String, config_path: PathBuf, output
     };
     
     if letters_to_generate.is_empty() {
-        eprintln!("{}", "Error: No valid letters provided.".red());
+        error!("{}", "Error: No valid letters provided.".red());
         process::exit(1);
     }
     
@@ -300,183 +821,115 @@ async fn generate_initials_command(letters: String, config_path: PathBuf, output
     std::fs::create_dir_all(&output_dir)
         .context("Failed to create output directory")?;
     
-    println!("{}", format!("Generating illuminated initials for: {}", 
+    info!("{}", format!("Generating illuminated initials for: {}", 
         letters_to_generate.iter().collect::<String>()).cyan());
     
+    match initials::migrate(&output_dir) {
+        Ok(0) => {}
+        Ok(n) => info!("{}", format!("Migrated {} illuminated initial(s) to image files", n).cyan()),
+        Err(e) => warn!("{}", format!("Warning: {:#}", e).yellow()),
+    }
+    
     // Generate initials in parallel
     let mut tasks = Vec::new();
+    let missing = letters_to_generate.iter().filter(|l| !initials::is_cached(&output_dir, **l)).count();
+    let illuminated = progress::Progress::new(missing as u64, "Illuminating");
     
     for letter in letters_to_generate {
-        let initial_path = output_dir.join(format!("{}.txt", letter));
-        if !initial_path.exists() {
-            println!("Generating illuminated initial '{}'", letter);
+        if !initials::is_cached(&output_dir, letter) {
+            if !illuminated.is_shown() {
+                info!("Generating illuminated initial '{}'", letter);
+            }
             let api_key = api_key.clone();
+            let title = config.title.clone();
+            let options = config.initials.clone();
             let task = tokio::spawn(async move {
-                SiteGenerator::generate_illuminated_initial_static(letter, "Custom", &api_key).await
+                // No post here, so `{title}` is the site title
+                SiteGenerator::generate_illuminated_initial_static(letter, &title, &options, &api_key).await
             });
-            tasks.push((task, initial_path, letter));
+            tasks.push((task, letter));
         } else {
-            println!("Illuminated initial for '{}' already exists, skipping", letter);
+            info!("Illuminated initial for '{}' already exists, skipping", letter);
         }
     }
     
     // Wait for all tasks to complete
-    for (task, initial_path, letter) in tasks {
+    for (task, letter) in tasks {
         match task.await {
-            Ok(Ok(image_url)) => {
-                println!("Successfully generated illuminated initial for '{}'", letter);
-                std::fs::write(initial_path, image_url)?;
+            Ok(Ok(image)) => {
+                illuminated.inc_or_log(format!("Successfully generated illuminated initial for '{}'", letter));
+                image.save(&output_dir, letter)?;
             }
             Ok(Err(e)) => {
-                eprintln!("Failed to generate illuminated initial for '{}': {}", letter, e);
+                error!("Failed to generate illuminated initial for '{}': {}", letter, e);
             }
             Err(e) => {
-                eprintln!("Task failed for illuminated initial '{}': {}", letter, e);
+                error!("Task failed for illuminated initial '{}': {}", letter, e);
             }
         }
     }
     
-    println!("{}", "Illuminated initials generation complete!".green());
+    drop(illuminated);
+    info!("{}", "Illuminated initials generation complete!".green());
     
     Ok(())
 }
 
-fn sanitize_slug(input: &str) -> String {
-    let lowered = input.to_lowercase();
-    let provisional: String = lowered
-        .chars()
-        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
-        .collect();
-    let collapsed = {
-        // collapse runs of '-'
-        let mut out = String::with_capacity(provisional.len());
-        let mut last_dash = false;
-        for ch in provisional.chars() {
-            if ch == '-' {
-                if !last_dash { out.push('-'); }
-                last_dash = true;
-            } else {
-                out.push(ch);
-                last_dash = false;
+/// Answers to the `scribe create` prompts, read from a TOML file with `--from`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CreateAnswers {
+    #[serde(default = "default_create_title")]
+    title: String,
+    #[serde(default = "default_create_description")]
+    description: String,
+    author: String,
+    url: Option<String>,
+    /// Scaffold into a directory that already has files in it
+    #[serde(default)]
+    allow_non_empty: bool,
+}
+
+fn default_create_title() -> String {
+    "My Blog".to_string()
+}
+
+fn default_create_description() -> String {
+    "A minimal blog powered by Scribe".to_string()
+}
+
+impl CreateAnswers {
+    fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .context(format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).context(format!("Failed to parse {}", path.display()))
+    }
+}
+
+async fn create_project(directory: PathBuf, from: Option<PathBuf>) -> Result<()> {
+    let answers = match from {
+        Some(path) => {
+            let answers = CreateAnswers::load(&path)?;
+            if !answers.allow_non_empty && directory.exists() && directory.read_dir()?.next().is_some() {
+                anyhow::bail!("Directory '{}' already exists and is not empty; set allow_non_empty = true in {} to continue anyway", directory.display(), path.display());
             }
+            info!("{}", format!("Creating project from {}", path.display()).cyan());
+            answers
         }
-        out
+        None => match prompt_create_answers(&directory)? {
+            Some(answers) => answers,
+            None => return Ok(()),
+        },
     };
-    let trimmed = collapsed.trim_matches('-').to_string();
-    if trimmed.is_empty() { "untitled".to_string() } else { trimmed }
-}
-
-async fn create_project(directory: PathBuf) -> Result<()> {
-    use std::io::{self, Write};
+    let CreateAnswers { title, description, author, url, .. } = answers;
     
-    // Clear screen and show header
-    print!("\x1B[2J\x1B[1;1H");
-    println!("{}", r#"
-   ◜ s c r i b e ◝
-    ink • eternal
-    
-"#.cyan().bold());
-    
-    println!("{}", "Welcome to Scribe project creation!".green().bold());
-    println!("{}", "Let's set up your new blog...".white());
-    println!();
-    
-    // Check if directory exists and handle accordingly
-    if directory.exists() && directory.read_dir()?.next().is_some() {
-        println!("{}", format!("Directory '{}' already exists and is not empty.", directory.display()).yellow());
-        print!("Continue anyway? (y/N): ");
-        io::stdout().flush()?;
-        let mut response = String::new();
-        io::stdin().read_line(&mut response)?;
-        if !response.trim().to_lowercase().starts_with('y') {
-            println!("{}", "Project creation cancelled.".red());
-            return Ok(());
-        }
-        println!();
-    }
-    
-    // Helper function for prompts
-    let prompt = |question: &str, default: Option<&str>| -> Result<String> {
-        loop {
-            if let Some(def) = default {
-                print!("{} [{}]: ", question.cyan().bold(), def.green());
-            } else {
-                print!("{}: ", question.cyan().bold());
-            }
-            io::stdout().flush()?;
-            
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)?;
-            let input = input.trim();
-            
-            if input.is_empty() {
-                if let Some(def) = default {
-                    return Ok(def.to_string());
-                } else {
-                    println!("{}", "  This field is required. Please enter a value.".red());
-                    continue;
-                }
-            }
-            
-            return Ok(input.to_string());
-        }
-    };
-    
-    // Collect configuration with nice prompts
-    println!("{}", "Site Configuration".white().bold().underline());
-    println!();
-    
-    let title = prompt("Site title", Some("My Blog"))?;
-    let description = prompt(
-        "Site description", 
-        Some("A minimal blog powered by Scribe")
-    )?;
-    let author = prompt("Author name", None)?;
-    
-    print!("{} (optional): ", "Site URL".cyan().bold());
-    io::stdout().flush()?;
-    let mut url_input = String::new();
-    io::stdin().read_line(&mut url_input)?;
-    let url = if url_input.trim().is_empty() {
-        None
-    } else {
-        Some(url_input.trim().to_string())
-    };
-    
-    println!();
-    
-    // Show configuration summary
-    println!("{}", "Configuration Summary".white().bold().underline());
-    println!();
-    println!("  {}: {}", "Title".white().bold(), title.green());
-    println!("  {}: {}", "Description".white().bold(), description.green());
-    println!("  {}: {}", "Author".white().bold(), author.green());
-    if let Some(ref url_val) = url {
-        println!("  {}: {}", "URL".white().bold(), url_val.green());
-    } else {
-        println!("  {}: {}", "URL".white().bold(), "Not set".yellow());
-    }
-    println!("  {}: {}", "Directory".white().bold(), directory.display().to_string().green());
-    println!();
-    
-    // Confirm creation
-    print!("{}", "Create project with these settings? (Y/n): ".cyan().bold());
-    io::stdout().flush()?;
-    let mut confirm = String::new();
-    io::stdin().read_line(&mut confirm)?;
-    if confirm.trim().to_lowercase().starts_with('n') {
-        println!("{}", "Project creation cancelled.".red());
-        return Ok(());
-    }
-    
-    println!();
-    println!("{}", "Creating project...".yellow().bold());
+    info!("\n{}", "Creating project...".yellow().bold());
     
     // Create directory if it doesn't exist
     if !directory.exists() {
         std::fs::create_dir_all(&directory)
             .context("Failed to create project directory")?;
-        println!("  {} Directory created", "✓".green());
+        info!("  {} Directory created", "✓".green());
     }
     
     // Create config
@@ -488,7 +941,7 @@ async fn create_project(directory: PathBuf) -> Result<()> {
         posts_dir: "posts".to_string(),
         output_dir: "dist".to_string(),
         openai_api_key: None,
-        theme: config::Theme::default(),
+        ..Config::default()
     };
     
     // Write config file
@@ -497,13 +950,13 @@ async fn create_project(directory: PathBuf) -> Result<()> {
         .context("Failed to serialize config")?;
     std::fs::write(&config_path, config_content)
         .context("Failed to write config file")?;
-    println!("  {} Configuration file created", "✓".green());
+    info!("  {} Configuration file created", "✓".green());
     
     // Create posts directory
     let posts_dir = directory.join("posts");
     std::fs::create_dir_all(&posts_dir)
         .context("Failed to create posts directory")?;
-    println!("  {} Posts directory created", "✓".green());
+    info!("  {} Posts directory created", "✓".green());
     
     // Create sample post with dynamic content
     let sample_post = format!(r#"---
@@ -548,7 +1001,7 @@ Happy writing, and welcome to the world of beautiful, minimal blogging!
     let sample_post_path = posts_dir.join("welcome.md");
     std::fs::write(&sample_post_path, sample_post)
         .context("Failed to create sample post")?;
-    println!("  {} Welcome post created", "✓".green());
+    info!("  {} Welcome post created", "✓".green());
     
     // Create .gitignore
     let gitignore_content = r#"# Generated site
@@ -590,7 +1043,7 @@ pids
     let gitignore_path = directory.join(".gitignore");
     std::fs::write(&gitignore_path, gitignore_content)
         .context("Failed to create .gitignore")?;
-    println!("  {} Git ignore file created", "✓".green());
+    info!("  {} Git ignore file created", "✓".green());
     
     // Create README
     let readme_content = format!(r#"# {}
@@ -649,277 +1102,871 @@ Built with [Scribe](https://github.com/your-username/scribe) • ink • eternal
     let readme_path = directory.join("README.md");
     std::fs::write(&readme_path, readme_content)
         .context("Failed to create README")?;
-    println!("  {} README file created", "✓".green());
+    info!("  {} README file created", "✓".green());
     
-    println!();
-    println!("{}", "Project created successfully!".green().bold());
+    info!("\n{}", "Project created successfully!".green().bold());
     
     // Show file tree
+    info!("\n{}", "Project structure:".white().bold());
+    info!("{}                                                      ", directory.display().to_string().cyan().bold());
+    info!("├── {}", "config.json".white());
+    info!("├── {}", "README.md".white());
+    info!("├── {}", ".gitignore".white());
+    info!("└── {}/", "posts".white());
+    info!("    └── {}", "welcome.md".white());
+    
+    info!("\n{}", "Next steps:".yellow().bold());
+    
+    if directory != Path::new(".") {
+        info!("  1. {}", format!("cd {}", directory.display()).cyan());
+    }
+    
+    info!("  {}. {}", if directory == Path::new(".") { "1" } else { "2" }, "Set up OpenAI API key (optional):".white());
+    info!("     {}", "export OPENAI_API_KEY=\"your-key-here\"".cyan());
+    
+    info!("  {}. {}", if directory == Path::new(".") { "2" } else { "3" }, "Generate your site:".white());
+    info!("     {}", "scribe generate".cyan());
+    
+    info!("  {}. {}", if directory == Path::new(".") { "3" } else { "4" }, "Start development server:".white());
+    info!("     {}", "scribe serve".cyan());
+    
+    info!("\n{}", "Happy blogging!".cyan().bold());
+    info!("{}", "Visit http://localhost:3007 after running the commands above.".white());
+    
+    Ok(())
+}
+
+/// Ask for the project settings interactively; `None` if the user cancels.
+fn prompt_create_answers(directory: &Path) -> Result<Option<CreateAnswers>> {
+    use std::io::{self, Write};
+    
+    // Clear screen and show header
+    print!("\x1B[2J\x1B[1;1H");
+    println!("{}", r#"
+   ◜ s c r i b e ◝
+    ink • eternal
+    
+"#.cyan().bold());
+    
+    println!("{}", "Welcome to Scribe project creation!".green().bold());
+    println!("{}", "Let's set up your new blog...".white());
+    println!();
+    
+    // Check if directory exists and handle accordingly
+    if directory.exists() && directory.read_dir()?.next().is_some() {
+        println!("{}", format!("Directory '{}' already exists and is not empty.", directory.display()).yellow());
+        print!("Continue anyway? (y/N): ");
+        io::stdout().flush()?;
+        let mut response = String::new();
+        io::stdin().read_line(&mut response)?;
+        if !response.trim().to_lowercase().starts_with('y') {
+            println!("{}", "Project creation cancelled.".red());
+            return Ok(None);
+        }
+        println!();
+    }
+    
+    // Collect configuration with nice prompts
+    println!("{}", "Site Configuration".white().bold().underline());
+    println!();
+    
+    let title = prompt("Site title", Some("My Blog"))?;
+    let description = prompt(
+        "Site description", 
+        Some("A minimal blog powered by Scribe")
+    )?;
+    let author = prompt("Author name", None)?;
+    
+    let url = prompt_optional("Site URL", None)?;
+    
     println!();
-    println!("{}", "Project structure:".white().bold());
-    println!("{}                                                      ", directory.display().to_string().cyan().bold());
-    println!("├── {}", "config.json".white());
-    println!("├── {}", "README.md".white());
-    println!("├── {}", ".gitignore".white());
-    println!("└── {}/", "posts".white());
-    println!("    └── {}", "welcome.md".white());
     
+    // Show configuration summary
+    println!("{}", "Configuration Summary".white().bold().underline());
+    println!();
+    println!("  {}: {}", "Title".white().bold(), title.green());
+    println!("  {}: {}", "Description".white().bold(), description.green());
+    println!("  {}: {}", "Author".white().bold(), author.green());
+    if let Some(ref url_val) = url {
+        println!("  {}: {}", "URL".white().bold(), url_val.green());
+    } else {
+        println!("  {}: {}", "URL".white().bold(), "Not set".yellow());
+    }
+    println!("  {}: {}", "Directory".white().bold(), directory.display().to_string().green());
     println!();
-    println!("{}", "Next steps:".yellow().bold());
     
-    if directory != PathBuf::from(".") {
-        println!("  1. {}", format!("cd {}", directory.display()).cyan());
+    // Confirm creation
+    print!("{}", "Create project with these settings? (Y/n): ".cyan().bold());
+    io::stdout().flush()?;
+    let mut confirm = String::new();
+    io::stdin().read_line(&mut confirm)?;
+    if confirm.trim().to_lowercase().starts_with('n') {
+        println!("{}", "Project creation cancelled.".red());
+        return Ok(None);
+    }
+    
+    Ok(Some(CreateAnswers { title, description, author, url, allow_non_empty: true }))
+}
+
+/// Ask `question` until answered, or take `default` on an empty answer.
+fn prompt(question: &str, default: Option<&str>) -> Result<String> {
+    use std::io::{self, Write};
+    
+    loop {
+        if let Some(def) = default {
+            print!("{} [{}]: ", question.cyan().bold(), def.green());
+        } else {
+            print!("{}: ", question.cyan().bold());
+        }
+        io::stdout().flush()?;
+        
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let input = input.trim();
+        
+        if input.is_empty() {
+            if let Some(def) = default {
+                return Ok(def.to_string());
+            } else {
+                println!("{}", "  This field is required. Please enter a value.".red());
+                continue;
+            }
+        }
+        
+        return Ok(input.to_string());
     }
+}
+
+/// Ask `question`, which may be left empty; an empty answer keeps `default`.
+fn prompt_optional(question: &str, default: Option<&str>) -> Result<Option<String>> {
+    use std::io::{self, Write};
     
-    println!("  {}. {}", if directory == PathBuf::from(".") { "1" } else { "2" }, "Set up OpenAI API key (optional):".white());
-    println!("     {}", "export OPENAI_API_KEY=\"your-key-here\"".cyan());
+    match default {
+        Some(def) => print!("{} (optional) [{}]: ", question.cyan().bold(), def.green()),
+        None => print!("{} (optional): ", question.cyan().bold()),
+    }
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let input = input.trim();
+    Ok(if input.is_empty() { default.map(str::to_string) } else { Some(input.to_string()) })
+}
+
+/// Details of a post for `scribe new`, from flags or prompts.
+struct NewPost {
+    title: String,
+    excerpt: Option<String>,
+    tags: Vec<String>,
+    series: Option<String>,
+    /// Overrides the slug derived from the title
+    slug: Option<String>,
+    draft: bool,
+    /// Archetype to start from
+    kind: Option<String>,
+}
+
+/// Ask for the details of a new post, offering `defaults` (from the flags); `None` if the user
+/// cancels.
+fn prompt_new_post(defaults: NewPost) -> Result<Option<NewPost>> {
+    use std::io::{self, Write};
     
-    println!("  {}. {}", if directory == PathBuf::from(".") { "2" } else { "3" }, "Generate your site:".white());
-    println!("     {}", "scribe generate".cyan());
+    println!("{}", "New Post".white().bold().underline());
+    println!();
     
-    println!("  {}. {}", if directory == PathBuf::from(".") { "3" } else { "4" }, "Start development server:".white());
-    println!("     {}", "scribe serve".cyan());
+    let kinds = archetypes::kinds(Path::new(archetypes::DIR));
+    let kind = if kinds.is_empty() {
+        defaults.kind
+    } else {
+        prompt_optional(&format!("Kind ({})", kinds.join(", ")), defaults.kind.as_deref())?
+    };
+    let title = prompt("Title", Some(defaults.title.as_str()).filter(|t| !t.is_empty()))?;
+    let excerpt = prompt_optional("Excerpt", defaults.excerpt.as_deref())?;
+    let tags = prompt_optional("Tags (comma-separated)", Some(defaults.tags.join(", ").as_str()).filter(|t| !t.is_empty()))?
+        .map(|tags| tags.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect())
+        .unwrap_or_default();
+    let series = prompt_optional("Series", defaults.series.as_deref())?;
+    let slug = prompt("Slug", Some(defaults.slug.as_deref().map_or_else(|| title_slug(&title), title_slug).as_str()))?;
+    
+    print!("{} ({}): ", "Draft?".cyan().bold(), if defaults.draft { "Y/n" } else { "y/N" });
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim().to_lowercase();
+    let draft = if answer.is_empty() { defaults.draft } else { answer.starts_with('y') };
+    println!();
     
+    print!("{}", "Create this post? (Y/n): ".cyan().bold());
+    io::stdout().flush()?;
+    let mut confirm = String::new();
+    io::stdin().read_line(&mut confirm)?;
+    if confirm.trim().to_lowercase().starts_with('n') {
+        println!("{}", "Post creation cancelled.".red());
+        return Ok(None);
+    }
     println!();
-    println!("{}", "Happy blogging!".cyan().bold());
-    println!("{}", "Visit http://localhost:3007 after running the commands above.".white());
     
-    Ok(())
+    Ok(Some(NewPost { title, excerpt, tags, series, slug: Some(slug), draft, kind }))
 }
 
 async fn pin_to_ipfs(
+    config_path: PathBuf,
     dist_path: PathBuf, 
     ipfs_api: String, 
     name: Option<String>, 
-    recursive: bool
+    recursive: bool,
+    ipns_key: Option<String>,
+    update_ens: bool,
 ) -> Result<()> {
+    // The config is optional here; it only adds the extra nodes and the ENS name
+    let publish = if config_path.exists() {
+        Config::load(&config_path).context("Failed to load configuration")?.publish
+    } else {
+        config::PublishOptions::default()
+    };
+    if update_ens && publish.ens.is_none() {
+        error!("{}", format!("Error: --update-ens needs a `publish.ens` section in {}", config_path.display()).red());
+        process::exit(1);
+    }
+    let (root_hash, ipns_name) = pin_dist(&dist_path, &ipfs_api, name, recursive, ipns_key, &publish).await?;
+    let ens_name = publish.ens.as_ref().filter(|_| update_ens).map(|ens| update_ens_contenthash(ens, &root_hash));
+    
+    info!("\n{}", "IPFS Pinning Complete!".green().bold());
+    info!("\n{}", "Access your site via IPFS:".white().bold());
+    info!("  {}: {}", "IPFS Hash".white(), root_hash.clone().cyan());
+    info!("  {}: {}", "IPFS Gateway".white(), format!("https://ipfs.io/ipfs/{}", root_hash).blue());
+    info!("  {}: {}", "Local Gateway".white(), format!("http://127.0.0.1:8080/ipfs/{}", root_hash).blue());
+    if let Some(ref ipns) = ipns_name {
+        info!("  {}: {}", "IPNS".white(), format!("https://ipfs.io/ipns/{}", ipns).blue());
+    }
+    if let Some(ref ens) = ens_name {
+        info!("  {}: {}", "ENS".white(), format!("https://{}.limo", ens).blue());
+    }
+    
+    // Show alternative gateways
+    info!("\n{}", "Alternative IPFS Gateways:".white().bold());
+    info!("  • {}", format!("https://gateway.pinata.cloud/ipfs/{}", root_hash).blue());
+    info!("  • {}", format!("https://cloudflare-ipfs.com/ipfs/{}", root_hash).blue());
+    info!("  • {}", format!("https://dweb.link/ipfs/{}", root_hash).blue());
+    
+    info!("\n{}", "💡 Pro Tips:".yellow().bold());
+    info!("  • Pin your content on multiple IPFS nodes for better availability");
+    info!("  • Consider using a pinning service like Pinata or Infura for production");
+    info!("  • Share the IPFS hash for decentralized access to your site");
+    
+    Ok(())
+}
+
+fn verify_pin(dist_path: PathBuf, cid: String) -> Result<()> {
+    let Some(version) = car::CidVersion::of(&cid) else {
+        error!("{}", format!("Error: '{}' is not a CIDv0 (Qm...) or base32 CIDv1 (bafy...)", cid).red());
+        process::exit(1);
+    };
+    if !dist_path.is_dir() {
+        error!("{}", format!("Error: Directory '{}' does not exist. Run 'scribe generate' first.", dist_path.display()).red());
+        process::exit(1);
+    }
+    
+    info!("{}", format!("Hashing {}...", dist_path.display()).yellow());
+    let local = car::hash(&dist_path, version)?;
+    // Name the pin the CIDs belong to, when the ledger knows it
+    let ledger = pins::PinLedger::load(pins::LEDGER_PATH).unwrap_or_default();
+    let pinned = |hash: &str| {
+        ledger
+            .pins
+            .iter()
+            .rev()
+            .find(|p| p.hash == hash)
+            .map(|p| format!(" (pinned {})", p.timestamp.format("%Y-%m-%d %H:%M")))
+            .unwrap_or_default()
+    };
+    
+    if local == cid {
+        info!("{} {} matches {}{}", "✓".green(), dist_path.display(), cid.cyan(), pinned(&cid));
+        return Ok(());
+    }
+    error!("{}", format!("✗ {} does not match {}{}", dist_path.display(), cid, pinned(&cid)).red());
+    error!("  {}: {}{}", "Local build".white(), local.cyan(), pinned(&local));
+    process::exit(1);
+}
+
+fn export_car(dist_path: PathBuf, car_path: PathBuf) -> Result<()> {
+    info!("{}", format!("Packing {} into {}...", dist_path.display(), car_path.display()).yellow());
+    let summary = match car::export(&dist_path, &car_path) {
+        Ok(summary) => summary,
+        Err(e) => {
+            error!("{}", format!("Error: {:#}", e).red());
+            process::exit(1);
+        }
+    };
+    
+    info!("{} Wrote {} ({} files, {} blocks, {} bytes)", "✓".green(), car_path.display(), summary.files, summary.blocks, summary.bytes);
+    info!("{}", format!("Root CID: {}", summary.root).cyan().bold());
+    info!("Upload it to a pinning service, or import it with: ipfs dag import {}", car_path.display());
+    Ok(())
+}
+
+/// Add `dist_path` to IPFS, pin it there and on the extra `publish.nodes`, optionally point an
+/// IPNS name at it and record it in the pin ledger. Returns the root hash and the published IPNS name.
+async fn pin_dist(
+    dist_path: &Path,
+    ipfs_api: &str,
+    name: Option<String>,
+    recursive: bool,
+    ipns_key: Option<String>,
+    publish: &config::PublishOptions,
+) -> Result<(String, Option<String>)> {
     // Check if dist directory exists
     if !dist_path.exists() {
-        eprintln!("{}", format!("Error: Directory '{}' does not exist. Run 'scribe generate' first.", dist_path.display()).red());
+        error!("{}", format!("Error: Directory '{}' does not exist. Run 'scribe generate' first.", dist_path.display()).red());
         process::exit(1);
     }
 
     if !dist_path.is_dir() {
-        eprintln!("{}", format!("Error: '{}' is not a directory.", dist_path.display()).red());
+        error!("{}", format!("Error: '{}' is not a directory.", dist_path.display()).red());
         process::exit(1);
     }
 
-    println!("{}", format!("Connecting to IPFS node at {}...", ipfs_api).blue());
+    info!("{}", format!("Connecting to IPFS node at {}...", ipfs_api).blue());
     
     // Create IPFS client
-    let client = IpfsClient::from_str(&ipfs_api)
+    let client = IpfsClient::from_str(ipfs_api)
         .context("Failed to create IPFS client")?;
     
     // Test connection to IPFS node
     match client.version().await {
         Ok(version) => {
-            println!("{} Connected to IPFS node (version: {})", "✓".green(), version.version);
+            info!("{} Connected to IPFS node (version: {})", "✓".green(), version.version);
         }
         Err(e) => {
-            eprintln!("{}", format!("Error: Failed to connect to IPFS node at {}", ipfs_api).red());
-            eprintln!("{}", format!("Make sure IPFS daemon is running. Error: {}", e).yellow());
-            eprintln!("{}", "Start IPFS daemon with: ipfs daemon".cyan());
+            error!("{}", format!("Error: Failed to connect to IPFS node at {}", ipfs_api).red());
+            error!("{}", format!("Make sure IPFS daemon is running. Error: {}", e).yellow());
+            error!("{}", "Start IPFS daemon with: ipfs daemon".cyan());
             process::exit(1);
         }
     }
     
-    println!("{}", format!("Adding directory {} to IPFS...", dist_path.display()).yellow());
+    info!("{}", format!("Adding directory {} to IPFS...", dist_path.display()).yellow());
     
     // Add the directory to IPFS
-    let add_result = client
-        .add_path(&dist_path)
-        .await
-        .context("Failed to add directory to IPFS")?;
-    
-    // Find the root directory hash
-    let mut root_hash = None;
-    let mut total_files = 0;
-    
-    for item in add_result {
-        total_files += 1;
-        // The root directory will have the same name as the source directory
-        if item.name == dist_path.file_name().unwrap().to_str().unwrap() {
-            root_hash = Some(item.hash.clone());
+    let (root_hash, total_files) = match pins::add_dist_to_ipfs(&client, dist_path).await {
+        Ok(added) => added,
+        Err(e) => {
+            error!("{}", format!("Error: {}", e).red());
+            process::exit(1);
         }
-        println!("  {} Added: {} ({})", "✓".green(), item.name, item.hash);
-    }
-    
-    let root_hash = root_hash.unwrap_or_else(|| {
-        eprintln!("{}", "Error: Could not determine root directory hash".red());
-        process::exit(1);
-    });
-    
-    println!("{}", format!("Successfully added {} files to IPFS", total_files).green());
-    println!("{}", format!("Root directory hash: {}", root_hash).cyan().bold());
+    };
     
     // Pin the content
     if recursive {
-        println!("{}", "Pinning content recursively...".yellow());
+        info!("{}", "Pinning content recursively...".yellow());
         match client.pin_add(&root_hash, recursive).await {
             Ok(_) => {
-                println!("{} Content pinned successfully!", "✓".green());
+                info!("{} Content pinned successfully!", "✓".green());
             }
             Err(e) => {
-                eprintln!("{}", format!("Warning: Failed to pin content: {}", e).yellow());
-                eprintln!("{}", "Content is still available on IPFS but may be garbage collected".yellow());
+                warn!("{}", format!("Warning: Failed to pin content: {}", e).yellow());
+                warn!("{}", "Content is still available on IPFS but may be garbage collected".yellow());
             }
         }
     }
     
     // Set pin name if provided
-    if let Some(pin_name) = name {
-        println!("{}", format!("Setting pin name to '{}'...", pin_name).yellow());
+    if let Some(ref pin_name) = name {
+        info!("{}", format!("Setting pin name to '{}'...", pin_name).yellow());
         // Note: pin naming is not available in all IPFS implementations
         // This is a placeholder for when the API supports it
-        println!("{}", format!("Pin name '{}' noted (naming support varies by IPFS implementation)", pin_name).cyan());
+        info!("{}", format!("Pin name '{}' noted (naming support varies by IPFS implementation)", pin_name).cyan());
     }
     
-    println!();
-    println!("{}", "IPFS Pinning Complete!".green().bold());
-    println!();
-    println!("{}", "Access your site via IPFS:".white().bold());
-    println!("  {}: {}", "IPFS Hash".white(), root_hash.clone().cyan());
-    println!("  {}: {}", "IPFS Gateway".white(), format!("https://ipfs.io/ipfs/{}", root_hash).blue());
-    println!("  {}: {}", "Local Gateway".white(), format!("http://127.0.0.1:8080/ipfs/{}", root_hash).blue());
+    // Point the IPNS name at the new root so the site keeps a stable address
+    let ipns_name = if let Some(key) = ipns_key {
+        info!("{}", format!("Publishing to IPNS (key: {})...", key).yellow());
+        match client.name_publish(&format!("/ipfs/{}", root_hash), true, None, None, Some(&key)).await {
+            Ok(published) => {
+                info!("{} Published /ipns/{}", "✓".green(), published.name);
+                Some(published.name)
+            }
+            Err(e) => {
+                warn!("{}", format!("Warning: Failed to publish to IPNS: {}", e).yellow());
+                None
+            }
+        }
+    } else {
+        None
+    };
     
-    // Show alternative gateways
-    println!();
-    println!("{}", "Alternative IPFS Gateways:".white().bold());
-    println!("  • {}", format!("https://gateway.pinata.cloud/ipfs/{}", root_hash).blue());
-    println!("  • {}", format!("https://cloudflare-ipfs.com/ipfs/{}", root_hash).blue());
-    println!("  • {}", format!("https://dweb.link/ipfs/{}", root_hash).blue());
+    // Pin on the extra nodes from the config
+    let mut pinned_on = Vec::new();
+    if !publish.nodes.is_empty() {
+        info!("{}", format!("Pinning on {} more node(s)...", publish.nodes.len()).yellow());
+        let results = pins::pin_on_nodes(&publish.nodes, dist_path, &root_hash, name.as_deref(), publish.retries).await;
+        for (node, result) in publish.nodes.iter().zip(results) {
+            match result {
+                Ok(status) => {
+                    info!("  {} {} ({})", "✓".green(), node.endpoint(), status);
+                    pinned_on.push(node.endpoint().to_string());
+                }
+                Err(e) => warn!("  {} {}", "✗".red(), format!("{}: {:#}", node.endpoint(), e).yellow()),
+            }
+        }
+        if pinned_on.len() < publish.nodes.len() {
+            warn!("{}", format!("Warning: Pinned on {} of {} extra nodes", pinned_on.len(), publish.nodes.len()).yellow());
+        }
+    }
     
-    println!();
-    println!("{}", "💡 Pro Tips:".yellow().bold());
-    println!("  • Pin your content on multiple IPFS nodes for better availability");
-    println!("  • Consider using a pinning service like Pinata or Infura for production");
-    println!("  • Share the IPFS hash for decentralized access to your site");
+    // Record the pin in the ledger
+    let record = pins::PinRecord {
+        hash: root_hash.clone(),
+        timestamp: chrono::Utc::now(),
+        files: total_files,
+        name,
+        ipns: ipns_name.clone(),
+        nodes: pinned_on,
+    };
+    match pins::PinLedger::record(pins::LEDGER_PATH, record) {
+        Ok(()) => info!("{} Recorded in {}", "✓".green(), pins::LEDGER_PATH),
+        Err(e) => warn!("{}", format!("Warning: Failed to record pin history: {}", e).yellow()),
+    }
     
-    Ok(())
+    Ok((root_hash, ipns_name))
 }
 
-async fn handle_websocket(ws: WebSocket, hot_reload_tx: HotReloadSender) {
-    let mut ws = ws;
+async fn deploy_site(config_path: PathBuf, skip_generate: bool) -> Result<()> {
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
     
-    // Get the reload receiver
-    let mut reload_rx = {
-        let guard = hot_reload_tx.read().await;
-        if let Some(tx) = guard.as_ref() {
-            tx.subscribe()
-        } else {
-            return; // No broadcaster available
-        }
+    let Some(target) = config.deploy.clone() else {
+        error!("{}", "Error: No `deploy` section in config. Add a github-pages, rsync or s3 target.".red());
+        process::exit(1);
     };
     
-    // Listen for reload messages and forward them to the WebSocket
-    while let Ok(msg) = reload_rx.recv().await {
-        if ws.send(Message::text(msg)).await.is_err() {
-            break; // Client disconnected
+    if !skip_generate {
+        let mut generator = SiteGenerator::new(config.clone());
+        if let Err(e) = generator.generate().await {
+            error!("{}", format!("Error: {:#}", e).red());
+            process::exit(1);
         }
     }
-}
-
-
-
-struct WatcherHandle {
-    _watcher: PollWatcher,
-    _task_handle: tokio::task::JoinHandle<()>,
-}
-
-async fn setup_file_watcher(config_path: PathBuf, hot_reload_tx: Option<broadcast::Sender<String>>) -> Result<WatcherHandle> {
-    let (tx, rx) = mpsc::channel();
     
-    let mut watcher = PollWatcher::new(
-        move |res| {
-            if let Ok(event) = res {
-                if let Err(e) = tx.send(event) {
-                    eprintln!("Failed to send file watch event: {}", e);
-                }
+    info!("{}", "Deploying site...".yellow());
+    match deploy::deploy(&config, &target) {
+        Ok(url) => {
+            info!("{}", "Deploy complete!".green().bold());
+            info!("  {}: {}", "Published".white(), url.blue());
+        }
+        Err(e) => {
+            error!("{}", format!("Error: Deploy failed: {:#}", e).red());
+            process::exit(1);
+        }
+    }
+    
+    Ok(())
+}
+
+async fn publish_site(config_path: PathBuf, skip_generate: bool, name: Option<String>) -> Result<()> {
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
+    let publish = config.publish.clone();
+    
+    if !skip_generate {
+        let mut generator = SiteGenerator::new(config.clone());
+        if let Err(e) = generator.generate().await {
+            error!("{}", format!("Error: {:#}", e).red());
+            process::exit(1);
+        }
+    }
+    
+    let dist = PathBuf::from(&config.output_dir);
+    let (root_hash, ipns_name) = pin_dist(&dist, &publish.ipfs_api, name, true, publish.ipns_key.clone(), &publish).await?;
+    
+    // DNSLink points at the immutable root: it is updated on every publish anyway, and resolving
+    // /ipfs/ is faster than going through IPNS
+    let dnslink = match &publish.dnslink {
+        Some(dnslink) => {
+            let record = dnslink.record_name();
+            info!("{}", format!("Updating DNSLink record {}...", record).yellow());
+            if let Err(e) = dnslink::update(dnslink, &format!("/ipfs/{}", root_hash)).await {
+                error!("{}", format!("Error: DNSLink update failed: {:#}", e).red());
+                process::exit(1);
             }
-        },
-        notify::Config::default().with_poll_interval(Duration::from_secs(1)),
-    )?;
+            info!("{} {} now points at /ipfs/{}", "✓".green(), record, root_hash);
+            Some(record.trim_start_matches("_dnslink.").to_string())
+        }
+        None => None,
+    };
+    let ens_name = publish.ens.as_ref().map(|ens| update_ens_contenthash(ens, &root_hash));
+    
+    info!("{}", "Publish complete!".green().bold());
+    info!("  {}: {}", "IPFS".white(), format!("https://dweb.link/ipfs/{}", root_hash).blue());
+    if let Some(ipns) = ipns_name {
+        info!("  {}: {}", "IPNS".white(), format!("https://dweb.link/ipns/{}", ipns).blue());
+    }
+    if let Some(domain) = dnslink {
+        info!("  {}: {}", "DNSLink".white(), format!("https://dweb.link/ipns/{}", domain).blue());
+    }
+    if let Some(ens) = ens_name {
+        info!("  {}: {}", "ENS".white(), format!("https://{}.limo", ens).blue());
+    }
+    
+    Ok(())
+}
+
+/// Point the ENS name at `root_hash`, exiting on failure, and return the name.
+fn update_ens_contenthash(ens: &config::Ens, root_hash: &str) -> String {
+    info!("{}", format!("Updating the contenthash of {}...", ens.name).yellow());
+    match ens::update(ens, root_hash) {
+        Ok(true) => info!("{} {} now points at /ipfs/{}", "✓".green(), ens.name, root_hash),
+        Ok(false) => info!("{} {} already points at /ipfs/{}", "✓".green(), ens.name, root_hash),
+        Err(e) => {
+            error!("{}", format!("Error: ENS update failed: {:#}", e).red());
+            process::exit(1);
+        }
+    }
+    ens.name.clone()
+}
+
+async fn export_graph(
+    config_path: PathBuf,
+    format: graph::GraphFormat,
+    include_tags: bool,
+    since: Option<chrono::NaiveDate>,
+    until: Option<chrono::NaiveDate>,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
+    let policy = config.trailing_slash;
+    let mut generator = SiteGenerator::new(config);
+    let posts = generator.read_posts().await?;
+    
+    let link_graph = graph::LinkGraph::build(posts, include_tags, since, until, policy);
+    let rendered = link_graph.render(format);
+    
+    match output {
+        Some(path) => {
+            std::fs::write(&path, rendered)
+                .context("Failed to write graph")?;
+            info!(
+                "{}",
+                format!("Wrote {} nodes and {} edges to {}", link_graph.nodes.len(), link_graph.edges.len(), path.display()).green()
+            );
+        }
+        None => print!("{}", rendered),
+    }
+    
+    Ok(())
+}
+
+async fn export_posts(
+    config_path: PathBuf,
+    format: export::ExportFormat,
+    selection: book::Selection,
+    title: Option<String>,
+    cover: Option<PathBuf>,
+    renderer: String,
+    output: PathBuf,
+) -> Result<()> {
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
+    if config.url.is_none() {
+        warn!("{}", "Warning: no url in config; links in the export stay relative to the post".yellow());
+    }
+    let mut generator = SiteGenerator::new(config.clone());
+    // Exports are the author's own copies, with private posts readable
+    let posts: Vec<_> = generator.read_posts().await?.iter().map(|p| p.revealed()).collect();
+    std::fs::create_dir_all(&output).context("Failed to create output directory")?;
+    
+    if let export::ExportFormat::Email = format {
+        let Some(slug) = &selection.post else {
+            error!("{}", "Error: --format email exports a single post; pass --post <slug>.".red());
+            process::exit(1);
+        };
+        let Some(post) = posts.iter().find(|p| &p.slug == slug || &p.original_slug == slug) else {
+            error!("{}", format!("Error: no published post '{}'.", slug).red());
+            process::exit(1);
+        };
+        let email = export::email(&config, post);
+        let html_path = output.join(format!("{}.email.html", post.slug));
+        let text_path = output.join(format!("{}.email.txt", post.slug));
+        std::fs::write(&html_path, &email.html).context("Failed to write HTML email")?;
+        std::fs::write(&text_path, &email.text).context("Failed to write plain-text email")?;
+        info!("{}", format!("Wrote '{}' to {} and {}", email.subject, html_path.display(), text_path.display()).green());
+        return Ok(());
+    }
+    
+    let mut book = book::Book::new(&config, &posts, &selection, title);
+    if book.posts.is_empty() {
+        error!("{}", "Error: no published posts match the selection.".red());
+        process::exit(1);
+    }
+    book.cover = cover;
+    let name = sanitize_slug(&book.title);
+    let path = match format {
+        export::ExportFormat::Epub => {
+            let path = output.join(format!("{}.epub", name));
+            std::fs::write(&path, book::epub(&config, &book)?).context("Failed to write EPUB")?;
+            path
+        }
+        _ => {
+            let path = output.join(format!("{}.pdf", name));
+            book::pdf(&config, &book, &renderer, &path)?;
+            path
+        }
+    };
+    info!("{}", format!("Wrote '{}' ({} posts) to {}", book.title, book.posts.len(), path.display()).green());
+    
+    Ok(())
+}
+
+async fn import_content(from: import::Source, path: PathBuf, dry_run: bool, config_path: PathBuf) -> Result<()> {
+    let config = if config_path.exists() {
+        Config::load(&config_path).context("Failed to load configuration")?
+    } else {
+        Config::default()
+    };
+    let import = match import::convert(from, &path) {
+        Ok(import) => import,
+        Err(e) => {
+            error!("{}", format!("Error: {:#}", e).red());
+            process::exit(1);
+        }
+    };
+    if import.posts.is_empty() {
+        error!("{}", format!("Error: no posts found in {}.", path.display()).red());
+        process::exit(1);
+    }
+    
+    if dry_run {
+        info!("Would write to {}:", config.posts_dir);
+        for post in &import.posts {
+            info!("  {} <- {}", post.path.display(), post.source.display());
+        }
+        for asset in &import.assets {
+            info!("  {} <- {}", asset.to.display(), asset.from.display());
+        }
+    } else {
+        let skipped = import.write(Path::new(&config.posts_dir))?;
+        for path in &skipped {
+            warn!("{}", format!("Skipped {}: it already exists", path.display()).yellow());
+        }
+        let written = import.posts.len() + import.assets.len() - skipped.len();
+        info!("{}", format!("Imported {} posts and {} files into {} ({} written)", import.posts.len(), import.assets.len(), config.posts_dir, written).green());
+    }
+    if !import.issues.is_empty() {
+        warn!("{}", format!("{} things couldn't be mapped as they were:", import.issues.len()).yellow());
+        for issue in &import.issues {
+            warn!("  {}", issue);
+        }
+    }
     
-    // Load config to get posts directory
-    let config = Config::load(&config_path)?;
+    Ok(())
+}
+
+async fn preview_post(file: PathBuf, config_path: PathBuf, no_open: bool) -> Result<()> {
+    if !file.is_file() {
+        error!("{}", format!("Error: '{}' is not a file.", file.display()).red());
+        process::exit(1);
+    }
+    
+    // Use the project's config when there is one, without creating it
+    let config = if config_path.exists() {
+        Config::load(&config_path).context("Failed to load configuration")?
+    } else {
+        Config::default()
+    };
+    
+    let preview_dir = std::env::temp_dir().join("scribe-preview");
+    let generator = SiteGenerator::new(config);
+    let page = generator.preview(&file, &preview_dir).await?;
+    
+    info!("{}", format!("Preview written to {}", page.display()).green());
+    if !no_open {
+        open_in_browser(&page.display().to_string());
+    }
+    
+    Ok(())
+}
+
+/// Open a URL or file with the platform's default handler, warning on failure.
+fn open_in_browser(target: &str) {
+    let result = if cfg!(target_os = "macos") {
+        process::Command::new("open").arg(target).status()
+    } else if cfg!(target_os = "windows") {
+        process::Command::new("cmd").args(["/C", "start", "", target]).status()
+    } else {
+        process::Command::new("xdg-open").arg(target).status()
+    };
+    
+    if !result.map(|s| s.success()).unwrap_or(false) {
+        warn!("{}", format!("Warning: could not open a browser; visit {} manually", target).yellow());
+    }
+}
+
+async fn check_site(config_path: PathBuf, external: Option<check::ExternalCheck>) -> Result<()> {
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
+    let dist = PathBuf::from(&config.output_dir);
+    if !dist.is_dir() {
+        error!("{}", format!("Error: Directory '{}' does not exist. Run 'scribe generate' first.", dist.display()).red());
+        process::exit(1);
+    }
+    
+    let external = match external {
+        Some(_) if config.offline => {
+            info!("{}", "Offline: skipping external links".yellow());
+            None
+        }
+        external => external,
+    };
+    let base_path = config.base_path();
+    let mut generator = SiteGenerator::new(config);
+    let posts = generator.read_posts().await?;
+    if external.is_some() {
+        info!("{}", "Checking external links...".cyan());
+    }
+    let issues = check::check_site(&dist, &base_path, posts, external).await;
+    
+    if issues.is_empty() {
+        info!("{}", "No problems found".green());
+        return Ok(());
+    }
+    for issue in &issues {
+        error!("{}", issue.to_string().red());
+    }
+    error!("{}", format!("{} problem(s) found", issues.len()).red().bold());
+    process::exit(1);
+}
+
+async fn report_assets(prune: bool, config_path: PathBuf) -> Result<()> {
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
     let posts_dir = PathBuf::from(&config.posts_dir);
+    let output_dir = PathBuf::from(&config.output_dir);
+    let mut generator = SiteGenerator::new(config);
+    generator.read_posts().await?;
+    let report = generator.asset_report();
     
-    if posts_dir.exists() {
-        watcher.watch(&posts_dir, RecursiveMode::Recursive)?;
-        println!("{}", format!("Watching: {}", posts_dir.display()).blue());
-    }
-    
-    // Also watch config file
-    watcher.watch(&config_path, RecursiveMode::NonRecursive)?;
-    
-    // Spawn background task to handle file changes
-    let task_handle = tokio::spawn(async move {
-        let mut last_generation = std::time::Instant::now();
-        
-        loop {
-            match rx.recv_timeout(Duration::from_millis(100)) {
-                Ok(event) => {
-                    // Check if it's a markdown file or config file
-                    let is_relevant = event.paths.iter().any(|path| {
-                        path.extension().map_or(false, |ext| ext == "md") || 
-                        path.file_name().map_or(false, |name| name == "config.json")
-                    });
-                    
-                    if is_relevant {
-                        // Debounce: only regenerate if it's been at least 1 second since last generation
-                        if last_generation.elapsed() > Duration::from_secs(1) {
-                            // Accept various event types, not just Modify
-                            match event.kind {
-                                notify::EventKind::Create(_) | 
-                                notify::EventKind::Modify(_) | 
-                                notify::EventKind::Remove(_) => {
-                                    println!("{}", "File changed, regenerating site...".yellow());
-                                    last_generation = std::time::Instant::now();
-                                    
-                                    // Regenerate site
-                                    if let Err(e) = regenerate_site(&config_path).await {
-                                        eprintln!("{}", format!("Regeneration failed: {}", e).red());
-                                    } else {
-                                        println!("{}", "Site regenerated successfully!".green());
-                                        
-                                        // Send hot reload notification
-                                        if let Some(ref tx) = hot_reload_tx {
-                                            let _ = tx.send("reload".to_string());
-                                        }
-                                    }
-                                }
-                                _ => {
-                                    // Ignore other event types
-                                }
-                            }
-                        }
-                    }
-                }
-                Err(mpsc::RecvTimeoutError::Timeout) => {
-                    // Continue the loop
-                    continue;
+    for reference in &report.missing {
+        warn!("{}", format!("Warning: {}", reference).yellow());
+    }
+    info!("{} image(s) in use", report.used.len());
+    if report.unused.is_empty() {
+        info!("{}", "No unused images".green());
+        return Ok(());
+    }
+    
+    let sizes: Vec<u64> = report.unused.iter().map(|p| std::fs::metadata(posts_dir.join(p)).map_or(0, |m| m.len())).collect();
+    info!("{} unused image(s), {}:", report.unused.len(), format_size(sizes.iter().sum()));
+    for (path, size) in report.unused.iter().zip(&sizes) {
+        info!("  {} ({})", posts_dir.join(path).display(), format_size(*size));
+    }
+    if !prune {
+        info!("Delete them with: scribe assets --prune");
+        return Ok(());
+    }
+    
+    // A post that failed to load may be the one using an image
+    let failures = generator.failures();
+    if !failures.is_empty() {
+        error!("{}", format!("Error: {} post(s) failed to load; not pruning until they build", failures.len()).red());
+        process::exit(1);
+    }
+    for relative in &report.unused {
+        let path = posts_dir.join(relative);
+        std::fs::remove_file(&path)
+            .context(format!("Failed to remove {}", path.display()))?;
+        // Along with the copy the last build published
+        let published = output_dir.join(relative);
+        if published.is_file() {
+            std::fs::remove_file(&published)
+                .context(format!("Failed to remove {}", published.display()))?;
+        }
+    }
+    info!("{} {} unused image(s)", "Pruned".green(), report.unused.len());
+    Ok(())
+}
+
+async fn manage_cache(action: CacheAction, config_path: PathBuf) -> Result<()> {
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
+    let initials_dir = Path::new(&config.output_dir).join(initials::DIR);
+    
+    match action {
+        CacheAction::Stats => {
+            let url_meta = cache::UrlMetaCache::load(cache::URL_META_PATH)?;
+            let stats = cache::CacheStats::load(cache::STATS_PATH)?;
+            let last_build = |rate: Option<cache::HitRate>| rate.map_or("not recorded".to_string(), |r| r.to_string());
+            
+            info!("{}", "URL metadata".bold());
+            info!("  Entries:    {} ({} stale)", url_meta.entries.len(), url_meta.stale_count());
+            info!("  Size:       {}", format_size(dir_size(Path::new(cache::URL_META_PATH))));
+            info!("  Last build: {}", last_build(stats.url_meta));
+            
+            // Per-post initials live in a subdirectory per slug
+            let initials = walkdir::WalkDir::new(&initials_dir)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .count();
+            info!("{}", "Illuminated initials".bold());
+            info!("  Images:     {}", initials);
+            info!("  Size:       {}", format_size(dir_size(&initials_dir)));
+            info!("  Last build: {}", last_build(stats.initials));
+            
+            let favicons_dir = Path::new(&config.output_dir).join(favicon::DIR);
+            let favicons = std::fs::read_dir(&favicons_dir).map(|entries| entries.count()).unwrap_or(0);
+            info!("{}", "Favicons".bold());
+            info!("  Sites:      {}", favicons);
+            info!("  Size:       {}", format_size(dir_size(&favicons_dir)));
+        }
+        CacheAction::Clear { stale: true } => {
+            let mut url_meta = cache::UrlMetaCache::load(cache::URL_META_PATH)?;
+            let removed = url_meta.prune_stale();
+            url_meta.save(cache::URL_META_PATH)?;
+            info!("{}", format!("Removed {} stale URL metadata entries", removed).green());
+        }
+        CacheAction::Clear { stale: false } => {
+            for path in [Path::new(cache::URL_META_PATH), Path::new(cache::STATS_PATH)] {
+                if path.exists() {
+                    std::fs::remove_file(path)?;
                 }
-                Err(mpsc::RecvTimeoutError::Disconnected) => {
-                    break;
+            }
+            for dir in [initials_dir, Path::new(&config.output_dir).join(favicon::DIR)] {
+                if dir.exists() {
+                    std::fs::remove_dir_all(&dir)?;
                 }
             }
+            info!("{}", "Cleared URL metadata, illuminated initials and favicons".green());
         }
-    });
+        CacheAction::Warm => {
+            SiteGenerator::new(config).warm_caches().await?;
+        }
+    }
     
-    Ok(WatcherHandle {
-        _watcher: watcher,
-        _task_handle: task_handle,
-    })
+    Ok(())
 }
 
-async fn regenerate_site(config_path: &PathBuf) -> Result<()> {
-    let config = Config::load(config_path)?;
-    let mut generator = SiteGenerator::new(config);
-    generator.generate().await?;
-    Ok(())
+/// Total size in bytes of a file or everything under a directory.
+fn dir_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
 }
 
-async fn create_new_post(title: String, excerpt: Option<String>, config_path: PathBuf, posts_dir: Option<PathBuf>) -> Result<()> {
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1} KB", b as f64 / 1024.0),
+        b => format!("{} B", b),
+    }
+}
+
+async fn create_new_post(post: NewPost, edit: bool, config_path: PathBuf, posts_dir: Option<PathBuf>) -> Result<()> {
+    let NewPost { title, excerpt, tags, series, slug, draft, kind } = post;
     // Load configuration to get author and posts directory
     let config = Config::load(&config_path)
         .context("Failed to load configuration")?;
@@ -930,16 +1977,18 @@ async fn create_new_post(title: String, excerpt: Option<String>, config_path: Pa
     std::fs::create_dir_all(&posts_directory)
         .context("Failed to create posts directory")?;
     
-    // Generate slug from title
-    let slug = title
-        .to_lowercase()
-        .chars()
-        .map(|c| if c.is_alphanumeric() { c } else { '-' })
-        .collect::<String>()
-        .split('-')
-        .filter(|s| !s.is_empty())
-        .collect::<Vec<_>>()
-        .join("-");
+    // Generate slug from title unless one was given
+    let slug = title_slug(slug.as_deref().unwrap_or(&title));
+    if slug.is_empty() {
+        error!("{}", "Error: the title has no letters or digits to make a slug from; pass --slug".red());
+        process::exit(1);
+    }
+    
+    // Generated pages own their URLs
+    if reserved_slugs(&config).contains(&sanitize_slug(&slug)) {
+        error!("{}", format!("Error: the slug '{}' is reserved for a generated page; choose another title or --slug (or edit reserved_slugs in the config)", slug).red());
+        process::exit(1);
+    }
     
     // Create filename
     let filename = format!("{}.md", slug);
@@ -947,52 +1996,408 @@ async fn create_new_post(title: String, excerpt: Option<String>, config_path: Pa
     
     // Check if file already exists
     if file_path.exists() {
-        eprintln!("{}", format!("Error: File '{}' already exists.", file_path.display()).red());
+        error!("{}", format!("Error: File '{}' already exists.", file_path.display()).red());
         process::exit(1);
     }
     
     // Get current date
     let current_date = chrono::Utc::now().format("%Y-%m-%d").to_string();
     
-    // Create frontmatter and content
-    let excerpt_line = if let Some(ref exc) = excerpt {
-        format!("excerpt: \"{}\"\n", exc.replace('"', "\\\""))
-    } else {
-        String::new()
+    // Start from the archetype, then fill in the frontmatter it leaves out and the flags given
+    let archetypes_dir = Path::new(archetypes::DIR);
+    let archetype = match &kind {
+        Some(kind) => archetypes::load(archetypes_dir, kind)?,
+        None if archetypes::find(archetypes_dir, archetypes::DEFAULT_KIND).is_some() => {
+            archetypes::load(archetypes_dir, archetypes::DEFAULT_KIND)?
+        }
+        None => archetypes::BUILT_IN.to_string(),
     };
-    
-    let content = format!(
-        r#"---
-title: "{}"
-date: "{}"
-{}---
-
-Write your post content here...
-
-"#,
-        title.replace('"', "\\\""),
-        current_date,
-        excerpt_line
-    );
+    let variables = archetypes::Variables { title: &title, date: &current_date, author: &config.author, slug: &slug };
+    let mut content = archetypes::render(&archetype, &variables);
+    if !frontmatter::has_block(&content) {
+        content = format!("---\n---\n\n{}", content);
+    }
+    let archetype_name = kind.as_deref().unwrap_or(archetypes::DEFAULT_KIND);
+    let set = |content: &str, key: &str, value: &str| {
+        frontmatter::set_field(content, key, value).context(format!("Failed to set {} in the '{}' archetype", key, archetype_name))
+    };
+    for (key, value) in [("title", &title), ("date", &current_date)] {
+        if frontmatter::raw_field(&content, key).is_none() {
+            content = set(&content, key, value)?;
+        }
+    }
+    if let Some(ref exc) = excerpt {
+        content = set(&content, "excerpt", exc)?;
+    }
+    if !tags.is_empty() {
+        // Keep the archetype's tags, e.g. `review`
+        let mut all = frontmatter::split(&content).0.map(|f| f.tags).unwrap_or_default();
+        for tag in &tags {
+            if !all.contains(tag) {
+                all.push(tag.clone());
+            }
+        }
+        let quoted: Vec<String> = all.iter().map(|t| format!("\"{}\"", t.replace('"', "\\\""))).collect();
+        content = frontmatter::set_raw(&content, "tags", &format!("[{}]", quoted.join(", ")))?;
+    }
+    if let Some(ref series) = series {
+        content = set(&content, "series", series)?;
+    }
+    if draft {
+        content = frontmatter::set_raw(&content, "draft", "true")?;
+    }
     
     // Write the file
     std::fs::write(&file_path, content)
         .context("Failed to write new post file")?;
     
-    println!("{}", "New post created successfully!".green().bold());
-    println!();
-    println!("{}: {}", "Title".white().bold(), title.cyan());
-    println!("{}: {}", "Author".white().bold(), config.author.cyan());
-    println!("{}: {}", "Date".white().bold(), current_date.cyan());
+    info!("{}", "New post created successfully!".green().bold());
+    info!("\n{}: {}", "Title".white().bold(), title.cyan());
+    info!("{}: {}", "Author".white().bold(), config.author.cyan());
+    info!("{}: {}", "Date".white().bold(), current_date.cyan());
     if let Some(exc) = excerpt {
-        println!("{}: {}", "Excerpt".white().bold(), exc.cyan());
+        info!("{}: {}", "Excerpt".white().bold(), exc.cyan());
     }
-    println!("{}: {}", "File".white().bold(), file_path.display().to_string().cyan());
-    println!();
-    println!("Next steps:");
-    println!("  1. Edit the file: {}", file_path.display().to_string().yellow());
-    println!("  2. Generate site: {}", "scribe generate".yellow());
-    println!("  3. Serve locally: {}", "scribe serve".yellow());
+    if !tags.is_empty() {
+        info!("{}: {}", "Tags".white().bold(), tags.join(", ").cyan());
+    }
+    if let Some(series) = series {
+        info!("{}: {}", "Series".white().bold(), series.cyan());
+    }
+    if draft {
+        info!("{}: {}", "Draft".white().bold(), "yes (left out of builds until you remove `draft: true`)".cyan());
+    }
+    if let Some(kind) = kind {
+        info!("{}: {}", "Kind".white().bold(), kind.cyan());
+    }
+    info!("{}: {}", "File".white().bold(), file_path.display().to_string().cyan());
+    
+    if edit {
+        open_in_editor(&file_path)?;
+    }
+    
+    info!("\nNext steps:");
+    info!("  1. Edit the file: {}", file_path.display().to_string().yellow());
+    info!("  2. Generate site: {}", "scribe generate".yellow());
+    info!("  3. Serve locally: {}", "scribe serve".yellow());
     
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// Slug for a post titled `title`: lowercase letters and digits joined by dashes.
+fn title_slug(title: &str) -> String {
+    title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Open `path` in `$VISUAL` or `$EDITOR` and wait for it to close; warns when neither is set.
+fn open_in_editor(path: &Path) -> Result<()> {
+    let Some(editor) = ["VISUAL", "EDITOR"].iter().find_map(|var| std::env::var(var).ok().filter(|v| !v.trim().is_empty())) else {
+        warn!("{}", "Warning: set $EDITOR to open new posts with --edit".yellow());
+        return Ok(());
+    };
+    // The variable may carry arguments, e.g. "code --wait"
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or_default();
+    let status = process::Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .context(format!("Failed to start editor '{}'", editor))?;
+    if !status.success() {
+        warn!("{}", format!("Warning: editor '{}' exited with {}", editor, status).yellow());
+    }
+    Ok(())
+}
+
+/// Set the `date` (when `published`) or `updated` field of the post `slug` to now. The value keeps
+/// the granularity already used by the post: a full timestamp if it has one, otherwise a day.
+async fn summarize_posts(only: Vec<String>, config_path: PathBuf) -> Result<()> {
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
+    if config.offline {
+        error!("{}", "Error: summarizing posts needs network access; drop --offline.".red());
+        process::exit(1);
+    }
+    if config.openai_api_key.is_none() {
+        error!("{}", "Error: OPENAI_API_KEY not found in environment or config. Cannot summarize posts.".red());
+        process::exit(1);
+    }
+    
+    let mut generator = SiteGenerator::new(config.clone());
+    generator.set_only(only);
+    generator.read_posts().await?;
+    let updated = summarize::summarize_posts(&config, generator.selected_posts()).await?;
+    if updated == 0 {
+        info!("{}", "Every post already has an excerpt and TL;DR".green());
+    } else {
+        info!("{}", format!("Summarized {} post(s)", updated).green());
+    }
+    Ok(())
+}
+
+fn touch_post(slug: String, published: bool, config_path: PathBuf) -> Result<()> {
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
+    
+    let Some(path) = find_post_file(Path::new(&config.posts_dir), &config.input_formats, &slug) else {
+        error!("{}", format!("Error: no post with slug '{}' in {}", slug, config.posts_dir).red());
+        process::exit(1);
+    };
+    
+    let content = std::fs::read_to_string(&path)
+        .context(format!("Failed to read {}", path.display()))?;
+    let field = if published { "date" } else { "updated" };
+    let has_time = frontmatter::raw_field(&content, field)
+        .or_else(|| frontmatter::raw_field(&content, "date"))
+        .is_some_and(|raw| raw.contains('T'));
+    let now = chrono::Utc::now();
+    let value = if has_time {
+        now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    } else {
+        now.format("%Y-%m-%d").to_string()
+    };
+    
+    let touched = frontmatter::set_field(&content, field, &value)
+        .context(format!("Failed to update {}", path.display()))?;
+    std::fs::write(&path, touched)
+        .context(format!("Failed to write {}", path.display()))?;
+    
+    info!("{} {} {} = {}", "Touched".green(), path.display(), field, value.cyan());
+    Ok(())
+}
+
+fn move_post(old: String, new: String, config_path: PathBuf) -> Result<()> {
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
+    let posts_dir = Path::new(&config.posts_dir);
+    
+    let Some(source) = find_post_file(posts_dir, &config.input_formats, &old) else {
+        error!("{}", format!("Error: no post with slug '{}' in {}", old, config.posts_dir).red());
+        process::exit(1);
+    };
+    let old_slug = source.file_stem().and_then(|s| s.to_str()).map(sanitize_slug).unwrap_or_default();
+    let new_slug = sanitize_slug(&new);
+    if new_slug.is_empty() || new_slug == old_slug {
+        error!("{}", format!("Error: '{}' is not a new slug for '{}'", new, old_slug).red());
+        process::exit(1);
+    }
+    if let Some(existing) = find_post_file(posts_dir, &config.input_formats, &new_slug) {
+        error!("{}", format!("Error: {} already uses the slug '{}'", existing.display(), new_slug).red());
+        process::exit(1);
+    }
+    if reserved_slugs(&config).contains(&new_slug) {
+        error!("{}", format!("Error: the slug '{}' is reserved for a generated page", new_slug).red());
+        process::exit(1);
+    }
+    
+    // Links in every post, the moved one included
+    let mut links = 0;
+    let mut posts = 0;
+    for path in post_files(posts_dir, &config.input_formats) {
+        let content = std::fs::read_to_string(&path)
+            .context(format!("Failed to read {}", path.display()))?;
+        let (updated, changed) = retarget_links(&content, &old_slug, &new_slug);
+        if changed > 0 {
+            std::fs::write(&path, updated)
+                .context(format!("Failed to write {}", path.display()))?;
+            info!("  {} {} ({} link(s))", "updated".green(), path.display(), changed);
+            links += changed;
+            posts += 1;
+        }
+    }
+    
+    // Remember the old slug so the next build leaves a redirect behind
+    let mut content = std::fs::read_to_string(&source)
+        .context(format!("Failed to read {}", source.display()))?;
+    if !frontmatter::has_block(&content) {
+        content = format!("---\n---\n\n{}", content);
+    }
+    let mut aliases = frontmatter::split(&content).0.map(|fm| fm.aliases).unwrap_or_default();
+    aliases.retain(|a| sanitize_slug(a) != new_slug);
+    if !aliases.iter().any(|a| sanitize_slug(a) == old_slug) {
+        aliases.push(old_slug.clone());
+    }
+    let content = frontmatter::set_field(&content, "aliases", &aliases.join(", "))
+        .context(format!("Failed to update {}", source.display()))?;
+    std::fs::write(&source, content)
+        .context(format!("Failed to write {}", source.display()))?;
+    
+    let extension = source.extension().and_then(|ext| ext.to_str()).unwrap_or("md");
+    let target = source.with_file_name(format!("{}.{}", new_slug, extension));
+    std::fs::rename(&source, &target)
+        .context(format!("Failed to move {} to {}", source.display(), target.display()))?;
+    let sidecar = annotations::sidecar_path(&source);
+    if sidecar.exists() {
+        std::fs::rename(&sidecar, annotations::sidecar_path(&target))
+            .context(format!("Failed to move {}", sidecar.display()))?;
+    }
+    // Per-post initials are stored under the slug
+    let initials_dir = Path::new(&config.output_dir).join(initials::DIR);
+    if initials_dir.join(&old_slug).is_dir() {
+        std::fs::rename(initials_dir.join(&old_slug), initials_dir.join(&new_slug))?;
+    }
+    
+    info!("{} {} → {}", "Moved".green(), source.display(), target.display());
+    info!("Updated {} link(s) in {} post(s)", links, posts);
+    let url = |slug: &str| match config.site_url() {
+        Some(site) => config.trailing_slash.post_href(&site, slug),
+        None => config.trailing_slash.post_href(&config.base_path(), slug),
+    };
+    info!("\n{}", "URL change".bold());
+    info!("  {} → {}", url(&old_slug), url(&new_slug));
+    info!("  The next build leaves a redirect at the old URL (aliases: {}).", aliases.join(", "));
+    info!("  Links from other sites, bookmarks, feed readers and search engines still use the old URL until they pick up the redirect.");
+    Ok(())
+}
+
+fn trash_post(slug: String, config_path: PathBuf) -> Result<()> {
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
+    let posts_dir = Path::new(&config.posts_dir);
+    let Some(path) = find_post_file(posts_dir, &config.input_formats, &slug) else {
+        error!("{}", format!("Error: no post with slug '{}' in {}", slug, config.posts_dir).red());
+        process::exit(1);
+    };
+    
+    let trashed = trash::trash(posts_dir, &path)?;
+    info!("{} {} → {}", "Trashed".green(), path.display(), trashed.display());
+    
+    // Links to the post will dangle once its page is gone
+    let slug = sanitize_slug(path.file_stem().and_then(|s| s.to_str()).unwrap_or_default());
+    let linking: Vec<PathBuf> = post_files(posts_dir, &config.input_formats)
+        .into_iter()
+        .filter(|p| std::fs::read_to_string(p).is_ok_and(|content| retarget_links(&content, &slug, &slug).1 > 0))
+        .collect();
+    if !linking.is_empty() {
+        let mut warning = format!("Warning: {} post(s) still link to '{}':", linking.len(), slug);
+        for p in &linking {
+            warning.push_str(&format!("\n  {}", p.display()));
+        }
+        warn!("{}", warning.yellow());
+    }
+    let action = match config.trash.page {
+        config::TrashedPage::Remove => "removes its page",
+        config::TrashedPage::Gone => "replaces its page with a \"gone\" notice",
+        config::TrashedPage::Redirect => "replaces its page with a redirect",
+    };
+    info!("The next build {}. Undo with: scribe restore {}", action, slug);
+    Ok(())
+}
+
+fn restore_post(slug: Option<String>, config_path: PathBuf) -> Result<()> {
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
+    let posts_dir = Path::new(&config.posts_dir);
+    
+    let Some(slug) = slug else {
+        let files = trash::files(posts_dir);
+        if files.is_empty() {
+            info!("The trash is empty");
+        }
+        for path in files {
+            info!("{}", path.display());
+        }
+        return Ok(());
+    };
+    let Some(trashed) = trash::find(posts_dir, &slug) else {
+        error!("{}", format!("Error: no post with slug '{}' in the trash", slug).red());
+        process::exit(1);
+    };
+    if let Some(existing) = find_post_file(posts_dir, &config.input_formats, &slug) {
+        error!("{}", format!("Error: {} has taken the slug '{}'; rename it first", existing.display(), sanitize_slug(&slug)).red());
+        process::exit(1);
+    }
+    
+    let restored = trash::restore(posts_dir, &trashed)?;
+    info!("{} {}", "Restored".green(), restored.display());
+    Ok(())
+}
+
+async fn show_stats(log: PathBuf, since: Option<chrono::NaiveDate>, top: usize, config_path: PathBuf) -> Result<()> {
+    if !log.exists() {
+        error!("{}", format!("Error: no access log at {}; serve with --access-log to record one", log.display()).red());
+        process::exit(1);
+    }
+    let mut entries = access::read(&log)?;
+    if let Some(since) = since {
+        entries.retain(|entry| entry.time.date_naive() >= since);
+    }
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
+    let mut generator = SiteGenerator::new(config.clone());
+    let posts = generator.read_posts().await?;
+    let stats = access::summarize(&entries, &config, posts);
+    
+    let Some((first, last)) = stats.first.zip(stats.last) else {
+        info!("No page views recorded{}", if stats.bots > 0 { format!(" ({} by bots)", stats.bots) } else { String::new() });
+        return Ok(());
+    };
+    info!(
+        "{} page views from {} to {} ({} by bots left out)",
+        stats.views.to_string().bold(),
+        first.format("%Y-%m-%d"),
+        last.format("%Y-%m-%d"),
+        stats.bots
+    );
+    let list = |heading: &str, rows: Vec<(String, usize)>| {
+        if rows.is_empty() {
+            return;
+        }
+        info!("{}", heading.bold());
+        for (name, views) in rows.into_iter().take(top) {
+            info!("  {:>6}  {}", views, name);
+        }
+    };
+    list("Posts", stats.posts.iter().map(|(post, views)| (format!("{} {}", post.title, format!("({})", post.slug).dimmed()), *views)).collect());
+    list("Other pages", stats.pages);
+    list("Referrers", stats.referrers);
+    list("Browsers", stats.agents);
+    Ok(())
+}
+
+fn clean_output(keep_initials: bool, config_path: PathBuf) -> Result<()> {
+    let config = Config::load(&config_path)
+        .context("Failed to load configuration")?;
+    let output_dir = Path::new(&config.output_dir);
+    if !output_dir.exists() {
+        info!("Nothing to clean: {} does not exist", output_dir.display());
+        return Ok(());
+    }
+    
+    // Refuse output directories that hold the sources
+    let output = output_dir.canonicalize()?;
+    let sources = [Path::new(&config.posts_dir), config_path.as_path(), Path::new(".")];
+    if let Some(source) = sources.iter().find(|s| s.canonicalize().is_ok_and(|s| s.starts_with(&output))) {
+        error!("{}", format!("Error: {} is inside the output directory {}; not deleting it", source.display(), output_dir.display()).red());
+        process::exit(1);
+    }
+    
+    let mut removed = 0;
+    for entry in std::fs::read_dir(output_dir)? {
+        let path = entry?.path();
+        if keep_initials && path.file_name().is_some_and(|name| name == initials::DIR) {
+            continue;
+        }
+        if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        }
+        .context(format!("Failed to remove {}", path.display()))?;
+        removed += 1;
+    }
+    if !keep_initials {
+        std::fs::remove_dir(output_dir)
+            .context(format!("Failed to remove {}", output_dir.display()))?;
+    }
+    info!("{} {} ({} entries)", "Cleaned".green(), output_dir.display(), removed);
+    Ok(())
+}
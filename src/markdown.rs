@@ -0,0 +1,335 @@
+use crate::config::MarkdownOptions;
+use pulldown_cmark::{html, Event, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Longest context snippet kept for a link, in characters.
+const CONTEXT_CHARS: usize = 200;
+
+/// A link found in a post's Markdown (or inline HTML `<a href>`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Link {
+    pub href: String,
+    /// Anchor text; empty for raw HTML anchors
+    pub text: String,
+    /// Plain text of the paragraph, list item or cell containing the link
+    pub context: String,
+}
+
+/// Custom HTML (e.g. a JS demo) from a `::: raw` … `:::` block, published exactly as written.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RawBlock {
+    pub html: String,
+    /// Render in a sandboxed iframe (`::: raw iframe`) rather than an isolating `<div>`
+    pub iframe: bool,
+    /// Iframe height in pixels (`height=300`)
+    pub height: Option<u32>,
+}
+
+impl RawBlock {
+    /// The block's HTML inside its wrapper. The `<div>` keeps the block's layout and painting
+    /// to itself; the iframe also keeps its scripts, styles and unbalanced tags away from the page.
+    pub fn render(&self) -> String {
+        if self.iframe {
+            let srcdoc = self.html.replace('&', "&amp;").replace('"', "&quot;");
+            let style = self.height.map(|h| format!(" style=\"height: {}px\"", h)).unwrap_or_default();
+            format!("<iframe class=\"raw-html-frame\" sandbox=\"allow-scripts\" loading=\"lazy\" title=\"Embedded demo\"{} srcdoc=\"{}\"></iframe>", style, srcdoc)
+        } else {
+            format!("<div class=\"raw-html\">\n{}\n</div>", self.html.trim_end())
+        }
+    }
+}
+
+/// Take the `::: raw` blocks out of `markdown` (outside fenced code), leaving a placeholder HTML
+/// block for each so wiki links, autolinks, Markdown and typography passes never see them. A
+/// block without a closing `:::` runs to the end of the post.
+pub fn extract_raw_blocks(markdown: &str) -> (String, Vec<RawBlock>) {
+    let opening = Regex::new(r"^:::\s*raw\b(.*)$").unwrap();
+    let height = Regex::new(r"\bheight=(\d+)").unwrap();
+    let mut output = String::with_capacity(markdown.len());
+    let mut blocks: Vec<RawBlock> = Vec::new();
+    let mut fences = CodeFences::default();
+    let mut current: Option<RawBlock> = None;
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if let Some(block) = current.as_mut() {
+            if trimmed == ":::" {
+                output.push_str(&raw_placeholder(blocks.len()));
+                blocks.push(current.take().unwrap());
+            } else {
+                block.html.push_str(line);
+            }
+            continue;
+        }
+        let in_fence = fences.is_code(line);
+        match opening.captures(trimmed).filter(|_| !in_fence) {
+            Some(caps) => {
+                let options = &caps[1];
+                current = Some(RawBlock {
+                    html: String::new(),
+                    iframe: options.split_whitespace().any(|o| o == "iframe"),
+                    height: height.captures(options).and_then(|h| h[1].parse().ok()),
+                });
+            }
+            None => output.push_str(line),
+        }
+    }
+    if let Some(block) = current {
+        output.push_str(&raw_placeholder(blocks.len()));
+        blocks.push(block);
+    }
+    (output, blocks)
+}
+
+/// Put each block's wrapped HTML back in place of its placeholder in rendered `html`.
+pub fn restore_raw_blocks(html: &str, blocks: &[RawBlock]) -> String {
+    if blocks.is_empty() {
+        return html.to_string();
+    }
+    let placeholder = Regex::new(r#"<div data-raw-block="(\d+)"></div>"#).unwrap();
+    placeholder
+        .replace_all(html, |caps: &regex::Captures| {
+            caps[1].parse::<usize>().ok().and_then(|i| blocks.get(i)).map_or_else(String::new, RawBlock::render)
+        })
+        .to_string()
+}
+
+/// A Markdown HTML block standing in for raw block `index`, blank lines around it so it never
+/// joins a paragraph.
+fn raw_placeholder(index: usize) -> String {
+    format!("\n<div data-raw-block=\"{}\"></div>\n\n", index)
+}
+
+/// The whole of a hand-written HTML post as one raw block, published exactly as written.
+pub fn raw_document(html: &str) -> (String, Vec<RawBlock>) {
+    (raw_placeholder(0), vec![RawBlock { html: html.trim().to_string(), ..RawBlock::default() }])
+}
+
+/// Tracks fenced code blocks for passes that go through Markdown line by line. A fence is three
+/// or more backticks or tildes (a backtick fence's info string has no backticks), and only a
+/// line of the same character, at least as long and with nothing after it, closes it. Unlike
+/// CommonMark any indentation is allowed, so fences nested in list items count too.
+#[derive(Debug, Default)]
+pub struct CodeFences {
+    /// The open fence's character and length
+    open: Option<(char, usize)>,
+}
+
+impl CodeFences {
+    /// Whether `line`, the next line of the document, is a fence or inside a fenced block.
+    pub fn is_code(&mut self, line: &str) -> bool {
+        let trimmed = line.trim_start();
+        let Some(fence @ ('`' | '~')) = trimmed.chars().next() else {
+            return self.open.is_some();
+        };
+        let len = trimmed.chars().take_while(|&c| c == fence).count();
+        let rest = &trimmed[len..];
+        match self.open {
+            Some((open, open_len)) => {
+                if fence == open && len >= open_len && rest.trim().is_empty() {
+                    self.open = None;
+                }
+                true
+            }
+            None if len >= 3 && !(fence == '`' && rest.contains('`')) => {
+                self.open = Some((fence, len));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// `line` cut into `(piece, is_code)` pieces that join back into it, where code pieces are
+/// inline code spans: a run of backticks up to the next run of the same length. A run without
+/// a match, or escaped with `\`, is text.
+pub fn code_spans(line: &str) -> Vec<(&str, bool)> {
+    let bytes = line.as_bytes();
+    let run = |at: usize| bytes[at..].iter().take_while(|&&b| b == b'`').count();
+    let mut pieces = Vec::new();
+    let (mut text_start, mut i) = (0, 0);
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'`' => {
+                let len = run(i);
+                let mut at = i + len;
+                let close = loop {
+                    let Some(next) = bytes[at..].iter().position(|&b| b == b'`') else {
+                        break None;
+                    };
+                    let other = run(at + next);
+                    if other == len {
+                        break Some(at + next + len);
+                    }
+                    at += next + other;
+                };
+                match close {
+                    Some(end) => {
+                        if text_start < i {
+                            pieces.push((&line[text_start..i], false));
+                        }
+                        pieces.push((&line[i..end], true));
+                        (text_start, i) = (end, end);
+                    }
+                    None => i += len,
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    if text_start < line.len() {
+        pieces.push((&line[text_start..], false));
+    }
+    pieces
+}
+
+/// `line` with `replace` applied to the text outside its inline code spans.
+pub fn replace_outside_code(line: &str, mut replace: impl FnMut(&str) -> String) -> String {
+    code_spans(line).into_iter().map(|(piece, code)| if code { piece.to_string() } else { replace(piece) }).collect()
+}
+
+/// The text of `line` outside its inline code spans, with a space in place of each span.
+pub fn strip_code_spans(line: &str) -> String {
+    code_spans(line).into_iter().map(|(piece, code)| if code { " " } else { piece }).collect()
+}
+
+/// Render Markdown to HTML with the extensions enabled in the `markdown` config section.
+pub fn render(markdown: &str, options: &MarkdownOptions) -> String {
+    let mut output = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut output, Parser::new_ext(markdown, flags(options)));
+    output
+}
+
+/// Every link in `markdown`, in document order, with its anchor text and surrounding text.
+pub fn links(markdown: &str, options: &MarkdownOptions) -> Vec<Link> {
+    let html_href = anchor_href();
+    let mut links: Vec<Link> = Vec::new();
+    // Index of the first link in the current block, and the block's text so far
+    let mut block_start = 0;
+    let mut block_text = String::new();
+    // Links whose anchor text is still being collected
+    let mut open: Vec<usize> = Vec::new();
+
+    for event in Parser::new_ext(markdown, flags(options)) {
+        match event {
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                open.push(links.len());
+                links.push(Link { href: dest_url.to_string(), ..Link::default() });
+            }
+            Event::End(TagEnd::Link) => {
+                open.pop();
+            }
+            Event::Text(text) | Event::Code(text) => {
+                for &index in &open {
+                    links[index].text.push_str(&text);
+                }
+                block_text.push_str(&text);
+            }
+            Event::SoftBreak | Event::HardBreak => block_text.push(' '),
+            Event::Html(fragment) | Event::InlineHtml(fragment) => links.extend(html_links_with(&html_href, &fragment)),
+            Event::End(TagEnd::Paragraph | TagEnd::Item | TagEnd::TableCell | TagEnd::Heading(_) | TagEnd::DefinitionListDefinition) => {
+                let context = snippet(&block_text);
+                for link in &mut links[block_start..] {
+                    link.context.clone_from(&context);
+                }
+                block_start = links.len();
+                block_text.clear();
+            }
+            _ => {}
+        }
+    }
+    links
+}
+
+/// Links in hand-written HTML, from its `<a href>` tags (without anchor text).
+pub fn html_links(html: &str) -> Vec<Link> {
+    html_links_with(&anchor_href(), html).collect()
+}
+
+fn html_links_with<'a>(anchor_href: &'a Regex, html: &'a str) -> impl Iterator<Item = Link> + 'a {
+    anchor_href.captures_iter(html).map(|caps| {
+        let href = caps.get(1).or_else(|| caps.get(2)).map_or("", |m| m.as_str());
+        Link { href: href.to_string(), ..Link::default() }
+    })
+}
+
+/// The `href` of an `<a>` tag, double- or single-quoted.
+fn anchor_href() -> Regex {
+    Regex::new(r#"(?i)<a\b[^>]*\bhref\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap()
+}
+
+/// Text of hand-written HTML, for counting its words: scripts, styles and tags dropped.
+pub fn html_text(html: &str) -> String {
+    let hidden = Regex::new(r"(?is)<(script|style)\b.*?</(script|style)\s*>|<!--.*?-->").unwrap();
+    let tags = Regex::new(r"<[^>]*>").unwrap();
+    tags.replace_all(&hidden.replace_all(html, " "), " ").to_string()
+}
+
+/// Readable text of `markdown`, one line per block, leaving out code blocks.
+pub fn plain_text(markdown: &str, options: &MarkdownOptions) -> String {
+    let mut text = String::new();
+    let mut in_code = false;
+    for event in Parser::new_ext(markdown, flags(options)) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code = true,
+            Event::End(TagEnd::CodeBlock) => in_code = false,
+            Event::Text(t) | Event::Code(t) if !in_code => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            Event::End(TagEnd::Paragraph | TagEnd::Item | TagEnd::TableCell | TagEnd::Heading(_) | TagEnd::BlockQuote(_)) => text.push('\n'),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Source ranges of the top-level paragraphs and lists, the blocks annotations attach to.
+pub fn annotatable_blocks(markdown: &str, options: &MarkdownOptions) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut depth = 0;
+    for (event, range) in Parser::new_ext(markdown, flags(options)).into_offset_iter() {
+        match event {
+            Event::Start(tag) => {
+                if depth == 0 && matches!(tag, Tag::Paragraph | Tag::List(_)) {
+                    blocks.push(range);
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+    }
+    blocks
+}
+
+fn snippet(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(CONTEXT_CHARS) {
+        Some((cut, _)) => format!("{}…", text[..cut].trim_end()),
+        None => text,
+    }
+}
+
+fn flags(options: &MarkdownOptions) -> Options {
+    let mut flags = Options::empty();
+    if options.footnotes {
+        flags.insert(Options::ENABLE_FOOTNOTES);
+    }
+    if options.tables {
+        flags.insert(Options::ENABLE_TABLES);
+    }
+    if options.strikethrough {
+        flags.insert(Options::ENABLE_STRIKETHROUGH);
+    }
+    if options.tasklists {
+        flags.insert(Options::ENABLE_TASKLISTS);
+    }
+    if options.definition_lists {
+        flags.insert(Options::ENABLE_DEFINITION_LIST);
+    }
+    if options.smart_punctuation {
+        flags.insert(Options::ENABLE_SMART_PUNCTUATION);
+    }
+    flags
+}
@@ -0,0 +1,475 @@
+use crate::config::InputFormat;
+use crate::frontmatter;
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Read the post at `path`, converting AsciiDoc and Org sources to Markdown.
+pub fn read(path: &Path) -> io::Result<String> {
+    let content = fs::read_to_string(path)?;
+    Ok(match InputFormat::of(path) {
+        Some(format) => to_markdown(format, &content),
+        None => content,
+    })
+}
+
+/// Markdown for an AsciiDoc or Org document. Its header (`= Title` and `:attributes:`, or
+/// `#+KEYWORDS:`) becomes a YAML frontmatter block, under any frontmatter block the file opens
+/// with (whose fields win), and its body the closest Markdown constructs.
+pub fn to_markdown(format: InputFormat, content: &str) -> String {
+    let (block, body) = frontmatter::split_fields(content);
+    let (mut fields, markdown) = match format {
+        InputFormat::Asciidoc => asciidoc(&body),
+        InputFormat::Org => org(&body),
+    };
+    fields.extend(block.unwrap_or_default());
+    if fields.is_empty() {
+        return markdown;
+    }
+    format!("---\n{}---\n\n{}", serde_yaml::to_string(&fields).unwrap_or_default(), markdown)
+}
+
+/// Header fields and Markdown body of an Org document.
+fn org(body: &str) -> (Map<String, Value>, String) {
+    let keyword = Regex::new(r"^#\+(\w+):\s*(.*)$").unwrap();
+    let begin = Regex::new(r"(?i)^#\+begin_(\w+)\s*(\S*)").unwrap();
+    let heading = Regex::new(r"^(\*+)\s+(?:(?:TODO|DONE)\s+)?(?:\[#[A-Z]\]\s+)?(.*?)(?:\s+:[\w@#%:]+:)?\s*$").unwrap();
+    let drawer = Regex::new(r"^:[\w-]+:$").unwrap();
+    let item = Regex::new(r"^(\s*)(?:[-+]|(\s)\*)\s+(.*)$").unwrap();
+    let numbered = Regex::new(r"^(\s*)(\d+)[.)]\s+(.*)$").unwrap();
+    let footnote = Regex::new(r"^\[fn:([\w-]+)\]\s*(.*)$").unwrap();
+    let rule = Regex::new(r"^-{5,}$").unwrap();
+    let separator = Regex::new(r"^\|[-+|]+\|?$").unwrap();
+
+    let mut fields = Map::new();
+    let mut out: Vec<String> = Vec::new();
+    let mut block: Option<Block> = None;
+    let mut in_drawer = false;
+    let mut table_rows = 0;
+    for line in body.lines() {
+        let trimmed = line.trim();
+        if let Some(open) = &block {
+            if trimmed.to_lowercase().starts_with("#+end_") {
+                if let Some(close) = open.closing() {
+                    out.push(close.to_string());
+                }
+                block = None;
+            } else if let Some(line) = open.line(line, org_inline) {
+                out.push(line);
+            }
+            continue;
+        }
+        if in_drawer {
+            in_drawer = !trimmed.eq_ignore_ascii_case(":end:");
+            continue;
+        }
+        if !trimmed.starts_with('|') {
+            table_rows = 0;
+        }
+        if drawer.is_match(trimmed) {
+            in_drawer = true;
+        } else if let Some(caps) = begin.captures(trimmed) {
+            let (opened, opening) = Block::open(&caps[1].to_lowercase(), &caps[2]);
+            out.extend(opening);
+            block = Some(opened);
+        } else if let Some(caps) = keyword.captures(trimmed) {
+            let value = caps[2].trim();
+            match caps[1].to_lowercase().as_str() {
+                "title" => set(&mut fields, "title", value.into()),
+                "date" => set(&mut fields, "date", date(value).into()),
+                "filetags" | "tags" => set(&mut fields, "tags", list(value, &[':', ' ', ','])),
+                "description" => set(&mut fields, "excerpt", value.into()),
+                "language" => set(&mut fields, "lang", value.into()),
+                _ => {}
+            }
+        } else if trimmed == "#" || trimmed.starts_with("# ") {
+            // Comment line
+        } else if let Some(caps) = heading.captures(line) {
+            out.push(format!("{} {}", "#".repeat((caps[1].len() + 1).min(6)), org_inline(&caps[2])));
+        } else if rule.is_match(trimmed) {
+            // Not `---`, which would make the line above a heading
+            out.push("***".to_string());
+        } else if trimmed.starts_with('|') {
+            let row = if separator.is_match(trimmed) { trimmed.replace('+', "|") } else { org_inline(trimmed) };
+            // Markdown tables need a separator under their first row
+            if table_rows == 1 && !separator.is_match(trimmed) {
+                let columns = out.last().map_or(1, |header| header.matches('|').count().saturating_sub(1).max(1));
+                out.push(format!("|{}", "---|".repeat(columns)));
+            }
+            out.push(row);
+            table_rows += 1;
+        } else if let Some(caps) = footnote.captures(line) {
+            out.push(format!("[^{}]: {}", &caps[1], org_inline(&caps[2])));
+        } else if let Some(caps) = item.captures(line) {
+            let indent = format!("{}{}", &caps[1], caps.get(2).map_or("", |m| m.as_str()));
+            out.push(format!("{}- {}", indent, org_inline(&caps[3])));
+        } else if let Some(caps) = numbered.captures(line) {
+            out.push(format!("{}{}. {}", &caps[1], &caps[2], org_inline(&caps[3])));
+        } else {
+            out.push(org_inline(line));
+        }
+    }
+    if let Some(close) = block.and_then(|open| open.closing()) {
+        out.push(close.to_string());
+    }
+    (fields, finish(out))
+}
+
+/// An Org line's links, code, footnote references and emphasis as Markdown.
+fn org_inline(line: &str) -> String {
+    let link = Regex::new(r"\[\[([^\[\]]+)\](?:\[([^\[\]]*)\])?\]").unwrap();
+    let code = Regex::new(r"(^|[\s(\[{'\x22-])[=~]([^\s=~](?:[^=~]*[^\s=~])?)[=~]").unwrap();
+    let footnote = Regex::new(r"\[fn:([\w-]+)\]").unwrap();
+    let mut held = Held::default();
+    let text = link.replace_all(line, |caps: &regex::Captures| {
+        let markdown = org_link(&caps[1], caps.get(2).map(|m| m.as_str()));
+        held.hold(markdown)
+    });
+    let text = code.replace_all(&text, |caps: &regex::Captures| format!("{}{}", &caps[1], held.hold(format!("`{}`", &caps[2]))));
+    let text = footnote.replace_all(&text, "[^$1]");
+    let text = emphasis(&text, '*', "**");
+    let text = emphasis(&text, '/', "*");
+    let text = emphasis(&text, '+', "~~");
+    held.restore(&text)
+}
+
+/// Markdown for an Org link to `target`: web links and images as they are, `file:` links to
+/// other Org files as wiki links, and anything else as a wiki link to a post by title.
+fn org_link(target: &str, text: Option<&str>) -> String {
+    let target = target.trim();
+    let is_image = |t: &str| [".png", ".jpg", ".jpeg", ".gif", ".svg", ".webp"].iter().any(|ext| t.to_lowercase().ends_with(ext));
+    if let Some(file) = target.strip_prefix("file:") {
+        return match (file.strip_suffix(".org"), text) {
+            (Some(post), Some(text)) => format!("[[{}|{}]]", post, org_inline(text)),
+            (Some(post), None) => format!("[[{}]]", post),
+            (None, _) if is_image(file) && text.is_none() => format!("![]({})", file),
+            (None, text) => format!("[{}]({})", text.map_or_else(|| file.to_string(), org_inline), file),
+        };
+    }
+    if target.contains(':') {
+        return match text {
+            None if is_image(target) => format!("![]({})", target),
+            None => format!("<{}>", target),
+            Some(text) => format!("[{}]({})", org_inline(text), target),
+        };
+    }
+    if let Some(anchor) = target.strip_prefix('#').or_else(|| target.strip_prefix('*')) {
+        return format!("[{}](#{})", text.map_or_else(|| anchor.to_string(), org_inline), anchor.trim().to_lowercase().replace(' ', "-"));
+    }
+    match text {
+        Some(text) => format!("[[{}|{}]]", target, org_inline(text)),
+        None => format!("[[{}]]", target),
+    }
+}
+
+/// Header fields and Markdown body of an AsciiDoc document.
+fn asciidoc(body: &str) -> (Map<String, Value>, String) {
+    let attribute = Regex::new(r"^:(!?[\w-]+!?):\s*(.*)$").unwrap();
+    let revision = Regex::new(r"^v?[\d.]+,\s*([^:]+)").unwrap();
+    let heading = Regex::new(r"^(={1,6})\s+(.*)$").unwrap();
+    let delimiter = Regex::new(r"^(-{4,}|\.{4,}|_{4,}|\+{4,}|/{4,}|={4,}|\*{4,}|\|===)$").unwrap();
+    let item = Regex::new(r"^(\*+|-)\s+(.*)$").unwrap();
+    let numbered = Regex::new(r"^(\.+)\s+(.*)$").unwrap();
+    let admonition = Regex::new(r"^(NOTE|TIP|IMPORTANT|WARNING|CAUTION):\s+(.*)$").unwrap();
+    let image = Regex::new(r"^image::([^\[]+)\[([^,\]]*)[^\]]*\]$").unwrap();
+    let title = Regex::new(r"^\.([^\s.].*)$").unwrap();
+
+    let mut fields = Map::new();
+    let lines: Vec<&str> = body.lines().collect();
+
+    // The header: the document title, attribute entries and author and revision lines, up to
+    // the first blank line
+    let mut start = lines.iter().take_while(|line| line.trim().is_empty()).count();
+    let header = lines[start..].first().is_some_and(|line| line.starts_with("= ") || attribute.is_match(line));
+    while header && start < lines.len() && !lines[start].trim().is_empty() {
+        let line = lines[start].trim();
+        if let Some(text) = line.strip_prefix("= ") {
+            set(&mut fields, "title", text.trim().into());
+        } else if let Some(caps) = attribute.captures(line) {
+            let value = caps[2].trim();
+            match &caps[1] {
+                "revdate" | "date" => set(&mut fields, "date", date(value).into()),
+                "updated" => set(&mut fields, "updated", date(value).into()),
+                "description" => set(&mut fields, "excerpt", value.into()),
+                "keywords" | "tags" => set(&mut fields, "tags", list(value, &[','])),
+                "lang" => set(&mut fields, "lang", value.into()),
+                "series" => set(&mut fields, "series", value.into()),
+                "draft" => set(&mut fields, "draft", Value::Bool(value != "false")),
+                _ => {}
+            }
+        } else if let Some(caps) = revision.captures(line) {
+            if !fields.contains_key("date") {
+                set(&mut fields, "date", date(caps[1].trim()).into());
+            }
+        }
+        start += 1;
+    }
+
+    let mut out: Vec<String> = Vec::new();
+    let mut block: Option<(String, Block)> = None;
+    let mut table: Option<Vec<String>> = None;
+    let mut columns = 0;
+    let mut style: Option<String> = None;
+    for &line in &lines[start..] {
+        let trimmed = line.trim_end();
+        if let Some(cells) = table.as_mut() {
+            if trimmed == "|===" {
+                out.extend(markdown_table(cells, columns.max(1)));
+                table = None;
+            } else if let Some(text) = trimmed.strip_prefix('|') {
+                let row: Vec<String> = text.split('|').map(|cell| asciidoc_inline(cell.trim())).collect();
+                if columns == 0 {
+                    columns = row.len();
+                }
+                cells.extend(row);
+            }
+            continue;
+        }
+        if let Some((fence, open)) = &block {
+            if trimmed == fence {
+                if let Some(close) = open.closing() {
+                    out.push(close.to_string());
+                }
+                block = None;
+            } else if let Some(line) = open.line(line, asciidoc_inline) {
+                out.push(line);
+            }
+            continue;
+        }
+        if trimmed == "|===" {
+            table = Some(Vec::new());
+            columns = 0;
+            style = None;
+            continue;
+        }
+        if delimiter.is_match(trimmed) {
+            let kind = match trimmed.chars().next() {
+                Some('-') | Some('.') => "src",
+                Some('_') => "quote",
+                Some('+') => "export",
+                Some('/') => "comment",
+                _ => "",
+            };
+            // `[source,rust]` above a listing names its language
+            let language = style.take().and_then(|s| s.split(',').nth(1).map(|l| l.trim().to_string()));
+            let argument = if kind == "export" { "html".to_string() } else { language.unwrap_or_default() };
+            let (opened, opening) = Block::open(kind, &argument);
+            out.extend(opening);
+            block = Some((trimmed.to_string(), opened));
+            continue;
+        }
+        if trimmed.starts_with("//") {
+            continue;
+        }
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            style = Some(trimmed.trim_start_matches('[').trim_end_matches(']').to_string());
+            continue;
+        }
+        style = None;
+        if let Some(caps) = heading.captures(trimmed) {
+            out.push(format!("{} {}", "#".repeat(caps[1].len()), asciidoc_inline(&caps[2])));
+        } else if trimmed == "'''" {
+            out.push("***".to_string());
+        } else if trimmed == "<<<" || trimmed == "+" {
+            // Page breaks and list continuations
+        } else if let Some(caps) = image.captures(trimmed) {
+            out.push(format!("![{}]({})", &caps[2], &caps[1]));
+        } else if let Some(caps) = admonition.captures(trimmed) {
+            let label = format!("{}{}", &caps[1][..1], caps[1][1..].to_lowercase());
+            out.push(format!("> **{}:** {}", label, asciidoc_inline(&caps[2])));
+        } else if let Some(caps) = title.captures(trimmed) {
+            out.push(format!("**{}**", asciidoc_inline(&caps[1])));
+            out.push(String::new());
+        } else if let Some(caps) = item.captures(trimmed) {
+            let depth = if &caps[1] == "-" { 0 } else { caps[1].len() - 1 };
+            out.push(format!("{}- {}", "  ".repeat(depth), asciidoc_inline(&caps[2])));
+        } else if let Some(caps) = numbered.captures(trimmed) {
+            out.push(format!("{}1. {}", "   ".repeat(caps[1].len() - 1), asciidoc_inline(&caps[2])));
+        } else {
+            out.push(asciidoc_inline(line));
+        }
+    }
+    if let Some(close) = block.and_then(|(_, open)| open.closing()) {
+        out.push(close.to_string());
+    }
+    (fields, finish(out))
+}
+
+/// An AsciiDoc line's macros, cross references, monospace and emphasis as Markdown.
+fn asciidoc_inline(line: &str) -> String {
+    let code = Regex::new(r"`\+?([^`]+?)\+?`").unwrap();
+    let image = Regex::new(r"image:([^\s\[:][^\s\[]*)\[([^,\]]*)[^\]]*\]").unwrap();
+    let url = Regex::new(r"\b((?:https?|ftp|mailto):[^\s\[]+)\[([^\]]*)\]").unwrap();
+    let link = Regex::new(r"\blink:([^\s\[]+)\[([^\]]*)\]").unwrap();
+    let xref = Regex::new(r"\bxref:([^\s\[]+)\[([^\]]*)\]|<<([^,>]+)(?:,\s*([^>]+))?>>").unwrap();
+    let strong = Regex::new(r"\*\*(.+?)\*\*").unwrap();
+    let italic = Regex::new(r"__(.+?)__").unwrap();
+    let mut held = Held::default();
+    let text = code.replace_all(line, |caps: &regex::Captures| held.hold(format!("`{}`", &caps[1])));
+    let text = image.replace_all(&text, |caps: &regex::Captures| held.hold(format!("![{}]({})", &caps[2], &caps[1])));
+    let text = url.replace_all(&text, |caps: &regex::Captures| {
+        let label = if caps[2].is_empty() { held.hold(caps[1].to_string()) } else { caps[2].to_string() };
+        held.hold(format!("[{}]({})", label, &caps[1]))
+    });
+    let text = link.replace_all(&text, |caps: &regex::Captures| held.hold(format!("[{}]({})", &caps[2], &caps[1])));
+    let text = xref.replace_all(&text, |caps: &regex::Captures| {
+        let (target, label) = match caps.get(1) {
+            Some(target) => (target.as_str(), caps.get(2).map(|m| m.as_str())),
+            None => (&caps[3], caps.get(4).map(|m| m.as_str())),
+        };
+        let label = label.map(str::trim).filter(|l| !l.is_empty());
+        let (document, anchor) = target.split_once('#').unwrap_or(("", target));
+        let post = document.strip_suffix(".adoc").or_else(|| anchor.strip_suffix(".adoc"));
+        held.hold(match (post, label) {
+            (Some(post), Some(label)) => format!("[[{}|{}]]", post, label),
+            (Some(post), None) => format!("[[{}]]", post),
+            (None, label) => format!("[{}](#{})", label.unwrap_or(anchor), anchor),
+        })
+    });
+    let text = strong.replace_all(&text, |caps: &regex::Captures| held.hold(format!("**{}**", &caps[1])));
+    let text = italic.replace_all(&text, |caps: &regex::Captures| held.hold(format!("*{}*", &caps[1])));
+    let text = emphasis(&text, '*', "**");
+    let text = emphasis(&text, '_', "*");
+    let text = match text.strip_suffix(" +") {
+        Some(line) => format!("{}\\", line),
+        None => text,
+    };
+    held.restore(&text)
+}
+
+/// A delimited block being converted, by what its lines become.
+enum Block {
+    /// Source code or a literal block, fenced and kept as it is
+    Code,
+    /// Quoted paragraphs
+    Quote,
+    /// HTML passed through, in a `::: raw` block
+    Raw,
+    /// Comments and output for other formats, dropped
+    Skip,
+    /// Examples, sidebars and the like, converted like the rest of the document
+    Plain,
+}
+
+impl Block {
+    /// The block opened by `#+begin_<kind> <argument>` (or its AsciiDoc delimiter), with the
+    /// Markdown line opening it, if any.
+    fn open(kind: &str, argument: &str) -> (Block, Option<String>) {
+        match kind {
+            "src" => (Block::Code, Some(format!("```{}", argument))),
+            "example" => (Block::Code, Some("```".to_string())),
+            "quote" | "verse" => (Block::Quote, None),
+            "export" if argument.eq_ignore_ascii_case("html") => (Block::Raw, Some("::: raw".to_string())),
+            "export" | "comment" => (Block::Skip, None),
+            _ => (Block::Plain, None),
+        }
+    }
+
+    /// A line inside the block as Markdown, with `inline` converting running text.
+    fn line(&self, line: &str, inline: fn(&str) -> String) -> Option<String> {
+        match self {
+            Block::Code | Block::Raw => Some(line.to_string()),
+            Block::Quote if line.trim().is_empty() => Some(">".to_string()),
+            Block::Quote => Some(format!("> {}", inline(line.trim()))),
+            Block::Skip => None,
+            Block::Plain => Some(inline(line)),
+        }
+    }
+
+    /// The Markdown line closing the block, if any.
+    fn closing(&self) -> Option<&'static str> {
+        match self {
+            Block::Code => Some("```"),
+            Block::Raw => Some(":::"),
+            _ => None,
+        }
+    }
+}
+
+/// Markdown table from AsciiDoc cells, `columns` to a row, the first row its header.
+fn markdown_table(cells: &[String], columns: usize) -> Vec<String> {
+    let mut rows: Vec<String> = cells.chunks(columns).map(|row| format!("| {} |", row.join(" | "))).collect();
+    if !rows.is_empty() {
+        rows.insert(1, format!("|{}", "---|".repeat(columns)));
+    }
+    rows
+}
+
+/// Pieces of a line already turned into Markdown, kept out of later rewrites behind placeholders.
+#[derive(Default)]
+struct Held(Vec<String>);
+
+impl Held {
+    fn hold(&mut self, markdown: String) -> String {
+        self.0.push(markdown);
+        format!("\u{E000}{}\u{E001}", self.0.len() - 1)
+    }
+
+    fn restore(&self, text: &str) -> String {
+        let placeholder = Regex::new("\u{E000}(\\d+)\u{E001}").unwrap();
+        let mut text = text.to_string();
+        // Held pieces may hold others
+        while placeholder.is_match(&text) {
+            text = placeholder
+                .replace_all(&text, |caps: &regex::Captures| caps[1].parse::<usize>().ok().and_then(|i| self.0.get(i)).cloned().unwrap_or_default())
+                .to_string();
+        }
+        text
+    }
+}
+
+/// Spans between a pair of `marker`s at word boundaries, with `with` in place of each marker:
+/// the opening one at the start or after a space or opening punctuation and before a non-space,
+/// the closing one after a non-space and before the end, a space or punctuation.
+fn emphasis(text: &str, marker: char, with: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let opens = |i: usize| {
+        (i == 0 || chars[i - 1].is_whitespace() || "([{'\"-".contains(chars[i - 1]))
+            && chars.get(i + 1).is_some_and(|c| !c.is_whitespace() && *c != marker)
+    };
+    let closes = |i: usize| {
+        i > 0 && !chars[i - 1].is_whitespace() && chars.get(i + 1).is_none_or(|c| c.is_whitespace() || ")]}'\".,;:!?-".contains(*c))
+    };
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == marker && opens(i) {
+            if let Some(end) = (i + 2..chars.len()).find(|&j| chars[j] == marker && closes(j)) {
+                out.push_str(with);
+                out.extend(&chars[i + 1..end]);
+                out.push_str(with);
+                i = end + 1;
+                continue;
+            }
+        }
+        out.push(chars[i]);
+        i += 1;
+    }
+    out
+}
+
+/// The `YYYY-MM-DD` day in an Org timestamp like `<2024-01-05 Fri>`, or `value` as it is.
+fn date(value: &str) -> String {
+    let day = Regex::new(r"\d{4}-\d{2}-\d{2}").unwrap();
+    match day.find(value) {
+        Some(day) if value.starts_with(['<', '[']) => day.as_str().to_string(),
+        _ => value.to_string(),
+    }
+}
+
+/// The non-empty items of `value` split on any of `separators`.
+fn list(value: &str, separators: &[char]) -> Value {
+    value.split(separators).map(str::trim).filter(|item| !item.is_empty()).map(|item| Value::String(item.to_string())).collect()
+}
+
+fn set(fields: &mut Map<String, Value>, key: &str, value: Value) {
+    fields.insert(key.to_string(), value);
+}
+
+/// Converted lines as a document, without the blank lines the header leaves at its start.
+fn finish(lines: Vec<String>) -> String {
+    let text = lines.join("\n");
+    format!("{}\n", text.trim_start_matches('\n').trim_end())
+}
@@ -0,0 +1,1048 @@
+use crate::config::MathMode;
+use crate::markdown::CodeFences;
+use anyhow::{Context, Result};
+use colored::*;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+/// Directory inside the output directory holding the bundled KaTeX stylesheet, script and fonts.
+pub const DIR: &str = "katex";
+
+/// Where the KaTeX download is kept between builds, relative to the project root, so cleaning
+/// the output directory doesn't mean fetching it again.
+pub const VENDOR_DIR: &str = ".scribe/katex";
+
+/// KaTeX release bundled for `math: "katex"`.
+const KATEX_VERSION: &str = "0.16.11";
+
+/// A `$…$` or `$$…$$` span taken out of a post's Markdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Math {
+    /// TeX between the delimiters
+    pub tex: String,
+    /// `$$…$$`, set on a line of its own
+    pub display: bool,
+}
+
+/// Take the math out of `markdown` (outside fenced and inline code), leaving a placeholder for
+/// each span so emphasis, smart punctuation and autolinks never see the TeX. Inline `$…$`
+/// stays on one line and needs no space inside either dollar, and no digit after the closing
+/// one, so prices like "$5 and $10" stay text; `\$` is always a literal dollar. `$$…$$` may
+/// span lines within a paragraph, and becomes a block of its own when it fills its lines.
+pub fn extract(markdown: &str) -> (String, Vec<Math>) {
+    let mut scanner = Scanner::default();
+    let mut fences = CodeFences::default();
+    for line in markdown.split_inclusive('\n') {
+        if scanner.open.is_none() && fences.is_code(line) {
+            scanner.output.push_str(line);
+            continue;
+        }
+        scanner.line(line);
+    }
+    scanner.finish()
+}
+
+/// Put each span back in place of its placeholder in rendered `html`: MathML for
+/// `MathMode::Mathml`, otherwise the escaped TeX in a `.math` element for KaTeX to typeset.
+pub fn restore(html: &str, spans: &[Math], mode: MathMode) -> String {
+    if spans.is_empty() {
+        return html.to_string();
+    }
+    let placeholder = Regex::new(r#"<(span|div) data-math="(\d+)"></(?:span|div)>"#).unwrap();
+    placeholder
+        .replace_all(html, |caps: &regex::Captures| {
+            let Some(math) = caps[2].parse::<usize>().ok().and_then(|i| spans.get(i)) else {
+                return String::new();
+            };
+            if mode == MathMode::Mathml {
+                return to_mathml(&math.tex, math.display);
+            }
+            let class = if math.display { "math-display" } else { "math-inline" };
+            format!("<{0} class=\"math {1}\">{2}</{0}>", &caps[1], class, escape(&math.tex))
+        })
+        .to_string()
+}
+
+/// TeX converted to a MathML `<math>` element, with the source kept as an annotation for
+/// copying. Only the subset listed under Math in the README is covered, the TeX blog posts
+/// commonly use; anything else shows as an error in place, and `math: "katex"` is the way to
+/// full TeX.
+pub fn to_mathml(tex: &str, display: bool) -> String {
+    let mut parser = TexParser { chars: tex.chars().collect(), pos: 0, display, variant: Variant::Normal };
+    let mut row = String::new();
+    // Stray row and column separators outside an environment are dropped
+    loop {
+        row.push_str(&parser.row());
+        if !parser.skip_separator() {
+            if parser.peek().is_none() {
+                break;
+            }
+            row.push_str(&parser.stray());
+        }
+    }
+    format!(
+        "<math{}><semantics><mrow>{}</mrow><annotation encoding=\"application/x-tex\">{}</annotation></semantics></math>",
+        if display { " display=\"block\"" } else { "" },
+        row,
+        escape(tex.trim())
+    )
+}
+
+/// Download KaTeX's stylesheet, script and woff2 fonts into [`VENDOR_DIR`] unless already there,
+/// then copy them into `output_dir/katex`, so pages typeset math without a CDN and keep working
+/// from IPFS.
+pub async fn bundle(output_dir: &Path, offline: bool) -> Result<()> {
+    let vendor = Path::new(VENDOR_DIR);
+    // The stylesheet is written last, so it marks a complete download
+    if !vendor.join("katex.min.css").exists() {
+        if offline {
+            anyhow::bail!("KaTeX not downloaded yet; build once without --offline");
+        }
+        download(vendor).await?;
+    }
+    for file in files() {
+        let bytes = fs::read(vendor.join(&file)).context(format!("Failed to read {}/{}", VENDOR_DIR, file))?;
+        let target = output_dir.join(DIR).join(&file);
+        // Left alone when unchanged, so rebuilds don't touch their mtimes
+        if fs::read(&target).ok().as_deref() != Some(bytes.as_slice()) {
+            fs::create_dir_all(target.parent().unwrap_or(output_dir)).context("Failed to create katex directory")?;
+            write(&target, &bytes)?;
+        }
+    }
+    Ok(())
+}
+
+/// Files of the KaTeX release, relative to its `dist/`: the stylesheet, the script and the fonts
+/// the downloaded stylesheet uses.
+pub fn files() -> Vec<String> {
+    let css = fs::read_to_string(Path::new(VENDOR_DIR).join("katex.min.css")).unwrap_or_default();
+    let mut files = vec!["katex.min.css".to_string(), "katex.min.js".to_string()];
+    files.extend(fonts(&css).into_iter().map(str::to_string));
+    files
+}
+
+/// Where `file` of the KaTeX release is downloaded from.
+pub fn url(file: &str) -> String {
+    format!("https://cdn.jsdelivr.net/npm/katex@{}/dist/{}", KATEX_VERSION, file)
+}
+
+fn fonts(css: &str) -> BTreeSet<&str> {
+    let woff2 = Regex::new(r"url\((fonts/[^)]+\.woff2)\)").unwrap();
+    woff2.captures_iter(css).map(|caps| caps.get(1).unwrap().as_str()).collect()
+}
+
+async fn download(vendor: &Path) -> Result<()> {
+    fs::create_dir_all(vendor.join("fonts")).context(format!("Failed to create {}", VENDOR_DIR))?;
+    let client = reqwest::Client::new();
+    let css = String::from_utf8_lossy(&fetch(&client, "katex.min.css").await?).to_string();
+    let fonts = fonts(&css);
+    for font in &fonts {
+        write(&vendor.join(font), &fetch(&client, font).await?)?;
+    }
+    write(&vendor.join("katex.min.js"), &fetch(&client, "katex.min.js").await?)?;
+    write(&vendor.join("katex.min.css"), css.as_bytes())?;
+    info!("{} KaTeX {} with {} fonts into {}", "Downloaded".green(), KATEX_VERSION, fonts.len(), VENDOR_DIR);
+    Ok(())
+}
+
+async fn fetch(client: &reqwest::Client, file: &str) -> Result<Vec<u8>> {
+    let url = url(file);
+    let bytes = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .context(format!("Failed to fetch {}", url))?
+        .bytes()
+        .await?;
+    Ok(bytes.to_vec())
+}
+
+fn write(path: &Path, bytes: &[u8]) -> Result<()> {
+    fs::write(path, bytes).context(format!("Failed to write {}", path.display()))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Markdown with math replaced by placeholders, built a line at a time.
+#[derive(Default)]
+struct Scanner {
+    output: String,
+    spans: Vec<Math>,
+    /// `$$` display math not closed on its line yet
+    open: Option<OpenDisplay>,
+}
+
+struct OpenDisplay {
+    tex: String,
+    /// Only whitespace came before the `$$` on its line
+    block: bool,
+}
+
+impl Scanner {
+    fn line(&mut self, line: &str) {
+        let Some(open) = self.open.as_mut() else {
+            self.scan(line, true);
+            return;
+        };
+        match line.find("$$") {
+            Some(end) => {
+                open.tex.push_str(&line[..end]);
+                let open = self.open.take().unwrap();
+                let after = &line[end + 2..];
+                if open.block && after.trim().is_empty() {
+                    self.push_block(&open.tex);
+                } else {
+                    self.push_inline(&open.tex, true);
+                    self.scan(after, false);
+                }
+            }
+            // A blank line ends the paragraph, so the `$$` was never closed
+            None if line.trim().is_empty() => {
+                let open = self.open.take().unwrap();
+                self.output.push_str("$$");
+                self.output.push_str(&open.tex);
+                self.output.push_str(line);
+            }
+            None => open.tex.push_str(line),
+        }
+    }
+
+    /// Copy `text` to the output, replacing the math in it. `line_start` is whether `text`
+    /// begins its line.
+    fn scan(&mut self, text: &str, line_start: bool) {
+        // Start of the text not copied to the output yet
+        let mut copied = 0;
+        let mut i = 0;
+        while i < text.len() {
+            let rest = &text[i..];
+            if let Some(escaped) = rest.strip_prefix('\\') {
+                i += 1 + escaped.chars().next().map_or(0, char::len_utf8);
+            } else if rest.starts_with('`') {
+                let run = rest.len() - rest.trim_start_matches('`').len();
+                i += run + closing_backticks(&rest[run..], run).map_or(0, |end| end + run);
+            } else if let Some(display) = rest.strip_prefix("$$") {
+                self.output.push_str(&text[copied..i]);
+                let block = line_start && text[..i].trim().is_empty();
+                match display.find("$$") {
+                    Some(end) if block && display[end + 2..].trim().is_empty() => {
+                        self.push_block(&display[..end]);
+                        return;
+                    }
+                    Some(end) => {
+                        self.push_inline(&display[..end], true);
+                        i += end + 4;
+                        copied = i;
+                    }
+                    None => {
+                        self.open = Some(OpenDisplay { tex: display.to_string(), block });
+                        return;
+                    }
+                }
+            } else if let Some(inline) = rest.strip_prefix('$') {
+                match inline_end(inline) {
+                    Some(end) => {
+                        self.output.push_str(&text[copied..i]);
+                        self.push_inline(&inline[..end], false);
+                        i += end + 2;
+                        copied = i;
+                    }
+                    None => i += 1,
+                }
+            } else {
+                i += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        self.output.push_str(&text[copied..]);
+    }
+
+    fn push_inline(&mut self, tex: &str, display: bool) {
+        self.output.push_str(&format!("<span data-math=\"{}\"></span>", self.spans.len()));
+        self.spans.push(Math { tex: tex.trim().to_string(), display });
+    }
+
+    /// An HTML block for display math filling its lines, with a blank line after it so the
+    /// next line starts a new paragraph rather than joining the block.
+    fn push_block(&mut self, tex: &str) {
+        self.output.push_str(&format!("<div data-math=\"{}\"></div>\n\n", self.spans.len()));
+        self.spans.push(Math { tex: tex.trim().to_string(), display: true });
+    }
+
+    fn finish(mut self) -> (String, Vec<Math>) {
+        if let Some(open) = self.open.take() {
+            self.output.push_str("$$");
+            self.output.push_str(&open.tex);
+        }
+        (self.output, self.spans)
+    }
+}
+
+/// Offset in `text` of a run of exactly `run` backticks, closing a code span.
+fn closing_backticks(text: &str, run: usize) -> Option<usize> {
+    let mut i = 0;
+    while let Some(start) = text[i..].find('`') {
+        let start = i + start;
+        let len = text[start..].len() - text[start..].trim_start_matches('`').len();
+        if len == run {
+            return Some(start);
+        }
+        i = start + len;
+    }
+    None
+}
+
+/// Offset in `text`, just after an opening `$`, of the dollar closing inline math.
+fn inline_end(text: &str) -> Option<usize> {
+    if text.is_empty() || text.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut chars = text.char_indices();
+    let mut prev = ' ';
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+                prev = '\\';
+            }
+            '\n' => return None,
+            '$' if !prev.is_whitespace() && !text[i + 1..].starts_with(|n: char| n.is_ascii_digit()) => {
+                return Some(i);
+            }
+            _ => prev = c,
+        }
+    }
+    None
+}
+
+/// Letter style set by `\mathbf`, `\mathbb` and the other font commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Variant {
+    Normal,
+    Roman,
+    Bold,
+    DoubleStruck,
+    Script,
+    Fraktur,
+}
+
+impl Variant {
+    fn of(command: &str) -> Option<Self> {
+        Some(match command {
+            "mathrm" | "rm" | "mathup" => Variant::Roman,
+            "mathit" | "mathnormal" => Variant::Normal,
+            "mathbf" | "bf" | "boldsymbol" | "bm" => Variant::Bold,
+            "mathbb" | "Bbb" => Variant::DoubleStruck,
+            "mathcal" | "mathscr" => Variant::Script,
+            "mathfrak" => Variant::Fraktur,
+            _ => return None,
+        })
+    }
+
+    /// `c` in this style, from the Mathematical Alphanumeric Symbols block where it has one.
+    fn style(self, c: char) -> char {
+        // Letters the block leaves out because older code points already had them
+        let exception = match (self, c) {
+            (Variant::DoubleStruck, 'C') => Some('ℂ'),
+            (Variant::DoubleStruck, 'H') => Some('ℍ'),
+            (Variant::DoubleStruck, 'N') => Some('ℕ'),
+            (Variant::DoubleStruck, 'P') => Some('ℙ'),
+            (Variant::DoubleStruck, 'Q') => Some('ℚ'),
+            (Variant::DoubleStruck, 'R') => Some('ℝ'),
+            (Variant::DoubleStruck, 'Z') => Some('ℤ'),
+            (Variant::Script, 'B') => Some('ℬ'),
+            (Variant::Script, 'E') => Some('ℰ'),
+            (Variant::Script, 'F') => Some('ℱ'),
+            (Variant::Script, 'H') => Some('ℋ'),
+            (Variant::Script, 'I') => Some('ℐ'),
+            (Variant::Script, 'L') => Some('ℒ'),
+            (Variant::Script, 'M') => Some('ℳ'),
+            (Variant::Script, 'R') => Some('ℛ'),
+            (Variant::Script, 'e') => Some('ℯ'),
+            (Variant::Script, 'g') => Some('ℊ'),
+            (Variant::Script, 'o') => Some('ℴ'),
+            (Variant::Fraktur, 'C') => Some('ℭ'),
+            (Variant::Fraktur, 'H') => Some('ℌ'),
+            (Variant::Fraktur, 'I') => Some('ℑ'),
+            (Variant::Fraktur, 'R') => Some('ℜ'),
+            (Variant::Fraktur, 'Z') => Some('ℨ'),
+            _ => None,
+        };
+        if let Some(styled) = exception {
+            return styled;
+        }
+        // First capital, small letter and digit of each style
+        let (upper, lower, digit) = match self {
+            Variant::Bold => (0x1D400, 0x1D41A, Some(0x1D7CE)),
+            Variant::DoubleStruck => (0x1D538, 0x1D552, Some(0x1D7D8)),
+            Variant::Script => (0x1D49C, 0x1D4B6, None),
+            Variant::Fraktur => (0x1D504, 0x1D51E, None),
+            Variant::Normal | Variant::Roman => return c,
+        };
+        let code = match c {
+            'A'..='Z' => upper + (c as u32 - 'A' as u32),
+            'a'..='z' => lower + (c as u32 - 'a' as u32),
+            '0'..='9' => match digit {
+                Some(zero) => zero + (c as u32 - '0' as u32),
+                None => return c,
+            },
+            _ => return c,
+        };
+        char::from_u32(code).unwrap_or(c)
+    }
+}
+
+/// One parsed piece of a formula: its MathML, and how scripts attach to it.
+struct Atom {
+    mathml: String,
+    /// Scripts go above and below (`\sum`, `\lim`) rather than to the side, in display math
+    limits: bool,
+    /// Space after a function name like `\sin`, placed after its scripts
+    space_after: bool,
+}
+
+impl Atom {
+    fn new(mathml: String) -> Self {
+        Atom { mathml, limits: false, space_after: false }
+    }
+}
+
+/// Recursive-descent TeX to MathML conversion over the formula's characters.
+struct TexParser {
+    chars: Vec<char>,
+    pos: usize,
+    display: bool,
+    variant: Variant,
+}
+
+impl TexParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// The command name at the current position, if it is a `\command`, without consuming it.
+    fn peek_command(&self) -> Option<String> {
+        if self.peek() != Some('\\') {
+            return None;
+        }
+        let name: String = self.chars[self.pos + 1..].iter().take_while(|c| c.is_ascii_alphabetic()).collect();
+        if name.is_empty() {
+            self.chars.get(self.pos + 1).map(|c| c.to_string())
+        } else {
+            Some(name)
+        }
+    }
+
+    fn read_command(&mut self) -> String {
+        let name = self.peek_command().unwrap_or_default();
+        self.pos += 1 + name.chars().count();
+        name
+    }
+
+    /// Consume a `&` or `\\` separating cells or rows, returning whether there was one.
+    fn skip_separator(&mut self) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some('&') {
+            self.pos += 1;
+            return true;
+        }
+        if self.peek_command().as_deref() == Some("\\") {
+            self.pos += 2;
+            self.skip_bracketed();
+            return true;
+        }
+        false
+    }
+
+    /// Skip an optional `[…]` argument, e.g. the spacing after `\\`.
+    fn skip_bracketed(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        if self.peek() != Some('[') {
+            return None;
+        }
+        let start = self.pos + 1;
+        let end = self.chars[start..].iter().position(|&c| c == ']').map_or(self.chars.len(), |i| start + i);
+        self.pos = (end + 1).min(self.chars.len());
+        Some(self.chars[start..end].iter().collect())
+    }
+
+    /// A `{…}` group's source, unparsed, for `\text` and environment names.
+    fn raw_group(&mut self) -> String {
+        self.skip_whitespace();
+        if self.peek() != Some('{') {
+            return self.peek().map(|c| {
+                self.pos += 1;
+                c.to_string()
+            }).unwrap_or_default();
+        }
+        let mut depth = 0;
+        let start = self.pos + 1;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return self.chars[start..self.pos - 1].iter().collect();
+                    }
+                }
+                '\\' => self.pos += 1,
+                _ => {}
+            }
+        }
+        self.chars[start.min(self.chars.len())..].iter().collect()
+    }
+
+    /// Atoms up to the end of the formula or group, a cell or row separator, `\right` or `\end`.
+    fn row(&mut self) -> String {
+        let mut row = String::new();
+        while let Some(atom) = self.scripted() {
+            row.push_str(&atom);
+        }
+        row
+    }
+
+    /// An atom with any `^`, `_` and `'` scripts attached.
+    fn scripted(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        let base = match self.peek() {
+            // A script with nothing before it, as in `{}^{14}C`
+            Some('^') | Some('_') => Atom::new("<mrow></mrow>".to_string()),
+            _ => self.atom()?,
+        };
+        let (mut sub, mut sup) = (None, Vec::new());
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('\'') => {
+                    self.pos += 1;
+                    sup.push("<mo>′</mo>".to_string());
+                }
+                Some('^') => {
+                    self.pos += 1;
+                    sup.push(self.argument());
+                }
+                Some('_') => {
+                    self.pos += 1;
+                    sub = Some(self.argument());
+                }
+                _ => {
+                    if self.peek_command().as_deref().is_some_and(|c| c == "limits" || c == "nolimits") {
+                        self.read_command();
+                        continue;
+                    }
+                    break;
+                }
+            }
+        }
+        // Primes and a superscript share the one superscript
+        let sup = match sup.len() {
+            0 => None,
+            1 => sup.pop(),
+            _ => Some(format!("<mrow>{}</mrow>", sup.concat())),
+        };
+        let (under, over) = if base.limits && self.display { ("munder", "mover") } else { ("msub", "msup") };
+        let mut mathml = match (sub, sup) {
+            (None, None) => base.mathml,
+            (Some(sub), None) => format!("<{0}>{1}{2}</{0}>", under, base.mathml, sub),
+            (None, Some(sup)) => format!("<{0}>{1}{2}</{0}>", over, base.mathml, sup),
+            (Some(sub), Some(sup)) => {
+                let both = if base.limits && self.display { "munderover" } else { "msubsup" };
+                format!("<{0}>{1}{2}{3}</{0}>", both, base.mathml, sub, sup)
+            }
+        };
+        if base.space_after {
+            mathml.push_str("<mspace width=\"0.1667em\"/>");
+        }
+        Some(mathml)
+    }
+
+    /// A single atom as a command or script argument, as an `<mrow>` when it is a group.
+    fn argument(&mut self) -> String {
+        self.skip_whitespace();
+        self.atom().map(|a| a.mathml).unwrap_or_else(|| "<mrow></mrow>".to_string())
+    }
+
+    fn atom(&mut self) -> Option<Atom> {
+        self.skip_whitespace();
+        let c = self.peek()?;
+        match c {
+            '}' | '&' => None,
+            '{' => {
+                self.pos += 1;
+                let row = self.row();
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                }
+                Some(Atom::new(format!("<mrow>{}</mrow>", row)))
+            }
+            '\\' => self.command(),
+            '0'..='9' | '.' => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+                let number: String = self.chars[start..self.pos].iter().map(|&c| self.variant.style(c)).collect();
+                Some(Atom::new(format!("<mn>{}</mn>", number)))
+            }
+            '~' => {
+                self.pos += 1;
+                Some(Atom::new("<mspace width=\"0.3333em\"/>".to_string()))
+            }
+            c if c.is_alphabetic() => {
+                self.pos += 1;
+                Some(Atom::new(self.identifier(c)))
+            }
+            c => {
+                self.pos += 1;
+                let op = match c {
+                    '-' => "−".to_string(),
+                    '*' => "∗".to_string(),
+                    c => escape(&c.to_string()),
+                };
+                Some(Atom::new(format!("<mo>{}</mo>", op)))
+            }
+        }
+    }
+
+    fn identifier(&self, c: char) -> String {
+        match self.variant {
+            Variant::Roman => format!("<mi mathvariant=\"normal\">{}</mi>", c),
+            variant => format!("<mi>{}</mi>", variant.style(c)),
+        }
+    }
+
+    fn command(&mut self) -> Option<Atom> {
+        let name = self.peek_command()?;
+        if matches!(name.as_str(), "right" | "end" | "\\") {
+            return None;
+        }
+        self.read_command();
+        if let Some(letter) = greek(&name) {
+            return Some(Atom::new(if letter.is_uppercase() {
+                format!("<mi mathvariant=\"normal\">{}</mi>", letter)
+            } else {
+                format!("<mi>{}</mi>", letter)
+            }));
+        }
+        if let Some(symbol) = identifier_symbol(&name) {
+            return Some(Atom::new(format!("<mi>{}</mi>", symbol)));
+        }
+        if let Some(op) = operator_symbol(&name) {
+            return Some(Atom::new(format!("<mo>{}</mo>", escape(op))));
+        }
+        if let Some((op, limits)) = large_operator(&name) {
+            return Some(Atom { mathml: format!("<mo>{}</mo>", op), limits, space_after: false });
+        }
+        if let Some((function, limits)) = function(&name) {
+            return Some(Atom { mathml: format!("<mi>{}</mi>", function), limits, space_after: true });
+        }
+        if let Some(variant) = Variant::of(&name) {
+            let outer = std::mem::replace(&mut self.variant, variant);
+            let argument = self.argument();
+            self.variant = outer;
+            return Some(Atom::new(argument));
+        }
+        if let Some(accent) = accent(&name) {
+            let base = self.argument();
+            let (element, stretchy) = match name.as_str() {
+                "underline" => ("munder", " stretchy=\"true\""),
+                "overline" | "widehat" | "widetilde" | "overrightarrow" => ("mover", " stretchy=\"true\""),
+                _ => ("mover", ""),
+            };
+            let accent_attr = if element == "munder" { "accentunder" } else { "accent" };
+            return Some(Atom::new(format!("<{0} {1}=\"true\">{2}<mo{3}>{4}</mo></{0}>", element, accent_attr, base, stretchy, accent)));
+        }
+        if let Some(width) = space(&name) {
+            return Some(Atom::new(format!("<mspace width=\"{}\"/>", width)));
+        }
+        let mathml = match name.as_str() {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let numerator = self.argument();
+                let denominator = self.argument();
+                format!("<mfrac>{}{}</mfrac>", numerator, denominator)
+            }
+            "binom" | "dbinom" | "tbinom" => {
+                let n = self.argument();
+                let k = self.argument();
+                format!("<mrow><mo>(</mo><mfrac linethickness=\"0\">{}{}</mfrac><mo>)</mo></mrow>", n, k)
+            }
+            "sqrt" => match self.skip_bracketed() {
+                Some(index) => {
+                    let radicand = self.argument();
+                    let mut index_parser = TexParser { chars: index.chars().collect(), pos: 0, display: false, variant: self.variant };
+                    format!("<mroot>{}<mrow>{}</mrow></mroot>", radicand, index_parser.row())
+                }
+                None => format!("<msqrt>{}</msqrt>", self.argument()),
+            },
+            "text" | "textrm" | "textup" | "textnormal" | "mbox" | "textit" | "textbf" | "textsf" | "texttt" => {
+                let text = self.raw_group();
+                // Spaces at the edges of a text run would otherwise collapse
+                let text = text.replace(' ', "\u{a0}");
+                format!("<mtext>{}</mtext>", escape(&text))
+            }
+            "operatorname" => {
+                let text = self.raw_group();
+                return Some(Atom { mathml: format!("<mi>{}</mi>", escape(&text)), limits: false, space_after: true });
+            }
+            "left" => {
+                let open = self.delimiter();
+                let row = self.row();
+                let close = if self.peek_command().as_deref() == Some("right") {
+                    self.read_command();
+                    self.delimiter()
+                } else {
+                    String::new()
+                };
+                format!(
+                    "<mrow><mo fence=\"true\" form=\"prefix\">{}</mo>{}<mo fence=\"true\" form=\"postfix\">{}</mo></mrow>",
+                    open, row, close
+                )
+            }
+            "begin" => {
+                let environment = self.raw_group();
+                self.environment(&environment)
+            }
+            "label" | "tag" => {
+                self.raw_group();
+                String::new()
+            }
+            "displaystyle" | "textstyle" | "nonumber" | "notag" => String::new(),
+            c if c.len() == 1 && !c.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                // `\{`, `\%`, `\$` and friends are the character itself
+                format!("<mo>{}</mo>", escape(c))
+            }
+            _ => format!("<merror><mtext>\\{}</mtext></merror>", escape(&name)),
+        };
+        Some(Atom::new(mathml))
+    }
+
+    /// What to make of a `}`, `\right` or `\end` a row stopped at outside the group,
+    /// `\left` or environment it belongs to, consuming it.
+    fn stray(&mut self) -> String {
+        match self.peek_command().as_deref() {
+            Some("end") => {
+                self.read_command();
+                self.raw_group();
+                String::new()
+            }
+            Some(_) => {
+                self.read_command();
+                format!("<mo>{}</mo>", self.delimiter())
+            }
+            None => {
+                self.pos += 1;
+                String::new()
+            }
+        }
+    }
+
+    /// The delimiter after `\left` or `\right`; `.` is none.
+    fn delimiter(&mut self) -> String {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('\\') => {
+                let name = self.read_command();
+                match name.as_str() {
+                    "{" | "lbrace" => "{".to_string(),
+                    "}" | "rbrace" => "}".to_string(),
+                    "|" | "Vert" | "lVert" | "rVert" => "‖".to_string(),
+                    name => operator_symbol(name).map(escape).unwrap_or_default(),
+                }
+            }
+            Some('.') => {
+                self.pos += 1;
+                String::new()
+            }
+            Some(c) => {
+                self.pos += 1;
+                escape(&c.to_string())
+            }
+            None => String::new(),
+        }
+    }
+
+    /// The body of `\begin{environment}` up to its `\end`, as a table.
+    fn environment(&mut self, environment: &str) -> String {
+        let name = environment.trim_end_matches('*');
+        if name == "array" {
+            // Column spec
+            self.raw_group();
+        }
+        let mut rows: Vec<Vec<String>> = vec![Vec::new()];
+        loop {
+            let cell = self.row();
+            rows.last_mut().unwrap().push(cell);
+            self.skip_whitespace();
+            match self.peek() {
+                Some('&') => self.pos += 1,
+                Some('\\') if self.peek_command().as_deref() == Some("\\") => {
+                    self.pos += 2;
+                    self.skip_bracketed();
+                    rows.push(Vec::new());
+                }
+                Some('\\') if self.peek_command().as_deref() == Some("end") => {
+                    self.read_command();
+                    self.raw_group();
+                    break;
+                }
+                Some(_) => {
+                    let stray = self.stray();
+                    rows.last_mut().unwrap().last_mut().unwrap().push_str(&stray);
+                }
+                None => break,
+            }
+        }
+        // A trailing `\\` leaves an empty last row
+        if rows.len() > 1 && rows.last().is_some_and(|row| row.iter().all(String::is_empty)) {
+            rows.pop();
+        }
+        if matches!(name, "equation" | "gather" | "gathered") && rows.iter().all(|row| row.len() == 1) && rows.len() == 1 {
+            return format!("<mrow>{}</mrow>", rows[0][0]);
+        }
+        let columnalign = match name {
+            "cases" => " columnalign=\"left left\"",
+            "aligned" | "align" | "split" => " columnalign=\"right left right left right left\"",
+            _ => "",
+        };
+        let displaystyle = if matches!(name, "aligned" | "align" | "split" | "gather" | "gathered" | "equation") {
+            " displaystyle=\"true\""
+        } else {
+            ""
+        };
+        let table: String = rows
+            .iter()
+            .map(|row| format!("<mtr>{}</mtr>", row.iter().map(|cell| format!("<mtd>{}</mtd>", cell)).collect::<String>()))
+            .collect();
+        let table = format!("<mtable{}{}>{}</mtable>", columnalign, displaystyle, table);
+        let (open, close) = match name {
+            "pmatrix" => ("(", ")"),
+            "bmatrix" => ("[", "]"),
+            "vmatrix" => ("|", "|"),
+            "cases" => ("{", ""),
+            _ => return table,
+        };
+        format!(
+            "<mrow><mo fence=\"true\" form=\"prefix\">{}</mo>{}<mo fence=\"true\" form=\"postfix\">{}</mo></mrow>",
+            open, table, close
+        )
+    }
+}
+
+fn greek(name: &str) -> Option<char> {
+    Some(match name {
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ϵ',
+        "varepsilon" => 'ε',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "vartheta" => 'ϑ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "omicron" => 'ο',
+        "pi" => 'π',
+        "varpi" => 'ϖ',
+        "rho" => 'ρ',
+        "varrho" => 'ϱ',
+        "sigma" => 'σ',
+        "varsigma" => 'ς',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "phi" => 'ϕ',
+        "varphi" => 'φ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Theta" => 'Θ',
+        "Lambda" => 'Λ',
+        "Xi" => 'Ξ',
+        "Pi" => 'Π',
+        "Sigma" => 'Σ',
+        "Upsilon" => 'Υ',
+        "Phi" => 'Φ',
+        "Psi" => 'Ψ',
+        "Omega" => 'Ω',
+        _ => return None,
+    })
+}
+
+/// Symbols set as identifiers, upright or italic like letters.
+fn identifier_symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "emptyset" | "varnothing" => "∅",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "aleph" => "ℵ",
+        "angle" => "∠",
+        "prime" => "′",
+        _ => return None,
+    })
+}
+
+/// Binary operators, relations, arrows, delimiters and dots.
+fn operator_symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "pm" => "±",
+        "mp" => "∓",
+        "times" => "×",
+        "div" => "÷",
+        "cdot" => "⋅",
+        "ast" => "∗",
+        "star" => "⋆",
+        "circ" => "∘",
+        "bullet" => "∙",
+        "oplus" => "⊕",
+        "otimes" => "⊗",
+        "cap" => "∩",
+        "cup" => "∪",
+        "vee" | "lor" => "∨",
+        "wedge" | "land" => "∧",
+        "setminus" => "∖",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "ll" => "≪",
+        "gg" => "≫",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "approx" => "≈",
+        "cong" => "≅",
+        "equiv" => "≡",
+        "propto" => "∝",
+        "subset" => "⊂",
+        "supset" => "⊃",
+        "subseteq" => "⊆",
+        "supseteq" => "⊇",
+        "in" => "∈",
+        "ni" => "∋",
+        "notin" => "∉",
+        "mid" => "∣",
+        "parallel" => "∥",
+        "perp" => "⊥",
+        "leftarrow" | "gets" => "←",
+        "rightarrow" | "to" => "→",
+        "leftrightarrow" => "↔",
+        "Leftarrow" => "⇐",
+        "Rightarrow" => "⇒",
+        "Leftrightarrow" => "⇔",
+        "implies" | "Longrightarrow" => "⟹",
+        "impliedby" | "Longleftarrow" => "⟸",
+        "iff" | "Longleftrightarrow" => "⟺",
+        "longrightarrow" => "⟶",
+        "longleftarrow" => "⟵",
+        "mapsto" => "↦",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+        "forall" => "∀",
+        "exists" => "∃",
+        "neg" | "lnot" => "¬",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "vert" | "lvert" | "rvert" => "|",
+        "Vert" | "lVert" | "rVert" => "‖",
+        "lbrace" => "{",
+        "rbrace" => "}",
+        "backslash" => "\\",
+        "colon" => ":",
+        "ldots" | "dots" | "dotsc" => "…",
+        "cdots" | "dotsb" => "⋯",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        _ => return None,
+    })
+}
+
+/// Sums, products and integrals, and whether their scripts become limits in display math.
+fn large_operator(name: &str) -> Option<(&'static str, bool)> {
+    Some(match name {
+        "sum" => ("∑", true),
+        "prod" => ("∏", true),
+        "bigcup" => ("⋃", true),
+        "bigcap" => ("⋂", true),
+        "int" => ("∫", false),
+        "iint" => ("∬", false),
+        "oint" => ("∮", false),
+        _ => return None,
+    })
+}
+
+/// Function names set upright whose scripts stay to the side.
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "sinh", "cosh", "tanh", "arcsin", "arccos", "arctan", "log", "ln", "exp", "arg",
+    "dim", "ker",
+];
+
+/// Function names set upright, and whether their scripts become limits in display math.
+fn function(name: &str) -> Option<(&'static str, bool)> {
+    Some(match name {
+        "lim" => ("lim", true),
+        "max" => ("max", true),
+        "min" => ("min", true),
+        "sup" => ("sup", true),
+        "inf" => ("inf", true),
+        "det" => ("det", true),
+        "gcd" => ("gcd", true),
+        "argmax" => ("arg max", true),
+        "argmin" => ("arg min", true),
+        _ => return FUNCTIONS.iter().find(|f| **f == name).map(|f| (*f, false)),
+    })
+}
+
+/// The mark placed over (or under) the argument of an accent command.
+fn accent(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "hat" | "widehat" => "^",
+        "tilde" | "widetilde" => "~",
+        "dot" => "˙",
+        "ddot" => "¨",
+        "bar" => "¯",
+        "vec" | "overrightarrow" => "→",
+        "overline" => "‾",
+        "underline" => "_",
+        _ => return None,
+    })
+}
+
+/// Widths of the spacing commands.
+fn space(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "," | "thinspace" => "0.1667em",
+        ":" | ">" | "medspace" => "0.2222em",
+        ";" | "thickspace" => "0.2778em",
+        " " => "0.25em",
+        "quad" => "1em",
+        "qquad" => "2em",
+        "!" | "negthinspace" => "0em",
+        _ => return None,
+    })
+}
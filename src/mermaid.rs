@@ -0,0 +1,158 @@
+use crate::config::Mermaid;
+use crate::generator::Post;
+use crate::progress::Progress;
+use anyhow::{bail, Context, Result};
+use colored::*;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+/// Directory inside the output directory holding the bundled mermaid.js runtime.
+pub const DIR: &str = "mermaid";
+
+/// SVGs rendered by `mode: "svg"`, named by a hash of the diagram source and theme.
+pub const CACHE_DIR: &str = ".scribe/mermaid";
+
+/// Where the mermaid.js download is kept between builds, relative to the project root, so
+/// cleaning the output directory doesn't mean fetching it again.
+pub const VENDOR_PATH: &str = ".scribe/mermaid/mermaid.min.js";
+
+/// mermaid.js release bundled for `mode: "script"`.
+const MERMAID_VERSION: &str = "11.4.1";
+
+/// Turn ```` ```mermaid ```` code blocks in rendered `html` into `<pre class="mermaid">`
+/// diagrams, still holding their escaped source, which is what mermaid.js looks for.
+pub fn mark(html: &str) -> String {
+    let code_block = Regex::new(r#"(?s)<pre><code class="language-mermaid">(.*?)</code></pre>"#).unwrap();
+    code_block.replace_all(html, r#"<pre class="mermaid">$1</pre>"#).to_string()
+}
+
+/// Source of each diagram in `html`, unescaped.
+pub fn sources(html: &str) -> Vec<String> {
+    diagram().captures_iter(html).map(|caps| unescape(&caps[1])).collect()
+}
+
+/// Replace each diagram in `html` with its rendered SVG from the cache. Diagrams that haven't
+/// been rendered keep showing their source.
+pub fn inline(html: &str, options: &Mermaid) -> String {
+    diagram()
+        .replace_all(html, |caps: &regex::Captures| {
+            match fs::read_to_string(cache_path(&unescape(&caps[1]), options)) {
+                Ok(svg) => format!("<figure class=\"mermaid-diagram\">{}</figure>", svg.trim()),
+                Err(_) => caps[0].to_string(),
+            }
+        })
+        .to_string()
+}
+
+/// Render the diagrams in `posts` that have no cached SVG yet with `options.command`,
+/// returning how many were rendered. Failures are logged and leave the diagram as source.
+pub fn render_missing(posts: &[&Post], options: &Mermaid) -> usize {
+    let missing: BTreeSet<String> = posts
+        .iter()
+        .flat_map(|post| sources(&post.html_content))
+        .filter(|source| !cache_path(source, options).exists())
+        .collect();
+    let rendered = Progress::new(missing.len() as u64, "Diagramming");
+    let mut count = 0;
+    for source in &missing {
+        let target = cache_path(source, options);
+        let first_line = source.lines().next().unwrap_or_default().trim();
+        match render(source, options, &target) {
+            Ok(()) => {
+                count += 1;
+                rendered.inc_or_log(format!("{} {}", "Rendered diagram".green(), first_line));
+            }
+            Err(e) => warn!("{}", format!("Warning: diagram '{}' not rendered: {:#}", first_line, e).yellow()),
+        }
+    }
+    count
+}
+
+/// Download mermaid.js into [`VENDOR_PATH`] unless already there, then copy it into
+/// `output_dir/mermaid`, so diagrams draw without a CDN and keep working from IPFS.
+pub async fn bundle(output_dir: &Path, offline: bool) -> Result<()> {
+    let vendor = Path::new(VENDOR_PATH);
+    if !vendor.exists() {
+        if offline {
+            bail!("mermaid.js not downloaded yet; build once without --offline");
+        }
+        let url = url();
+        let bytes = reqwest::get(&url)
+            .await
+            .and_then(|r| r.error_for_status())
+            .context(format!("Failed to fetch {}", url))?
+            .bytes()
+            .await?;
+        fs::create_dir_all(CACHE_DIR).context(format!("Failed to create {}", CACHE_DIR))?;
+        fs::write(vendor, bytes).context(format!("Failed to write {}", VENDOR_PATH))?;
+        info!("{} mermaid.js {} into {}", "Downloaded".green(), MERMAID_VERSION, VENDOR_PATH);
+    }
+    let bytes = fs::read(vendor).context(format!("Failed to read {}", VENDOR_PATH))?;
+    let target = output_dir.join(DIR).join("mermaid.min.js");
+    // Left alone when unchanged, so rebuilds don't touch its mtime
+    if fs::read(&target).ok().as_deref() != Some(bytes.as_slice()) {
+        fs::create_dir_all(output_dir.join(DIR)).context("Failed to create mermaid directory")?;
+        fs::write(&target, bytes).context(format!("Failed to write {}", target.display()))?;
+    }
+    Ok(())
+}
+
+/// Where mermaid.js is downloaded from.
+pub fn url() -> String {
+    format!("https://cdn.jsdelivr.net/npm/mermaid@{}/dist/mermaid.min.js", MERMAID_VERSION)
+}
+
+fn diagram() -> Regex {
+    Regex::new(r#"(?s)<pre class="mermaid">(.*?)</pre>"#).unwrap()
+}
+
+fn hash(source: &str, options: &Mermaid) -> String {
+    let digest = Sha256::digest(format!("{}\n{}", options.theme, source).as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn cache_path(source: &str, options: &Mermaid) -> PathBuf {
+    Path::new(CACHE_DIR).join(format!("{}.svg", hash(source, options)))
+}
+
+/// Run the renderer on one diagram and save its SVG to `target`.
+fn render(source: &str, options: &Mermaid, target: &Path) -> Result<()> {
+    let hash = hash(source, options);
+    let scratch = std::env::temp_dir().join(format!("scribe-mermaid-{}", std::process::id()));
+    fs::create_dir_all(&scratch).context("Failed to create a scratch directory")?;
+    let (input, output) = (scratch.join(format!("{}.mmd", hash)), scratch.join(format!("{}.svg", hash)));
+    fs::write(&input, source)?;
+
+    let mut words = options.command.split_whitespace();
+    let program = words.next().context("mermaid.command is empty")?;
+    // Each SVG gets its own id, since its styles are scoped to it and a page can have several
+    let status = Command::new(program)
+        .args(words)
+        .arg("-i")
+        .arg(&input)
+        .arg("-o")
+        .arg(&output)
+        .args(["-t", &options.theme, "-b", "transparent", "-I", &format!("mermaid-{}", &hash[..12]), "-q"])
+        .status()
+        .context(format!("Failed to run {} (is mermaid-cli installed?)", program))?;
+    if !status.success() {
+        bail!("{} exited with {}", program, status);
+    }
+
+    let svg = fs::read_to_string(&output).context(format!("{} wrote no SVG", program))?;
+    let svg = svg.find("<svg").map_or(svg.as_str(), |start| &svg[start..]);
+    fs::create_dir_all(CACHE_DIR).context(format!("Failed to create {}", CACHE_DIR))?;
+    fs::write(target, svg).context(format!("Failed to write {}", target.display()))?;
+    let _ = fs::remove_file(&input);
+    let _ = fs::remove_file(&output);
+    Ok(())
+}
+
+fn unescape(html: &str) -> String {
+    html.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&#39;", "'").replace("&amp;", "&")
+}
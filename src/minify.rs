@@ -0,0 +1,181 @@
+/// Elements whose content is kept exactly as written.
+const VERBATIM: &[&str] = &["pre", "textarea", "script", "style"];
+
+/// Elements around which whitespace doesn't render, so runs of it between them can go. Inline
+/// replaced elements such as `<math>`, `<svg>`, `<iframe>` and `<video>` sit in text like words,
+/// so the spaces around them stay.
+const BLOCKS: &[&str] = &[
+    "html", "head", "body", "title", "meta", "link", "script", "style", "noscript", "base", "div", "p", "main", "header", "footer", "nav",
+    "section", "article", "aside", "h1", "h2", "h3", "h4", "h5", "h6", "ul", "ol", "li", "dl", "dt", "dd", "table", "thead", "tbody",
+    "tfoot", "tr", "th", "td", "caption", "colgroup", "col", "figure", "figcaption", "blockquote", "hr", "br", "form", "fieldset",
+    "legend", "details", "summary", "dialog", "template", "pre", "address", "option", "optgroup", "source", "track",
+];
+
+/// `html` without comments (other than scribe's own annotations and conditional comments),
+/// with whitespace between block elements removed, other runs of whitespace collapsed to one
+/// character and inline `<style>` minified.
+pub fn html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    // Whether the last tag written was a block element
+    let mut after_block = true;
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").map_or(comment.len(), |i| i + 3);
+            if comment.starts_with(" scribe") || comment.starts_with("[if") {
+                out.push_str(&rest[..end + 4]);
+            }
+            rest = &comment[end..];
+            continue;
+        }
+        if rest.starts_with('<') {
+            let end = tag_end(rest);
+            let tag = &rest[..end];
+            let name = tag_name(tag);
+            out.push_str(tag);
+            rest = &rest[end..];
+            after_block = BLOCKS.contains(&name.as_str()) || tag.starts_with("<!");
+            // Keep the content of verbatim elements, minifying only stylesheets
+            if !tag.starts_with("</") && VERBATIM.contains(&name.as_str()) {
+                let close = format!("</{}", name);
+                let content_end = find_ignore_case(rest, &close).unwrap_or(rest.len());
+                let content = &rest[..content_end];
+                if name == "style" {
+                    out.push_str(&css(content));
+                } else {
+                    out.push_str(content);
+                }
+                rest = &rest[content_end..];
+            }
+            continue;
+        }
+        let text_end = rest.find('<').unwrap_or(rest.len());
+        let text = &rest[..text_end];
+        rest = &rest[text_end..];
+        let before_block = rest.starts_with("<!") || BLOCKS.contains(&tag_name(&rest[..tag_end(rest)]).as_str());
+        if text.trim().is_empty() && (after_block || before_block || rest.is_empty()) {
+            continue;
+        }
+        out.push_str(&collapse_whitespace(text));
+    }
+    out
+}
+
+/// `css` without comments, the whitespace around braces, semicolons, commas and child
+/// combinators and after colons, and each block's last semicolon. Strings are left as written.
+pub fn css(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut chars = css.chars().peekable();
+    let mut pending_space = false;
+    // Depth of parentheses, where spaces (e.g. `calc(1px + 2px)`) matter
+    let mut parens = 0usize;
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+                pending_space = true;
+            }
+            '"' | '\'' => {
+                flush_space(&mut out, &mut pending_space);
+                out.push(c);
+                while let Some(s) = chars.next() {
+                    out.push(s);
+                    if s == '\\' {
+                        if let Some(escaped) = chars.next() {
+                            out.push(escaped);
+                        }
+                    } else if s == c {
+                        break;
+                    }
+                }
+            }
+            c if c.is_ascii_whitespace() => pending_space = true,
+            '{' | '}' | ';' | ',' | '>' if parens == 0 || c == ',' => {
+                pending_space = false;
+                if c == '}' && out.ends_with(';') {
+                    out.pop();
+                }
+                out.push(c);
+                while chars.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+                    chars.next();
+                }
+            }
+            // Whitespace after a colon only ever separates a property or media feature from its value
+            ':' => {
+                flush_space(&mut out, &mut pending_space);
+                out.push(c);
+                while chars.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+                    chars.next();
+                }
+            }
+            _ => {
+                if c == '(' {
+                    parens += 1;
+                } else if c == ')' {
+                    parens = parens.saturating_sub(1);
+                }
+                flush_space(&mut out, &mut pending_space);
+                out.push(c);
+            }
+        }
+    }
+    out.trim().to_string()
+}
+
+/// Write a collapsed run of whitespace, unless it starts the output or follows punctuation that
+/// whitespace never matters after.
+fn flush_space(out: &mut String, pending: &mut bool) {
+    if std::mem::take(pending) && !out.is_empty() && !out.ends_with(['{', '}', ';', ',', ':', '>']) {
+        out.push(' ');
+    }
+}
+
+/// Runs of whitespace as a single newline or space.
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut run: Option<bool> = None;
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            run = Some(run.unwrap_or(false) || c == '\n');
+        } else {
+            if let Some(newline) = run.take() {
+                out.push(if newline { '\n' } else { ' ' });
+            }
+            out.push(c);
+        }
+    }
+    if let Some(newline) = run {
+        out.push(if newline { '\n' } else { ' ' });
+    }
+    out
+}
+
+/// Length of the tag at the start of `html`, quoted attribute values included.
+fn tag_end(html: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in html.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return i + 1,
+            _ => {}
+        }
+    }
+    html.len()
+}
+
+/// Lowercase element name of a tag such as `<div class="x">` or `</div>`.
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<').trim_start_matches('/').chars().take_while(|c| c.is_ascii_alphanumeric()).collect::<String>().to_ascii_lowercase()
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.to_ascii_lowercase().find(&needle.to_ascii_lowercase())
+}
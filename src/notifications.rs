@@ -0,0 +1,90 @@
+use crate::config::Notifications;
+use anyhow::{Context, Result};
+use colored::*;
+use std::process::Command;
+use tracing::warn;
+
+/// Result of a build, as reported to notification targets.
+pub enum BuildOutcome {
+    Success,
+    Failure(String),
+}
+
+impl BuildOutcome {
+    fn summary(&self, site_title: &str) -> String {
+        match self {
+            BuildOutcome::Success => format!("{} built successfully", site_title),
+            BuildOutcome::Failure(e) => format!("{} build failed: {}", site_title, e),
+        }
+    }
+}
+
+/// Show a native desktop notification if enabled. Failures are reported as warnings only.
+pub fn notify_desktop(config: &Notifications, site_title: &str, outcome: &BuildOutcome) {
+    if !config.desktop {
+        return;
+    }
+
+    let body = outcome.summary(site_title);
+    let result = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification \"{}\" with title \"scribe\"",
+            body.replace('\\', "\\\\").replace('"', "\\\"")
+        );
+        Command::new("osascript").arg("-e").arg(script).status()
+    } else {
+        Command::new("notify-send").arg("scribe").arg(&body).status()
+    };
+
+    if let Err(e) = result {
+        warn!("{}", format!("Warning: desktop notification failed: {}", e).yellow());
+    }
+}
+
+/// POST the build outcome to every configured webhook (generic JSON, Slack, ntfy).
+pub async fn notify_webhooks(config: &Notifications, site_title: &str, outcome: &BuildOutcome) {
+    let client = reqwest::Client::new();
+    let summary = outcome.summary(site_title);
+
+    if let Some(url) = &config.webhook {
+        let (status, error) = match outcome {
+            BuildOutcome::Success => ("success", None),
+            BuildOutcome::Failure(e) => ("failure", Some(e.as_str())),
+        };
+        let payload = serde_json::json!({
+            "site": site_title,
+            "status": status,
+            "error": error,
+            "message": summary,
+        });
+        report(client.post(url).json(&payload).send().await, "webhook");
+    }
+
+    if let Some(url) = &config.slack {
+        let payload = serde_json::json!({ "text": summary });
+        report(client.post(url).json(&payload).send().await, "Slack");
+    }
+
+    if let Some(url) = &config.ntfy {
+        let tags = match outcome {
+            BuildOutcome::Success => "white_check_mark",
+            BuildOutcome::Failure(_) => "x",
+        };
+        let request = client
+            .post(url)
+            .header("Title", "scribe")
+            .header("Tags", tags)
+            .body(summary.clone());
+        report(request.send().await, "ntfy");
+    }
+}
+
+fn report(result: reqwest::Result<reqwest::Response>, target: &str) {
+    let checked: Result<()> = result
+        .and_then(|r| r.error_for_status())
+        .map(|_| ())
+        .with_context(|| format!("{} notification failed", target));
+    if let Err(e) = checked {
+        warn!("{}", format!("Warning: {:#}", e).yellow());
+    }
+}
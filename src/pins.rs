@@ -0,0 +1,192 @@
+use crate::config::PinNode;
+use crate::progress::Progress;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use colored::*;
+use futures::StreamExt;
+use ipfs_api_backend_hyper::{request, response, Form, IpfsApi, IpfsClient, TryFromUri};
+use ipfs_api_prelude::Backend;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tracing::info;
+use walkdir::WalkDir;
+
+/// Files streamed from disk while adding; later ones are read into memory first.
+const OPEN_FILE_LIMIT: usize = 127;
+
+/// Location of the pin ledger, relative to the project root.
+pub const LEDGER_PATH: &str = ".scribe/pins.json";
+
+/// One successful `scribe pin` run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinRecord {
+    pub hash: String,
+    pub timestamp: DateTime<Utc>,
+    pub files: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// IPNS name updated to point at `hash`, if published
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipns: Option<String>,
+    /// Extra nodes that pinned `hash` too
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<String>,
+}
+
+/// Append-only history of pins stored in `.scribe/pins.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PinLedger {
+    pub pins: Vec<PinRecord>,
+}
+
+impl PinLedger {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .context("Failed to read pin ledger")?;
+        serde_json::from_str(&content)
+            .context("Failed to parse pin ledger")
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .context("Failed to create .scribe directory")?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .context("Failed to serialize pin ledger")?;
+        fs::write(path, content)
+            .context("Failed to write pin ledger")
+    }
+
+    /// Load the ledger at `path`, append `record` and write it back.
+    pub fn record<P: AsRef<Path>>(path: P, record: PinRecord) -> Result<()> {
+        let path = path.as_ref();
+        let mut ledger = Self::load(path)?;
+        ledger.pins.push(record);
+        ledger.save(path)
+    }
+}
+
+/// Add every file under `dist_path` to IPFS and return the root directory hash and file count.
+pub async fn add_dist_to_ipfs(client: &IpfsClient, dist_path: &Path) -> Result<(String, usize)> {
+    // Built like `IpfsApi::add_path`, but the response is read as a stream to report progress
+    let entries: Vec<_> = WalkDir::new(dist_path).into_iter().collect::<Result<_, _>>()?;
+    let prefix = dist_path.parent().unwrap_or(Path::new(""));
+    let mut form = Form::default();
+    for (index, entry) in entries.iter().filter(|e| e.file_type().is_file()).enumerate() {
+        let name = entry.path().strip_prefix(prefix).unwrap_or(entry.path()).to_string_lossy().to_string();
+        let file = fs::File::open(entry.path()).context(format!("Failed to read {}", entry.path().display()))?;
+        // Past the open file limit, the rest are read into memory
+        if index < OPEN_FILE_LIMIT {
+            form.add_reader_file("path", file, name);
+        } else {
+            form.add_reader_file("path", std::io::Cursor::new(fs::read(entry.path())?), name);
+        }
+    }
+    let request = client
+        .build_base_request(request::Add::default(), Some(form))
+        .context("Failed to add directory to IPFS")?;
+    let mut responses = client.request_stream_json::<response::AddResponse>(request);
+    
+    // Find the root directory hash
+    let mut root_hash = None;
+    let mut total_files = 0;
+    let root_name = dist_path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    // The node reports every file and directory once it is added
+    let adding = Progress::new(entries.len() as u64, "Adding");
+    
+    while let Some(item) = responses.next().await {
+        let item = item.context("Failed to add directory to IPFS")?;
+        total_files += 1;
+        // The root directory will have the same name as the source directory
+        if item.name == root_name {
+            root_hash = Some(item.hash.clone());
+        }
+        adding.inc_or_log(format!("  {} Added: {} ({})", "✓".green(), item.name, item.hash));
+    }
+    drop(adding);
+    
+    let root_hash = root_hash.context("Could not determine root directory hash")?;
+    
+    info!("{}", format!("Successfully added {} files to IPFS", total_files).green());
+    info!("{}", format!("Root directory hash: {}", root_hash).cyan().bold());
+    
+    Ok((root_hash, total_files))
+}
+
+
+/// Pin `root` (the hash of `dist_path`) on every node at once, retrying each failure up to
+/// `retries` times with exponential backoff. Returns each node's outcome, in order.
+pub async fn pin_on_nodes(
+    nodes: &[PinNode],
+    dist_path: &Path,
+    root: &str,
+    name: Option<&str>,
+    retries: u32,
+) -> Vec<Result<String>> {
+    futures::future::join_all(nodes.iter().map(|node| async move {
+        let mut attempt = 0;
+        loop {
+            match pin_on_node(node, dist_path, root, name).await {
+                Err(e) if attempt < retries => {
+                    let delay = Duration::from_secs(1 << attempt);
+                    attempt += 1;
+                    info!("{}", format!("  {} failed ({:#}), retrying in {}s", node.endpoint(), e, delay.as_secs()).yellow());
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }))
+    .await
+}
+
+/// Pin `root` on one node and return its status, e.g. `pinned` or `queued`.
+async fn pin_on_node(node: &PinNode, dist_path: &Path, root: &str, name: Option<&str>) -> Result<String> {
+    match node {
+        PinNode::Api { api } => {
+            let client = IpfsClient::from_str(api).context("Invalid IPFS API endpoint")?;
+            let added = client.add_path(dist_path).await.context("Failed to add directory")?;
+            let root_name = dist_path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            match added.iter().find(|item| item.name == root_name) {
+                Some(item) if item.hash == root => {}
+                // Nodes with other import settings (CID version, chunker) address the site differently
+                Some(item) => bail!("node added the site as {} instead of {}", item.hash, root),
+                None => bail!("could not determine root directory hash"),
+            }
+            client.pin_add(root, true).await.context("Failed to pin")?;
+            Ok("pinned".to_string())
+        }
+        PinNode::Service { service, token_env } => {
+            let token = std::env::var(token_env)
+                .ok()
+                .filter(|t| !t.is_empty())
+                .context(format!("Set {} to the pinning service access token", token_env))?;
+            let mut request = serde_json::json!({ "cid": root });
+            if let Some(name) = name {
+                request["name"] = name.into();
+            }
+            let response = reqwest::Client::new()
+                .post(format!("{}/pins", service.trim_end_matches('/')))
+                .bearer_auth(token)
+                .json(&request)
+                .send()
+                .await?;
+            let status = response.status();
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            if !status.is_success() {
+                let reason = body["error"]["reason"].as_str().unwrap_or_else(|| status.as_str());
+                bail!("pinning service returned {}", reason);
+            }
+            // Services fetch the content from the network, so the pin is usually still queued
+            Ok(body["status"].as_str().unwrap_or("queued").to_string())
+        }
+    }
+}
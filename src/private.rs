@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use openssl::symm::{encrypt_aead, Cipher};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// PBKDF2-SHA256 rounds turning the passphrase into a key, in the build and in the browser.
+pub const ITERATIONS: usize = 600_000;
+
+/// Stands in for the sealed page in a private post's prompt page until the page is final.
+pub const PLACEHOLDER: &str = "scribe:sealed";
+
+/// A page encrypted with AES-256-GCM, as the prompt page's script reads it: base64 salt, nonce
+/// and ciphertext with the tag appended, as WebCrypto expects.
+#[derive(Debug, Serialize)]
+pub struct Sealed {
+    pub salt: String,
+    pub iv: String,
+    pub iterations: usize,
+    pub data: String,
+}
+
+/// Encrypt `html` with `passphrase`. The salt comes from `slug` and the nonce from the key and
+/// page, so the same page seals to the same bytes on every build while different pages never
+/// share a nonce.
+pub fn seal(html: &str, passphrase: &str, slug: &str) -> Result<Sealed> {
+    let salt = &Sha256::digest(format!("scribe private {}", slug))[..16];
+    let mut key = [0u8; 32];
+    openssl::pkcs5::pbkdf2_hmac(passphrase.as_bytes(), salt, ITERATIONS, MessageDigest::sha256(), &mut key)
+        .context("Failed to derive the private post key")?;
+    let hmac = PKey::hmac(&key)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &hmac)?;
+    signer.update(html.as_bytes())?;
+    let iv = &signer.sign_to_vec()?[..12];
+    let mut tag = [0u8; 16];
+    let mut data = encrypt_aead(Cipher::aes_256_gcm(), &key, Some(iv), &[], html.as_bytes(), &mut tag)
+        .context("Failed to encrypt private post")?;
+    data.extend_from_slice(&tag);
+    let base64 = base64::engine::general_purpose::STANDARD;
+    Ok(Sealed { salt: base64.encode(salt), iv: base64.encode(iv), iterations: ITERATIONS, data: base64.encode(data) })
+}
+
+/// `page` (a prompt page from [`PLACEHOLDER`]) with `html` sealed into it.
+pub fn fill(page: &str, html: &str, passphrase: &str, slug: &str) -> Result<String> {
+    let sealed = serde_json::to_string(&seal(html, passphrase, slug)?)?;
+    Ok(page.replacen(PLACEHOLDER, &sealed, 1))
+}
+
+/// Prompt page script: derives the key from the passphrase, decrypts the page and replaces the
+/// prompt with it. A passphrase that worked is kept for the tab, unlocking other private posts.
+pub const SCRIPT: &str = r#"<script>
+(() => {
+  const sealed = JSON.parse(document.getElementById('sealed').textContent);
+  const form = document.getElementById('unlock');
+  const key = 'scribe:passphrase';
+  const bytes = (text) => Uint8Array.from(atob(text), (c) => c.charCodeAt(0));
+  const open = async (passphrase) => {
+    try {
+      const material = await crypto.subtle.importKey('raw', new TextEncoder().encode(passphrase), 'PBKDF2', false, ['deriveKey']);
+      const aes = await crypto.subtle.deriveKey(
+        { name: 'PBKDF2', salt: bytes(sealed.salt), iterations: sealed.iterations, hash: 'SHA-256' },
+        material, { name: 'AES-GCM', length: 256 }, false, ['decrypt']);
+      const page = await crypto.subtle.decrypt({ name: 'AES-GCM', iv: bytes(sealed.iv) }, aes, bytes(sealed.data));
+      sessionStorage.setItem(key, passphrase);
+      document.open();
+      document.write(new TextDecoder().decode(page));
+      document.close();
+      return true;
+    } catch (e) {
+      return false;
+    }
+  };
+  const saved = sessionStorage.getItem(key);
+  if (saved) open(saved);
+  form.addEventListener('submit', async (event) => {
+    event.preventDefault();
+    form.querySelector('.unlock-error').hidden = await open(form.passphrase.value);
+  });
+})();
+</script>"#;
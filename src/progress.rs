@@ -0,0 +1,81 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::LazyLock;
+use tracing::info;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Every bar on screen, so log lines can be printed above them.
+static BARS: LazyLock<MultiProgress> = LazyLock::new(MultiProgress::new);
+
+/// Show progress bars for long operations. They are off by default, and should stay off when
+/// stderr is not a terminal or output is quiet or machine-readable.
+pub fn enable(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Run `f`, which writes to the terminal, with any bars cleared from the screen meanwhile.
+pub fn suspend<R>(f: impl FnOnce() -> R) -> R {
+    if ENABLED.load(Ordering::Relaxed) {
+        BARS.suspend(f)
+    } else {
+        f()
+    }
+}
+
+/// Progress through a known number of items: a bar with a count and ETA when bars are enabled,
+/// otherwise nothing, or the log lines given to [`Progress::inc_or_log`]. The bar is cleared
+/// when dropped, so summaries logged afterwards replace it.
+pub struct Progress {
+    bar: Option<ProgressBar>,
+}
+
+impl Progress {
+    /// Progress through `len` items, labelled e.g. `Rendering`. No bar is shown for zero items.
+    pub fn new(len: u64, label: &'static str) -> Self {
+        if !ENABLED.load(Ordering::Relaxed) || len == 0 {
+            return Self { bar: None };
+        }
+        let style = ProgressStyle::with_template("{prefix:>12.cyan} [{bar:30}] {pos}/{len} ({eta}) {wide_msg}")
+            .unwrap()
+            .progress_chars("=> ");
+        let bar = BARS.add(ProgressBar::new(len).with_style(style).with_prefix(label));
+        Self { bar: Some(bar) }
+    }
+
+    /// Whether a bar is on screen, so per-item log lines can be left out.
+    pub fn is_shown(&self) -> bool {
+        self.bar.is_some()
+    }
+
+    /// Count one item done, showing `item` beside the bar.
+    pub fn inc(&self, item: impl Display) {
+        if let Some(bar) = &self.bar {
+            // Estimates can fall short, e.g. when the same URL is fetched for two posts
+            if bar.length().is_some_and(|len| bar.position() >= len) {
+                bar.inc_length(1);
+            }
+            bar.set_message(item.to_string());
+            bar.inc(1);
+        }
+    }
+
+    /// Count one item done, or log `line` when there is no bar.
+    pub fn inc_or_log(&self, line: impl Display) {
+        if self.is_shown() {
+            self.inc(line);
+        } else {
+            info!("{}", line);
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(bar) = self.bar.take() {
+            bar.finish_and_clear();
+            BARS.remove(&bar);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::avatar;
+use crate::config::{Config, TrailingSlash};
+use crate::generator::NOT_FOUND_PAGE;
+use sha2::{Digest, Sha256};
+
+/// Web app manifest, at the root of the output directory.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Service worker script, at the root of the output directory so its scope is the whole site.
+pub const WORKER_FILE: &str = "sw.js";
+
+/// Extensions of the files a service worker precaches: pages, styles, scripts, fonts and images.
+const PRECACHED: &[&str] = &["html", "css", "js", "woff2", "woff", "ttf", "png", "jpg", "jpeg", "gif", "webp", "avif", "svg", "ico"];
+
+/// Worker written in place of the real one once `pwa` is switched off, so readers who installed
+/// the site stop being served from their cache.
+pub const RETIRED_WORKER: &str = r#"// The site no longer works offline: drop its caches and step aside
+self.addEventListener('install', () => self.skipWaiting());
+self.addEventListener('activate', (event) => {
+  const prefix = 'scribe:' + self.registration.scope + ':';
+  event.waitUntil(
+    caches.keys()
+      .then((keys) => Promise.all(keys.filter((key) => key.startsWith(prefix)).map((key) => caches.delete(key))))
+      .then(() => self.registration.unregister())
+  );
+});
+"#;
+
+/// Whether a file (relative to the output directory) belongs in the precache by its type.
+pub fn is_precached(path: &str) -> bool {
+    path.rsplit_once('.').is_some_and(|(_, ext)| PRECACHED.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Address of a file relative to the site root as readers request it: directory pages by their
+/// directory, and pages without trailing slashes without `.html`.
+pub fn request_path(policy: TrailingSlash, path: &str) -> String {
+    if path == "index.html" {
+        return "./".to_string();
+    }
+    if let Some(dir) = path.strip_suffix("/index.html") {
+        return format!("{}/", dir);
+    }
+    match (policy, path.strip_suffix(".html")) {
+        (TrailingSlash::Never, Some(page)) if path != NOT_FOUND_PAGE => page.to_string(),
+        _ => path.to_string(),
+    }
+}
+
+/// `manifest.json` for the site: its title and colors, starting and scoped at the site root
+/// wherever that is served from, with the avatar as its icon when there is one.
+pub fn manifest(config: &Config) -> String {
+    let mut manifest = serde_json::json!({
+        "name": config.title,
+        "short_name": config.pwa.short_name.as_deref().unwrap_or(&config.title),
+        "start_url": "./",
+        "scope": "./",
+        "display": "standalone",
+        "background_color": config.theme.background_color,
+        "theme_color": config.theme.background_color,
+        "lang": config.languages.default,
+    });
+    if let Some(description) = &config.description {
+        manifest["description"] = description.as_str().into();
+    }
+    if let Some(avatar) = &config.avatar {
+        let size = format!("{0}x{0}", avatar.size);
+        manifest["icons"] = serde_json::json!([{ "src": avatar::file_name(avatar), "sizes": size, "purpose": "any" }]);
+    }
+    format!("{}\n", serde_json::to_string_pretty(&manifest).unwrap_or_default())
+}
+
+/// The service worker. It precaches `files` (request paths relative to the site root, each with
+/// its content) when installed, and drops caches of earlier builds when activated. Pages are
+/// fetched from the network first and from the cache when offline, falling back to the 404 page;
+/// everything else comes from the cache first, and is kept once fetched.
+pub fn worker(files: &[(String, &[u8])]) -> String {
+    let mut hasher = Sha256::new();
+    for (path, content) in files {
+        hasher.update(path.as_bytes());
+        hasher.update(Sha256::digest(content));
+    }
+    let version = format!("{:x}", hasher.finalize());
+    let precache = serde_json::to_string_pretty(&files.iter().map(|(path, _)| path).collect::<Vec<_>>()).unwrap_or_default();
+    format!(
+        r#"// Generated by scribe: keeps the site readable offline
+const VERSION = '{version}';
+const PRECACHE = {precache};
+const PREFIX = 'scribe:' + self.registration.scope + ':';
+const CACHE = PREFIX + VERSION;
+const NOT_FOUND = '{not_found}';
+
+self.addEventListener('install', (event) => {{
+  event.waitUntil(caches.open(CACHE).then((cache) => cache.addAll(PRECACHE)).then(() => self.skipWaiting()));
+}});
+
+self.addEventListener('activate', (event) => {{
+  event.waitUntil(
+    caches.keys()
+      .then((keys) => Promise.all(keys.filter((key) => key.startsWith(PREFIX) && key !== CACHE).map((key) => caches.delete(key))))
+      .then(() => self.clients.claim())
+  );
+}});
+
+self.addEventListener('fetch', (event) => {{
+  const request = event.request;
+  if (request.method !== 'GET' || !request.url.startsWith(self.registration.scope)) return;
+  const keep = (response) => {{
+    if (response.ok) {{
+      const copy = response.clone();
+      caches.open(CACHE).then((cache) => cache.put(request, copy));
+    }}
+    return response;
+  }};
+  if (request.mode === 'navigate') {{
+    event.respondWith(
+      fetch(request).then(keep).catch(() =>
+        caches.match(request).then((cached) => cached || caches.match(new URL(NOT_FOUND, self.registration.scope).href))
+      )
+    );
+    return;
+  }}
+  event.respondWith(caches.match(request).then((cached) => cached || fetch(request).then(keep)));
+}});
+"#,
+        version = &version[..16],
+        precache = precache,
+        not_found = NOT_FOUND_PAGE,
+    )
+}
+
+/// Manifest link, theme color and worker registration for the head of a page whose relative
+/// site root is `home_path`.
+pub fn head(config: &Config, home_path: &str) -> String {
+    if !config.pwa.enabled {
+        return String::new();
+    }
+    format!(
+        r#"
+    <link rel="manifest" href="{home}{manifest}">
+    <meta name="theme-color" content="{color}">
+    <script>if ('serviceWorker' in navigator) navigator.serviceWorker.register('{home}{worker}');</script>"#,
+        home = home_path,
+        manifest = MANIFEST_FILE,
+        color = config.theme.background_color,
+        worker = WORKER_FILE,
+    )
+}
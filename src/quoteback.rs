@@ -0,0 +1,78 @@
+use regex::{Captures, Regex};
+
+/// Turn blockquotes ending in a citation line into quoteback cards:
+///
+/// ```markdown
+/// > The best way to predict the future is to invent it.
+/// > -- Alan Kay, [Interview](https://example.com/kay)
+/// ```
+///
+/// The citation may name an author, a linked source, or both. Works on rendered HTML so
+/// the quote itself keeps its Markdown formatting.
+pub fn render(html: &str) -> String {
+    let blockquote = Regex::new(r"(?s)<blockquote>\n?(.*?)</blockquote>").unwrap();
+    let citation = Regex::new(
+        r#"(?s)^(?:--|—|–)\s*(.*?)\s*,?\s*(?:<a href="([^"]*)"[^>]*>(.*?)</a>)?\s*$"#,
+    )
+    .unwrap();
+
+    blockquote
+        .replace_all(html, |caps: &Captures| {
+            let body = &caps[1];
+            // Nested quotes are left alone
+            if body.contains("<blockquote") {
+                return caps[0].to_string();
+            }
+            let Some(last_p) = body.rfind("<p>") else { return caps[0].to_string() };
+            let Some(end) = body[last_p..].find("</p>").map(|i| last_p + i) else { return caps[0].to_string() };
+            let paragraph = &body[last_p + 3..end];
+            let (quote_tail, cite_line) = match paragraph.rfind('\n') {
+                Some(i) => (&paragraph[..i], &paragraph[i + 1..]),
+                None => ("", paragraph),
+            };
+            let Some(cite) = citation.captures(cite_line.trim()) else { return caps[0].to_string() };
+
+            let author = cite.get(1).map_or("", |m| m.as_str()).trim();
+            let source = cite.get(2).map(|url| (url.as_str(), cite.get(3).map_or("", |m| m.as_str())));
+            if author.is_empty() && source.is_none() {
+                return caps[0].to_string();
+            }
+
+            let mut quote = body[..last_p].to_string();
+            if !quote_tail.trim().is_empty() {
+                quote.push_str(&format!("<p>{}</p>", quote_tail));
+            }
+            quote.push_str(&body[end + 4..]);
+
+            render_card(quote.trim(), author, source)
+        })
+        .to_string()
+}
+
+fn render_card(quote: &str, author: &str, source: Option<(&str, &str)>) -> String {
+    let cite_attr = source.map(|(url, _)| format!(" cite=\"{}\"", url)).unwrap_or_default();
+    let author_html = if author.is_empty() {
+        String::new()
+    } else {
+        format!("<span class=\"quoteback-author\">{}</span>", author)
+    };
+    let source_html = match source {
+        Some((url, text)) => {
+            // Filled in from the self-hosted favicon listed in the link metadata on load
+            let favicon = "<img class=\"quoteback-favicon\" alt=\"\" hidden>";
+            // Bare URLs get their page title from the link metadata on load
+            let auto_title = if text.is_empty() || text == url { " data-auto-title" } else { "" };
+            let label = if text.is_empty() { url } else { text };
+            format!(
+                "{}<a class=\"quoteback-source\" href=\"{}\"{}>{}</a>",
+                favicon, url, auto_title, label
+            )
+        }
+        None => String::new(),
+    };
+
+    format!(
+        "<figure class=\"quoteback\">\n<blockquote{}>\n{}\n</blockquote>\n<figcaption class=\"quoteback-footer\">{}{}</figcaption>\n</figure>\n",
+        cite_attr, quote, author_html, source_html
+    )
+}
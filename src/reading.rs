@@ -0,0 +1,60 @@
+/// Words a reader gets through per minute, or characters for Chinese and Japanese.
+const WORDS_PER_MINUTE: usize = 220;
+const CHARACTERS_PER_MINUTE: usize = 450;
+
+/// Longest derived excerpt, in words or, for Chinese and Japanese, characters.
+const EXCERPT_WORDS: usize = 50;
+const EXCERPT_CHARACTERS: usize = 120;
+
+/// Whether text in the language `lang` is counted by character rather than by word: Chinese and
+/// Japanese are written without spaces between words.
+pub fn counts_characters(lang: &str) -> bool {
+    let primary = lang.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+    matches!(primary.as_str(), "zh" | "ja" | "yue")
+}
+
+/// Han, kana and other CJK characters, each read as about a word.
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // Hiragana, Katakana
+        | 0x3400..=0x4DBF   // CJK Extension A
+        | 0x4E00..=0x9FFF   // CJK Unified Ideographs
+        | 0xF900..=0xFAFF   // CJK Compatibility Ideographs
+        | 0xFF66..=0xFF9F   // Halfwidth Katakana
+        | 0x20000..=0x2FA1F // CJK Extensions B and later
+    )
+}
+
+/// Words in `text`. In Chinese and Japanese every CJK character counts as one, plus any
+/// space-separated words in between (Latin names, numbers).
+pub fn word_count(text: &str, lang: &str) -> usize {
+    if !counts_characters(lang) {
+        return text.split_whitespace().count();
+    }
+    let characters = text.chars().filter(|&c| is_cjk(c)).count();
+    let words = text.split(|c: char| c.is_whitespace() || is_cjk(c)).filter(|w| w.chars().any(char::is_alphanumeric)).count();
+    characters + words
+}
+
+/// Minutes to read `words` words of `lang`, at least one.
+pub fn minutes(words: usize, lang: &str) -> usize {
+    let rate = if counts_characters(lang) { CHARACTERS_PER_MINUTE } else { WORDS_PER_MINUTE };
+    words.div_ceil(rate).max(1)
+}
+
+/// `text` cut to excerpt length at a word boundary, or at a character for Chinese and Japanese,
+/// with an ellipsis when anything was cut.
+pub fn excerpt(text: &str, lang: &str) -> String {
+    let text = text.trim();
+    let cut = if counts_characters(lang) {
+        text.char_indices().nth(EXCERPT_CHARACTERS).map(|(i, _)| i)
+    } else {
+        text.split_whitespace()
+            .nth(EXCERPT_WORDS)
+            .map(|word| word.as_ptr() as usize - text.as_ptr() as usize)
+    };
+    match cut {
+        Some(cut) => format!("{}…", text[..cut].trim_end()),
+        None => text.to_string(),
+    }
+}
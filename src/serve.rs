@@ -0,0 +1,823 @@
+use crate::access::{self, Entry};
+use crate::annotations;
+use crate::config::{self, Config, InputFormat};
+use crate::generator::{is_html_post, sanitize_slug, BuildSummary, SiteGenerator, CUSTOM_SCRIPT, CUSTOM_STYLESHEET, NOT_FOUND_PAGE};
+use crate::notifications::{self, BuildOutcome};
+use crate::pins;
+use crate::templates;
+use crate::tls::Tls;
+use anyhow::{Context, Result};
+use colored::*;
+use futures_util::sink::SinkExt;
+use ipfs_api_backend_hyper::{IpfsApi, IpfsClient, TryFromUri};
+use notify::{PollWatcher, RecursiveMode, Watcher};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use warp::ws::{Message, WebSocket};
+use warp::Filter;
+
+/// Hot reload broadcaster, set in watch mode
+type HotReloadSender = Arc<RwLock<Option<broadcast::Sender<String>>>>;
+
+/// Error chain of the latest failed watch-mode rebuild; cleared once a rebuild succeeds.
+type BuildError = Arc<RwLock<Option<Vec<String>>>>;
+
+/// What to serve and how.
+pub struct ServeOptions {
+    /// Generated site to serve
+    pub dist: PathBuf,
+    pub host: String,
+    /// Port to listen on; 0 picks a free one (see [`Server::addr`])
+    pub port: u16,
+    /// Config for the trailing-slash policy and base path (defaults if missing) and, in watch
+    /// mode, the posts directory to watch
+    pub config: PathBuf,
+    /// Regenerate on changes and hot-reload open pages
+    pub watch: bool,
+    /// Pin the site after watch-mode rebuilds
+    pub deploy: Option<DeployOnChange>,
+    /// Serve over HTTPS with this certificate
+    pub tls: Option<Tls>,
+    /// Append each request to this file (see [`access::LOG_PATH`]) for `scribe stats`
+    pub access_log: Option<PathBuf>,
+}
+
+/// A running server, stopped by [`Server::shutdown`] or when the handle is dropped.
+pub struct Server {
+    /// Address the server listens on, with the actual port when 0 was requested
+    pub addr: SocketAddr,
+    /// Path the site is served under, e.g. `/` or `/blog/`
+    pub base_path: String,
+    /// Whether the server speaks HTTPS
+    pub tls: bool,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+    _watcher: Option<WatcherHandle>,
+}
+
+impl Server {
+    /// URL of the site root; on this machine when bound to every interface.
+    pub fn url(&self) -> String {
+        let mut addr = self.addr;
+        if addr.ip().is_unspecified() {
+            addr.set_ip(if addr.is_ipv4() { Ipv4Addr::LOCALHOST.into() } else { Ipv6Addr::LOCALHOST.into() });
+        }
+        format!("{}://{}{}", self.scheme(), addr, self.base_path)
+    }
+
+    fn scheme(&self) -> &'static str {
+        if self.tls { "https" } else { "http" }
+    }
+
+    /// URLs other devices on the network can reach the site at, when bound to every interface.
+    pub fn network_urls(&self) -> Vec<String> {
+        if !self.addr.ip().is_unspecified() {
+            return Vec::new();
+        }
+        lan_addresses(self.addr.is_ipv6())
+            .into_iter()
+            .map(|ip| format!("{}://{}{}", self.scheme(), SocketAddr::new(ip, self.addr.port()), self.base_path))
+            .collect()
+    }
+
+    /// Run until the server task ends.
+    pub async fn wait(self) -> Result<()> {
+        let Server { task, shutdown, _watcher, .. } = self;
+        let result = task.await;
+        drop(shutdown);
+        result.context("Server task failed")
+    }
+
+    /// Stop accepting connections and wait for open ones to finish.
+    pub async fn shutdown(self) -> Result<()> {
+        let _ = self.shutdown.send(());
+        self.task.await.context("Server task failed")
+    }
+}
+
+/// Addresses this machine uses on the local network. A UDP socket "connected" to a public
+/// address reports the interface the OS would route through; no packets are sent.
+fn lan_addresses(ipv6: bool) -> Vec<IpAddr> {
+    let probes: &[(&str, &str)] = &[("0.0.0.0:0", "192.0.2.1:80"), ("[::]:0", "[2001:db8::1]:80")];
+    probes
+        .iter()
+        .take(if ipv6 { 2 } else { 1 })
+        .filter_map(|(bind, probe)| {
+            let socket = std::net::UdpSocket::bind(bind).ok()?;
+            socket.connect(probe).ok()?;
+            Some(socket.local_addr().ok()?.ip())
+        })
+        .filter(|ip| !ip.is_loopback() && !ip.is_unspecified())
+        .collect()
+}
+
+/// Bind the server and start serving in the background.
+pub async fn start(options: ServeOptions) -> Result<Server> {
+    let ServeOptions { dist: dist_path, host, port, config: config_path, watch, deploy, tls, access_log } = options;
+    if !dist_path.exists() {
+        anyhow::bail!("Directory '{}' does not exist. Run 'scribe generate' first.", dist_path.display());
+    }
+    if !dist_path.is_dir() {
+        anyhow::bail!("'{}' is not a directory.", dist_path.display());
+    }
+    let acceptor = tls.as_ref().map(Tls::acceptor).transpose()?;
+
+    // Trailing-slash policy decides the canonical form of post URLs; the base path where the site lives
+    let (trailing_slash, base_path) = if config_path.exists() {
+        let config = Config::load(&config_path)?;
+        (config.trailing_slash, config.base_path())
+    } else {
+        (config::TrailingSlash::default(), "/".to_string())
+    };
+    // Create hot reload broadcaster
+    let hot_reload_tx: HotReloadSender = Arc::new(RwLock::new(None));
+    let build_error: BuildError = Arc::new(RwLock::new(None));
+    
+    // Setup file watching if enabled
+    let watcher = if watch {
+        info!("{}", "File watching enabled - changes will trigger regeneration".yellow());
+        // Create broadcast channel for hot reload
+        let (reload_tx, _) = broadcast::channel(100);
+        *hot_reload_tx.write().await = Some(reload_tx.clone());
+        if let Some(ref d) = deploy {
+            info!("{}", format!("Deploy on change enabled - pinning to {} after {}s of quiet", d.ipfs_api, d.debounce.as_secs()).yellow());
+        }
+        Some(setup_file_watcher(config_path.clone(), Some(reload_tx), build_error.clone(), deploy).await?)
+    } else {
+        if deploy.is_some() {
+            warn!("{}", "Warning: --deploy-on-change has no effect without --watch".yellow());
+        }
+        None
+    };
+    
+    // Create static file serving route
+    let static_files = warp::fs::dir(dist_path.clone())
+        .or(warp::path::end().and(warp::fs::file(dist_path.join("index.html"))));
+
+    // Redirect route: map unsanitized or non-canonical single-segment paths to the canonical post URL
+    let redirect_dist = dist_path.clone();
+    let redirect_base = base_path.clone();
+    let sanitize_redirect = warp::path::param::<String>()
+        .and(warp::path::end())
+        .and(warp::path::full())
+        .and_then(move |slug: String, full_path: warp::path::FullPath| {
+            let redirect_dist = redirect_dist.clone();
+            let redirect_base = redirect_base.clone();
+            async move {
+                let sanitized = sanitize_slug(&slug);
+                let canonical = trailing_slash.post_href(&redirect_base, &sanitized);
+                // Only redirect if a generated page exists for the sanitized slug
+                if full_path.as_str() != canonical && redirect_dist.join(trailing_slash.post_path(&sanitized)).is_file() {
+                    let uri: warp::http::Uri = canonical.parse().unwrap();
+                    Ok::<_, warp::Rejection>(warp::redirect::see_other(uri))
+                } else {
+                    Err(warp::reject::not_found())
+                }
+            }
+        });
+
+    // Serve HTML pages ourselves so the hot-reload client can be injected in watch mode;
+    // this also serves `/slug` from `slug.html` (and `/series/name` from `series/name.html`)
+    // for the no-trailing-slash policy. While a watch-mode rebuild is failing, pages show the
+    // error instead of the stale output.
+    let html_dist = dist_path.clone();
+    let client_base = base_path.clone();
+    let page_build_error = build_error.clone();
+    let html_pages = warp::path::tail()
+        .and_then(move |tail: warp::path::Tail| {
+            let path = tail.as_str().trim_end_matches('/').to_string();
+            let valid = !path.split('/').any(|seg| seg == "..") && !path.contains("//");
+            let candidates = if path.is_empty() {
+                vec![html_dist.join("index.html")]
+            } else if path.ends_with(".html") {
+                vec![html_dist.join(&path)]
+            } else {
+                vec![html_dist.join(format!("{}.html", path)), html_dist.join(&path).join("index.html")]
+            };
+            let client_base = client_base.clone();
+            let build_error = page_build_error.clone();
+            async move {
+                if !valid {
+                    return Err(warp::reject::not_found());
+                }
+                for page in candidates {
+                    if let Ok(html) = tokio::fs::read_to_string(&page).await {
+                        if let Some(errors) = build_error.read().await.as_ref() {
+                            let html = inject_hot_reload_client(&templates::render_build_error(errors), &client_base);
+                            return Ok(warp::reply::with_status(warp::reply::html(html), warp::http::StatusCode::INTERNAL_SERVER_ERROR));
+                        }
+                        let html = if watch { inject_hot_reload_client(&html, &client_base) } else { html };
+                        return Ok(warp::reply::with_status(warp::reply::html(html), warp::http::StatusCode::OK));
+                    }
+                }
+                Err(warp::reject::not_found())
+            }
+        });
+
+    // Everything but the hot-reload socket lives under the base path, and `/` redirects there
+    let base_prefix = base_path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .fold(warp::any().boxed(), |prefix, segment| {
+            prefix
+                .and(warp::path::param::<String>())
+                .and_then(move |param: String| {
+                    let matches = param == segment;
+                    async move { if matches { Ok(()) } else { Err(warp::reject::not_found()) } }
+                })
+                .untuple_one()
+                .boxed()
+        });
+    let site = base_prefix.and(sanitize_redirect.or(html_pages).or(static_files));
+    let root_base = base_path.clone();
+    let base_redirect = warp::path::end().and_then(move || {
+        let uri: Option<warp::http::Uri> = (root_base != "/").then(|| root_base.parse().ok()).flatten();
+        async move { uri.map(warp::redirect::see_other).ok_or_else(warp::reject::not_found) }
+    });
+
+    // Anything left unmatched gets the site's own 404 page, when it has one
+    let not_found_page = dist_path.join(NOT_FOUND_PAGE);
+    let not_found_base = base_path.clone();
+    let not_found = move |rejection: warp::Rejection| {
+        let page = not_found_page.clone();
+        let client_base = not_found_base.clone();
+        async move {
+            if !rejection.is_not_found() {
+                return Err(rejection);
+            }
+            match tokio::fs::read_to_string(&page).await {
+                Ok(html) => {
+                    let html = if watch { inject_hot_reload_client(&html, &client_base) } else { html };
+                    Ok(warp::reply::with_status(warp::reply::html(html), warp::http::StatusCode::NOT_FOUND))
+                }
+                Err(_) => Err(rejection),
+            }
+        }
+    };
+
+    // Requests for the access log, without the reader's address or the hot-reload socket
+    let access_log = access_log.map(|path| access::Log::open(&path).map(Arc::new)).transpose()?;
+    let record = warp::log::custom(move |info: warp::log::Info| {
+        let Some(log) = &access_log else { return };
+        if info.path().starts_with("/__hot_reload__") {
+            return;
+        }
+        let host = info.host();
+        let referrer = info.referer().filter(|referrer| !host.is_some_and(|host| access::is_internal(referrer, host)));
+        log.write(&Entry {
+            time: chrono::Utc::now(),
+            method: info.method().to_string(),
+            path: info.path().to_string(),
+            protocol: format!("{:?}", info.version()),
+            status: info.status().as_u16(),
+            referrer: referrer.map(str::to_string),
+            agent: info.user_agent().map(str::to_string),
+        });
+    });
+
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_headers(vec!["content-type"])
+        .allow_methods(vec!["GET", "POST", "DELETE"]);
+
+    // Create routes with optional WebSocket for hot reload
+    let routes = if watch {
+        let hot_reload_tx_clone = hot_reload_tx.clone();
+        let ws_route = warp::path("__hot_reload__")
+            .and(warp::ws())
+            .and(warp::any().map(move || hot_reload_tx_clone.clone()))
+            .and_then(|ws: warp::ws::Ws, hot_reload_tx: HotReloadSender| async move {
+                Ok::<_, warp::Rejection>(ws.on_upgrade(move |socket| handle_websocket(socket, hot_reload_tx)))
+            });
+        
+        ws_route
+            .or(site)
+            .or(base_redirect)
+            .recover(not_found)
+            .with(cors)
+            .with(warp::log("scribe"))
+            .with(record.clone())
+            .boxed()
+    } else {
+        site
+            .or(base_redirect)
+            .recover(not_found)
+            .with(cors)
+            .with(warp::log("scribe"))
+            .with(record)
+            .boxed()
+    };
+
+    // Parse the host address
+    let addr: std::net::IpAddr = host.parse()
+        .context("Invalid host address")?;
+
+    // Start the server
+    let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+    let signal = async {
+        let _ = shutdown_rx.await;
+    };
+    let (addr, task) = match acceptor {
+        Some(acceptor) => {
+            let listener = tokio::net::TcpListener::bind((addr, port))
+                .await
+                .context(format!("Failed to bind {}:{}", host, port))?;
+            let addr = listener.local_addr()?;
+            let server = warp::serve(routes).serve_incoming_with_graceful_shutdown(tls_incoming(listener, acceptor), signal);
+            (addr, tokio::spawn(server))
+        }
+        None => {
+            let (addr, server) = warp::serve(routes)
+                .try_bind_with_graceful_shutdown((addr, port), signal)
+                .context(format!("Failed to bind {}:{}", host, port))?;
+            (addr, tokio::spawn(server))
+        }
+    };
+
+    Ok(Server {
+        addr,
+        base_path,
+        tls: tls.is_some(),
+        shutdown,
+        task,
+        _watcher: watcher,
+    })
+}
+
+/// Connections on `listener` once their TLS handshake is done. Handshakes run concurrently, so a
+/// slow or failing client (e.g. one rejecting a self-signed certificate) doesn't hold up others.
+fn tls_incoming(
+    listener: tokio::net::TcpListener,
+    acceptor: tokio_native_tls::TlsAcceptor,
+) -> impl futures_util::Stream<Item = std::io::Result<tokio_native_tls::TlsStream<tokio::net::TcpStream>>> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        loop {
+            let stream = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(_) => continue,
+                },
+                _ = tx.closed() => break,
+            };
+            let acceptor = acceptor.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                if let Ok(stream) = acceptor.accept(stream).await {
+                    let _ = tx.send(Ok(stream));
+                }
+            });
+        }
+    });
+    futures_util::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|stream| (stream, rx)) })
+} 
+
+
+/// Client for the `/__hot_reload__` socket. Post pages reload only when their own slug is
+/// in the change list; listing pages (index, series) reload on any change. Stylesheet-only
+/// changes swap in the new `style.css` without a reload. Scroll position is kept across reloads.
+/// When the server goes away the client keeps reconnecting, and reloads once it is back.
+const HOT_RELOAD_CLIENT: &str = r#"<script>
+(function() {
+    var key = 'scribe-scroll:' + location.pathname;
+    var saved = sessionStorage.getItem(key);
+    if (saved !== null) {
+        sessionStorage.removeItem(key);
+        window.addEventListener('load', function() { window.scrollTo(0, parseInt(saved, 10)); });
+    }
+    var path = location.pathname;
+    if (path.indexOf('{base_path}') === 0) path = path.slice('{base_path}'.length);
+    var segments = path.replace(/(\/index)?\.html$/, '').split('/').filter(Boolean);
+    var slug = segments.length === 1 ? decodeURIComponent(segments[0]) : null;
+    var url = (location.protocol === 'https:' ? 'wss://' : 'ws://') + location.host + '/__hot_reload__';
+    function connect(reconnecting) {
+        var socket = new WebSocket(url);
+        socket.onopen = function() {
+            // The server restarted while we were away, so the page may be stale
+            if (!reconnecting) return;
+            sessionStorage.setItem(key, String(window.scrollY));
+            location.reload();
+        };
+        socket.onclose = function() {
+            setTimeout(function() { connect(true); }, 1000);
+        };
+        socket.onmessage = onmessage;
+    }
+    function onmessage(event) {
+        var message = {};
+        try { message = JSON.parse(event.data); } catch (e) {}
+        if (message.css) {
+            document.querySelectorAll('link[rel="stylesheet"]').forEach(function(link) {
+                var href = link.getAttribute('href').replace(/\?v=\d+$/, '');
+                if (/style\.css$/.test(href)) link.setAttribute('href', href + '?v=' + Date.now());
+            });
+            return;
+        }
+        var changed = message.changed || null;
+        if (changed && slug !== null && changed.indexOf(slug) === -1) return;
+        sessionStorage.setItem(key, String(window.scrollY));
+        location.reload();
+    }
+    connect(false);
+})();
+</script>"#;
+
+fn inject_hot_reload_client(html: &str, base_path: &str) -> String {
+    let client = HOT_RELOAD_CLIENT.replace("{base_path}", base_path);
+    match html.rfind("</body>") {
+        Some(index) => format!("{}{}\n{}", &html[..index], client, &html[index..]),
+        None => format!("{}{}", html, client),
+    }
+}
+
+async fn handle_websocket(ws: WebSocket, hot_reload_tx: HotReloadSender) {
+    let mut ws = ws;
+    
+    // Get the reload receiver
+    let mut reload_rx = {
+        let guard = hot_reload_tx.read().await;
+        if let Some(tx) = guard.as_ref() {
+            tx.subscribe()
+        } else {
+            return; // No broadcaster available
+        }
+    };
+    
+    // Listen for reload messages and forward them to the WebSocket
+    while let Ok(msg) = reload_rx.recv().await {
+        if ws.send(Message::text(msg)).await.is_err() {
+            break; // Client disconnected
+        }
+    }
+}
+
+
+
+/// Settings for automatically pinning the site after watch-mode regeneration.
+pub struct DeployOnChange {
+    pub dist: PathBuf,
+    pub ipfs_api: String,
+    /// Quiet period after the last rebuild before deploying
+    pub debounce: Duration,
+}
+
+impl DeployOnChange {
+    /// Delay before retrying a failed deploy: double the `previous` delay, capped at
+    /// [`MAX_DEPLOY_BACKOFF`] but never shorter than the debounce.
+    pub fn retry_delay(&self, previous: Duration) -> Duration {
+        (previous * 2).min(MAX_DEPLOY_BACKOFF).max(self.debounce.max(Duration::from_secs(1)))
+    }
+}
+
+/// How long file events must stop before a rebuild starts, so a burst of saves builds once.
+const BUILD_QUIET_PERIOD: Duration = Duration::from_millis(300);
+
+/// Upper bound for the retry delay after repeated deploy failures.
+const MAX_DEPLOY_BACKOFF: Duration = Duration::from_secs(30 * 60);
+
+async fn deploy_after_change(deploy: &DeployOnChange) -> Result<String> {
+    let dist = deploy.dist.clone();
+    let ipfs_api = deploy.ipfs_api.clone();
+    // The IPFS client futures are not `Send`, so drive them on a dedicated runtime
+    tokio::task::spawn_blocking(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+        runtime.block_on(async {
+            let client = IpfsClient::from_str(&ipfs_api)
+                .context("Failed to create IPFS client")?;
+            let (root_hash, _) = pins::add_dist_to_ipfs(&client, &dist).await?;
+            client.pin_add(&root_hash, true).await
+                .context("Failed to pin content")?;
+            Ok(root_hash)
+        })
+    })
+    .await?
+}
+
+struct WatcherHandle {
+    _watcher: Arc<std::sync::Mutex<PollWatcher>>,
+    _task_handle: tokio::task::JoinHandle<()>,
+}
+
+/// Sent when only `style.css` changed, so open pages restyle in place.
+const CSS_RELOAD_MESSAGE: &str = r#"{"css":true}"#;
+
+/// Whether `new` differs from `old` at most in the theme stylesheet path, which only
+/// `style.css` depends on.
+fn only_stylesheet_differs(old: &Config, new: &Config) -> bool {
+    let mut new = new.clone();
+    new.theme.stylesheet = old.theme.stylesheet.clone();
+    serde_json::to_value(old).ok() == serde_json::to_value(&new).ok()
+}
+
+/// Posts touched by a batch of file events, broadcast to hot-reload clients as
+/// `{"changed": ["slug", ...]}`, or `{"changed": null}` when every page should reload.
+#[derive(Default)]
+struct ChangedPosts {
+    slugs: std::collections::BTreeSet<String>,
+    everything: bool,
+}
+
+impl ChangedPosts {
+    /// Reload every page.
+    fn everything() -> Self {
+        ChangedPosts { everything: true, ..Default::default() }
+    }
+
+    fn add(&mut self, event: &notify::Event) {
+        for path in &event.paths {
+            let name = path.file_name().and_then(|s| s.to_str()).unwrap_or_default();
+            let source = InputFormat::of(path).and_then(|_| path.file_stem()?.to_str());
+            match name.strip_suffix(".md").or_else(|| name.strip_suffix(".html")).or(source).or_else(|| name.strip_suffix(annotations::SIDECAR_SUFFIX)) {
+                Some(stem) => {
+                    self.slugs.insert(sanitize_slug(stem));
+                }
+                // Config changes can affect every page
+                None if name == "config.json" => self.everything = true,
+                None => {}
+            }
+        }
+    }
+
+    /// Fold in the changes of a build that was cancelled before it finished.
+    fn merge(&mut self, other: ChangedPosts) {
+        self.slugs.extend(other.slugs);
+        self.everything |= other.everything;
+    }
+
+    fn message(&self) -> String {
+        let changed = if self.everything { None } else { Some(&self.slugs) };
+        serde_json::json!({ "changed": changed }).to_string()
+    }
+}
+
+async fn setup_file_watcher(config_path: PathBuf, hot_reload_tx: Option<broadcast::Sender<String>>, build_error: BuildError, deploy: Option<DeployOnChange>) -> Result<WatcherHandle> {
+    // Tokio channel so waiting for events yields to the runtime instead of blocking a worker
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    
+    let mut watcher = PollWatcher::new(
+        move |res| {
+            if let Ok(event) = res {
+                if let Err(e) = tx.send(event) {
+                    error!("Failed to send file watch event: {}", e);
+                }
+            }
+        },
+        notify::Config::default().with_poll_interval(Duration::from_secs(1)),
+    )?;
+    
+    // Load config to get posts directory
+    let mut config = Config::load(&config_path)?;
+    let posts_dir = PathBuf::from(&config.posts_dir);
+    
+    if posts_dir.exists() {
+        watcher.watch(&posts_dir, RecursiveMode::Recursive)?;
+        info!("{}", format!("Watching: {}", posts_dir.display()).blue());
+    }
+    
+    // Also watch config file, the theme stylesheet and the trails file
+    watcher.watch(&config_path, RecursiveMode::NonRecursive)?;
+    if let Some(stylesheet) = &config.theme.stylesheet {
+        if let Err(e) = watcher.watch(Path::new(stylesheet), RecursiveMode::NonRecursive) {
+            warn!("{}", format!("Warning: cannot watch stylesheet {}: {}", stylesheet, e).yellow());
+        }
+    }
+    if let Some(trails) = &config.trails {
+        if let Err(e) = watcher.watch(Path::new(trails), RecursiveMode::NonRecursive) {
+            warn!("{}", format!("Warning: cannot watch trails file {}: {}", trails, e).yellow());
+        }
+    }
+    // and the project's custom stylesheet and script
+    if let Some(dir) = Path::new(CUSTOM_STYLESHEET).parent().filter(|dir| dir.is_dir()) {
+        if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+            warn!("{}", format!("Warning: cannot watch {}: {}", dir.display(), e).yellow());
+        }
+    }
+    for path in &config.watch.paths {
+        match watcher.watch(Path::new(path), RecursiveMode::Recursive) {
+            Ok(()) => info!("{}", format!("Watching: {}", path).blue()),
+            Err(e) => warn!("{}", format!("Warning: cannot watch {}: {}", path, e).yellow()),
+        }
+    }
+    let watcher = Arc::new(std::sync::Mutex::new(watcher));
+    let task_watcher = Arc::downgrade(&watcher);
+    
+    // Spawn background task to handle file changes
+    let task_handle = tokio::spawn(async move {
+        // Changes waiting for the next build, and when the last of them arrived
+        let mut queued: Option<ChangedPosts> = None;
+        let mut trails_changed = false;
+        let mut last_event = std::time::Instant::now();
+        // The build in flight, when it started and the changes it covers
+        let mut building: Option<(JoinHandle<Result<BuildSummary>>, std::time::Instant, ChangedPosts)> = None;
+        // Pending deploy deadline and current failure backoff for --deploy-on-change
+        let mut next_deploy: Option<std::time::Instant> = None;
+        let mut deploy_backoff = Duration::ZERO;
+        
+        loop {
+            if let (Some(d), Some(at)) = (&deploy, next_deploy) {
+                if std::time::Instant::now() >= at && building.is_none() {
+                    next_deploy = None;
+                    info!("{}", "Deploying site to IPFS...".yellow());
+                    match deploy_after_change(d).await {
+                        Ok(root_hash) => {
+                            deploy_backoff = Duration::ZERO;
+                            info!("{}", format!("Deployed: https://ipfs.io/ipfs/{}", root_hash).green());
+                        }
+                        Err(e) => {
+                            deploy_backoff = d.retry_delay(deploy_backoff);
+                            next_deploy = Some(std::time::Instant::now() + deploy_backoff);
+                            error!("{}", format!("Deploy failed: {} (retrying in {}s)", e, deploy_backoff.as_secs()).red());
+                        }
+                    }
+                }
+            }
+            
+            // Report a finished build
+            if building.as_ref().is_some_and(|(handle, _, _)| handle.is_finished()) {
+                let (handle, started, changed) = building.take().unwrap();
+                let result = handle.await.unwrap_or_else(|e| Err(anyhow::anyhow!("Build task failed: {}", e)));
+                match result {
+                    Ok(summary) => {
+                        info!("{} {}", format!("Rebuilt in {:.1}s:", started.elapsed().as_secs_f64()).green(), describe_rebuild(&summary));
+                        
+                        // Send hot reload notification; every page recovers from the error page, not just the changed ones
+                        let recovered = build_error.write().await.take().is_some();
+                        if let Some(ref tx) = hot_reload_tx {
+                            let _ = tx.send(if recovered { ChangedPosts::everything() } else { changed }.message());
+                        }
+                        
+                        // Restart the quiet period; an active backoff is never shortened
+                        if let Some(ref d) = deploy {
+                            next_deploy = Some(std::time::Instant::now() + d.debounce.max(deploy_backoff));
+                        }
+                    }
+                    // On failure, open pages reload into the error page
+                    Err(e) => {
+                        error!("{}", format!("Regeneration failed after {:.1}s: {:#}", started.elapsed().as_secs_f64(), e).red());
+                        *build_error.write().await = Some(e.chain().map(|cause| cause.to_string()).collect());
+                        if let Some(ref tx) = hot_reload_tx {
+                            let _ = tx.send(ChangedPosts::everything().message());
+                        }
+                    }
+                }
+            }
+            
+            match tokio::time::timeout(Duration::from_millis(100), rx.recv()).await {
+                Ok(Some(mut event)) => {
+                    event.paths.retain(|path| !config.watch.ignores(path));
+                    
+                    // Check if it's a markdown file, config file, stylesheet or under an extra watch path
+                    let is_relevant = event.paths.iter().any(|path| {
+                        path.extension().is_some_and(|ext| ext == "md") || 
+                        (is_html_post(path) && path.starts_with(&config.posts_dir)) ||
+                        InputFormat::of(path).is_some_and(|format| config.input_formats.contains(&format)) ||
+                        path.to_string_lossy().ends_with(annotations::SIDECAR_SUFFIX) ||
+                        path.file_name().is_some_and(|name| name == "config.json") ||
+                        config.theme.stylesheet.as_ref().is_some_and(|s| path.ends_with(s)) ||
+                        config.trails.as_ref().is_some_and(|t| path.ends_with(t)) ||
+                        path.ends_with(CUSTOM_STYLESHEET) || path.ends_with(CUSTOM_SCRIPT) ||
+                        config.watch.covers(path)
+                    });
+                    
+                    // Accept various event types, not just Modify
+                    let is_change = matches!(event.kind, notify::EventKind::Create(_) | notify::EventKind::Modify(_) | notify::EventKind::Remove(_));
+                    if is_relevant && is_change {
+                        // Trail navigation can appear on any post, every page runs the custom script
+                        // and extra watch paths can hold anything, so their edits reload every page
+                        trails_changed |= event.paths.iter().any(|path| {
+                            config.trails.as_ref().is_some_and(|t| path.ends_with(t)) || path.ends_with(CUSTOM_SCRIPT) || config.watch.covers(path)
+                        });
+                        queued.get_or_insert_with(ChangedPosts::default).add(&event);
+                        last_event = std::time::Instant::now();
+                        
+                        // The build in flight is already stale: stop it and fold its changes into the next one
+                        if let Some((handle, started, changed)) = building.take() {
+                            handle.abort();
+                            info!("{}", format!("More changes arrived, restarting the build ({:.1}s in)", started.elapsed().as_secs_f64()).yellow());
+                            queued.get_or_insert_with(ChangedPosts::default).merge(changed);
+                        }
+                    }
+                }
+                Ok(None) => break,
+                Err(_) => {}
+            }
+            
+            // Build once the queued changes have settled
+            if building.is_some() || last_event.elapsed() < BUILD_QUIET_PERIOD {
+                continue;
+            }
+            let Some(mut changed) = queued.take() else { continue };
+            changed.everything |= trails_changed;
+            
+            // Follow the stylesheet, trails file and extra watch paths to wherever the config now points
+            let new_config = Config::load(&config_path).ok();
+            if let Some(stylesheet) = new_config.as_ref().and_then(|c| c.theme.stylesheet.as_ref()) {
+                if config.theme.stylesheet.as_ref() != Some(stylesheet) {
+                    if let Some(watcher) = task_watcher.upgrade() {
+                        let _ = watcher.lock().unwrap().watch(Path::new(stylesheet), RecursiveMode::NonRecursive);
+                    }
+                }
+            }
+            if let Some(trails) = new_config.as_ref().and_then(|c| c.trails.as_ref()) {
+                if config.trails.as_ref() != Some(trails) {
+                    if let Some(watcher) = task_watcher.upgrade() {
+                        let _ = watcher.lock().unwrap().watch(Path::new(trails), RecursiveMode::NonRecursive);
+                    }
+                }
+            }
+            if let Some(new_config) = &new_config {
+                for path in new_config.watch.paths.iter().filter(|p| !config.watch.paths.contains(p)) {
+                    if let Some(watcher) = task_watcher.upgrade() {
+                        let _ = watcher.lock().unwrap().watch(Path::new(path), RecursiveMode::Recursive);
+                    }
+                }
+            }
+            
+            // Stylesheet edits only touch style.css, so leave the pages alone
+            if changed.slugs.is_empty() && !trails_changed && build_error.read().await.is_none() {
+                if let Some(new_config) = new_config.clone().filter(|c| only_stylesheet_differs(&config, c)) {
+                    if SiteGenerator::new(new_config.clone()).write_stylesheet().is_ok() {
+                        info!("{}", "Stylesheet changed, rebuilt style.css".green());
+                        if let Some(ref tx) = hot_reload_tx {
+                            let _ = tx.send(CSS_RELOAD_MESSAGE.to_string());
+                        }
+                        if let Some(ref d) = deploy {
+                            next_deploy = Some(std::time::Instant::now() + d.debounce.max(deploy_backoff));
+                        }
+                        config = new_config;
+                        trails_changed = false;
+                        continue;
+                    }
+                }
+            }
+            if let Some(new_config) = new_config {
+                config = new_config;
+            }
+            trails_changed = false;
+            
+            info!("{}", "File changed, regenerating site...".yellow());
+            let path = config_path.clone();
+            building = Some((tokio::spawn(async move { regenerate_site(&path).await }), std::time::Instant::now(), changed));
+        }
+    });
+    
+    Ok(WatcherHandle {
+        _watcher: watcher,
+        _task_handle: task_handle,
+    })
+}
+
+
+async fn regenerate_site(config_path: &PathBuf) -> Result<BuildSummary> {
+    let config = Config::load(config_path)?;
+    let notify_config = config.notifications.clone();
+    let site_title = config.title.clone();
+    let mut generator = SiteGenerator::new(config);
+    let result = generator.generate().await;
+    
+    let outcome = match &result {
+        Ok(()) => BuildOutcome::Success,
+        Err(e) => BuildOutcome::Failure(format!("{:#}", e)),
+    };
+    notifications::notify_desktop(&notify_config, &site_title, &outcome);
+    
+    result.map(|()| generator.into_summary())
+}
+
+/// One line describing a watch-mode rebuild, e.g. "2 pages updated, index regenerated, CSS unchanged".
+fn describe_rebuild(summary: &BuildSummary) -> String {
+    let is_page = |p: &PathBuf| p.extension().is_some_and(|ext| ext == "html") && p != Path::new("index.html");
+    let is_other = |p: &PathBuf| !is_page(p) && p != Path::new("index.html") && p != Path::new("style.css");
+    let changed = |p: &Path| summary.created.iter().chain(&summary.updated).any(|c| c == p);
+    let plural = |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+    
+    let mut parts = Vec::new();
+    let created = summary.created.iter().filter(|p| is_page(p)).count();
+    let updated = summary.updated.iter().filter(|p| is_page(p)).count();
+    if updated > 0 {
+        parts.push(format!("{} updated", plural(updated, "page")).yellow().to_string());
+    }
+    if created > 0 {
+        parts.push(format!("{} created", plural(created, "page")).green().to_string());
+    }
+    if created + updated == 0 {
+        parts.push("pages unchanged".dimmed().to_string());
+    }
+    parts.push(if changed(Path::new("index.html")) { "index regenerated".yellow() } else { "index unchanged".dimmed() }.to_string());
+    parts.push(if changed(Path::new("style.css")) { "CSS updated".yellow() } else { "CSS unchanged".dimmed() }.to_string());
+    let others = summary.created.iter().chain(&summary.updated).filter(|p| is_other(p)).count();
+    if others > 0 {
+        parts.push(format!("{} updated", plural(others, "other file")).yellow().to_string());
+    }
+    if !summary.removed.is_empty() {
+        parts.push(format!("{} removed", plural(summary.removed.len(), "stale page")).red().to_string());
+    }
+    parts.join(", ")
+}
+
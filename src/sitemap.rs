@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+
+/// The sitemap search engines are pointed at: the URL set itself, or an index of chunks.
+pub const FILE: &str = "sitemap.xml";
+
+/// Most URLs one sitemap file may list.
+pub const CHUNK_SIZE: usize = 50_000;
+
+/// A page listed in the sitemap.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub loc: String,
+    pub lastmod: Option<DateTime<Utc>>,
+}
+
+/// Name of the `n`th (1-based) chunk of a sitemap too large for one file.
+pub fn chunk_file(n: usize) -> String {
+    format!("sitemap-{}.xml", n)
+}
+
+/// Whether `name` is a sitemap chunk file, e.g. `sitemap-3.xml`.
+pub fn is_chunk_file(name: &str) -> bool {
+    name.strip_prefix("sitemap-")
+        .and_then(|rest| rest.strip_suffix(".xml"))
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Sitemap files for `entries` as `(file name, content)`: one `sitemap.xml` when they fit, or
+/// else `sitemap.xml` indexing chunks of [`CHUNK_SIZE`]. Entries should come oldest first, so
+/// new posts only change the last chunk and the index, and unchanged chunks keep their bytes.
+pub fn render(entries: &[Entry], site_url: &str) -> Vec<(String, String)> {
+    if entries.len() <= CHUNK_SIZE {
+        return vec![(FILE.to_string(), url_set(entries))];
+    }
+    let mut files = Vec::new();
+    let mut index = Vec::new();
+    for (i, chunk) in entries.chunks(CHUNK_SIZE).enumerate() {
+        let name = chunk_file(i + 1);
+        index.push(Entry { loc: format!("{}{}", site_url, name), lastmod: chunk.iter().filter_map(|e| e.lastmod).max() });
+        files.push((name, url_set(chunk)));
+    }
+    files.push((FILE.to_string(), document("sitemapindex", "sitemap", &index)));
+    files
+}
+
+fn url_set(entries: &[Entry]) -> String {
+    document("urlset", "url", entries)
+}
+
+fn document(root: &str, element: &str, entries: &[Entry]) -> String {
+    let mut xml = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<{} xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n", root);
+    for entry in entries {
+        xml.push_str(&format!("  <{}>\n    <loc>{}</loc>\n", element, escape(&entry.loc)));
+        if let Some(lastmod) = entry.lastmod {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", lastmod.format("%Y-%m-%d")));
+        }
+        xml.push_str(&format!("  </{}>\n", element));
+    }
+    xml.push_str(&format!("</{}>\n", root));
+    xml
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
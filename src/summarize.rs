@@ -0,0 +1,120 @@
+use crate::config::{Config, SummaryOptions};
+use crate::frontmatter;
+use crate::generator::{find_post_file, Post};
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use tracing::{error, info, warn};
+
+/// Longest stretch of a post sent to the model, in characters.
+const MAX_INPUT_CHARS: usize = 24_000;
+
+/// Summaries of one post, as returned by the model.
+#[derive(Debug, Deserialize)]
+pub struct Summary {
+    /// One sentence for the index and `<meta name="description">`
+    pub excerpt: String,
+    /// Two or three sentences shown above the post
+    pub tldr: String,
+}
+
+/// Whether `post` lacks an authored excerpt or TL;DR. Private posts are never sent off to be
+/// summarized.
+pub fn needs_summary(post: &Post) -> bool {
+    !post.is_private() && (post.frontmatter.excerpt.is_none() || post.frontmatter.tldr.is_none())
+}
+
+/// Ask the OpenAI chat API to summarize a post.
+pub async fn request(title: &str, markdown: &str, options: &SummaryOptions, api_key: &str) -> Result<Summary> {
+    let body: String = markdown.chars().take(MAX_INPUT_CHARS).collect();
+    let request_body = serde_json::json!({
+        "model": options.model,
+        "response_format": { "type": "json_object" },
+        "messages": [
+            {
+                "role": "system",
+                "content": "You summarize blog posts in the author's voice. Reply with a JSON object with two string fields: \"excerpt\", one sentence of at most 160 characters for a post listing, and \"tldr\", two or three sentences giving the post's main points. Plain text only, no Markdown."
+            },
+            { "role": "user", "content": format!("Title: {}\n\n{}", title, body) }
+        ]
+    });
+
+    let response = reqwest::Client::new()
+        .post("https://api.openai.com/v1/chat/completions")
+        .header("Authorization", format!("Bearer {}", api_key))
+        .json(&request_body)
+        .send()
+        .await?;
+    let status = response.status();
+    let response_text = response.text().await?;
+    if !status.is_success() {
+        anyhow::bail!("API call failed with status {}: {}", status, response_text);
+    }
+
+    let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
+    let content = response_json
+        .pointer("/choices/0/message/content")
+        .and_then(|c| c.as_str())
+        .context("Could not extract the summary from the API response")?;
+    serde_json::from_str(content).context("The model did not return an excerpt and TL;DR")
+}
+
+/// Summarize every post in `posts` missing an excerpt or TL;DR and write the missing fields into
+/// its frontmatter, so later builds reuse them. Returns the number of posts updated.
+pub async fn summarize_posts(config: &Config, posts: Vec<&Post>) -> Result<usize> {
+    let Some(api_key) = config.openai_api_key.clone() else {
+        anyhow::bail!("OPENAI_API_KEY not found in environment or config");
+    };
+    let pending: Vec<&Post> = posts.into_iter().filter(|p| needs_summary(p)).collect();
+    if pending.is_empty() {
+        return Ok(0);
+    }
+    info!("{}", format!("Summarizing {} post(s)...", pending.len()).cyan());
+
+    let mut tasks = Vec::new();
+    for post in pending {
+        let api_key = api_key.clone();
+        let options = config.summaries.clone();
+        let (title, markdown) = (post.title.clone(), post.content.clone());
+        let task = tokio::spawn(async move { request(&title, &markdown, &options, &api_key).await });
+        tasks.push((task, post));
+    }
+
+    let mut updated = 0;
+    for (task, post) in tasks {
+        match task.await {
+            Ok(Ok(summary)) => {
+                let Some(path) = find_post_file(Path::new(&config.posts_dir), &config.input_formats, &post.original_slug) else {
+                    warn!("{}", format!("Warning: source of '{}' not found, summary dropped", post.slug).yellow());
+                    continue;
+                };
+                write_summary(&path, post, &summary)?;
+                info!("{} {}", "Summarized".green(), path.display());
+                updated += 1;
+            }
+            Ok(Err(e)) => error!("{}", format!("Failed to summarize '{}': {:#}", post.slug, e).red()),
+            Err(e) => error!("{}", format!("Task failed summarizing '{}': {}", post.slug, e).red()),
+        }
+    }
+    Ok(updated)
+}
+
+/// Add the fields `post` is missing to the frontmatter at `path`, leaving authored ones alone.
+fn write_summary(path: &Path, post: &Post, summary: &Summary) -> Result<()> {
+    let mut content = fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    if !frontmatter::has_block(&content) {
+        content = format!("---\n---\n\n{}", content);
+    }
+    let single_line = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+    if post.frontmatter.excerpt.is_none() {
+        content = frontmatter::set_field(&content, "excerpt", &single_line(&summary.excerpt))
+            .context(format!("Failed to update {}", path.display()))?;
+    }
+    if post.frontmatter.tldr.is_none() {
+        content = frontmatter::set_field(&content, "tldr", &single_line(&summary.tldr))
+            .context(format!("Failed to update {}", path.display()))?;
+    }
+    fs::write(path, content).context(format!("Failed to write {}", path.display()))
+}
@@ -1,16 +1,51 @@
-use crate::config::Config;
-use crate::generator::Post;
+use crate::api;
+use crate::avatar;
+use crate::config::{Analytics, Comments, Config, Direction, InitialsMode, MathMode, MermaidMode, TrailingSlash};
+use crate::feed;
+use crate::fonts;
+use crate::frontmatter::PageLayout;
+use crate::generator::{is_remote_asset, series_members, series_path, Post, ARCHIVE_PAGE, CUSTOM_SCRIPT, CUSTOM_SCRIPT_FILE, GRAPH_PAGE};
+use crate::initials;
+use crate::markdown;
+use crate::math;
+use crate::mermaid;
+use crate::private;
+use crate::pwa;
+use crate::trails::Trail;
+use crate::webmention::{Kind, Mention};
 use anyhow::Result;
-use regex;
+use chrono::Datelike;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 
-pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_meta_json: Option<String>) -> Result<String> {
-    let backlinks = find_backlinks(all_posts, &post.slug, &post.original_slug);
+pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], trails: &[Trail], annotation_meta_json: Option<String>) -> Result<String> {
+    let backlinks = find_backlinks(all_posts, post, config.trailing_slash);
     
-    let has_initial = post.first_letter.is_some();
+    // Use relative paths (works for both regular hosting and IPFS)
+    let home_path = &config.trailing_slash.page_root(&post.slug);
+    let language_home = format!("{}{}", home_path, config.languages.index_path(&post.lang));
+    
+    // Illuminated initial: an SVG drop cap, or a generated image saved in the output directory
+    let initial = post.first_letter.and_then(|letter| {
+        let src = match config.initials.mode {
+            InitialsMode::Svg => Some(initials::svg_data_url(letter, &config.initials.svg)),
+            InitialsMode::Openai => {
+                let output_dir = std::path::Path::new(&config.output_dir);
+                initials::find(&initials::dir(output_dir, &config.initials, &post.slug), letter).map(|path| {
+                    let relative = path.strip_prefix(output_dir).unwrap_or(&path);
+                    let segments: Vec<_> = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+                    format!("{}{}", home_path, segments.join("/"))
+                })
+            }
+            InitialsMode::None => None,
+        };
+        src.map(|src| (letter, src))
+    });
     
     // Remove the first letter from the first paragraph if we have an illuminated initial
     let mut processed_content = post.html_content.clone();
-    if has_initial {
+    if initial.is_some() {
         // Find the first paragraph and remove its first letter
         let re = regex::Regex::new(r"<p>([^<])(.*?)</p>").unwrap();
         processed_content = re.replace(&processed_content, |caps: &regex::Captures| {
@@ -18,28 +53,22 @@ pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_
         }).to_string();
     }
     // Rewrite internal links that may reference original, unsanitized slugs
-    processed_content = rewrite_internal_links(&processed_content, all_posts);
-
-    // Load the illuminated initial data URL if it exists
-    let initial_html = if has_initial {
-        let initial_path = std::path::Path::new(&config.output_dir).join("initials").join(format!("{}.txt", post.first_letter.unwrap()));
-        if initial_path.exists() {
-            if let Ok(image_data) = std::fs::read_to_string(initial_path) {
-                format!(
-                    r#"<div class="illuminated-initial">
+    processed_content = rewrite_internal_links(&processed_content, all_posts, config.trailing_slash);
+    processed_content = apply_base_path(&processed_content, &config.base_path());
+    processed_content = markdown::restore_raw_blocks(&processed_content, &post.raw_blocks);
+    if config.mermaid.mode == MermaidMode::Svg {
+        processed_content = mermaid::inline(&processed_content, &config.mermaid);
+    }
+
+    let initial_html = match initial {
+        Some((letter, src)) => format!(
+            r#"<div class="illuminated-initial">
                         <img src="{}" alt="Illuminated initial {}" class="initial-image">
                     </div>"#,
-                    image_data,
-                    post.first_letter.unwrap()
-                )
-            } else {
-                String::new()
-            }
-        } else {
-            String::new()
-        }
-    } else {
-        String::new()
+            src,
+            letter
+        ),
+        None => String::new(),
     };
     
     let backlinks_html = if backlinks.is_empty() {
@@ -47,7 +76,14 @@ pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_
     } else {
         let links: String = backlinks
             .iter()
-            .map(|link| format!("<li><a href=\"{}\">{}</a></li>", link.url, link.title))
+            .map(|link| {
+                let context = if link.context.is_empty() {
+                    String::new()
+                } else {
+                    format!("<p class=\"backlink-context\">{}</p>", html_escape(&link.context))
+                };
+                format!("<li><a href=\"{}\">{}</a>{}</li>", link.url, link.title, context)
+            })
             .collect::<Vec<_>>()
             .join("\n                    ");
         
@@ -63,9 +99,6 @@ pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_
         )
     };
     
-    // Use relative paths (works for both regular hosting and IPFS)
-    let (css_path, home_path) = ("../style.css", "../");
-
     let annotation_meta = match annotation_meta_json {
         Some(json) if !json.is_empty() => format!("<script id=\"annotation-meta\" type=\"application/json\">{}</script>", json),
         _ => String::new(),
@@ -77,45 +110,198 @@ pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_
         _ => String::new(),
     };
     let meta_published = format!("<meta property=\"article:published_time\" content=\"{}\">", post.date.to_rfc3339());
+    let meta_modified = match post.frontmatter.parsed_updated() {
+        Some(updated) => format!("<meta property=\"article:modified_time\" content=\"{}\">", updated.to_rfc3339()),
+        None => String::new(),
+    };
 
-    let html = format!(
-        r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    {}
-    {}
-    <title>{} - {}</title>
-    <link rel="stylesheet" href="{}">
-    <link rel="preconnect" href="https://fonts.googleapis.com">
-    <link rel="preconnect" href="https://fonts.gstatic.com" crossorigin>
-    <link href="https://fonts.googleapis.com/css2?family=Crimson+Text:ital,wght@0,400;0,600;1,400&family=Inter:wght@400;600;700&display=swap" rel="stylesheet">
-    {}
-</head>
-<body>
-    <div class="container">
-        <header>
-            <div class="header-content">
-                <a href="{}" class="main-title">{}</a>
-            </div>
-        </header>
-        
-        <main class="content">
+    // Previous/next navigation within the post's series
+    let series_html = match post.series() {
+        Some(name) => {
+            let members = series_members(all_posts, name);
+            let position = members.iter().position(|p| p.slug == post.slug).unwrap_or(0);
+            let link = |p: &Post, class: &str, label: String| {
+                format!("<a class=\"{}\" href=\"{}\">{}</a>", class, config.trailing_slash.post_href(home_path, &p.slug), label)
+            };
+            let previous = position
+                .checked_sub(1)
+                .and_then(|i| members.get(i))
+                .map(|p| link(p, "series-prev", format!("← {}", p.title)))
+                .unwrap_or_default();
+            let next = members
+                .get(position + 1)
+                .map(|p| link(p, "series-next", format!("{} →", p.title)))
+                .unwrap_or_default();
+            format!(
+                r#"
+            <nav class="series-nav">
+                <p class="series-label">Part {} of {} in <a href="{}">{}</a></p>
+                <div class="series-links">{}{}</div>
+            </nav>"#,
+                position + 1,
+                members.len(),
+                config.trailing_slash.post_href(home_path, &series_path(name)),
+                name,
+                previous,
+                next
+            )
+        }
+        None => String::new(),
+    };
+
+    // Next step on each curated trail the post is on
+    let trail_html: String = trails
+        .iter()
+        .filter_map(|trail| {
+            let members = trail.members(all_posts);
+            let position = members.iter().position(|(p, _)| p.slug == post.slug)?;
+            let next = match members.get(position + 1) {
+                Some((next, blurb)) => format!(
+                    r#"<a class="trail-next" href="{}">Next: {} →</a>{}"#,
+                    config.trailing_slash.post_href(home_path, &next.slug),
+                    next.title,
+                    blurb.map(|b| format!("<p class=\"trail-blurb\">{}</p>", html_escape(b))).unwrap_or_default()
+                ),
+                None => "<p class=\"trail-end\">You've reached the end of this trail.</p>".to_string(),
+            };
+            Some(format!(
+                r#"
+            <nav class="trail-nav">
+                <p class="trail-label">Step {} of {} on the <a href="{}">{}</a> trail</p>
+                {}
+            </nav>"#,
+                position + 1,
+                members.len(),
+                config.trailing_slash.post_href(home_path, &trail.path()),
+                trail.name,
+                next
+            ))
+        })
+        .collect();
+
+    // Author card and og:image from the cached avatar
+    let meta_image = avatar_meta(avatar::public_url(config, home_path).as_deref());
+    let author_card = match &config.avatar {
+        Some(avatar) => format!(
+            r#"
+            <section class="author-card">
+                <img src="{}{}" alt="{}" class="author-avatar">
+                <span class="author-name">{}</span>
+            </section>"#,
+            home_path, avatar::file_name(avatar), config.author, config.author
+        ),
+        None => String::new(),
+    };
+
+    let tldr_html = match post.frontmatter.tldr.as_deref().map(str::trim) {
+        Some(tldr) if !tldr.is_empty() => format!(
+            r#"
+                <aside class="tldr"><span class="tldr-label">TL;DR</span> {}</aside>"#,
+            html_escape(tldr)
+        ),
+        _ => String::new(),
+    };
+    let tldr_html = partial(config, "tldr", tldr_html);
+    let reading_time_html = if config.theme.reading_time {
+        format!(
+            r#"
+                <p class="reading-time">{} min read</p>"#,
+            post.reading_minutes()
+        )
+    } else {
+        String::new()
+    };
+    let breadcrumbs_html = partial(config, "breadcrumbs", render_breadcrumbs(config, &breadcrumbs(config, post, home_path)));
+    let initial_html = partial(config, "initial", initial_html);
+    let processed_content = partial(config, "content", processed_content);
+    let series_html = partial(config, "series-nav", series_html);
+    let trail_html = partial(config, "trail-nav", trail_html);
+    let author_card = partial(config, "author-card", author_card);
+    let backlinks_html = partial(config, "backlinks", backlinks_html);
+    let webmentions_html = partial(config, "webmentions", render_webmentions(&post.webmentions));
+    let comments_html = match &config.comments {
+        Some(comments) if post.frontmatter.comments != Some(false) => render_comments(comments, &post.lang),
+        _ => String::new(),
+    };
+    let comments_html = partial(config, "comments", comments_html);
+    let annotation_meta = partial(config, "annotation-meta", annotation_meta);
+    let mut renderers_head = if config.math == MathMode::Katex && processed_content.contains("class=\"math ") {
+        katex_head(home_path)
+    } else {
+        String::new()
+    };
+    if config.mermaid.mode == MermaidMode::Script && processed_content.contains("<pre class=\"mermaid\">") {
+        renderers_head.push_str(&mermaid_head(home_path, &config.mermaid.theme));
+    }
+    // Where other sites send webmentions (and pingbacks) for this post
+    let webmention_links = match &config.webmention {
+        Some(webmention) => {
+            let mut links = format!("\n    <link rel=\"webmention\" href=\"{}\">", html_escape(&webmention.endpoint()));
+            if webmention.pingback {
+                links.push_str(&format!("\n    <link rel=\"pingback\" href=\"{}\">", html_escape(&webmention.pingback_endpoint())));
+            }
+            links
+        }
+        None => String::new(),
+    };
+    let api_link = if config.api {
+        format!("\n    <link rel=\"alternate\" type=\"application/json\" href=\"{}\">", api::href(config.trailing_slash, &post.slug))
+    } else {
+        String::new()
+    };
+
+    // The post's own stylesheets at the end of the head, its scripts after the page's
+    let asset_href = |asset: &String| match post.asset_path(asset) {
+        Some(path) => Some(format!("{}{}", home_path, path)),
+        None => is_remote_asset(asset).then(|| html_escape(asset)),
+    };
+    let post_css: String = post.frontmatter.css.iter().filter_map(asset_href).map(|href| format!("\n    <link rel=\"stylesheet\" href=\"{}\">", href)).collect();
+    let post_js: String = post.frontmatter.js.iter().filter_map(asset_href).map(|href| format!("\n    <script src=\"{}\" defer></script>", href)).collect();
+
+    let head = format!(
+        "{}\n    {}\n    {}\n    {}\n    {}{}{}{}{}",
+        meta_description, meta_published, meta_modified, meta_image, annotation_meta, webmention_links, api_link, renderers_head, post_css
+    );
+    // A bare post is its content alone, without the title and the navigation around it
+    let main = if post.frontmatter.layout == Some(PageLayout::Bare) {
+        format!(
+            r#"
+            <article>
+                <div class="post-content">
+                    {}
+                </div>
+            </article>"#,
+            processed_content
+        )
+    } else {
+        format!(
+            r#"{}
             <article>
-                <h1 class="post-title">{}</h1>
+                <h1 class="post-title">{}</h1>{}{}
                 <div class="post-content">
                     {}
                     {}
                 </div>
             </article>
+            {}{}
             {}
-        </main>
-        
-        <footer>
-            <a href="{}" class="home-link">← Back to all posts</a>
-        </footer>
-    </div>
+            {}{}{}"#,
+            breadcrumbs_html,
+            post.title,
+            reading_time_html,
+            tldr_html,
+            initial_html,
+            processed_content,
+            series_html,
+            trail_html,
+            author_card,
+            backlinks_html,
+            webmentions_html,
+            comments_html
+        )
+    };
+    let mut scripts = format!(
+        r#"
     <script>
     document.addEventListener('DOMContentLoaded', function() {{
         var meta = {{}};
@@ -123,10 +309,35 @@ pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_
         if (metaEl) {{
             try {{ meta = JSON.parse(metaEl.textContent || '{{}}'); }} catch(e) {{ meta = {{}}; }}
         }}
+        // Self-hosted favicon for a link, from its metadata entry
+        var siteRoot = '{}';
+        function faviconFor(path) {{
+            if (!path) return null;
+            var img = document.createElement('img');
+            img.className = 'annotation-favicon';
+            img.src = siteRoot + path;
+            img.alt = '';
+            img.loading = 'lazy';
+            return img;
+        }}
+
+        // Quoteback sources: show the fetched page title for bare URLs, and the description on hover
+        document.querySelectorAll('.quoteback-source').forEach(function(a) {{
+            var url = a.getAttribute('href');
+            var entry = meta[url] || meta[url.replace(/\/$/, '')] || meta[url + '/'];
+            if (!entry) return;
+            if (entry.title && a.hasAttribute('data-auto-title')) a.textContent = entry.title;
+            if (entry.description) a.title = entry.description;
+            var icon = a.parentElement.querySelector('.quoteback-favicon');
+            if (icon && entry.favicon) {{
+                icon.src = siteRoot + entry.favicon;
+                icon.hidden = false;
+            }}
+        }});
         var paragraphs = document.querySelectorAll('.post-content p');
         paragraphs.forEach(function(p) {{
             var text = (p.textContent || '').trim();
-            if (!text) return;
+            if (!text || p.closest('.raw-html')) return;
             var a = document.createElement('a');
             a.className = 'exa-link';
             a.target = '_blank';
@@ -139,6 +350,7 @@ pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_
         // Annotations: convert fenced blocks (```links / ```anno) into folded panels attached to the previous paragraph/list
         var codeBlocks = Array.prototype.slice.call(document.querySelectorAll('.post-content pre > code'));
         codeBlocks.forEach(function(code) {{
+            if (code.closest('.raw-html')) return;
             var cls = (code.getAttribute('class') || '').toLowerCase();
             var text = (code.textContent || '').trim();
             var isAnnotated = false;
@@ -201,7 +413,11 @@ pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_
                     }}
                 }}
 
-                if (!url) return;
+                // Lines without a URL are plain notes
+                if (!url) {{
+                    items.push({{ note: line }});
+                    return;
+                }}
                 if (!title) {{
                     try {{
                         var u = new URL(url);
@@ -227,7 +443,7 @@ pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_
                     if (metaEntry.description) desc = metaEntry.description;
                 }}
 
-                items.push({{ title: title, url: url, desc: desc }});
+                items.push({{ title: title, url: url, desc: desc, favicon: metaEntry && metaEntry.favicon }});
             }});
 
             if (!items.length) return;
@@ -242,6 +458,16 @@ pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_
                 var wrap = document.createElement('div');
                 wrap.className = 'annotation-item';
 
+                if (it.note) {{
+                    var note = document.createElement('div');
+                    note.className = 'annotation-item-note';
+                    note.textContent = it.note;
+                    wrap.appendChild(note);
+                    li.appendChild(wrap);
+                    ul.appendChild(li);
+                    return;
+                }}
+
                 var titleLine = document.createElement('div');
                 titleLine.className = 'annotation-item-titleline';
                 var aTitle = document.createElement('a');
@@ -258,6 +484,8 @@ pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_
                 aUrl.target = '_blank';
                 aUrl.rel = 'noopener noreferrer';
 
+                var icon = faviconFor(it.favicon);
+                if (icon) titleLine.appendChild(icon);
                 titleLine.appendChild(aTitle);
                 titleLine.appendChild(document.createTextNode(' '));
                 titleLine.appendChild(aUrl);
@@ -307,6 +535,7 @@ pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_
         // Annotations: detect plain paragraph 'Links:' followed by a list and fold it under previous block
         var all = Array.prototype.slice.call(document.querySelectorAll('.post-content p'));
         all.forEach(function(marker) {{
+            if (marker.closest('.raw-html')) return;
             var txt = (marker.textContent || '').trim().toLowerCase();
             if (txt !== 'links:' && txt !== 'links' && txt !== 'annotations:' && txt !== 'annotations') return;
             var list = marker.nextElementSibling;
@@ -354,6 +583,8 @@ pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_
                 aUrl.textContent = '(' + url + ')';
                 aUrl.target = '_blank';
                 aUrl.rel = 'noopener noreferrer';
+                var icon = faviconFor(metaEntry && metaEntry.favicon);
+                if (icon) titleLine.appendChild(icon);
                 titleLine.appendChild(aTitle);
                 titleLine.appendChild(document.createTextNode(' '));
                 titleLine.appendChild(aUrl);
@@ -392,66 +623,217 @@ pub fn render_post(config: &Config, post: &Post, all_posts: &[Post], annotation_
             marker.parentElement && marker.parentElement.removeChild(marker);
         }});
     }});
-    </script>
-</body>
-    </html>"#,
-        meta_description,
-        meta_published,
-        post.title,
-        config.title,
-        css_path,
-        annotation_meta,
-        home_path,
-        config.title.to_uppercase(),
-        post.title,
-        initial_html,
-        processed_content,
-        backlinks_html,
+    </script>"#,
         home_path
     );
+    scripts.push_str(&post_js);
+
+    // The post in each language it is translated into
+    let alternates = if config.languages.is_multilingual() {
+        all_posts
+            .iter()
+            .filter(|p| p.translation_key() == post.translation_key())
+            .map(|p| Alternate { lang: p.lang.clone(), path: config.trailing_slash.post_href("", &p.slug) })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let page = post.frontmatter.layout.unwrap_or(PageLayout::Default);
+    let html = render_layout(config, &Layout {
+        title: format!("{} - {}", post.title, config.title),
+        home_path,
+        head,
+        main,
+        footer: if page == PageLayout::Bare { String::new() } else { back_home_footer(&language_home) },
+        scripts,
+        lang: &post.lang,
+        dir: post.frontmatter.dir,
+        alternates,
+        page,
+    });
     
-    Ok(html)
+    Ok(annotate_page(config, "post", html))
+}
+
+/// Likes, reposts and bookmarks as lines of names, then replies and mentions with their text.
+fn render_webmentions(mentions: &[Mention]) -> String {
+    if mentions.is_empty() {
+        return String::new();
+    }
+    let author = |m: &Mention| match &m.author_url {
+        Some(url) => format!("<a href=\"{}\" rel=\"nofollow ugc\">{}</a>", html_escape(url), html_escape(&m.author)),
+        None => html_escape(&m.author),
+    };
+    let mut reactions = String::new();
+    for (kind, verb) in [(Kind::Like, "Liked"), (Kind::Repost, "Reposted"), (Kind::Bookmark, "Bookmarked")] {
+        let names: Vec<String> = mentions.iter().filter(|m| m.kind == kind).map(author).collect();
+        if !names.is_empty() {
+            reactions.push_str(&format!(
+                "\n                <p class=\"webmention-reactions\">{} by {}</p>",
+                verb,
+                names.join(", ")
+            ));
+        }
+    }
+    let items: Vec<String> = mentions
+        .iter()
+        .filter(|m| matches!(m.kind, Kind::Reply | Kind::Mention))
+        .map(|m| {
+            let verb = if m.kind == Kind::Reply { "replied" } else { "mentioned this" };
+            let date = m.published.map(|d| format!(" on {}", d.format("%Y-%m-%d"))).unwrap_or_default();
+            let text = if m.text.is_empty() {
+                String::new()
+            } else {
+                format!("<p class=\"webmention-text\">{}</p>", html_escape(&m.text))
+            };
+            format!(
+                "<li>{} <a href=\"{}\" class=\"webmention-source\" rel=\"nofollow ugc\">{}{}</a>{}</li>",
+                author(m),
+                html_escape(&m.url),
+                verb,
+                date,
+                text
+            )
+        })
+        .collect();
+    let list = if items.is_empty() {
+        String::new()
+    } else {
+        format!("\n                <ul>\n                    {}\n                </ul>", items.join("\n                    "))
+    };
+    format!(
+        r#"
+            <section class="webmentions">
+                <h2>Webmentions</h2>{}{}
+            </section>"#,
+        reactions, list
+    )
+}
+
+/// The giscus or utterances widget, which loads the post's thread in the reader's browser.
+fn render_comments(comments: &Comments, lang: &str) -> String {
+    let script = match comments {
+        Comments::Giscus { repo, repo_id, category, category_id, mapping, theme } => format!(
+            r#"<script src="https://giscus.app/client.js" data-repo="{}" data-repo-id="{}" data-category="{}" data-category-id="{}" data-mapping="{}" data-strict="1" data-reactions-enabled="1" data-emit-metadata="0" data-input-position="top" data-theme="{}" data-lang="{}" data-loading="lazy" crossorigin="anonymous" async></script>"#,
+            html_escape(repo),
+            html_escape(repo_id),
+            html_escape(category),
+            html_escape(category_id),
+            html_escape(mapping),
+            html_escape(theme),
+            html_escape(lang)
+        ),
+        Comments::Utterances { repo, issue_term, theme, label } => format!(
+            r#"<script src="https://utteranc.es/client.js" repo="{}" issue-term="{}" theme="{}"{} crossorigin="anonymous" async></script>"#,
+            html_escape(repo),
+            html_escape(issue_term),
+            html_escape(theme),
+            label.as_ref().map(|l| format!(" label=\"{}\"", html_escape(l))).unwrap_or_default()
+        ),
+    };
+    format!(
+        r#"
+            <section class="comments">
+                {}
+            </section>"#,
+        script
+    )
+}
+
+/// The bundled KaTeX stylesheet and script, and a script typesetting each `.math` element with
+/// them once the page has loaded.
+fn katex_head(home_path: &str) -> String {
+    format!(
+        r#"
+    <link rel="stylesheet" href="{0}{1}/katex.min.css">
+    <script defer src="{0}{1}/katex.min.js"></script>
+    <script>
+    document.addEventListener('DOMContentLoaded', function() {{
+        if (!window.katex) return;
+        document.querySelectorAll('.post-content .math').forEach(function(el) {{
+            katex.render(el.textContent, el, {{ displayMode: el.classList.contains('math-display'), throwOnError: false }});
+        }});
+    }});
+    </script>"#,
+        home_path,
+        math::DIR
+    )
+}
+
+/// The bundled mermaid.js runtime, drawing each `<pre class="mermaid">` once the page has loaded.
+fn mermaid_head(home_path: &str, theme: &str) -> String {
+    format!(
+        r#"
+    <script defer src="{0}{1}/mermaid.min.js"></script>
+    <script>
+    document.addEventListener('DOMContentLoaded', function() {{
+        if (!window.mermaid) return;
+        mermaid.initialize({{ startOnLoad: false, theme: '{2}' }});
+        mermaid.run({{ querySelector: '.post-content pre.mermaid' }});
+    }});
+    </script>"#,
+        home_path,
+        mermaid::DIR,
+        theme.replace(['\\', '\''], "")
+    )
 }
 
-fn rewrite_internal_links(content: &str, all_posts: &[Post]) -> String {
-    let mut result = content.to_string();
+/// Point internal links at sanitized slugs and apply the trailing-slash policy, keeping the authored prefix.
+fn rewrite_internal_links(content: &str, all_posts: &[Post], policy: TrailingSlash) -> String {
+    // Original and sanitized slug -> sanitized slug
+    let mut slugs: HashMap<&str, &str> = HashMap::with_capacity(all_posts.len() * 2);
     for p in all_posts {
-        if p.original_slug != p.slug {
-            let pairs = [
-                // absolute
-                (format!("href=\"/{}/\"", p.original_slug), format!("href=\"/{}/\"", p.slug)),
-                (format!("href=\"/{}\"", p.original_slug), format!("href=\"/{}/\"", p.slug)),
-                (format!("href=\"/{}.md\"", p.original_slug), format!("href=\"/{}/\"", p.slug)),
-                // dot-relative
-                (format!("href=\"./{}/\"", p.original_slug), format!("href=\"./{}/\"", p.slug)),
-                (format!("href=\"./{}\"", p.original_slug), format!("href=\"./{}/\"", p.slug)),
-                (format!("href=\"./{}.md\"", p.original_slug), format!("href=\"./{}/\"", p.slug)),
-                // dotdot-relative
-                (format!("href=\"../{}/\"", p.original_slug), format!("href=\"../{}/\"", p.slug)),
-                (format!("href=\"../{}\"", p.original_slug), format!("href=\"../{}/\"", p.slug)),
-                (format!("href=\"../{}.md\"", p.original_slug), format!("href=\"../{}/\"", p.slug)),
-                // plain relative (no ./)
-                (format!("href=\"{}/\"", p.original_slug), format!("href=\"{}/\"", p.slug)),
-                (format!("href=\"{}\"", p.original_slug), format!("href=\"{}/\"", p.slug)),
-                (format!("href=\"{}.md\"", p.original_slug), format!("href=\"{}/\"", p.slug)),
-            ];
-            for (from, to) in pairs {
-                result = result.replace(&from, &to);
-            }
+        slugs.insert(p.original_slug.as_str(), p.slug.as_str());
+        slugs.insert(p.slug.as_str(), p.slug.as_str());
+    }
+
+    let href = regex::Regex::new(r#"href="([^"]*)""#).unwrap();
+    href.replace_all(content, |caps: &regex::Captures| {
+        let target = &caps[1];
+        // absolute, dot-relative, dotdot-relative and plain relative forms
+        let (prefix, rest) = ["../", "./", "/"]
+            .iter()
+            .find_map(|prefix| target.strip_prefix(prefix).map(|rest| (*prefix, rest)))
+            .unwrap_or(("", target));
+        let slug = slugs.get(rest).or_else(|| {
+            ["/", ".md"].iter().find_map(|suffix| rest.strip_suffix(suffix).and_then(|source| slugs.get(source)))
+        });
+        match slug {
+            Some(slug) => format!("href=\"{}\"", policy.post_href(prefix, slug)),
+            None => caps[0].to_string(),
         }
+    })
+    .to_string()
+}
+
+/// Prefix root-relative `href` and `src` attributes with the site's base path, so authored
+/// links like `/images/a.png` keep working when the site lives under a subdirectory.
+fn apply_base_path(content: &str, base: &str) -> String {
+    if base == "/" {
+        return content.to_string();
     }
-    result
+    let root_relative = regex::Regex::new(r#"\b(href|src)="(/(?:[^/"][^"]*)?)""#).unwrap();
+    root_relative
+        .replace_all(content, |caps: &regex::Captures| {
+            let path = &caps[2];
+            if path.starts_with(base) || path == base.trim_end_matches('/') {
+                return caps[0].to_string();
+            }
+            format!("{}=\"{}{}\"", &caps[1], base, &path[1..])
+        })
+        .to_string()
 }
 
-pub fn render_index(config: &Config, posts: &[Post]) -> Result<String> {
-    let posts_list: String = posts
+fn render_post_list(config: &Config, posts: &[&Post], root: &str) -> String {
+    posts
         .iter()
         .map(|post| {
             let excerpt_html = post.excerpt.as_ref().map_or(String::new(), |excerpt| {
                 format!("<p class=\"excerpt\">{}</p>", excerpt)
             });
             
-            let post_path = format!("./{}/", post.slug);
+            let post_path = config.trailing_slash.post_href(root, &post.slug);
             
             format!(
                 r#"<article class="post-preview">
@@ -464,133 +846,960 @@ pub fn render_index(config: &Config, posts: &[Post]) -> Result<String> {
                 post_path,
                 post.title,
                 post.date.to_rfc3339(),
-                post.date.format("%d/%m/%Y").to_string(),
+                post.date.format("%d/%m/%Y"),
                 excerpt_html
             )
         })
         .collect::<Vec<_>>()
-        .join("\n");
-    
+        .join("\n")
+}
+
+/// The home page of the language `lang`: the site root for the default language, `<lang>/` otherwise.
+pub fn render_index(config: &Config, posts: &[&Post], lang: &str) -> Result<String> {
     // Use relative paths (works for both regular hosting and IPFS)
-    let (css_path, home_path) = ("./style.css", "./");
+    let home_path = if config.languages.is_prefixed(lang) { "../" } else { "./" };
+    let posts_list = partial(config, "post-list", render_post_list(config, posts, home_path));
+    
+    let mut head = avatar_meta(avatar::public_url(config, home_path).as_deref());
+    // The feed sits next to this index and is only built when the site has a `url`
+    if config.url.is_some() {
+        head.push_str(&format!(
+            r#"
+    <link rel="alternate" type="application/rss+xml" title="{}" href="{}">
+    <link rel="alternate" type="application/feed+json" title="{}" href="{}">"#,
+            html_escape(&config.title),
+            feed::FILE,
+            html_escape(&config.title),
+            feed::JSON_FILE
+        ));
+    }
+    let graph_link = if config.graph.enabled {
+        format!(
+            r#"
+        <footer>
+            <a href="{}" class="home-link">Explore the link graph →</a>
+        </footer>"#,
+            config.trailing_slash.post_href(home_path, GRAPH_PAGE)
+        )
+    } else {
+        String::new()
+    };
 
-    let html = format!(
-        r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>{}</title>
-    <link rel="stylesheet" href="{}">
-    <link rel="preconnect" href="https://fonts.googleapis.com">
-    <link rel="preconnect" href="https://fonts.gstatic.com" crossorigin>
-    <link href="https://fonts.googleapis.com/css2?family=Crimson+Text:ital,wght@0,400;0,600;1,400&family=Inter:wght@400;600;700&display=swap" rel="stylesheet">
-</head>
-<body>
-    <div class="container">
-        <header>
-            <div class="header-content">
-                <a href="{}" class="main-title">{}</a>
-            </div>
-        </header>
-        
-        <main class="content">
+    let main = format!(
+        r#"
             <section class="posts-list">
                 {}
-            </section>
-        </main>
-    </div>
-</body>
-</html>"#,
-        config.title,
-        css_path,
-        home_path,
-        config.title.to_uppercase(),
+            </section>"#,
         posts_list
     );
+
+    let html = render_layout(config, &Layout {
+        title: config.title.clone(),
+        home_path,
+        head,
+        main,
+        footer: graph_link,
+        scripts: String::new(),
+        lang,
+        dir: None,
+        alternates: if config.languages.is_multilingual() {
+            config.languages.codes().into_iter().map(|code| Alternate { lang: code.to_string(), path: config.languages.index_path(code) }).collect()
+        } else {
+            Vec::new()
+        },
+        page: PageLayout::Default,
+    });
     
-    Ok(html)
+    Ok(annotate_page(config, "index", html))
 }
 
-pub fn generate_css(_config: &Config) -> String {
-    // Use the exact CSS from the original implementation
-    r#"/* Reset and base styles */
-* {
-  margin: 0;
-  padding: 0;
-  box-sizing: border-box;
+/// Landing page listing the posts of one series in reading order.
+pub fn render_series(config: &Config, name: &str, posts: &[&Post]) -> Result<String> {
+    render_listing(config, "series", name, &series_path(name), posts)
 }
 
-body {
-  background-color: #0a0a0a;
-  color: #f5f5f5;
-  font-family: 'Crimson Text', Georgia, serif;
-  line-height: 1.7;
-  font-size: 18px;
-  -webkit-font-smoothing: antialiased;
-  -moz-osx-font-smoothing: grayscale;
+/// Hub page at `path` listing the posts that are `also_in` the collection `name`.
+pub fn render_collection(config: &Config, name: &str, path: &str, posts: &[&Post]) -> Result<String> {
+    render_listing(config, "collection", name, path, posts)
 }
 
-/* Container */
-.container {
-  max-width: 800px;
-  margin: 0 auto;
-  padding: 0 20px;
-}
+/// Page of a curated trail: its posts in order, each with its blurb.
+pub fn render_trail(config: &Config, trail: &Trail, members: &[(&Post, Option<&str>)]) -> Result<String> {
+    let home_path = config.trailing_slash.page_root(&trail.path());
+    let description = match trail.description.as_deref().map(str::trim) {
+        Some(description) if !description.is_empty() => format!("\n            <p class=\"trail-description\">{}</p>", html_escape(description)),
+        _ => String::new(),
+    };
+    let steps: String = members
+        .iter()
+        .map(|(post, blurb)| {
+            format!(
+                r#"
+                <li class="trail-step">
+                    <a href="{}">{}</a>{}
+                </li>"#,
+                config.trailing_slash.post_href(&home_path, &post.slug),
+                post.title,
+                blurb.map(|b| format!("\n                    <p class=\"trail-blurb\">{}</p>", html_escape(b))).unwrap_or_default()
+            )
+        })
+        .collect();
+    let steps = partial(config, "trail-steps", steps);
+
+    let main = format!(
+        r#"
+            <h1 class="post-title">{}</h1>{}
+            <ol class="trail-steps">{}
+            </ol>"#,
+        trail.name,
+        description,
+        steps
+    );
 
-/* Header */
-header {
-  padding: 40px 0;
-  margin-bottom: 60px;
+    let html = render_layout(config, &Layout {
+        title: format!("{} - {}", trail.name, config.title),
+        home_path: &home_path,
+        head: String::new(),
+        main,
+        footer: back_home_footer(&home_path),
+        scripts: String::new(),
+        lang: &config.languages.default,
+        dir: None,
+        alternates: Vec::new(),
+        page: PageLayout::Default,
+    });
+
+    Ok(annotate_page(config, "trail", html))
 }
 
-.header-content {
-  display: flex;
-  align-items: center;
-  justify-content: flex-end;
-}
+/// Page at `path` titled `name` that lists `posts`, rendered by the template `template`.
+fn render_listing(config: &Config, template: &str, name: &str, path: &str, posts: &[&Post]) -> Result<String> {
+    let home_path = config.trailing_slash.page_root(path);
+    let posts_list = partial(config, "post-list", render_post_list(config, posts, &home_path));
 
+    let main = format!(
+        r#"
+            <h1 class="post-title">{}</h1>
+            <section class="posts-list">
+                {}
+            </section>"#,
+        name,
+        posts_list
+    );
 
-.main-title {
-  font-family: 'Crimson Text', Georgia, serif;
-  font-size: 32px;
-  font-weight: 700;
-  letter-spacing: 0.1em;
-  text-transform: uppercase;
-  color: #f5f5f5;
-  position: relative;
-  text-decoration: none;
-  transition: color 0.2s ease;
+    let html = render_layout(config, &Layout {
+        title: format!("{} - {}", name, config.title),
+        home_path: &home_path,
+        head: String::new(),
+        main,
+        footer: back_home_footer(&home_path),
+        scripts: String::new(),
+        lang: &config.languages.default,
+        dir: None,
+        alternates: Vec::new(),
+        page: PageLayout::Default,
+    });
+
+    Ok(annotate_page(config, template, html))
 }
 
-.main-title:hover {
-  color: #8b8b8b;
+/// Page left at a post's former slug `alias`, forwarding readers and crawlers to the post.
+pub fn render_redirect(config: &Config, alias: &str, post: &Post) -> String {
+    redirect_page(config, alias, Some(&post.slug), &post.title, "This post has moved to")
 }
 
-.main-title::after {
-  content: '';
-  position: absolute;
-  bottom: -8px;
-  left: 0;
-  right: 0;
-  height: 1px;
-  background-color: #4a4a4a;
+/// Page for `post` inside a collection at `path`, pointing readers and crawlers at the post's own URL.
+pub fn render_collection_alias(config: &Config, path: &str, post: &Post) -> String {
+    redirect_page(config, path, Some(&post.slug), &post.title, "Read this post at")
 }
 
-/* Content */
-.content {
-  margin-bottom: 80px;
+/// Page left at the slug of a trashed post, forwarding to `target` or, without one, the home page.
+pub fn render_trashed_redirect(config: &Config, slug: &str, target: Option<&Post>) -> String {
+    let message = "This post has been removed. Continue to";
+    match target {
+        Some(post) => redirect_page(config, slug, Some(&post.slug), &post.title, message),
+        None => redirect_page(config, slug, None, &config.title, message),
+    }
 }
 
-/* Post titles */
-.post-title {
-  font-family: 'Crimson Text', Georgia, serif;
-  font-size: 42px;
-  font-weight: 700;
-  line-height: 1.2;
-  margin-bottom: 30px;
-  padding-bottom: 12px; /* reserve space for underline */
-  color: #f5f5f5;
+fn redirect_page(config: &Config, from: &str, to: Option<&str>, title: &str, message: &str) -> String {
+    let home_path = config.trailing_slash.page_root(from);
+    let (target, canonical) = match to {
+        Some(slug) => (
+            config.trailing_slash.post_href(&home_path, slug),
+            config.site_url().map(|url| config.trailing_slash.post_href(&url, slug)),
+        ),
+        None => (home_path.clone(), config.site_url()),
+    };
+    let canonical = canonical.unwrap_or_else(|| target.clone());
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>{}</title>
+    <link rel="canonical" href="{}">
+    <meta name="robots" content="noindex">
+    <meta http-equiv="refresh" content="0; url={}">
+</head>
+<body>
+    <p>{} <a href="{}">{}</a>.</p>
+</body>
+</html>"#,
+        title, canonical, target, message, target, title
+    )
+}
+
+/// Notice left at the slug of a trashed post, kept out of search results.
+pub fn render_gone(config: &Config, slug: &str) -> Result<String> {
+    let home_path = config.trailing_slash.page_root(slug);
+    let main = r#"
+            <h1 class="post-title">Gone</h1>
+            <p>This post has been removed.</p>"#
+        .to_string();
+
+    let html = render_layout(config, &Layout {
+        title: format!("Gone - {}", config.title),
+        home_path: &home_path,
+        head: r#"<meta name="robots" content="noindex">"#.to_string(),
+        main,
+        footer: back_home_footer(&home_path),
+        scripts: String::new(),
+        lang: &config.languages.default,
+        dir: None,
+        alternates: Vec::new(),
+        page: PageLayout::Default,
+    });
+
+    Ok(annotate_page(config, "gone", html))
+}
+
+/// Prompt page published in place of a private post: its title and a passphrase form, with the
+/// post's page sealed in by [`private::fill`].
+pub fn render_private(config: &Config, post: &Post) -> Result<String> {
+    let home_path = config.trailing_slash.page_root(&post.slug);
+    let main = format!(
+        r#"
+            <h1 class="post-title">{}</h1>
+            <form id="unlock" class="unlock">
+                <p>This post is private. Enter the passphrase to read it.</p>
+                <input type="password" name="passphrase" aria-label="Passphrase" autocomplete="current-password" required autofocus>
+                <button type="submit">Unlock</button>
+                <p class="unlock-error" hidden>That passphrase doesn't unlock this post.</p>
+            </form>
+            <noscript><p>Unlocking the post needs JavaScript.</p></noscript>"#,
+        html_escape(&post.title)
+    );
+
+    let html = render_layout(config, &Layout {
+        title: format!("{} - {}", html_escape(&post.title), config.title),
+        home_path: &home_path,
+        head: r#"<meta name="robots" content="noindex">"#.to_string(),
+        main,
+        footer: back_home_footer(&format!("{}{}", home_path, config.languages.index_path(&post.lang))),
+        scripts: format!(r#"<script type="application/json" id="sealed">{}</script>
+    {}"#, private::PLACEHOLDER, private::SCRIPT),
+        lang: &post.lang,
+        dir: post.frontmatter.dir,
+        alternates: Vec::new(),
+        page: PageLayout::Default,
+    });
+
+    Ok(annotate_page(config, "private", html))
+}
+
+/// Page for URLs that match nothing. It can be served at any depth, so links start from the
+/// site's base path rather than being relative.
+pub fn render_not_found(config: &Config) -> Result<String> {
+    let home_path = config.base_path();
+    let main = r#"
+            <h1 class="post-title">Page not found</h1>
+            <p>There is nothing at this address. It may have moved, or the link may be mistyped.</p>"#
+        .to_string();
+
+    let html = render_layout(config, &Layout {
+        title: format!("Not found - {}", config.title),
+        home_path: &home_path,
+        head: r#"<meta name="robots" content="noindex">"#.to_string(),
+        main,
+        footer: back_home_footer(&home_path),
+        scripts: String::new(),
+        lang: &config.languages.default,
+        dir: None,
+        alternates: Vec::new(),
+        page: PageLayout::Default,
+    });
+
+    Ok(annotate_page(config, "not-found", html))
+}
+
+/// A month of the archive and its posts.
+type ArchiveMonth<'a> = (u32, Vec<&'a Post>);
+
+/// Every post grouped by year, then month, newest first, with counts and jump links.
+pub fn render_archive(config: &Config, posts: &[Post]) -> Result<String> {
+    let home_path = config.trailing_slash.page_root(ARCHIVE_PAGE);
+    let mut by_month: BTreeMap<Reverse<(i32, u32)>, Vec<&Post>> = BTreeMap::new();
+    for post in posts {
+        by_month.entry(Reverse((post.date.year(), post.date.month()))).or_default().push(post);
+    }
+    let mut years: Vec<(i32, Vec<ArchiveMonth>)> = Vec::new();
+    for (Reverse((year, month)), mut month_posts) in by_month {
+        month_posts.sort_by_key(|p| Reverse(p.date));
+        match years.last_mut() {
+            Some((y, months)) if *y == year => months.push((month, month_posts)),
+            _ => years.push((year, vec![(month, month_posts)])),
+        }
+    }
+    let month_name = |month: u32| chrono::Month::try_from(month as u8).map(|m| m.name()).unwrap_or_default();
+    let year_count = |months: &[ArchiveMonth]| months.iter().map(|(_, p)| p.len()).sum::<usize>();
+
+    let jump_links: Vec<String> = years
+        .iter()
+        .map(|(year, months)| {
+            let month_links: Vec<String> = months
+                .iter()
+                .map(|(month, p)| format!("<a href=\"#y{}-{:02}\">{} ({})</a>", year, month, &month_name(*month)[..3], p.len()))
+                .collect();
+            format!(
+                "<li><a href=\"#y{}\" class=\"archive-year-link\">{} ({})</a> {}</li>",
+                year,
+                year,
+                year_count(months),
+                month_links.join(" ")
+            )
+        })
+        .collect();
+
+    let sections: Vec<String> = years
+        .iter()
+        .map(|(year, months)| {
+            let month_sections: Vec<String> = months
+                .iter()
+                .map(|(month, month_posts)| {
+                    let items: Vec<String> = month_posts
+                        .iter()
+                        .map(|post| {
+                            format!(
+                                "<li><time datetime=\"{}\">{}</time> <a href=\"{}\">{}</a></li>",
+                                post.date.to_rfc3339(),
+                                post.date.format("%d"),
+                                config.trailing_slash.post_href(&home_path, &post.slug),
+                                post.title
+                            )
+                        })
+                        .collect();
+                    format!(
+                        r#"
+                <h3 id="y{}-{:02}">{} <span class="archive-count">{}</span></h3>
+                <ul class="archive-posts">
+                    {}
+                </ul>"#,
+                        year,
+                        month,
+                        month_name(*month),
+                        month_posts.len(),
+                        items.join("\n                    ")
+                    )
+                })
+                .collect();
+            format!(
+                r#"
+            <section class="archive-year">
+                <h2 id="y{}">{} <span class="archive-count">{}</span></h2>{}
+            </section>"#,
+                year,
+                year,
+                year_count(months),
+                month_sections.concat()
+            )
+        })
+        .collect();
+
+    let main = format!(
+        r#"
+            <h1 class="post-title">Archive</h1>
+            <p class="archive-total">{} posts</p>
+            <nav class="archive-jump">
+                <ul>
+                    {}
+                </ul>
+            </nav>{}"#,
+        posts.len(),
+        jump_links.join("\n                    "),
+        partial(config, "archive-list", sections.concat())
+    );
+
+    let html = render_layout(config, &Layout {
+        title: format!("Archive - {}", config.title),
+        home_path: &home_path,
+        head: String::new(),
+        main,
+        footer: back_home_footer(&home_path),
+        scripts: String::new(),
+        lang: &config.languages.default,
+        dir: None,
+        alternates: Vec::new(),
+        page: PageLayout::Default,
+    });
+
+    Ok(annotate_page(config, "archive", html))
+}
+
+/// Interactive force-directed view of `graph_json` (a serialized `LinkGraph`). The data is
+/// embedded so the page also works where `graph.json` can't be fetched.
+pub fn render_graph(config: &Config, graph_json: &str) -> Result<String> {
+    let home_path = config.trailing_slash.page_root(GRAPH_PAGE);
+    // Keep the JSON from closing the script element early
+    let data = graph_json.replace("</", "<\\/");
+
+    let head = format!("<script id=\"graph-data\" type=\"application/json\">{}</script>", data);
+    let main = r#"
+            <h1 class="post-title">Link graph</h1>
+            <svg id="link-graph" class="link-graph" viewBox="-400 -300 800 600"></svg>"#;
+    let scripts = format!(
+        r#"
+    <script>
+    (function() {{
+        var root = '{}';
+        var data = JSON.parse(document.getElementById('graph-data').textContent);
+        var svg = document.getElementById('link-graph');
+        var ns = 'http://www.w3.org/2000/svg';
+        var byId = {{}};
+        data.nodes.forEach(function(n, i) {{
+            var angle = 2 * Math.PI * i / data.nodes.length;
+            n.x = 200 * Math.cos(angle); n.y = 200 * Math.sin(angle); n.vx = 0; n.vy = 0;
+            byId[n.id] = n;
+        }});
+        var edges = data.edges.filter(function(e) {{ return byId[e.source] && byId[e.target]; }});
+
+        function el(name, attrs, parent) {{
+            var e = document.createElementNS(ns, name);
+            for (var k in attrs) e.setAttribute(k, attrs[k]);
+            parent.appendChild(e);
+            return e;
+        }}
+        edges.forEach(function(e) {{
+            e.line = el('line', {{ 'class': 'graph-edge graph-edge-' + e.type }}, svg);
+        }});
+        data.nodes.forEach(function(n) {{
+            n.g = el('g', {{ 'class': 'graph-node graph-node-' + n.type }}, svg);
+            el('circle', {{ r: n.type === 'tag' ? 4 : 7 }}, n.g);
+            el('text', {{ x: 10, y: 4 }}, n.g).textContent = n.label;
+            if (n.url !== undefined) n.g.addEventListener('click', function() {{
+                if (!n.dragged) location.href = root + n.url;
+            }});
+            n.g.addEventListener('pointerdown', function(ev) {{
+                dragging = n; n.dragged = false; svg.setPointerCapture(ev.pointerId);
+            }});
+        }});
+
+        var dragging = null;
+        function toSvg(ev) {{
+            var p = svg.createSVGPoint(); p.x = ev.clientX; p.y = ev.clientY;
+            return p.matrixTransform(svg.getScreenCTM().inverse());
+        }}
+        svg.addEventListener('pointermove', function(ev) {{
+            if (!dragging) return;
+            var p = toSvg(ev);
+            dragging.x = p.x; dragging.y = p.y; dragging.dragged = true;
+            heat = Math.max(heat, 0.3); start();
+        }});
+        svg.addEventListener('pointerup', function() {{ setTimeout(function() {{ dragging = null; }}); }});
+
+        // Simple force layout: pairwise repulsion, springs along edges, pull towards the centre
+        var heat = 1, running = false;
+        function tick() {{
+            var nodes = data.nodes;
+            for (var i = 0; i < nodes.length; i++) {{
+                for (var j = i + 1; j < nodes.length; j++) {{
+                    var a = nodes[i], b = nodes[j];
+                    var dx = b.x - a.x, dy = b.y - a.y, d2 = dx * dx + dy * dy + 0.01;
+                    var f = 2000 / d2, d = Math.sqrt(d2);
+                    a.vx -= f * dx / d; a.vy -= f * dy / d; b.vx += f * dx / d; b.vy += f * dy / d;
+                }}
+            }}
+            edges.forEach(function(e) {{
+                var a = byId[e.source], b = byId[e.target];
+                var dx = b.x - a.x, dy = b.y - a.y, d = Math.sqrt(dx * dx + dy * dy) || 1;
+                var f = (d - 90) * 0.02;
+                a.vx += f * dx / d; a.vy += f * dy / d; b.vx -= f * dx / d; b.vy -= f * dy / d;
+            }});
+            nodes.forEach(function(n) {{
+                n.vx -= n.x * 0.005; n.vy -= n.y * 0.005;
+                if (n !== dragging) {{ n.x += n.vx * heat; n.y += n.vy * heat; }}
+                n.vx *= 0.6; n.vy *= 0.6;
+                n.g.setAttribute('transform', 'translate(' + n.x + ',' + n.y + ')');
+            }});
+            edges.forEach(function(e) {{
+                var a = byId[e.source], b = byId[e.target];
+                e.line.setAttribute('x1', a.x); e.line.setAttribute('y1', a.y);
+                e.line.setAttribute('x2', b.x); e.line.setAttribute('y2', b.y);
+            }});
+            heat *= 0.99;
+            if (heat > 0.01) requestAnimationFrame(tick); else running = false;
+        }}
+        function start() {{ if (!running) {{ running = true; requestAnimationFrame(tick); }} }}
+        start();
+    }})();
+    </script>"#,
+        home_path
+    );
+
+    let html = render_layout(config, &Layout {
+        title: format!("Link graph - {}", config.title),
+        home_path: &home_path,
+        head,
+        main: main.to_string(),
+        footer: back_home_footer(&home_path),
+        scripts,
+        lang: &config.languages.default,
+        dir: None,
+        alternates: Vec::new(),
+        page: PageLayout::Default,
+    });
+
+    Ok(annotate_page(config, "graph", html))
+}
+
+/// Page `serve --watch` shows while the latest rebuild is failing: the error chain, outermost
+/// first. Styled inline so it doesn't depend on the generated site.
+pub fn render_build_error(errors: &[String]) -> String {
+    let (message, causes) = errors.split_first().map_or(("Build failed", &[][..]), |(m, c)| (m.as_str(), c));
+    let causes: String = causes
+        .iter()
+        .map(|cause| format!("\n        <li>{}</li>", html_escape(cause)))
+        .collect();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Build failed</title>
+</head>
+<body style="margin: 0; padding: 3rem; background: #1a0a0a; color: #f5f5f5; font-family: ui-monospace, monospace;">
+    <h1 style="color: #ff6b6b; font-size: 1.25rem;">Build failed</h1>
+    <p style="white-space: pre-wrap;">{}</p>
+    <ul style="color: #c8c8c8; white-space: pre-wrap;">{}
+    </ul>
+    <p style="color: #8b8b8b;">The page reloads once the site builds again.</p>
+</body>
+</html>"#,
+        html_escape(message),
+        causes
+    )
+}
+
+/// Render context for `--debug-templates`: the template name, redacted config and page-specific `fields`.
+pub fn debug_context(config: &Config, template: &str, fields: serde_json::Value) -> String {
+    let mut context = serde_json::json!({ "template": template, "config": config.redacted() });
+    if let (Some(context), serde_json::Value::Object(fields)) = (context.as_object_mut(), fields) {
+        context.extend(fields);
+    }
+    serde_json::to_string_pretty(&context).unwrap_or_default()
+}
+
+/// Context fields of a post page, mirroring what `render_post` uses.
+pub fn post_context(config: &Config, post: &Post, all_posts: &[Post]) -> serde_json::Value {
+    let series: Vec<&str> = post
+        .series()
+        .map(|name| series_members(all_posts, name).iter().map(|p| p.slug.as_str()).collect())
+        .unwrap_or_default();
+    serde_json::json!({
+        "post": post,
+        "backlinks": find_backlinks(all_posts, post, config.trailing_slash),
+        "series": series,
+        "reading_minutes": post.reading_minutes(),
+        "breadcrumbs": breadcrumbs(config, post, &config.trailing_slash.page_root(&post.slug)),
+    })
+}
+
+/// One step of a breadcrumb trail; the current page has no link.
+#[derive(Debug, serde::Serialize)]
+struct Crumb {
+    title: String,
+    href: Option<String>,
+}
+
+/// Home → series (or else the first tag) → post, or nothing for posts with neither.
+fn breadcrumbs(config: &Config, post: &Post, home_path: &str) -> Vec<Crumb> {
+    if !config.theme.breadcrumbs {
+        return Vec::new();
+    }
+    let parent = match (post.series(), post.tags().first()) {
+        (Some(name), _) => Crumb {
+            title: name.to_string(),
+            href: Some(config.trailing_slash.post_href(home_path, &series_path(name))),
+        },
+        // Tags have no pages of their own
+        (None, Some(tag)) => Crumb { title: tag.clone(), href: None },
+        (None, None) => return Vec::new(),
+    };
+    vec![
+        Crumb { title: "Home".to_string(), href: Some(format!("{}{}", home_path, config.languages.index_path(&post.lang))) },
+        parent,
+        Crumb { title: post.title.clone(), href: None },
+    ]
+}
+
+fn render_breadcrumbs(config: &Config, crumbs: &[Crumb]) -> String {
+    if crumbs.is_empty() {
+        return String::new();
+    }
+    let last = crumbs.len() - 1;
+    let items: Vec<String> = crumbs
+        .iter()
+        .enumerate()
+        .map(|(i, crumb)| match &crumb.href {
+            Some(href) => format!("<a href=\"{}\">{}</a>", href, crumb.title),
+            None if i == last => format!("<span aria-current=\"page\">{}</span>", crumb.title),
+            None => format!("<span>{}</span>", crumb.title),
+        })
+        .collect();
+    let separator = format!("<span class=\"breadcrumb-separator\" aria-hidden=\"true\">{}</span>", html_escape(&config.theme.breadcrumb_separator));
+    format!(
+        r#"
+            <nav class="breadcrumbs" aria-label="Breadcrumb">{}</nav>"#,
+        items.join(&separator)
+    )
+}
+
+/// Wrap a non-empty partial in begin/end comments when debugging templates.
+fn partial(config: &Config, name: &str, html: String) -> String {
+    if !config.debug_templates || html.trim().is_empty() {
+        return html;
+    }
+    format!("<!-- scribe:begin {} -->{}<!-- scribe:end {} -->", name, html, name)
+}
+
+/// Name the page template in a comment right after the doctype when debugging templates.
+fn annotate_page(config: &Config, template: &str, html: String) -> String {
+    if !config.debug_templates {
+        return html;
+    }
+    match html.split_once('\n') {
+        Some((doctype, rest)) => format!("{}\n<!-- scribe:template {} -->\n{}", doctype, template, rest),
+        None => html,
+    }
+}
+
+/// The blocks a page fills in the shared layout.
+struct Layout<'a> {
+    /// Document title
+    title: String,
+    /// Relative path from the page to the site root
+    home_path: &'a str,
+    /// Extra tags at the end of `<head>`
+    head: String,
+    /// Contents of `<main>`
+    main: String,
+    /// Footer below `<main>`, empty for none
+    footer: String,
+    /// Scripts at the end of `<body>`
+    scripts: String,
+    /// Language of the page, for `<html lang>` and the language switcher
+    lang: &'a str,
+    /// Text direction, overriding the one of `lang`
+    dir: Option<Direction>,
+    /// The page in every language it exists in, for hreflang links and the language switcher
+    alternates: Vec<Alternate>,
+    /// `wide` spans the window, `bare` leaves out the site's header and footer
+    page: PageLayout,
+}
+
+/// A page in one language, at `path` from the site root (e.g. `nl/hello/`).
+struct Alternate {
+    lang: String,
+    path: String,
+}
+
+/// The HTML skeleton every page extends: head with stylesheet and fonts, site header, then the
+/// page's main content, footer and scripts.
+fn render_layout(config: &Config, layout: &Layout) -> String {
+    let archive_link = if config.archive {
+        format!(
+            r#"
+                <a href="{}" class="header-link">Archive</a>"#,
+            config.trailing_slash.post_href(layout.home_path, ARCHIVE_PAGE)
+        )
+    } else {
+        String::new()
+    };
+    let languages = &config.languages;
+    let dir = layout.dir.unwrap_or_else(|| languages.dir(layout.lang));
+    let body = if layout.page == PageLayout::Bare {
+        format!(
+            r#"<body class="layout-bare">
+    <main class="content">{}
+    </main>"#,
+            layout.main
+        )
+    } else {
+        format!(
+            r#"<body{}>
+    <div class="container">
+        <header>
+            <div class="header-content">
+                <a href="{}{}" class="main-title">{}</a>{}{}
+            </div>
+        </header>
+
+        <main class="content">{}
+        </main>
+        {}
+    </div>"#,
+            if layout.page == PageLayout::Wide { " class=\"layout-wide\"" } else { "" },
+            layout.home_path,
+            languages.index_path(layout.lang),
+            config.title.to_uppercase(),
+            archive_link,
+            language_switcher(config, layout),
+            layout.main,
+            layout.footer
+        )
+    };
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="{}" dir="{}">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{}</title>
+    <meta property="og:locale" content="{}">
+    <link rel="stylesheet" href="{}style.css">
+    {}
+    {}{}{}{}
+</head>
+{}{}{}
+</body>
+</html>"#,
+        layout.lang,
+        dir.as_str(),
+        layout.title,
+        layout.lang.replace('-', "_"),
+        layout.home_path,
+        font_links(config),
+        layout.head,
+        hreflang_links(config, layout),
+        pwa::head(config, layout.home_path),
+        analytics_script(config),
+        body,
+        layout.scripts,
+        custom_script(layout.home_path)
+    )
+}
+
+/// `<script>` loading the project's `static/custom.js`, after the page's own scripts.
+fn custom_script(home_path: &str) -> String {
+    if !Path::new(CUSTOM_SCRIPT).is_file() {
+        return String::new();
+    }
+    format!("\n    <script src=\"{}{}\" defer></script>", home_path, CUSTOM_SCRIPT_FILE)
+}
+
+/// `<script>` of the configured analytics; empty without analytics, and when Plausible has no
+/// domain or GoatCounter neither a code nor a host.
+pub fn analytics_script(config: &Config) -> String {
+    let instance = |host: &Option<String>, hosted: &str| host.as_deref().unwrap_or(hosted).trim_end_matches('/').to_string();
+    match &config.analytics {
+        None => String::new(),
+        Some(Analytics::Plausible { domain, host }) => {
+            let site_host = || config.url.as_deref().and_then(|url| url.split("://").nth(1)?.split('/').next()).map(str::to_string);
+            let Some(domain) = domain.clone().or_else(site_host).filter(|d| !d.is_empty()) else {
+                return String::new();
+            };
+            format!(
+                "\n    <script defer data-domain=\"{}\" src=\"{}/js/script.js\"></script>",
+                html_escape(&domain),
+                html_escape(&instance(host, "https://plausible.io"))
+            )
+        }
+        Some(Analytics::Goatcounter { code, host }) => {
+            let (endpoint, script) = match (host, code) {
+                (Some(host), _) => {
+                    let host = host.trim_end_matches('/');
+                    (format!("{}/count", host), format!("{}/count.js", host))
+                }
+                (None, Some(code)) => (format!("https://{}.goatcounter.com/count", code), "https://gc.zgo.at/count.js".to_string()),
+                (None, None) => return String::new(),
+            };
+            format!("\n    <script data-goatcounter=\"{}\" async src=\"{}\"></script>", html_escape(&endpoint), html_escape(&script))
+        }
+        Some(Analytics::Umami { website_id, host }) => format!(
+            "\n    <script defer src=\"{}/script.js\" data-website-id=\"{}\"></script>",
+            html_escape(&instance(host, "https://cloud.umami.is")),
+            html_escape(website_id)
+        ),
+    }
+}
+
+/// `<link rel="alternate" hreflang>` for each translation of the page, absolute when the site URL is known.
+fn hreflang_links(config: &Config, layout: &Layout) -> String {
+    if layout.alternates.len() < 2 {
+        return String::new();
+    }
+    let href = |path: &str| format!("{}{}", config.site_url().unwrap_or_else(|| layout.home_path.to_string()), path);
+    let default = layout.alternates.iter().find(|a| a.lang == config.languages.default);
+    layout
+        .alternates
+        .iter()
+        .map(|a| (a.lang.as_str(), a.path.as_str()))
+        .chain(default.map(|a| ("x-default", a.path.as_str())))
+        .map(|(lang, path)| format!("\n    <link rel=\"alternate\" hreflang=\"{}\" href=\"{}\">", lang, href(path)))
+        .collect()
+}
+
+/// Header links to the page in each language, or to that language's home page when it has no
+/// translation. Empty for single-language sites.
+fn language_switcher(config: &Config, layout: &Layout) -> String {
+    let languages = &config.languages;
+    if !languages.is_multilingual() {
+        return String::new();
+    }
+    let links: Vec<String> = languages
+        .codes()
+        .into_iter()
+        .map(|code| {
+            let path = layout
+                .alternates
+                .iter()
+                .find(|a| a.lang == code)
+                .map_or_else(|| languages.index_path(code), |a| a.path.clone());
+            let current = if code == layout.lang { r#" aria-current="true""# } else { "" };
+            format!(
+                r#"<a href="{}{}" hreflang="{}" lang="{}"{}>{}</a>"#,
+                layout.home_path,
+                path,
+                code,
+                code,
+                current,
+                html_escape(languages.name(code))
+            )
+        })
+        .collect();
+    format!(
+        r#"
+                <nav class="language-switcher" aria-label="Language">{}</nav>"#,
+        links.join("")
+    )
+}
+
+/// Footer linking back to the index.
+fn back_home_footer(home_path: &str) -> String {
+    format!(
+        r#"
+        <footer>
+            <a href="{}" class="home-link">← Back to all posts</a>
+        </footer>"#,
+        home_path
+    )
+}
+
+/// Google Fonts stylesheet links; left out when fonts are bundled, and of offline builds, where
+/// the CSS falls back to locally installed fonts.
+fn font_links(config: &Config) -> &'static str {
+    if config.offline || config.fonts.bundle {
+        return "";
+    }
+    r#"<link rel="preconnect" href="https://fonts.googleapis.com">
+    <link rel="preconnect" href="https://fonts.gstatic.com" crossorigin>
+    <link href="https://fonts.googleapis.com/css2?family=Crimson+Text:ital,wght@0,400;0,600;1,400&family=Inter:wght@400;600;700&display=swap" rel="stylesheet">"#
+}
+
+fn avatar_meta(avatar_url: Option<&str>) -> String {
+    match avatar_url {
+        Some(url) => format!("<meta property=\"og:image\" content=\"{}\">", url),
+        None => String::new(),
+    }
+}
+
+pub fn generate_css(config: &Config) -> String {
+    let font_faces = if config.fonts.bundle { fonts::font_face_css() } else { String::new() };
+    // Use the exact CSS from the original implementation
+    font_faces + r#"/* Reset and base styles */
+* {
+  margin: 0;
+  padding: 0;
+  box-sizing: border-box;
+}
+
+body {
+  background-color: #0a0a0a;
+  color: #f5f5f5;
+  font-family: 'Crimson Text', Georgia, serif;
+  line-height: 1.7;
+  font-size: 18px;
+  -webkit-font-smoothing: antialiased;
+  -moz-osx-font-smoothing: grayscale;
+}
+
+/* Container */
+.container {
+  max-width: 800px;
+  margin: 0 auto;
+  padding: 0 20px;
+}
+
+/* Posts with `layout: wide` span the window */
+.layout-wide .container {
+  max-width: none;
+}
+
+/* Header */
+header {
+  padding: 40px 0;
+  margin-bottom: 60px;
+}
+
+.header-content {
+  display: flex;
+  align-items: center;
+  justify-content: flex-end;
+}
+
+
+.main-title {
+  font-family: 'Crimson Text', Georgia, serif;
+  font-size: 32px;
+  font-weight: 700;
+  letter-spacing: 0.1em;
+  text-transform: uppercase;
+  color: #f5f5f5;
+  position: relative;
+  text-decoration: none;
+  transition: color 0.2s ease;
+}
+
+.main-title:hover {
+  color: #8b8b8b;
+}
+
+.main-title::after {
+  content: '';
+  position: absolute;
+  bottom: -8px;
+  left: 0;
+  right: 0;
+  height: 1px;
+  background-color: #4a4a4a;
+}
+
+/* Content */
+.content {
+  margin-bottom: 80px;
+}
+
+/* Post titles */
+.post-title {
+  font-family: 'Crimson Text', Georgia, serif;
+  font-size: 42px;
+  font-weight: 700;
+  line-height: 1.2;
+  margin-bottom: 30px;
+  padding-bottom: 12px; /* reserve space for underline */
+  color: #f5f5f5;
   position: relative;
 }
 
@@ -604,248 +1813,724 @@ header {
   background-color: #4a4a4a;
 }
 
-/* Post content */
-.post-content {
-  font-size: 20px;
-  line-height: 1.4;
-  margin-bottom: 60px;
+/* Post content */
+.post-content {
+  font-size: 20px;
+  line-height: 1.4;
+  margin-bottom: 60px;
+}
+
+.post-content hr {
+  border: none;
+  border-top: 1px solid #2a2a2a;
+  height: 0;
+  margin: 32px 0 24px 0;
+}
+
+.post-content p {
+  margin-bottom: 1.5em;
+  text-align: justify;
+  hyphens: auto;
+  position: relative;
+}
+
+.post-content h1 {
+  font-family: 'Crimson Text', Georgia, serif;
+  font-size: 32px;
+  font-weight: 600;
+  margin: 40px 0 20px 0;
+  color: #f5f5f5;
+  text-align: left;
+}
+
+.post-content h2 {
+  font-family: 'Crimson Text', Georgia, serif;
+  font-size: 28px;
+  font-weight: 600;
+  margin: 40px 0 20px 0;
+  padding-bottom: 8px; /* reserve space for underline */
+  color: #f5f5f5;
+  position: relative;
+  text-align: right;
+}
+
+.post-content h2::after {
+  content: '';
+  position: absolute;
+  bottom: 0;
+  left: 0;
+  right: 0;
+  height: 1px;
+  background-color: #4a4a4a;
+}
+
+.post-content h3 {
+  font-family: 'Crimson Text', Georgia, serif;
+  font-size: 22px;
+  font-weight: 600;
+  margin: 30px 0 15px 0;
+  color: #f5f5f5;
+  text-align: right;
+}
+
+
+.post-content ul, .post-content ol {
+  margin: 20px 0;
+  padding-left: 30px;
+}
+
+.post-content li {
+  margin-bottom: 8px;
+}
+
+.post-content blockquote {
+  border-left: 3px solid #4a4a4a;
+  padding-left: 20px;
+  margin: 30px 0;
+  font-style: italic;
+  color: #d0d0d0;
+}
+
+.post-content code {
+  background-color: #1a1a1a;
+  padding: 2px 6px;
+  border-radius: 3px;
+  font-family: 'SF Mono', Monaco, 'Cascadia Code', 'Roboto Mono', Consolas, 'Courier New', monospace;
+  font-size: 0.9em;
+}
+
+.post-content pre {
+  background-color: #1a1a1a;
+  padding: 20px;
+  border-radius: 6px;
+  overflow-x: auto;
+  margin: 20px 0;
+}
+
+.post-content pre code {
+  background: none;
+  padding: 0;
+}
+
+.post-content table {
+  width: 100%;
+  border-collapse: collapse;
+  margin: 30px 0;
+  font-size: 0.9em;
+}
+
+.post-content th,
+.post-content td {
+  border-bottom: 1px solid #2a2a2a;
+  padding: 8px 12px;
+  text-align: left;
+}
+
+.post-content th {
+  font-family: 'Inter', sans-serif;
+  font-weight: 600;
+}
+
+.post-content li input[type="checkbox"] {
+  margin-right: 8px;
+}
+
+.post-content dt {
+  font-weight: 600;
+  margin-top: 16px;
+}
+
+.post-content dd {
+  margin-left: 24px;
+  color: #d0d0d0;
+}
+
+/* Footnotes */
+.post-content sup.footnote-reference {
+  font-size: 0.7em;
+}
+
+.post-content .footnote-definition {
+  font-size: 16px;
+  color: #8b8b8b;
+  margin-top: 12px;
+}
+
+.post-content .footnote-definition p {
+  display: inline;
+  color: #8b8b8b;
+}
+
+/* Illuminated initial */
+.illuminated-initial {
+  float: left;
+  margin: 0 12px 20px 0;
+  shape-outside: rectangle(0, 0, 80px, 80px);
+}
+
+.initial-image {
+  width: 80px;
+  height: 80px;
+  object-fit: cover;
+  box-shadow: 0 4px 12px rgba(0, 0, 0, 0.3);
+  border: 1px solid #4a4a4a;
+}
+
+/* Links */
+a {
+  color: #8b8b8b;
+  text-decoration: underline;
+  text-decoration-color: #4a4a4a;
+  text-underline-offset: 2px;
+  transition: color 0.2s ease;
+}
+
+a:hover {
+  color: #f5f5f5;
+  text-decoration-color: #8b8b8b;
+}
+
+/* Exa search link per paragraph */
+.exa-link {
+  position: absolute;
+  right: -1.2em;
+  top: 0.1em;
+  font-size: 0.9em;
+  color: #8b8b8b;
+  text-decoration: none;
+  opacity: 0;
+  transition: opacity 0.2s ease, color 0.2s ease;
+  margin-left: 0.25em; /* used when inline on mobile */
+}
+
+.post-content p:hover .exa-link {
+  opacity: 1;
+}
+
+.exa-link:hover {
+  color: #f5f5f5;
+}
+
+/* Annotation toggle and panel */
+.annotation-toggle {
+  position: absolute;
+  left: 50%;
+  bottom: -0.6em;
+  transform: translateX(-50%);
+  background: transparent;
+  color: #8b8b8b;
+  border: none;
+  cursor: pointer;
+  font-family: 'Inter', sans-serif;
+  font-size: 0.9em;
+  line-height: 1;
+  padding: 0;
+  opacity: 0;
+  transition: opacity 0.2s ease, color 0.2s ease, transform 0.2s ease;
+}
+
+.post-content p:hover .annotation-toggle,
+.post-content ul:hover .annotation-toggle,
+.post-content ol:hover .annotation-toggle,
+.post-content blockquote:hover .annotation-toggle {
+  opacity: 1;
+}
+
+.annotation-toggle:hover { color: #f5f5f5; }
+.annotation-toggle.open { transform: translateX(-50%) rotate(180deg); }
+
+.annotation-panel {
+  display: none;
+  margin: 0.6em 0 1.2em 0;
+  padding: 10px 14px;
+  border-left: 2px solid #2a2a2a;
+  background-color: rgba(255,255,255,0.02);
+}
+
+.annotation-list {
+  margin: 0;
+  padding-left: 18px;
+}
+
+.annotation-list li { margin: 6px 0; }
+.annotation-list a { color: #8b8b8b; }
+.annotation-list a:hover { color: #f5f5f5; }
+
+.annotation-item-titleline {
+  font-family: 'Crimson Text', Georgia, serif;
+}
+
+.annotation-item-title {
+  color: #f5f5f5;
+  text-decoration: none;
+}
+
+.annotation-item-link {
+  color: #8b8b8b;
+  text-decoration: none;
+}
+
+.annotation-item-link:hover, .annotation-item-title:hover {
+  color: #f5f5f5;
+}
+
+.annotation-item-desc {
+  color: #d0d0d0;
+  font-size: 0.95em;
+}
+
+.annotation-favicon {
+  width: 14px;
+  height: 14px;
+  margin-right: 6px;
+  vertical-align: -2px;
+}
+
+.annotation-item-note {
+  color: #d0d0d0;
+  font-style: italic;
+}
+
+/* Quotebacks */
+.quoteback {
+  margin: 32px 0;
+  border: 1px solid #2a2a2a;
+  border-radius: 6px;
+  background-color: #111;
+}
+
+.quoteback blockquote {
+  margin: 0;
+  padding: 20px 24px 8px;
+}
+
+.quoteback-footer {
+  display: flex;
+  align-items: center;
+  gap: 8px;
+  padding: 10px 24px;
+  border-top: 1px solid #2a2a2a;
+  font-family: 'Inter', sans-serif;
+  font-size: 14px;
+  color: #8b8b8b;
+}
+
+.quoteback-favicon {
+  width: 16px;
+  height: 16px;
+}
+
+.quoteback-author {
+  color: #f5f5f5;
+  font-weight: 600;
+}
+
+.quoteback-author + .quoteback-favicon {
+  margin-left: auto;
+}
+
+.quoteback-source {
+  color: #8b8b8b;
+}
+
+/* Backlinks section */
+.backlinks {
+  margin-top: 60px;
+  padding-top: 40px;
+  border-top: 1px solid #2a2a2a;
+}
+
+.backlinks h2 {
+  font-family: 'Crimson Text', Georgia, serif;
+  font-size: 24px;
+  font-weight: 600;
+  margin-bottom: 20px;
+  color: #f5f5f5;
+}
+
+.backlinks ul {
+  list-style: none;
+  padding: 0;
+}
+
+.backlinks li {
+  margin-bottom: 12px;
+}
+
+.backlinks a {
+  font-size: 16px;
+  color: #8b8b8b;
+}
+
+.backlink-context {
+  margin: 4px 0 0;
+  font-size: 14px;
+  color: #6b6b6b;
+}
+
+/* Webmentions and comments */
+.webmentions,
+.comments {
+  margin-top: 60px;
+  padding-top: 40px;
+  border-top: 1px solid #2a2a2a;
+}
+
+.webmentions h2 {
+  font-family: 'Crimson Text', Georgia, serif;
+  font-size: 24px;
+  font-weight: 600;
+  margin-bottom: 20px;
+  color: #f5f5f5;
+}
+
+.webmention-reactions {
+  margin-bottom: 12px;
+  font-size: 15px;
+  color: #8b8b8b;
+}
+
+.webmentions ul {
+  list-style: none;
+  padding: 0;
+}
+
+.webmentions li {
+  margin-bottom: 16px;
+  font-size: 16px;
+}
+
+.webmentions a {
+  color: #8b8b8b;
+}
+
+.webmention-text {
+  margin: 4px 0 0;
+  font-size: 15px;
+  color: #6b6b6b;
+}
+
+/* Header links */
+.header-link {
+  font-family: 'Inter', sans-serif;
+  font-size: 14px;
+  letter-spacing: 0.05em;
+  color: #8b8b8b;
+  text-decoration: none;
+  margin-left: 32px;
+}
+
+.header-link:hover {
+  color: #f5f5f5;
+}
+
+/* Language switcher */
+.language-switcher {
+  display: flex;
+  gap: 16px;
+  margin-left: 32px;
+  font-family: 'Inter', sans-serif;
+  font-size: 14px;
+  letter-spacing: 0.05em;
+}
+
+.language-switcher a {
+  color: #8b8b8b;
+  text-decoration: none;
+}
+
+.language-switcher a:hover,
+.language-switcher a[aria-current] {
+  color: #f5f5f5;
+}
+
+/* Archive */
+.archive-total {
+  color: #8b8b8b;
+  margin-bottom: 24px;
+}
+
+.archive-jump ul {
+  list-style: none;
+  font-family: 'Inter', sans-serif;
+  font-size: 14px;
+  margin-bottom: 48px;
+}
+
+.archive-jump li {
+  margin-bottom: 6px;
+}
+
+.archive-jump a {
+  color: #8b8b8b;
+  margin-right: 8px;
+}
+
+.archive-jump .archive-year-link {
+  color: #f5f5f5;
+  font-weight: 600;
+}
+
+.archive-year {
+  margin-bottom: 48px;
+}
+
+.archive-year h2 {
+  font-size: 28px;
+  margin-bottom: 16px;
+}
+
+.archive-year h3 {
+  font-size: 20px;
+  margin: 24px 0 8px;
+}
+
+.archive-count {
+  color: #8b8b8b;
+  font-size: 0.7em;
+  font-weight: 400;
+}
+
+.archive-posts {
+  list-style: none;
+}
+
+.archive-posts li {
+  margin-bottom: 6px;
+}
+
+.archive-posts time {
+  display: inline-block;
+  width: 2.5em;
+  color: #8b8b8b;
+  font-family: 'Inter', sans-serif;
+  font-size: 14px;
+}
+
+/* Link graph */
+.link-graph {
+  width: 100%;
+  height: auto;
+  border: 1px solid #2a2a2a;
+  touch-action: none;
+}
+
+.graph-edge {
+  stroke: #4a4a4a;
+  stroke-width: 1;
 }
 
-.post-content hr {
-  border: none;
-  border-top: 1px solid #2a2a2a;
-  height: 0;
-  margin: 32px 0 24px 0;
+.graph-edge-tag {
+  stroke-dasharray: 3 3;
 }
 
-.post-content p {
-  margin-bottom: 1.5em;
-  text-align: justify;
-  hyphens: auto;
-  position: relative;
+.graph-node circle {
+  fill: #f5f5f5;
 }
 
-.post-content h1 {
-  font-family: 'Crimson Text', Georgia, serif;
-  font-size: 32px;
-  font-weight: 600;
-  margin: 40px 0 20px 0;
-  color: #f5f5f5;
-  text-align: left;
+.graph-node-tag circle {
+  fill: #8b8b8b;
 }
 
-.post-content h2 {
-  font-family: 'Crimson Text', Georgia, serif;
-  font-size: 28px;
-  font-weight: 600;
-  margin: 40px 0 20px 0;
-  padding-bottom: 8px; /* reserve space for underline */
-  color: #f5f5f5;
-  position: relative;
-  text-align: right;
+.graph-node text {
+  font-family: 'Inter', sans-serif;
+  font-size: 11px;
+  fill: #8b8b8b;
 }
 
-.post-content h2::after {
-  content: '';
-  position: absolute;
-  bottom: 0;
-  left: 0;
-  right: 0;
-  height: 1px;
-  background-color: #4a4a4a;
+.graph-node-post {
+  cursor: pointer;
 }
 
-.post-content h3 {
-  font-family: 'Crimson Text', Georgia, serif;
-  font-size: 22px;
-  font-weight: 600;
-  margin: 30px 0 15px 0;
-  color: #f5f5f5;
-  text-align: right;
+.graph-node-post:hover text {
+  fill: #f5f5f5;
 }
 
+/* Passphrase prompt of private posts */
+.unlock input,
+.unlock button {
+  font: inherit;
+  color: #f5f5f5;
+  background-color: #1a1a1a;
+  border: 1px solid #4a4a4a;
+  border-radius: 3px;
+  padding: 8px 12px;
+}
 
-.post-content ul, .post-content ol {
-  margin: 20px 0;
-  padding-left: 30px;
+.unlock button {
+  cursor: pointer;
 }
 
-.post-content li {
-  margin-bottom: 8px;
+.unlock-error {
+  color: #d08770;
 }
 
-.post-content blockquote {
-  border-left: 3px solid #4a4a4a;
-  padding-left: 20px;
+/* Raw HTML blocks: their layout, overflow and positioned children stay inside */
+.raw-html {
   margin: 30px 0;
-  font-style: italic;
-  color: #d0d0d0;
+  contain: layout paint;
+  isolation: isolate;
+  overflow: auto;
+  max-width: 100%;
 }
 
-.post-content code {
-  background-color: #1a1a1a;
-  padding: 2px 6px;
-  border-radius: 3px;
-  font-family: 'SF Mono', Monaco, 'Cascadia Code', 'Roboto Mono', Consolas, 'Courier New', monospace;
-  font-size: 0.9em;
+.raw-html-frame {
+  display: block;
+  width: 100%;
+  height: 400px;
+  margin: 30px 0;
+  border: 1px solid #2a2a2a;
+  background-color: #ffffff;
 }
 
-.post-content pre {
-  background-color: #1a1a1a;
-  padding: 20px;
-  border-radius: 6px;
+/* Math: wide display formulas scroll rather than overflow the column */
+.math-display,
+math[display="block"] {
+  display: block;
+  margin: 24px 0;
   overflow-x: auto;
-  margin: 20px 0;
+  overflow-y: hidden;
 }
 
-.post-content pre code {
+/* Diagrams: drawn ones lose the code block look; unrendered source keeps it */
+.post-content pre.mermaid[data-processed] {
   background: none;
-  padding: 0;
+  text-align: center;
 }
 
-/* Illuminated initial */
-.illuminated-initial {
-  float: left;
-  margin: 0 12px 20px 0;
-  shape-outside: rectangle(0, 0, 80px, 80px);
+.mermaid-diagram {
+  margin: 30px 0;
+  overflow-x: auto;
+  text-align: center;
 }
 
-.initial-image {
-  width: 80px;
-  height: 80px;
-  object-fit: cover;
-  box-shadow: 0 4px 12px rgba(0, 0, 0, 0.3);
-  border: 1px solid #4a4a4a;
+.mermaid-diagram svg {
+  max-width: 100%;
+  height: auto;
 }
 
-/* Links */
-a {
+/* Reading time */
+.reading-time {
+  margin: 0 0 32px 0;
+  font-family: 'Inter', sans-serif;
+  font-size: 14px;
   color: #8b8b8b;
-  text-decoration: underline;
-  text-decoration-color: #4a4a4a;
-  text-underline-offset: 2px;
-  transition: color 0.2s ease;
 }
 
-a:hover {
-  color: #f5f5f5;
-  text-decoration-color: #8b8b8b;
+/* TL;DR */
+.tldr {
+  margin-bottom: 32px;
+  padding: 16px 20px;
+  border-left: 2px solid #8b8b8b;
+  color: #c8c8c8;
+  font-size: 17px;
 }
 
-/* Exa search link per paragraph */
-.exa-link {
-  position: absolute;
-  right: -1.2em;
-  top: 0.1em;
-  font-size: 0.9em;
+.tldr-label {
+  font-family: 'Inter', sans-serif;
+  font-size: 13px;
+  font-weight: 600;
+  letter-spacing: 0.08em;
   color: #8b8b8b;
-  text-decoration: none;
-  opacity: 0;
-  transition: opacity 0.2s ease, color 0.2s ease;
-  margin-left: 0.25em; /* used when inline on mobile */
-}
-
-.post-content p:hover .exa-link {
-  opacity: 1;
+  margin-right: 8px;
 }
 
-.exa-link:hover {
-  color: #f5f5f5;
+/* Breadcrumbs */
+.breadcrumbs {
+  font-family: 'Inter', sans-serif;
+  font-size: 14px;
+  color: #8b8b8b;
+  margin-bottom: 24px;
 }
 
-/* Annotation toggle and panel */
-.annotation-toggle {
-  position: absolute;
-  left: 50%;
-  bottom: -0.6em;
-  transform: translateX(-50%);
-  background: transparent;
+.breadcrumbs a {
   color: #8b8b8b;
-  border: none;
-  cursor: pointer;
-  font-family: 'Inter', sans-serif;
-  font-size: 0.9em;
-  line-height: 1;
-  padding: 0;
-  opacity: 0;
-  transition: opacity 0.2s ease, color 0.2s ease, transform 0.2s ease;
 }
 
-.post-content p:hover .annotation-toggle,
-.post-content ul:hover .annotation-toggle,
-.post-content ol:hover .annotation-toggle,
-.post-content blockquote:hover .annotation-toggle {
-  opacity: 1;
+.breadcrumbs a:hover {
+  color: #f5f5f5;
 }
 
-.annotation-toggle:hover { color: #f5f5f5; }
-.annotation-toggle.open { transform: translateX(-50%) rotate(180deg); }
+.breadcrumb-separator {
+  margin: 0 8px;
+}
 
-.annotation-panel {
-  display: none;
-  margin: 0.6em 0 1.2em 0;
-  padding: 10px 14px;
-  border-left: 2px solid #2a2a2a;
-  background-color: rgba(255,255,255,0.02);
+/* Series navigation */
+.series-nav {
+  margin-top: 40px;
+  padding-top: 24px;
+  border-top: 1px solid #2a2a2a;
+  font-family: 'Inter', sans-serif;
+  font-size: 16px;
 }
 
-.annotation-list {
-  margin: 0;
-  padding-left: 18px;
+.series-label {
+  color: #8b8b8b;
+  margin-bottom: 12px;
 }
 
-.annotation-list li { margin: 6px 0; }
-.annotation-list a { color: #8b8b8b; }
-.annotation-list a:hover { color: #f5f5f5; }
+.series-links {
+  display: flex;
+  justify-content: space-between;
+  gap: 24px;
+}
 
-.annotation-item-titleline {
-  font-family: 'Crimson Text', Georgia, serif;
+.series-next {
+  margin-left: auto;
+  text-align: right;
 }
 
-.annotation-item-title {
-  color: #f5f5f5;
-  text-decoration: none;
+/* Curated trails */
+.trail-nav {
+  margin-top: 40px;
+  padding-top: 24px;
+  border-top: 1px solid #2a2a2a;
+  font-family: 'Inter', sans-serif;
+  font-size: 16px;
 }
 
-.annotation-item-link {
+.trail-label,
+.trail-blurb,
+.trail-end,
+.trail-description {
   color: #8b8b8b;
-  text-decoration: none;
 }
 
-.annotation-item-link:hover, .annotation-item-title:hover {
-  color: #f5f5f5;
+.trail-label {
+  margin-bottom: 12px;
 }
 
-.annotation-item-desc {
-  color: #d0d0d0;
-  font-size: 0.95em;
+.trail-blurb {
+  margin-top: 6px;
+  font-size: 15px;
 }
 
-/* Backlinks section */
-.backlinks {
-  margin-top: 60px;
-  padding-top: 40px;
-  border-top: 1px solid #2a2a2a;
+.trail-steps {
+  padding-left: 24px;
 }
 
-.backlinks h2 {
-  font-family: 'Crimson Text', Georgia, serif;
-  font-size: 24px;
-  font-weight: 600;
+.trail-step {
   margin-bottom: 20px;
-  color: #f5f5f5;
+  font-family: 'Inter', sans-serif;
 }
 
-.backlinks ul {
-  list-style: none;
-  padding: 0;
+/* Author card */
+.author-card {
+  display: flex;
+  align-items: center;
+  gap: 16px;
+  margin-top: 40px;
 }
 
-.backlinks li {
-  margin-bottom: 12px;
+.author-avatar {
+  width: 48px;
+  height: 48px;
+  border-radius: 50%;
+  object-fit: cover;
 }
 
-.backlinks a {
+.author-name {
+  font-family: 'Inter', sans-serif;
   font-size: 16px;
   color: #8b8b8b;
 }
@@ -926,6 +2611,88 @@ footer {
   color: #f5f5f5;
 }
 
+/* Right-to-left scripts */
+[dir="rtl"] .post-content h1 {
+  text-align: right;
+}
+
+[dir="rtl"] .post-content h2,
+[dir="rtl"] .post-content h3 {
+  text-align: left;
+}
+
+[dir="rtl"] .post-content ul,
+[dir="rtl"] .post-content ol {
+  padding-left: 0;
+  padding-right: 30px;
+}
+
+[dir="rtl"] .post-content blockquote {
+  border-left: none;
+  border-right: 3px solid #4a4a4a;
+  padding-left: 0;
+  padding-right: 20px;
+}
+
+[dir="rtl"] .post-content th,
+[dir="rtl"] .post-content td {
+  text-align: right;
+}
+
+[dir="rtl"] .post-content li input[type="checkbox"] {
+  margin-right: 0;
+  margin-left: 8px;
+}
+
+[dir="rtl"] .post-content dd {
+  margin-left: 0;
+  margin-right: 24px;
+}
+
+[dir="rtl"] .annotation-panel {
+  border-left: none;
+  border-right: 2px solid #2a2a2a;
+}
+
+[dir="rtl"] .tldr {
+  border-left: none;
+  border-right: 2px solid #8b8b8b;
+}
+
+[dir="rtl"] .header-link,
+[dir="rtl"] .language-switcher {
+  margin-left: 0;
+  margin-right: 32px;
+}
+
+[dir="rtl"] .series-next {
+  margin-left: 0;
+  margin-right: auto;
+  text-align: left;
+}
+
+[dir="rtl"] .trail-steps {
+  padding-left: 0;
+  padding-right: 24px;
+}
+
+@media (min-width: 769px) {
+  [dir="rtl"] .illuminated-initial {
+    float: right;
+    margin: 0 0 20px 12px;
+  }
+
+  [dir="rtl"] .exa-link {
+    right: auto;
+    left: -1.2em;
+  }
+
+  [dir="rtl"] .post-preview time {
+    margin-left: 0;
+    margin-right: 20px;
+  }
+}
+
 /* Responsive design */
 @media (max-width: 768px) {
   .container {
@@ -1012,54 +2779,34 @@ footer {
   }
   .annotation-toggle { display: none; }
   .annotation-panel { display: none; }
-}"#.to_string()
+}"#
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 struct Backlink {
     title: String,
     url: String,
+    /// Anchor text of the linking post's link
+    text: String,
+    /// Text surrounding the link in the linking post
+    context: String,
 }
 
-fn find_backlinks(posts: &[Post], current_slug: &str, current_original_slug: &str) -> Vec<Backlink> {
-    let mut backlinks = Vec::new();
-    
-    for post in posts {
-        if post.slug != current_slug {
-            // Simple backlink detection - look for links to current post
-            let patterns = [
-                // sanitized slug
-                format!("/{}/", current_slug),
-                format!("/{}\"", current_slug),
-                format!("/{}.md\"", current_slug),
-                format!("./{}/", current_slug),
-                format!("./{}\"", current_slug),
-                format!("./{}.md\"", current_slug),
-                format!("../{}/", current_slug),
-                format!("../{}\"", current_slug),
-                format!("../{}.md\"", current_slug),
-                format!("{}/", current_slug),
-                format!("{}\"", current_slug),
-                format!("{}.md\"", current_slug),
-                // original slug as might appear in authored markdown
-                format!("/{}/", current_original_slug),
-                format!("/{}\"", current_original_slug),
-                format!("/{}.md\"", current_original_slug),
-                format!("./{}/", current_original_slug),
-                format!("./{}\"", current_original_slug),
-                format!("./{}.md\"", current_original_slug),
-                format!("../{}/", current_original_slug),
-                format!("../{}\"", current_original_slug),
-                format!("../{}.md\"", current_original_slug),
-                format!("{}/", current_original_slug),
-                format!("{}\"", current_original_slug),
-                format!("{}.md\"", current_original_slug),
-            ];
-            if patterns.iter().any(|p| post.html_content.contains(p)) {
-                backlinks.push(Backlink { title: post.title.clone(), url: format!("../{}/", post.slug) });
-            }
-        }
-    }
-    
-    backlinks
-} 
\ No newline at end of file
+fn find_backlinks(posts: &[Post], current: &Post, policy: TrailingSlash) -> Vec<Backlink> {
+    posts
+        .iter()
+        .filter_map(|post| {
+            let link = post.link_to(current)?;
+            Some(Backlink {
+                title: post.title.clone(),
+                url: policy.post_href(&policy.page_root(&current.slug), &post.slug),
+                text: link.text.clone(),
+                context: link.context.clone(),
+            })
+        })
+        .collect()
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
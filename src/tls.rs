@@ -0,0 +1,88 @@
+use anyhow::{Context, Result};
+use openssl::asn1::Asn1Time;
+use openssl::bn::{BigNum, MsbOption};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::PKey;
+use openssl::x509::extension::{BasicConstraints, ExtendedKeyUsage, SubjectAlternativeName};
+use openssl::x509::{X509NameBuilder, X509};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the self-signed certificate and key are kept, so browsers only need to trust them once.
+pub const SELF_SIGNED_DIR: &str = ".scribe/tls";
+
+/// Days a self-signed certificate is valid for; browsers reject longer ones.
+const SELF_SIGNED_DAYS: u32 = 825;
+
+/// Certificate for serving over HTTPS.
+#[derive(Debug, Clone)]
+pub enum Tls {
+    /// PEM certificate chain and PKCS#8 private key, e.g. from mkcert
+    Files { cert: PathBuf, key: PathBuf },
+    /// A certificate for localhost made on first use and kept in [`SELF_SIGNED_DIR`]
+    SelfSigned,
+}
+
+impl Tls {
+    /// Acceptor that performs the TLS handshake on incoming connections.
+    pub fn acceptor(&self) -> Result<tokio_native_tls::TlsAcceptor> {
+        let (cert, key) = match self {
+            Tls::Files { cert, key } => (read(cert)?, read(key)?),
+            Tls::SelfSigned => self_signed(Path::new(SELF_SIGNED_DIR))?,
+        };
+        let identity = native_tls::Identity::from_pkcs8(&cert, &key).context("Invalid TLS certificate or key (the key must be PKCS#8 PEM)")?;
+        let acceptor = native_tls::TlsAcceptor::new(identity).context("Failed to set up TLS")?;
+        Ok(acceptor.into())
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).context(format!("Failed to read {}", path.display()))
+}
+
+/// Certificate and key in `dir`, made first if they don't exist yet.
+fn self_signed(dir: &Path) -> Result<(Vec<u8>, Vec<u8>)> {
+    let (cert_path, key_path) = (dir.join("cert.pem"), dir.join("key.pem"));
+    if cert_path.is_file() && key_path.is_file() {
+        return Ok((read(&cert_path)?, read(&key_path)?));
+    }
+    let (cert, key) = generate_self_signed().context("Failed to create a self-signed certificate")?;
+    fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+    fs::write(&cert_path, &cert).context(format!("Failed to write {}", cert_path.display()))?;
+    fs::write(&key_path, &key).context(format!("Failed to write {}", key_path.display()))?;
+    Ok((cert, key))
+}
+
+/// A P-256 certificate for localhost, 127.0.0.1 and ::1, as PEM certificate and PKCS#8 key.
+fn generate_self_signed() -> Result<(Vec<u8>, Vec<u8>)> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    let key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+
+    let mut name = X509NameBuilder::new()?;
+    name.append_entry_by_nid(Nid::COMMONNAME, "scribe localhost")?;
+    let name = name.build();
+
+    let mut serial = BigNum::new()?;
+    serial.rand(128, MsbOption::MAYBE_ZERO, false)?;
+    let serial = serial.to_asn1_integer()?;
+    let (not_before, not_after) = (Asn1Time::days_from_now(0)?, Asn1Time::days_from_now(SELF_SIGNED_DAYS)?);
+
+    let mut builder = X509::builder()?;
+    builder.set_version(2)?;
+    builder.set_serial_number(&serial)?;
+    builder.set_subject_name(&name)?;
+    builder.set_issuer_name(&name)?;
+    builder.set_pubkey(&key)?;
+    builder.set_not_before(&not_before)?;
+    builder.set_not_after(&not_after)?;
+    builder.append_extension(BasicConstraints::new().build()?)?;
+    builder.append_extension(ExtendedKeyUsage::new().server_auth().build()?)?;
+    let context = builder.x509v3_context(None, None);
+    let san = SubjectAlternativeName::new().dns("localhost").ip("127.0.0.1").ip("::1").build(&context)?;
+    builder.append_extension(san)?;
+    builder.sign(&key, MessageDigest::sha256())?;
+
+    Ok((builder.build().to_pem()?, key.private_key_to_pem_pkcs8()?))
+}
@@ -0,0 +1,60 @@
+use crate::generator::{sanitize_slug, Post};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Directory of the trail pages in the output.
+pub const DIR: &str = "trails";
+
+/// A hand-picked reading order through the site, e.g. "Start here".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trail {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub posts: Vec<TrailStep>,
+}
+
+/// One post on a trail, with a line on why it's there.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrailStep {
+    pub slug: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blurb: Option<String>,
+}
+
+impl Trail {
+    /// Page path of the trail, e.g. `trails/start-here`.
+    pub fn path(&self) -> String {
+        format!("{}/{}", DIR, sanitize_slug(&self.name))
+    }
+
+    /// Posts on the trail in order, with their blurbs; steps naming no known post are left out.
+    pub fn members<'a>(&'a self, posts: &'a [Post]) -> Vec<(&'a Post, Option<&'a str>)> {
+        self.posts
+            .iter()
+            .filter_map(|step| {
+                let post = find(posts, &step.slug)?;
+                Some((post, step.blurb.as_deref().map(str::trim).filter(|b| !b.is_empty())))
+            })
+            .collect()
+    }
+
+    /// Slugs on the trail that match no post.
+    pub fn unknown_slugs<'a>(&'a self, posts: &[Post]) -> Vec<&'a str> {
+        self.posts.iter().map(|step| step.slug.as_str()).filter(|slug| find(posts, slug).is_none()).collect()
+    }
+}
+
+fn find<'a>(posts: &'a [Post], slug: &str) -> Option<&'a Post> {
+    let wanted = sanitize_slug(slug);
+    posts.iter().find(|p| p.slug == wanted || p.original_slug == slug)
+}
+
+/// Read the trails file: a YAML list of trails, each with a `name`, optional `description` and
+/// its `posts` as `slug`/`blurb` pairs.
+pub fn load(path: &Path) -> Result<Vec<Trail>> {
+    let content = fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    serde_yaml::from_str(&content).context(format!("Failed to parse {}", path.display()))
+}
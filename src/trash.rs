@@ -0,0 +1,81 @@
+use crate::annotations;
+use crate::config::InputFormat;
+use crate::generator::{is_post_file, sanitize_slug};
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Directory under the posts directory holding posts removed with `scribe rm`. Builds skip it.
+pub const DIR: &str = ".trash";
+
+/// Trashed post files, at their path relative to the posts directory under `.trash/`.
+pub fn files(posts_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(posts_dir.join(DIR))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .map(|e| e.into_path())
+        .filter(|p| is_post_file(p, &InputFormat::ALL))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Sanitized slugs of every trashed post.
+pub fn slugs(posts_dir: &Path) -> BTreeSet<String> {
+    files(posts_dir)
+        .iter()
+        .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(sanitize_slug))
+        .collect()
+}
+
+/// Trashed file of the post `slug` (original or sanitized).
+pub fn find(posts_dir: &Path, slug: &str) -> Option<PathBuf> {
+    let wanted = sanitize_slug(slug);
+    files(posts_dir)
+        .into_iter()
+        .find(|p| p.file_stem().and_then(|s| s.to_str()).is_some_and(|stem| stem == slug || sanitize_slug(stem) == wanted))
+}
+
+/// Move the post at `path` (and its annotations sidecar) into the trash, returning its new path.
+pub fn trash(posts_dir: &Path, path: &Path) -> Result<PathBuf> {
+    let relative = path.strip_prefix(posts_dir).unwrap_or(path);
+    let target = posts_dir.join(DIR).join(relative);
+    if target.exists() {
+        anyhow::bail!("{} is already in the trash; restore or delete it first", target.display());
+    }
+    move_with_sidecar(path, &target)?;
+    Ok(target)
+}
+
+/// Move a trashed post back to where it was, returning its restored path.
+pub fn restore(posts_dir: &Path, trashed: &Path) -> Result<PathBuf> {
+    let relative = trashed.strip_prefix(posts_dir.join(DIR)).unwrap_or(trashed);
+    let target = posts_dir.join(relative);
+    if target.exists() {
+        anyhow::bail!("{} already exists", target.display());
+    }
+    move_with_sidecar(trashed, &target)?;
+    // Drop directories the trash no longer needs
+    let mut dir = trashed.parent();
+    while let Some(d) = dir.filter(|d| d.starts_with(posts_dir.join(DIR))) {
+        if fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+    Ok(target)
+}
+
+fn move_with_sidecar(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).context(format!("Failed to create {}", parent.display()))?;
+    }
+    fs::rename(from, to).context(format!("Failed to move {} to {}", from.display(), to.display()))?;
+    let sidecar = annotations::sidecar_path(from);
+    if sidecar.exists() {
+        fs::rename(&sidecar, annotations::sidecar_path(to)).context(format!("Failed to move {}", sidecar.display()))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Location of the fetched webmentions, relative to the project root.
+pub const CACHE_PATH: &str = ".scribe/webmentions.json";
+
+/// webmention.io's feed of the mentions of a page.
+const API: &str = "https://webmention.io/api/mentions.jf2";
+
+/// Requests to webmention.io in flight at once.
+const CONCURRENT_FETCHES: usize = 8;
+
+/// Longest reply or mention text kept, in characters.
+const TEXT_CHARS: usize = 500;
+
+/// Mentions of a post fetched longer ago than this are fetched again.
+pub fn refresh_after() -> Duration {
+    Duration::hours(1)
+}
+
+/// What a mention does with the post, from webmention.io's `wm-property`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Reply,
+    Like,
+    Repost,
+    Bookmark,
+    Mention,
+}
+
+/// A webmention received for a post.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mention {
+    pub kind: Kind,
+    /// Page that sent it
+    pub url: String,
+    pub author: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<DateTime<Utc>>,
+    /// Plain text of a reply or mention, cut short
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+}
+
+/// Mentions fetched for each post, keyed by the post's absolute URL and stored in
+/// `.scribe/webmentions.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cache {
+    targets: BTreeMap<String, Fetched>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Fetched {
+    fetched_at: DateTime<Utc>,
+    mentions: Vec<Mention>,
+}
+
+impl Cache {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        match fs::read_to_string(path.as_ref()) {
+            Ok(content) => serde_json::from_str(&content).context(format!("Failed to parse {}", path.as_ref().display())),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent).context("Failed to create .scribe directory")?;
+        }
+        fs::write(path.as_ref(), serde_json::to_string_pretty(self)?).context(format!("Failed to write {}", path.as_ref().display()))
+    }
+
+    /// Mentions of `target` as last fetched, oldest first.
+    pub fn mentions(&self, target: &str) -> &[Mention] {
+        self.fetched(target).unwrap_or_default()
+    }
+
+    /// Mentions of `target`, or none when they were never fetched.
+    pub fn fetched(&self, target: &str) -> Option<&[Mention]> {
+        self.targets.get(target).map(|f| f.mentions.as_slice())
+    }
+
+    fn is_fresh(&self, target: &str) -> bool {
+        self.targets.get(target).is_some_and(|f| Utc::now() - f.fetched_at < refresh_after())
+    }
+}
+
+/// Fetch the mentions of each of `targets` (absolute post URLs) that weren't fetched within
+/// [`refresh_after`], several at a time. Returns how many targets were fetched, and the errors
+/// of those that couldn't be; those keep their cached mentions.
+pub async fn refresh(cache: &mut Cache, targets: &[String]) -> (usize, Vec<String>) {
+    let client = reqwest::Client::new();
+    let stale: Vec<String> = targets.iter().filter(|t| !cache.is_fresh(t)).cloned().collect();
+    let results: Vec<(String, Result<Vec<Mention>>)> = stream::iter(stale)
+        .map(|target| {
+            let client = client.clone();
+            async move {
+                let result = fetch(&client, &target).await;
+                (target, result)
+            }
+        })
+        .buffer_unordered(CONCURRENT_FETCHES)
+        .collect()
+        .await;
+
+    let mut fetched = 0;
+    let mut errors = Vec::new();
+    for (target, result) in results {
+        match result {
+            Ok(mentions) => {
+                fetched += 1;
+                cache.targets.insert(target, Fetched { fetched_at: Utc::now(), mentions });
+            }
+            Err(e) => errors.push(format!("{}: {:#}", target, e)),
+        }
+    }
+    (fetched, errors)
+}
+
+/// The webmention.io URL the mentions of `target` come from, which the lockfile keys them by.
+pub fn feed_url(target: &str) -> String {
+    reqwest::Url::parse_with_params(API, [("target", target)]).map_or_else(|_| API.to_string(), String::from)
+}
+
+async fn fetch(client: &reqwest::Client, target: &str) -> Result<Vec<Mention>> {
+    let response: serde_json::Value = client
+        .get(API)
+        .query(&[("target", target), ("per-page", "200")])
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .context("Failed to reach webmention.io")?
+        .json()
+        .await
+        .context("Unexpected response from webmention.io")?;
+    Ok(parse(&response))
+}
+
+/// Mentions in a webmention.io JF2 feed, oldest first.
+pub fn parse(feed: &serde_json::Value) -> Vec<Mention> {
+    let text = |value: &serde_json::Value| value.as_str().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+    // Links end up in pages, so only web addresses are kept
+    let link = |value: &serde_json::Value| text(value).filter(|u| u.starts_with("https://") || u.starts_with("http://"));
+    let mut mentions: Vec<Mention> = feed["children"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let url = link(&entry["url"]).or_else(|| link(&entry["wm-source"]))?;
+            let kind = match entry["wm-property"].as_str() {
+                Some("in-reply-to") => Kind::Reply,
+                Some("like-of") => Kind::Like,
+                Some("repost-of") => Kind::Repost,
+                Some("bookmark-of") => Kind::Bookmark,
+                _ => Kind::Mention,
+            };
+            let author_url = link(&entry["author"]["url"]);
+            // Anonymous senders go by their site's host
+            let author = text(&entry["author"]["name"]).unwrap_or_else(|| {
+                reqwest::Url::parse(author_url.as_deref().unwrap_or(&url))
+                    .ok()
+                    .and_then(|u| u.host_str().map(str::to_string))
+                    .unwrap_or_else(|| url.clone())
+            });
+            let published = [&entry["published"], &entry["wm-received"]]
+                .into_iter()
+                .find_map(|date| DateTime::parse_from_rfc3339(date.as_str()?).ok())
+                .map(|date| date.with_timezone(&Utc));
+            let text = match kind {
+                Kind::Reply | Kind::Mention => {
+                    let content = text(&entry["content"]["text"]).unwrap_or_default();
+                    match content.char_indices().nth(TEXT_CHARS) {
+                        Some((cut, _)) => format!("{}…", content[..cut].trim_end()),
+                        None => content,
+                    }
+                }
+                _ => String::new(),
+            };
+            Some(Mention { kind, url, author, author_url, published, text })
+        })
+        .collect();
+    mentions.sort_by_key(|m| m.published);
+    mentions
+}
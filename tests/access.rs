@@ -0,0 +1,64 @@
+//! Tests for reading and summarizing the access log behind `scribe stats`.
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use scribe::access::{self, Entry};
+use scribe::generator::SiteGenerator;
+
+fn entry(day: u32, path: &str, status: u16, referrer: Option<&str>, agent: &str) -> Entry {
+    Entry {
+        time: Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap(),
+        method: "GET".to_string(),
+        path: path.to_string(),
+        protocol: "HTTP/1.1".to_string(),
+        status,
+        referrer: referrer.map(str::to_string),
+        agent: Some(agent.to_string()),
+    }
+}
+
+#[test]
+fn log_lines_read_back() {
+    let entry = entry(3, "/a \"quoted\" path/", 304, None, "Mozilla/5.0 (X11) Gecko/20100101 Firefox/126.0");
+    let line = entry.line();
+    assert!(line.starts_with("- - - [03/May/2024:12:00:00 +0000] \"GET /a \\\"quoted\\\" path/ HTTP/1.1\" 304 - \"-\" \"Mozilla"), "{}", line);
+    assert_eq!(Entry::parse(&line), Some(entry));
+    assert_eq!(Entry::parse("not a log line"), None);
+}
+
+#[tokio::test]
+async fn summarizes_views_per_post_referrer_and_browser() {
+    let dir = common::project(
+        "access",
+        "stats",
+        &[
+            ("Hello World.md", "---\ntitle: Hello World\ndate: 2024-01-01\n---\n\nHi.\n"),
+            ("second.md", "---\ntitle: Second\ndate: 2024-01-02\n---\n\nMore.\n"),
+        ],
+    );
+    let config = common::config(&dir);
+    let mut generator = SiteGenerator::new(config.clone());
+    let posts = generator.read_posts().await.unwrap();
+
+    let firefox = "Mozilla/5.0 (X11; Linux x86_64; rv:126.0) Gecko/20100101 Firefox/126.0";
+    let chrome = "Mozilla/5.0 (Macintosh) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/125.0 Safari/537.36";
+    let entries = vec![
+        entry(1, "/hello-world/", 200, Some("https://www.example.com/links"), firefox),
+        entry(2, "/hello-world/index.html", 304, Some("https://example.com/"), chrome),
+        entry(2, "/second", 200, None, chrome),
+        entry(3, "/", 200, None, firefox),
+        entry(3, "/style.css", 200, None, firefox),
+        entry(3, "/missing/", 404, None, firefox),
+        entry(4, "/second/", 200, None, "Googlebot/2.1 (+http://www.google.com/bot.html)"),
+    ];
+    let stats = access::summarize(&entries, &config, posts);
+    assert_eq!((stats.views, stats.bots), (4, 1));
+    assert_eq!(stats.first, Some(entries[0].time));
+    assert_eq!(stats.last, Some(entries[3].time));
+    let posts: Vec<(&str, usize)> = stats.posts.iter().map(|(post, views)| (post.slug.as_str(), *views)).collect();
+    assert_eq!(posts, [("hello-world", 2), ("second", 1)]);
+    assert_eq!(stats.pages, [("/".to_string(), 1)]);
+    assert_eq!(stats.referrers, [("example.com".to_string(), 2)]);
+    assert_eq!(stats.agents, [("Chrome".to_string(), 2), ("Firefox".to_string(), 2)]);
+}
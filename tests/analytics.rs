@@ -0,0 +1,44 @@
+//! Tests for the analytics script added to every page.
+
+mod common;
+
+use scribe::config::{Analytics, Config};
+use scribe::generator::SiteGenerator;
+use std::fs;
+
+/// Index and post page of a one-post site built with `analytics`.
+async fn build(name: &str, url: Option<&str>, analytics: Analytics) -> (String, String) {
+    let dir = common::project("analytics", name, &[("hello.md", "---\ntitle: Hello\ndate: 2024-01-01\n---\n\nHi.\n")]);
+    let config = Config { url: url.map(str::to_string), analytics: Some(analytics), ..common::config(&dir) };
+    SiteGenerator::new(config).generate().await.unwrap();
+    (fs::read_to_string(dir.join("dist/index.html")).unwrap(), fs::read_to_string(dir.join("dist/hello/index.html")).unwrap())
+}
+
+#[tokio::test]
+async fn plausible_defaults_to_the_site_domain() {
+    let (index, post) = build("plausible", Some("https://blog.example.com/"), Analytics::Plausible { domain: None, host: None }).await;
+    let script = r#"<script defer data-domain="blog.example.com" src="https://plausible.io/js/script.js"></script>"#;
+    assert!(index.contains(script), "{}", index);
+    assert!(post.contains(script), "{}", post);
+
+    let (index, _) = build("plausible-unset", None, Analytics::Plausible { domain: None, host: None }).await;
+    assert!(!index.contains("plausible"), "{}", index);
+}
+
+#[tokio::test]
+async fn goatcounter_and_umami_scripts() {
+    let (index, _) = build("goatcounter", None, Analytics::Goatcounter { code: Some("myblog".to_string()), host: None }).await;
+    assert!(index.contains(r#"<script data-goatcounter="https://myblog.goatcounter.com/count" async src="https://gc.zgo.at/count.js"></script>"#), "{}", index);
+
+    let analytics = Analytics::Umami { website_id: "94db1cb1".to_string(), host: Some("https://stats.example.com/".to_string()) };
+    let (_, post) = build("umami", None, analytics).await;
+    assert!(post.contains(r#"<script defer src="https://stats.example.com/script.js" data-website-id="94db1cb1"></script>"#), "{}", post);
+}
+
+#[test]
+fn reads_the_config_block() {
+    let analytics: Analytics = serde_json::from_str(r#"{"provider": "umami", "website_id": "94db1cb1"}"#).unwrap();
+    assert!(matches!(analytics, Analytics::Umami { ref website_id, host: None } if website_id == "94db1cb1"));
+    let analytics: Analytics = serde_json::from_str(r#"{"provider": "goatcounter", "code": "myblog"}"#).unwrap();
+    assert!(matches!(analytics, Analytics::Goatcounter { code: Some(_), host: None }));
+}
@@ -0,0 +1,86 @@
+//! Tests for the JSON Feed, per-post JSON and headless builds.
+
+mod common;
+
+use scribe::api::BuildFormat;
+use scribe::{Config, SiteGenerator};
+use std::fs;
+
+#[tokio::test]
+async fn builds_json_feed_and_post_data() {
+    let dir = common::project(
+        "api",
+        "feed",
+        &[
+            ("first.md", "---\ntitle: First\ndate: 2024-01-01\ntags: [notes]\n---\n\nSee [the second](../second/).\n"),
+            ("second.md", "---\ntitle: Second\ndate: 2024-02-01\n---\n\nHello.\n"),
+        ],
+    );
+    let config = Config { url: Some("https://example.com".to_string()), api: true, ..common::config(&dir) };
+    SiteGenerator::new(config.clone()).generate().await.unwrap();
+
+    let feed: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("dist/feed.json")).unwrap()).unwrap();
+    assert_eq!(feed["version"], "https://jsonfeed.org/version/1.1");
+    assert_eq!(feed["feed_url"], "https://example.com/feed.json");
+    assert_eq!(feed["items"][0]["id"], "https://example.com/second/");
+    assert_eq!(feed["items"][1]["tags"], serde_json::json!(["notes"]));
+    assert!(feed["items"][1]["content_html"].as_str().unwrap().contains(r#"href="https://example.com/second/""#));
+
+    let post: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("dist/first/index.json")).unwrap()).unwrap();
+    assert_eq!(post["title"], "First");
+    assert_eq!(post["url"], "https://example.com/first/");
+    assert_eq!(post["date"], "2024-01-01T00:00:00Z");
+    let page = fs::read_to_string(dir.join("dist/first/index.html")).unwrap();
+    assert!(page.contains(r#"<link rel="alternate" type="application/json" href="index.json">"#));
+
+    // Turning the API off removes the JSON with the stale pages
+    fs::remove_file(dir.join("posts/second.md")).unwrap();
+    SiteGenerator::new(Config { api: false, ..config }).generate().await.unwrap();
+    assert!(!dir.join("dist/first/index.json").exists() && !dir.join("dist/second").exists());
+}
+
+#[tokio::test]
+async fn headless_builds_write_only_content() {
+    let dir = common::project(
+        "api",
+        "headless",
+        &[
+            ("first.md", "---\ntitle: First\ndate: 2024-01-01\ntags: [notes, rust]\n---\n\n::: raw\n<canvas></canvas>\n:::\n"),
+            ("second.md", "---\ntitle: Second\ndate: 2024-02-01\ntags: [notes]\n---\n\nSee [the first](../first/).\n"),
+        ],
+    );
+    let config = common::config(&dir);
+    let mut generator = SiteGenerator::new(config);
+    generator.set_format(BuildFormat::Json);
+    generator.generate().await.unwrap();
+
+    let posts: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("dist/posts.json")).unwrap()).unwrap();
+    assert_eq!(posts[0]["slug"], "second");
+    assert_eq!(posts[1]["path"], "first/");
+    assert!(posts[1]["html"].as_str().unwrap().contains("<canvas></canvas>"));
+    let tags: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("dist/tags.json")).unwrap()).unwrap();
+    assert_eq!(tags, serde_json::json!([{ "name": "notes", "posts": ["second", "first"] }, { "name": "rust", "posts": ["first"] }]));
+    let graph: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("dist/graph.json")).unwrap()).unwrap();
+    assert_eq!(graph["edges"].as_array().unwrap().len(), 1);
+    assert!(!dir.join("dist/index.html").exists() && !dir.join("dist/style.css").exists());
+}
+
+#[tokio::test]
+async fn switching_formats_prunes_the_other_formats_files() {
+    let dir = common::project("api", "switch", &[("hello.md", "---\ntitle: Hello\ndate: 2024-01-01\n---\n\nHi.\n")]);
+    let config = Config { url: Some("https://example.com".to_string()), ..common::config(&dir) };
+    let site = ["feed.xml", "feed.json", "sitemap.xml", "_redirects", "index.html"];
+    let content = ["posts.json", "tags.json", "graph.json"];
+
+    SiteGenerator::new(config.clone()).generate().await.unwrap();
+    assert!(site.iter().all(|file| dir.join("dist").join(file).is_file()));
+    let mut generator = SiteGenerator::new(config.clone());
+    generator.set_format(BuildFormat::Json);
+    generator.generate().await.unwrap();
+    assert!(content.iter().all(|file| dir.join("dist").join(file).is_file()));
+    assert!(site.iter().all(|file| !dir.join("dist").join(file).exists()));
+
+    SiteGenerator::new(config).generate().await.unwrap();
+    assert!(site.iter().all(|file| dir.join("dist").join(file).is_file()));
+    assert!(content.iter().all(|file| !dir.join("dist").join(file).exists()));
+}
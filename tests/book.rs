@@ -0,0 +1,43 @@
+//! Tests for compiling posts into EPUB books.
+
+mod common;
+
+use scribe::book::{self, Book, Selection};
+use scribe::generator::SiteGenerator;
+use std::fs;
+
+#[tokio::test]
+async fn packs_series_into_an_epub() {
+    let dir = common::scratch("book", "series");
+    fs::create_dir_all(dir.join("posts/images")).unwrap();
+    fs::write(dir.join("posts/images/dot.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+    fs::write(
+        dir.join("posts/one.md"),
+        "---\ntitle: Part One\ndate: 2024-02-01\nseries: Walks\nseries_order: 1\n---\n\nOn to [part two](../two/).<br>\n\n![A dot](/images/dot.png)\n",
+    )
+    .unwrap();
+    fs::write(dir.join("posts/two.md"), "---\ntitle: Part Two & More\ndate: 2024-01-01\nseries: Walks\nseries_order: 2\n---\n\nThe end.[^1]\n\n- [x] Walked\n- [ ] Rested\n\n<input type=checkbox checked>\n\n[^1]: For now.\n").unwrap();
+    fs::write(dir.join("posts/other.md"), "---\ntitle: Other\ndate: 2024-03-01\n---\n\nNot in the book.\n").unwrap();
+    let config = common::config(&dir);
+    let mut generator = SiteGenerator::new(config.clone());
+    let posts = generator.read_posts().await.unwrap();
+    let selection = Selection { series: Some("Walks".to_string()), ..Default::default() };
+    let book = Book::new(&config, posts, &selection, None);
+    assert_eq!(book.title, "Walks");
+    assert_eq!(book.posts.iter().map(|p| p.slug.as_str()).collect::<Vec<_>>(), ["one", "two"]);
+
+    let epub = book::epub(&config, &book).unwrap();
+    assert!(epub.starts_with(b"PK\x03\x04"));
+    assert_eq!(&epub[30..58], b"mimetypeapplication/epub+zip");
+    let contents = String::from_utf8_lossy(&epub);
+    assert!(contents.contains(r#"<li><a href="chapter-2.xhtml">Part Two &amp; More</a></li>"#));
+    assert!(contents.contains(r#"On to <a href="chapter-2.xhtml">part two</a>.<br /></p>"#));
+    assert!(contents.contains(r#"<img src="images/1.png" alt="A dot" />"#));
+    assert!(contents.contains(r#"<item id="resource-1" href="images/1.png" media-type="image/png"/>"#));
+    assert!(contents.contains(r##"<sup class="footnote-reference"><a href="#fn-1">1</a></sup>"##), "{}", contents);
+    assert!(contents.contains(r#"<div class="footnote-definition" id="fn-1">"#));
+    assert!(contents.contains(r#"<input disabled="" type="checkbox" checked="" />"#));
+    assert!(contents.contains(r#"<input type="checkbox" checked="checked" />"#));
+    assert!(!contents.contains("Not in the book."));
+    assert_eq!(epub, book::epub(&config, &book).unwrap());
+}
@@ -0,0 +1,89 @@
+//! Tests for hashing and packing directories the way `ipfs add` does.
+
+mod common;
+
+use scribe::car::{self, CidVersion};
+use std::fs;
+use std::path::PathBuf;
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = common::scratch("car", name);
+    fs::create_dir_all(dir.join("site")).unwrap();
+    dir
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> usize {
+    let (mut value, mut shift) = (0, 0);
+    loop {
+        let byte = bytes[*pos];
+        *pos += 1;
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+#[test]
+fn empty_directory_has_the_well_known_cid() {
+    let dir = scratch("empty");
+    let summary = car::export(&dir.join("site"), &dir.join("site.car")).unwrap();
+    assert_eq!(summary.root, "bafybeiczsscdsbs7ffqz55asqdf3smv6klcw3gofszvwlyarci47bgf354");
+    assert_eq!(summary.blocks, 1);
+}
+
+#[test]
+fn blocks_are_framed_and_deduplicated() {
+    let dir = scratch("framed");
+    fs::create_dir_all(dir.join("site/posts")).unwrap();
+    fs::write(dir.join("site/index.html"), "<h1>Hi</h1>").unwrap();
+    fs::write(dir.join("site/posts/copy.html"), "<h1>Hi</h1>").unwrap();
+    // Three 256 KiB chunks under one file node
+    fs::write(dir.join("site/big.bin"), vec![7u8; 600_000]).unwrap();
+
+    let summary = car::export(&dir.join("site"), &dir.join("site.car")).unwrap();
+    assert_eq!(summary.files, 3);
+    // Root and posts directories, one shared page block, two distinct chunks and the file node
+    assert_eq!(summary.blocks, 6);
+
+    let bytes = fs::read(dir.join("site.car")).unwrap();
+    assert_eq!(bytes.len() as u64, summary.bytes);
+    let mut pos = 0;
+    let header_len = read_varint(&bytes, &mut pos);
+    pos += header_len;
+    let mut blocks = 0;
+    while pos < bytes.len() {
+        let len = read_varint(&bytes, &mut pos);
+        pos += len;
+        blocks += 1;
+    }
+    assert_eq!(pos, bytes.len());
+    assert_eq!(blocks, summary.blocks);
+}
+
+#[test]
+fn hashes_match_ipfs_add() {
+    let dir = scratch("hash");
+    let file = dir.join("site/hello.txt");
+    fs::write(&file, "hello world\n").unwrap();
+    assert_eq!(car::hash(&file, CidVersion::V0).unwrap(), "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o");
+    fs::write(&file, "hello world").unwrap();
+    assert_eq!(car::hash(&file, CidVersion::V1).unwrap(), "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e");
+    fs::remove_file(&file).unwrap();
+    assert_eq!(car::hash(&dir.join("site"), CidVersion::V0).unwrap(), "QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn");
+    assert_eq!(CidVersion::of("QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn"), Some(CidVersion::V0));
+}
+
+#[test]
+fn directories_ipfs_add_would_shard_are_refused() {
+    let dir = scratch("shard");
+    // 28-byte names and 36-byte CIDv1s: 4096 entries reach the 256 KiB `ipfs add` shards at
+    for i in 0..4095 {
+        fs::write(dir.join(format!("site/{:024}.htm", i)), "").unwrap();
+    }
+    car::export(&dir.join("site"), &dir.join("site.car")).unwrap();
+    fs::write(dir.join("site/999999999999999999999999.htm"), "").unwrap();
+    let error = car::export(&dir.join("site"), &dir.join("site.car")).unwrap_err();
+    assert!(error.to_string().contains("`ipfs add` would shard it"), "{}", error);
+}
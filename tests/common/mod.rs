@@ -0,0 +1,37 @@
+//! Fixtures shared by the integration tests: scratch projects under the temp dir and offline configs.
+
+#![allow(dead_code)]
+
+use scribe::config::Config;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A fresh, empty directory under the temp dir for test `name` of `suite`, unique to this process.
+pub fn scratch(suite: &str, name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("scribe-{}-{}-{}", suite, name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A scratch project whose `posts` holds `files`, given as paths relative to `posts` and their contents.
+pub fn project(suite: &str, name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = scratch(suite, name);
+    fs::create_dir_all(dir.join("posts")).unwrap();
+    for (path, content) in files {
+        let path = dir.join("posts").join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+/// A config building the project's `posts` offline into its `dist`.
+pub fn config(dir: &Path) -> Config {
+    Config {
+        posts_dir: dir.join("posts").display().to_string(),
+        output_dir: dir.join("dist").display().to_string(),
+        offline: true,
+        ..Default::default()
+    }
+}
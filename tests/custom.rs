@@ -0,0 +1,31 @@
+//! Tests for the project's own `static/custom.css` and `static/custom.js`. They are found
+//! relative to the working directory, so this file holds a single test.
+
+mod common;
+
+use scribe::config::Config;
+use scribe::generator::SiteGenerator;
+use std::fs;
+
+#[tokio::test]
+async fn appends_custom_css_and_loads_custom_js() {
+    let dir = common::project("custom", "static", &[("hello.md", "---\ntitle: Hello\ndate: 2024-01-01\n---\n\nHi.\n")]);
+    fs::create_dir_all(dir.join("static")).unwrap();
+    fs::write(dir.join("theme.css"), ".theme { color: red; }\n").unwrap();
+    fs::write(dir.join("static/custom.css"), ".mine { color: blue; }\n").unwrap();
+    fs::write(dir.join("static/custom.js"), "console.log('hello');\n").unwrap();
+    std::env::set_current_dir(&dir).unwrap();
+    let mut config = Config { offline: true, ..Default::default() };
+    config.theme.stylesheet = Some("theme.css".to_string());
+    SiteGenerator::new(config).generate().await.unwrap();
+
+    let css = fs::read_to_string(dir.join("dist/style.css")).unwrap();
+    let theme = css.find(".theme").unwrap();
+    let custom = css.find(".mine").unwrap();
+    assert!(theme < custom, "custom.css should come last:\n{}", css);
+    assert_eq!(fs::read_to_string(dir.join("dist/custom.js")).unwrap(), "console.log('hello');\n");
+    let page = fs::read_to_string(dir.join("dist/hello/index.html")).unwrap();
+    assert!(page.contains(r#"<script src="../custom.js" defer></script>"#), "{}", page);
+    let index = fs::read_to_string(dir.join("dist/index.html")).unwrap();
+    assert!(index.contains(r#"<script src="./custom.js" defer></script>"#), "{}", index);
+}
@@ -0,0 +1,60 @@
+//! Tests for registering output emitters on the generator.
+
+mod common;
+
+use scribe::config::{Config, InitialsMode};
+use scribe::emit::{Output, OutputEmitter, SiteContext};
+use scribe::generator::SiteGenerator;
+use std::fs;
+use std::path::PathBuf;
+
+/// Writes `titles.txt` (or `options.file`) listing every post title.
+struct Titles;
+
+impl OutputEmitter for Titles {
+    fn name(&self) -> &str {
+        "titles"
+    }
+
+    fn emit(&self, site: &SiteContext, options: &serde_json::Value) -> anyhow::Result<Vec<Output>> {
+        let file = options["file"].as_str().unwrap_or("titles.txt");
+        let titles: Vec<&str> = site.posts.iter().map(|p| p.title.as_str()).collect();
+        Ok(vec![Output::new(file, titles.join("\n"))])
+    }
+}
+
+/// A project under the temp dir with one post, building offline into its `dist`.
+fn project(name: &str, emitters: serde_json::Value) -> (PathBuf, Config) {
+    let dir = common::project("emit", name, &[("hello.md", "---\ntitle: Hello\ndate: 2024-01-01\n---\n\nHi there.\n")]);
+    let mut config = Config { emitters: serde_json::from_value(emitters).unwrap(), ..common::config(&dir) };
+    config.initials.mode = InitialsMode::Svg;
+    (dir, config)
+}
+
+#[tokio::test]
+async fn enabled_emitter_writes_its_outputs() {
+    let (dir, config) = project("enabled", serde_json::json!({ "titles": { "file": "api/titles.txt" } }));
+    let mut generator = SiteGenerator::new(config);
+    generator.add_emitter(Titles);
+    generator.generate().await.unwrap();
+    assert_eq!(fs::read_to_string(dir.join("dist/api/titles.txt")).unwrap(), "Hello");
+}
+
+#[tokio::test]
+async fn emitter_only_runs_when_listed_in_config() {
+    let (dir, config) = project("disabled", serde_json::json!({}));
+    let mut generator = SiteGenerator::new(config);
+    generator.add_emitter(Titles);
+    generator.generate().await.unwrap();
+    assert!(!dir.join("dist/titles.txt").exists());
+}
+
+#[tokio::test]
+async fn emitter_cannot_write_outside_the_output_or_over_site_files() {
+    for file in ["../escape.txt", "index.html"] {
+        let (_, config) = project("paths", serde_json::json!({ "titles": { "file": file } }));
+        let mut generator = SiteGenerator::new(config);
+        generator.add_emitter(Titles);
+        assert!(generator.generate().await.is_err(), "{} was accepted", file);
+    }
+}
@@ -0,0 +1,12 @@
+//! Tests for ENS contenthash encoding.
+
+use scribe::ens;
+
+#[test]
+fn contenthash_matches_eip_1577() {
+    let expected = "0xe3010170122029f2d17be6139079dc48696d1f582a8530eb9805b561eda517e22a892c7e3f1f";
+    assert_eq!(ens::contenthash("QmRAQB6YaCyidP37UdDnjFY5vQuiBrcqdyoW1CuDgwxkD4").unwrap(), expected);
+    // The same content as a base32 CIDv1
+    assert_eq!(ens::contenthash("bafybeibj6lixxzqtsb45ysdjnupvqkufgdvzqbnvmhw2kf7cfkesy7r7d4").unwrap(), expected);
+    assert!(ens::contenthash("not-a-cid").is_err());
+}
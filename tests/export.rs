@@ -0,0 +1,39 @@
+//! Tests for exporting posts as newsletter emails.
+
+mod common;
+
+use scribe::config::Config;
+use scribe::export;
+use scribe::generator::SiteGenerator;
+
+#[test]
+fn converts_html_to_plain_text() {
+    let html = "<h2>Title</h2>\n<p>See <a href=\"https://example.com\">this</a> &amp; that.</p>\n<ul>\n<li>one</li>\n<li>two</li>\n</ul>\n<blockquote>\n<p>Quoted</p>\n</blockquote>\n<pre><code>a  &lt;b&gt;\n  c\n</code></pre>\n<ol start=\"3\">\n<li>three</li>\n</ol>\n";
+    assert_eq!(
+        export::html_to_text(html),
+        "Title\n=====\n\nSee this (https://example.com) & that.\n\n- one\n- two\n\n> Quoted\n\na  <b>\n  c\n\n3. three"
+    );
+}
+
+#[tokio::test]
+async fn emails_inline_styles_and_absolute_links() {
+    let dir = common::project(
+        "export",
+        "email",
+        &[("hello.md", "---\ntitle: Hello\ndate: 2024-01-01\ntldr: Short.\n---\n\nSee [the other](../other/), [a note](#fn) and ![a cat](/assets/cat.png).\n")],
+    );
+    let config = Config { url: Some("https://blog.example.com".to_string()), ..common::config(&dir) };
+    let mut generator = SiteGenerator::new(config.clone());
+    let posts = generator.read_posts().await.unwrap();
+    let email = export::email(&config, &posts[0]);
+
+    assert_eq!(email.subject, "Hello");
+    assert!(email.html.contains(r#"<a style="color: #1a1a1a; text-decoration: underline;" href="https://blog.example.com/other/">"#));
+    assert!(email.html.contains(r##"href="#fn""##));
+    assert!(email.html.contains(r#"src="https://blog.example.com/assets/cat.png""#));
+    assert!(email.html.contains("<p style=\"margin: 0 0 20px;"));
+    assert!(!email.html.contains("<link") && !email.html.contains("<script"));
+    assert!(email.text.starts_with("Hello\n=====\n\nJanuary 1, 2024\n\nTL;DR Short.\n\n"));
+    assert!(email.text.contains("the other (https://blog.example.com/other/)"));
+    assert!(email.text.ends_with("Read this post on Scribe: https://blog.example.com/hello/\n"));
+}
@@ -0,0 +1,174 @@
+//! Tests for finding and editing the frontmatter block at the top of a post.
+
+use scribe::frontmatter::{self, has_block, raw_field, set_field, set_raw, validate};
+use std::path::Path;
+
+#[test]
+fn splits_frontmatter_from_body() {
+    let (fm, body) = frontmatter::split("---\ntitle: Hello\ntags: [a, b]\n---\n\nFirst paragraph.\n");
+    let fm = fm.expect("frontmatter");
+    assert_eq!(fm.title.as_deref(), Some("Hello"));
+    assert_eq!(fm.tags, ["a", "b"]);
+    assert_eq!(body, "\nFirst paragraph.");
+}
+
+#[test]
+fn later_horizontal_rule_stays_in_body() {
+    let (fm, body) = frontmatter::split("---\ntitle: Hello\n---\nAbove.\n\n---\n\nBelow.\n");
+    assert_eq!(fm.unwrap().title.as_deref(), Some("Hello"));
+    assert_eq!(body, "Above.\n\n---\n\nBelow.");
+}
+
+#[test]
+fn leading_horizontal_rule_is_not_frontmatter() {
+    let content = "---\n\nA post without frontmatter.\n\n---\n\nAfter the rule.\n";
+    let (fm, body) = frontmatter::split(content);
+    assert!(fm.is_none());
+    assert_eq!(body, content);
+    assert!(!has_block(content));
+}
+
+#[test]
+fn unclosed_block_is_not_frontmatter() {
+    let content = "---\ntitle: Hello\n\nThe fence never closes.\n";
+    let (fm, body) = frontmatter::split(content);
+    assert!(fm.is_none());
+    assert_eq!(body, content);
+}
+
+#[test]
+fn indented_fence_inside_block_scalar_does_not_close() {
+    let (fm, body) = frontmatter::split("---\nexcerpt: |\n  before\n  ---\n  after\ntitle: Hello\n---\nBody\n");
+    let fm = fm.unwrap();
+    assert_eq!(fm.excerpt.as_deref(), Some("before\n---\nafter\n"));
+    assert_eq!(fm.title.as_deref(), Some("Hello"));
+    assert_eq!(body, "Body");
+}
+
+#[test]
+fn accepts_dots_fence_crlf_bom_and_empty_blocks() {
+    let (fm, body) = frontmatter::split("---\ntitle: Dots\n...\nBody\n");
+    assert_eq!(fm.unwrap().title.as_deref(), Some("Dots"));
+    assert_eq!(body, "Body");
+
+    let (fm, body) = frontmatter::split("\u{feff}---\r\ntitle: Windows\r\n---\r\nBody\r\n");
+    assert_eq!(fm.unwrap().title.as_deref(), Some("Windows"));
+    assert_eq!(body, "Body");
+
+    let (fm, body) = frontmatter::split("---\n---\nBody\n");
+    assert!(fm.unwrap().title.is_none());
+    assert_eq!(body, "Body");
+}
+
+#[test]
+fn edits_only_the_frontmatter() {
+    let content = "---\ntitle: 'Hello'\n---\nText\n\n---\n\nupdated: not a field\n";
+    assert_eq!(raw_field(content, "title"), Some("Hello"));
+    assert_eq!(raw_field(content, "updated"), None);
+
+    let updated = set_field(content, "updated", "2024-02-01").unwrap();
+    assert_eq!(updated, "---\ntitle: 'Hello'\nupdated: \"2024-02-01\"\n---\nText\n\n---\n\nupdated: not a field\n");
+    let retitled = set_field(&updated, "title", "It's \"here\"").unwrap();
+    assert!(retitled.starts_with("---\ntitle: 'It''s \"here\"'\n"));
+    assert_eq!(frontmatter::split(&retitled).0.unwrap().title.as_deref(), Some("It's \"here\""));
+}
+
+#[test]
+fn refuses_to_edit_without_frontmatter() {
+    assert!(set_field("---\n\nJust a rule.\n", "updated", "2024-02-01").is_err());
+}
+
+#[test]
+fn sets_raw_values() {
+    let content = set_raw("---\n---\n\nBody\n", "draft", "true").unwrap();
+    let content = set_raw(&content, "tags", "[\"a\", \"b\"]").unwrap();
+    assert_eq!(content, "---\ndraft: true\ntags: [\"a\", \"b\"]\n---\n\nBody\n");
+    assert!(frontmatter::split(&content).0.unwrap().draft);
+    assert!(set_raw(&content, "tags", "[unclosed").is_err());
+}
+
+#[test]
+fn reports_fields_by_line() {
+    let content = "---\ntitle: [Hello]\ndate: 01/02/2024\ndraft: maybe\n---\nBody\n";
+    let problems: Vec<String> = validate(Path::new("post.md"), content).iter().map(ToString::to_string).collect();
+    assert_eq!(problems.len(), 3);
+    assert!(problems[0].starts_with("post.md:2: `title`: invalid type"));
+    assert!(problems[1].starts_with("post.md:3: `date`: \"01/02/2024\""));
+    assert!(problems[2].starts_with("post.md:4: `draft`: invalid type"));
+    // The readable fields survive the bad ones
+    assert_eq!(frontmatter::split(content).0.unwrap().date.as_deref(), Some("01/02/2024"));
+}
+
+#[test]
+fn one_wrong_field_keeps_the_others() {
+    let content = "---\ntitle: Hello\ndate: 2024-01-02\ntags: [a, b]\ndraft: maybe\nlayout: wide\nmood: calm\n---\nBody\n";
+    let fm = frontmatter::split(content).0.unwrap();
+    assert_eq!(fm.title.as_deref(), Some("Hello"));
+    assert_eq!(fm.date.as_deref(), Some("2024-01-02"));
+    assert_eq!(fm.tags, ["a", "b"]);
+    assert_eq!(fm.layout, Some(frontmatter::PageLayout::Wide));
+    assert!(!fm.draft);
+    assert_eq!(fm.extra["draft"], "maybe");
+    assert_eq!(fm.extra["mood"], "calm");
+    let problems = validate(Path::new("post.md"), content);
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].to_string().starts_with("post.md:5: `draft`: invalid type"), "{}", problems[0]);
+}
+
+#[test]
+fn reports_yaml_that_does_not_parse() {
+    let problems = validate(Path::new("post.md"), "---\ntitle: Hello\ntags: [a, b\n---\nBody\n");
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].message.starts_with("frontmatter is not valid YAML"));
+    assert!(validate(Path::new("post.md"), "---\ntitle: Hello\ndate: 2024-01-01\n---\nBody\n").is_empty());
+    assert!(validate(Path::new("post.md"), "---\n\nJust a rule.\n").is_empty());
+    assert_eq!(validate(Path::new("post.md"), "---\ndate: 2024-01-01\n---\n")[0].message, "missing `title`; the file name is used instead");
+}
+
+#[test]
+fn reads_toml_and_json_into_the_same_fields() {
+    let (fm, body) = frontmatter::split("+++\ntitle = \"Hello\"\ndate = 2024-01-02\ntags = [\"a\", \"b\"]\n\n[extra]\nmood = \"calm\"\n+++\nBody\n");
+    let fm = fm.expect("TOML frontmatter");
+    assert_eq!(fm.title.as_deref(), Some("Hello"));
+    assert_eq!(fm.date.as_deref(), Some("2024-01-02"));
+    assert_eq!(fm.tags, ["a", "b"]);
+    assert_eq!(fm.extra["extra"]["mood"], "calm");
+    assert_eq!(body, "Body");
+
+    let (fm, body) = frontmatter::split("{\n  \"title\": \"Hello\",\n  \"tags\": \"a, b\"\n}\n\nBody {with braces}\n");
+    let fm = fm.expect("JSON frontmatter");
+    assert_eq!(fm.title.as_deref(), Some("Hello"));
+    assert_eq!(fm.tags, ["a", "b"]);
+    assert_eq!(body, "\nBody {with braces}");
+    assert!(!has_block("{{< note >}}\nNot frontmatter\n"));
+}
+
+#[test]
+fn edits_toml_and_json_in_place() {
+    let toml = "+++\ntitle = 'Hello'\n\n[extra]\nmood = \"calm\"\n+++\nBody\n";
+    let updated = set_field(toml, "updated", "2024-02-01").unwrap();
+    assert_eq!(updated, "+++\ntitle = 'Hello'\nupdated = \"2024-02-01\"\n\n[extra]\nmood = \"calm\"\n+++\nBody\n");
+    assert_eq!(raw_field(&updated, "title"), Some("Hello"));
+    let retitled = set_field(&updated, "title", "It's here").unwrap();
+    assert_eq!(frontmatter::split(&retitled).0.unwrap().title.as_deref(), Some("It's here"));
+
+    let json = "{\n  \"title\": \"Hello\"\n}\nBody\n";
+    let updated = set_field(json, "updated", "2024-02-01").unwrap();
+    assert_eq!(updated, "{\n  \"updated\": \"2024-02-01\",\n  \"title\": \"Hello\"\n}\nBody\n");
+    let updated = set_raw(&updated, "draft", "true").unwrap();
+    assert!(frontmatter::split(&updated).0.unwrap().draft);
+    assert_eq!(raw_field(&updated, "updated"), Some("2024-02-01"));
+}
+
+#[test]
+fn reports_toml_and_json_errors_by_line() {
+    let problems = validate(Path::new("post.md"), "+++\ntitle = \"Hello\"\ndate = \n+++\nBody\n");
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].message.starts_with("frontmatter is not valid TOML"), "{}", problems[0].message);
+    assert_eq!(problems[0].line, 3);
+
+    let problems = validate(Path::new("post.md"), "{\n  \"title\": \"Hello\",\n  \"date\": \n}\nBody\n");
+    assert!(problems[0].message.starts_with("frontmatter is not valid JSON"), "{}", problems[0].message);
+    let problems = validate(Path::new("post.md"), "{\n  \"title\": \"Hello\",\n  \"draft\": \"no\"\n}\nBody\n");
+    assert_eq!(problems[0].to_string(), "post.md:3: `draft`: invalid type: string \"no\", expected a boolean");
+}
@@ -0,0 +1,37 @@
+//! Tests for output that works on IPFS gateways and under a subpath.
+
+mod common;
+
+use scribe::config::Config;
+use scribe::generator::SiteGenerator;
+use std::fs;
+
+#[tokio::test]
+async fn links_are_relative_and_redirects_are_listed() {
+    let dir = common::scratch("gateway", "relative");
+    fs::create_dir_all(dir.join("posts/images")).unwrap();
+    fs::write(dir.join("posts/images/a.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+    fs::write(
+        dir.join("posts/hello.md"),
+        "---\ntitle: Hello\ndate: 2024-01-01\naliases: [hi]\n---\n\n![A](/blog/images/a.png) and [the other](/other) or [again](../other#top).\n",
+    )
+    .unwrap();
+    fs::write(dir.join("posts/other.md"), "---\ntitle: Other\ndate: 2024-01-02\n---\n\nBack to [hello](https://example.com/blog/hello/).\n").unwrap();
+    let config = Config { url: Some("https://example.com".to_string()), base_path: Some("/blog/".to_string()), ..common::config(&dir) };
+    SiteGenerator::new(config).generate().await.unwrap();
+
+    let hello = fs::read_to_string(dir.join("dist/hello/index.html")).unwrap();
+    assert!(hello.contains(r#"src="../images/a.png""#), "{}", hello);
+    assert!(hello.contains(r#"href="../other/""#), "{}", hello);
+    assert!(hello.contains(r#"href="../other/#top""#), "{}", hello);
+    assert!(!hello.contains(r#"href="/"#), "{}", hello);
+    let other = fs::read_to_string(dir.join("dist/other/index.html")).unwrap();
+    assert!(other.contains(r#"href="https://example.com/blog/hello/""#), "{}", other);
+    // The 404 page is served at any depth, so it keeps its links from the base path
+    let not_found = fs::read_to_string(dir.join("dist/404.html")).unwrap();
+    assert!(not_found.contains(r#"href="/blog/""#), "{}", not_found);
+
+    let redirects = fs::read_to_string(dir.join("dist/_redirects")).unwrap();
+    let rules: Vec<Vec<&str>> = redirects.lines().map(|line| line.split_whitespace().collect()).collect();
+    assert_eq!(rules, [vec!["/blog/hi/", "/blog/hello/", "301"], vec!["/blog/*", "/blog/404.html", "404"]]);
+}
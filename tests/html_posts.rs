@@ -0,0 +1,38 @@
+//! Tests for hand-written HTML posts.
+
+mod common;
+
+use scribe::generator::SiteGenerator;
+use std::fs;
+
+#[tokio::test]
+async fn html_posts_keep_their_markup_inside_the_site() {
+    let dir = common::scratch("html-posts", "markup");
+    fs::create_dir_all(dir.join("posts/demos")).unwrap();
+    fs::write(
+        dir.join("posts/My Art Piece.html"),
+        "---\ntitle: My Art Piece\ndate: 2024-02-01\n---\n<section class=\"piece\">\n  <p>Some *words* and [[not-a-link]] here.</p>\n\n    <a href=\"/hello/\">Back</a>\n</section>\n<script>draw();</script>\n",
+    )
+    .unwrap();
+    fs::write(dir.join("posts/hello.md"), "---\ntitle: Hello\ndate: 2024-01-01\n---\n\nHi.\n").unwrap();
+    // HTML without frontmatter isn't a post
+    fs::write(dir.join("posts/demos/frame.html"), "<p>Just a demo</p>\n").unwrap();
+    let config = common::config(&dir);
+    let mut generator = SiteGenerator::new(config);
+    generator.generate().await.unwrap();
+
+    let page = fs::read_to_string(dir.join("dist/my-art-piece/index.html")).unwrap();
+    assert!(page.contains("<h1 class=\"post-title\">My Art Piece</h1>"), "{}", page);
+    assert!(page.contains("<p>Some *words* and [[not-a-link]] here.</p>\n\n    <a href=\"../hello/\">Back</a>"), "{}", page);
+    assert!(page.contains("<script>draw();</script>"), "{}", page);
+    assert!(!page.contains("illuminated-initial"), "{}", page);
+    assert!(!dir.join("dist/frame/index.html").exists());
+
+    let index = fs::read_to_string(dir.join("dist/index.html")).unwrap();
+    assert!(index.contains("My Art Piece"), "{}", index);
+    let post = generator.posts().iter().find(|p| p.slug == "my-art-piece").unwrap();
+    assert_eq!(post.words, 6);
+    assert_eq!(post.links.len(), 1);
+    let hello = fs::read_to_string(dir.join("dist/hello/index.html")).unwrap();
+    assert!(hello.contains("Backlinks"), "{}", hello);
+}
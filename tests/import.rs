@@ -0,0 +1,130 @@
+//! Tests for importing content from Jekyll, Hugo and Obsidian.
+
+mod common;
+
+use scribe::generator::SiteGenerator;
+use scribe::import::{self, Source};
+use std::fs;
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn imports_a_jekyll_site() {
+    let dir = common::scratch("import", "jekyll");
+    fs::create_dir_all(dir.join("site/_posts")).unwrap();
+    fs::create_dir_all(dir.join("site/_drafts")).unwrap();
+    fs::create_dir_all(dir.join("site/assets")).unwrap();
+    fs::write(dir.join("site/assets/map.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+    fs::write(
+        dir.join("site/_posts/2023-04-01-first-walk.md"),
+        "---\nlayout: post\ntitle: \"First: a walk\"\ndate: 2023-04-01 10:30:00 +0200\ncategories: travel\ntags: hills rain\npermalink: /walk/\nmood: cheerful\n---\n\nRoute below.\n\n![Map]({{ site.baseurl }}/assets/map.png)\n\n<!--more-->\n\nNext: [the second]({% post_url 2023-05-01-second-walk %}).\n\n{% include gallery.html %}\n\n{% highlight rust %}\nfn main() {}\n{% endhighlight %}\n",
+    )
+    .unwrap();
+    fs::write(dir.join("site/_posts/2023-05-01-second-walk.md"), "---\ntitle: Second\n---\n\nShorter.\n").unwrap();
+    fs::write(dir.join("site/_drafts/later.md"), "---\ntitle: Later\ndate: 2023-06-01\n---\n\nSoon.\n").unwrap();
+
+    let import = import::convert(Source::Jekyll, &dir.join("site")).unwrap();
+    let paths: Vec<String> = import.posts.iter().map(|p| p.path.display().to_string()).collect();
+    assert_eq!(paths, ["first-walk.md", "second-walk.md", "later.md"]);
+    let first = &import.posts[0].content;
+    assert!(first.contains("title: 'First: a walk'"), "{}", first);
+    assert!(first.contains("date: 2023-04-01T08:30:00Z"), "{}", first);
+    assert!(first.contains("tags: [\"hills\",\"rain\",\"travel\"]"), "{}", first);
+    assert!(first.contains("aliases: [\"walk\"]"), "{}", first);
+    assert!(first.contains("mood: cheerful"), "{}", first);
+    assert!(!first.contains("layout"), "{}", first);
+    assert!(first.contains("![Map](/assets/map.png)"), "{}", first);
+    assert!(first.contains("[the second](../second-walk/)"), "{}", first);
+    assert!(first.contains("```rust\nfn main() {}\n```"), "{}", first);
+    assert!(!first.contains("<!--more-->"), "{}", first);
+    assert!(import.posts[1].content.contains("date: 2023-05-01\n"));
+    assert!(import.posts[2].content.contains("draft: true"));
+    assert_eq!(import.assets.len(), 1);
+    assert_eq!(import.assets[0].to, PathBuf::from("assets/map.png"));
+
+    let issues: Vec<String> = import.issues.iter().map(|i| i.to_string()).collect();
+    assert!(issues.iter().any(|i| i.contains("{% include gallery.html %}")), "{:?}", issues);
+    assert!(issues.iter().any(|i| i.contains("mood")), "{:?}", issues);
+    assert!(issues.iter().any(|i| i.contains("/travel/2023/05/01/second-walk.html") || i.contains("/2023/05/01/second-walk.html")), "{:?}", issues);
+
+    // Imported posts build, and a second import leaves them alone
+    let posts_dir = dir.join("posts");
+    assert!(import.write(&posts_dir).unwrap().is_empty());
+    assert!(posts_dir.join("assets/map.png").is_file());
+    assert_eq!(import.write(&posts_dir).unwrap().len(), 4);
+    let mut generator = SiteGenerator::new(common::config(&dir));
+    let posts = generator.read_posts().await.unwrap();
+    let first = posts.iter().find(|p| p.slug == "first-walk").unwrap();
+    assert_eq!(first.title, "First: a walk");
+    assert_eq!(first.tags(), ["hills", "rain", "travel"]);
+}
+
+#[test]
+fn imports_hugo_bundles_and_translations() {
+    let dir = common::scratch("import", "hugo");
+    fs::create_dir_all(dir.join("content/posts/trip")).unwrap();
+    fs::create_dir_all(dir.join("static/img")).unwrap();
+    fs::write(dir.join("content/_index.md"), "---\ntitle: Home\n---\n").unwrap();
+    fs::write(dir.join("content/posts/trip/photo.jpg"), b"jpeg").unwrap();
+    fs::write(dir.join("static/img/logo.png"), b"png").unwrap();
+    fs::write(
+        dir.join("content/posts/trip/index.md"),
+        "---\ntitle: Trip\ndate: 2022-08-01T09:00:00Z\nlastmod: 2022-08-02\nsummary: Away\naliases: [/old-trip/]\n---\n\n![Beach](photo.jpg)\n\n{{< figure src=\"/img/logo.png\" alt=\"Logo\" >}}\n\nSee [notes]({{< ref \"notes.md\" >}}).\n\n{{< tweet 123 >}}\n",
+    )
+    .unwrap();
+    fs::write(dir.join("content/posts/notes.md"), "+++\ntitle = \"Notes\"\n+++\n\nToml.\n").unwrap();
+    fs::write(dir.join("content/posts/notes.nl.md"), "---\ntitle: Notities\ndate: 2022-01-01\n---\n\nHallo.\n").unwrap();
+
+    let import = import::convert(Source::Hugo, &dir).unwrap();
+    let paths: Vec<String> = import.posts.iter().map(|p| p.path.display().to_string()).collect();
+    assert_eq!(paths, ["notes.md", "nl/notes.md", "trip.md"]);
+    assert!(import.posts[0].content.contains("title: Notes\n"));
+    assert!(import.posts[1].content.contains("lang: nl"));
+    let trip = &import.posts[2].content;
+    assert!(trip.contains("updated: 2022-08-02\n"), "{}", trip);
+    assert!(trip.contains("excerpt: Away"), "{}", trip);
+    assert!(trip.contains("aliases: [\"old-trip\"]"), "{}", trip);
+    assert!(trip.contains("![Logo](/img/logo.png)"), "{}", trip);
+    assert!(trip.contains("[notes](../notes/)"), "{}", trip);
+    let targets: Vec<String> = import.assets.iter().map(|a| a.to.display().to_string()).collect();
+    assert!(targets.contains(&"trip/photo.jpg".to_string()), "{:?}", targets);
+    assert!(targets.contains(&"img/logo.png".to_string()), "{:?}", targets);
+    let issues: Vec<String> = import.issues.iter().map(|i| i.to_string()).collect();
+    assert!(issues.iter().any(|i| i.contains("{{< tweet 123 >}}")), "{:?}", issues);
+}
+
+#[test]
+fn imports_an_obsidian_vault() {
+    let dir = common::scratch("import", "obsidian");
+    fs::create_dir_all(dir.join("vault/Attachments")).unwrap();
+    fs::create_dir_all(dir.join("vault/.obsidian")).unwrap();
+    fs::write(dir.join("vault/.obsidian/app.md"), "not a note").unwrap();
+    fs::write(dir.join("vault/Attachments/Sketch 1.png"), b"png").unwrap();
+    fs::write(
+        dir.join("vault/Garden Ideas.md"),
+        "---\ncreated: 2024-03-02\naliases: [Garden]\n---\n\nPlanting #spring bulbs. ==Water daily==. %%private%%\n\n![[Sketch 1.png|300]]\n\nSee [[Compost#Ratios|the ratios]] and [[Missing Note]].\n\n````md\n```\n[[Compost]] stays in code\n```\n#not-a-tag\n````\n\nAnd `==this==` too.\n",
+    )
+    .unwrap();
+    fs::write(dir.join("vault/Compost.md"), "Back to [[Garden]] and [the ideas](Garden%20Ideas.md).\n").unwrap();
+
+    let import = import::convert(Source::Obsidian, &dir.join("vault")).unwrap();
+    let paths: Vec<String> = import.posts.iter().map(|p| p.path.display().to_string()).collect();
+    assert_eq!(paths, ["compost.md", "garden-ideas.md"]);
+    let garden = &import.posts[1].content;
+    assert!(garden.contains("title: Garden Ideas"), "{}", garden);
+    assert!(garden.contains("date: 2024-03-02\n"), "{}", garden);
+    assert!(garden.contains("tags: [\"spring\"]"), "{}", garden);
+    assert!(garden.contains("<mark>Water daily</mark>"), "{}", garden);
+    assert!(!garden.contains("private"), "{}", garden);
+    assert!(garden.contains("![](/Attachments/Sketch%201.png)"), "{}", garden);
+    assert!(garden.contains("[[compost|the ratios]]"), "{}", garden);
+    assert!(garden.contains("[[Compost]] stays in code"), "{}", garden);
+    assert!(garden.contains("#not-a-tag") && garden.contains("`==this==`"), "{}", garden);
+    assert!(!garden.contains("[[Missing Note]]"), "{}", garden);
+    let compost = &import.posts[0].content;
+    assert!(compost.contains("[[garden-ideas|Garden]]"), "{}", compost);
+    assert!(compost.contains("[[garden-ideas|the ideas]]"), "{}", compost);
+    assert_eq!(import.assets[0].to, PathBuf::from("Attachments/Sketch 1.png"));
+    let issues: Vec<String> = import.issues.iter().map(|i| i.to_string()).collect();
+    assert!(issues.iter().any(|i| i.contains("Missing Note")), "{:?}", issues);
+    assert!(issues.iter().any(|i| i.contains("heading")), "{:?}", issues);
+}
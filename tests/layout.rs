@@ -0,0 +1,56 @@
+//! Tests for per-post layouts and the `css:` and `js:` files copied next to a post.
+
+use scribe::config::{Config, TrailingSlash};
+use scribe::generator::SiteGenerator;
+mod common;
+
+use common::config;
+use std::fs;
+
+fn site(name: &str) -> std::path::PathBuf {
+    common::project(
+        "layout",
+        name,
+        &[
+            ("art/sketch.md", "---\ntitle: Sketch\ndate: 2024-01-01\nlayout: bare\ncss: sketch.css\njs: [lib/draw.js, https://cdn.example.com/p5.js, ../secret.js]\n---\n\n<canvas id=\"c\"></canvas>\n"),
+            ("art/sketch.css", "canvas { width: 100%; }\n"),
+            ("art/lib/draw.js", "draw();\n"),
+            ("wide.md", "---\ntitle: Wide\ndate: 2024-01-02\nlayout: wide\n---\n\nA big table.\n"),
+            ("plain.md", "---\ntitle: Plain\ndate: 2024-01-03\n---\n\nHi.\n"),
+        ],
+    )
+}
+
+#[tokio::test]
+async fn bare_and_wide_layouts_with_post_assets() {
+    let dir = site("slash");
+    SiteGenerator::new(config(&dir)).generate().await.unwrap();
+
+    let sketch = fs::read_to_string(dir.join("dist/sketch/index.html")).unwrap();
+    assert!(sketch.contains(r#"<body class="layout-bare">"#), "{}", sketch);
+    assert!(!sketch.contains("<header>") && !sketch.contains("post-title"), "{}", sketch);
+    assert!(sketch.contains(r#"<link rel="stylesheet" href="../sketch/sketch.css">"#), "{}", sketch);
+    assert!(sketch.contains(r#"<script src="../sketch/lib/draw.js" defer></script>"#), "{}", sketch);
+    assert!(sketch.contains(r#"<script src="https://cdn.example.com/p5.js" defer></script>"#), "{}", sketch);
+    assert!(!sketch.contains("secret.js"), "{}", sketch);
+    assert_eq!(fs::read_to_string(dir.join("dist/sketch/sketch.css")).unwrap(), "canvas { width: 100%; }\n");
+    assert_eq!(fs::read_to_string(dir.join("dist/sketch/lib/draw.js")).unwrap(), "draw();\n");
+
+    let wide = fs::read_to_string(dir.join("dist/wide/index.html")).unwrap();
+    assert!(wide.contains(r#"<body class="layout-wide">"#), "{}", wide);
+    assert!(wide.contains("<header>"), "{}", wide);
+    let plain = fs::read_to_string(dir.join("dist/plain/index.html")).unwrap();
+    assert!(plain.contains("<body>\n"), "{}", plain);
+}
+
+#[tokio::test]
+async fn post_assets_go_under_the_slug_without_trailing_slashes() {
+    let dir = site("never");
+    let config = Config { trailing_slash: TrailingSlash::Never, ..config(&dir) };
+    SiteGenerator::new(config).generate().await.unwrap();
+
+    let sketch = fs::read_to_string(dir.join("dist/sketch.html")).unwrap();
+    assert!(sketch.contains(r#"href="./sketch/sketch.css""#), "{}", sketch);
+    assert!(dir.join("dist/sketch/sketch.css").is_file());
+    assert!(dir.join("dist/sketch/lib/draw.js").is_file());
+}
@@ -0,0 +1,88 @@
+//! Tests for building sites through the library API, as programs embedding scribe do.
+
+mod common;
+
+use scribe::config::InitialsMode;
+use scribe::{fonts, templates, Config, SiteGenerator};
+use std::fs;
+use std::path::PathBuf;
+
+/// A project under the temp dir with two posts, building offline into its `dist`.
+fn project(name: &str) -> (PathBuf, Config) {
+    let dir = common::project(
+        "library",
+        name,
+        &[
+            ("first.md", "---\ntitle: First\ndate: 2024-01-01\n---\n\nThe first post.\n"),
+            ("second.md", "---\ntitle: Second\ndate: 2024-02-01\ntags: [notes]\n---\n\nLinks to [the first](/first/).\n"),
+        ],
+    );
+    let mut config = Config { title: "Embedded".to_string(), ..common::config(&dir) };
+    config.initials.mode = InitialsMode::Svg;
+    (dir, config)
+}
+
+#[tokio::test]
+async fn generate_builds_the_site_and_keeps_the_posts() {
+    let (dir, config) = project("generate");
+    let mut generator = SiteGenerator::new(config);
+    generator.generate().await.unwrap();
+
+    assert!(dir.join("dist/index.html").is_file());
+    assert!(dir.join("dist/first/index.html").is_file());
+    let titles: Vec<&str> = generator.posts().iter().map(|p| p.title.as_str()).collect();
+    assert_eq!(titles, ["Second", "First"]);
+    assert!(generator.posts()[0].links_to(&generator.posts()[1]));
+    assert!(!generator.into_summary().created.is_empty());
+}
+
+#[tokio::test]
+async fn read_posts_parses_without_writing() {
+    let (dir, config) = project("read");
+    let mut generator = SiteGenerator::new(config);
+    let posts = generator.read_posts().await.unwrap();
+
+    assert_eq!(posts.len(), 2);
+    assert_eq!(posts[0].tags(), ["notes"]);
+    assert!(!dir.join("dist").exists());
+}
+
+#[tokio::test]
+async fn templates_render_single_pages() {
+    let (_, config) = project("templates");
+    let mut generator = SiteGenerator::new(config.clone());
+    let posts = generator.read_posts().await.unwrap();
+
+    let page = templates::render_post(&config, &posts[1], posts, &[], None).unwrap();
+    assert!(page.contains("The first post."));
+    let index = templates::render_index(&config, &posts.iter().collect::<Vec<_>>(), "").unwrap();
+    assert!(index.contains("Second") && index.contains("Embedded"));
+}
+
+#[tokio::test]
+async fn generate_after_read_posts_writes_the_site() {
+    let (dir, config) = project("reuse");
+    let mut generator = SiteGenerator::new(config);
+    generator.read_posts().await.unwrap();
+    generator.generate().await.unwrap();
+    assert!(dir.join("dist/second/index.html").is_file());
+}
+
+#[tokio::test]
+async fn lockfile_is_kept_in_the_project() {
+    let (dir, mut config) = project("lock");
+    // Bundled fonts as an earlier build downloaded them
+    config.fonts.bundle = true;
+    for face in fonts::FACES {
+        let path = dir.join("dist").join(face.path());
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, face.path()).unwrap();
+    }
+    SiteGenerator::new(config.clone()).generate().await.unwrap();
+    assert!(!dir.join("scribe.lock").exists(), "offline builds fetch nothing, so lock nothing");
+
+    config.offline = false;
+    SiteGenerator::new(config).generate().await.unwrap();
+    let lock = fs::read_to_string(dir.join("scribe.lock")).unwrap();
+    assert!(lock.contains("\"font\"") && lock.contains("\"sha256\""), "{}", lock);
+}
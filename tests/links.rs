@@ -0,0 +1,36 @@
+//! Tests for wiki links between posts and for retargeting links when `scribe mv` renames one.
+
+mod common;
+
+use scribe::generator::{retarget_links, SiteGenerator};
+use std::fs;
+
+#[tokio::test]
+async fn wiki_links_in_code_stay_as_written() {
+    let dir = common::project(
+        "links",
+        "wiki-code",
+        &[
+            ("hello.md", "---\ntitle: Hello\ndate: 2024-01-01\n---\n\nHi.\n"),
+            ("notes.md", "---\ntitle: Notes\ndate: 2024-01-02\n---\n\nSee [[hello]], not `[[missing]]`.\n\n~~~\n[[missing]]\n```\n[[missing]]\n~~~\n\n````md\n```\n[[missing]]\n```\n````\n"),
+        ],
+    );
+    // A wiki link to an unknown post fails the build, so these were all left alone
+    SiteGenerator::new(common::config(&dir)).generate().await.unwrap();
+
+    let notes = fs::read_to_string(dir.join("dist/notes/index.html")).unwrap();
+    assert!(notes.contains(r#"<a href="../hello/">hello</a>"#), "{}", notes);
+    assert!(notes.contains("<code>[[missing]]</code>"), "{}", notes);
+    assert!(notes.contains("<pre><code>[[missing]]\n```\n[[missing]]\n"), "{}", notes);
+}
+
+#[test]
+fn retargeting_skips_code() {
+    let markdown = "See [[old]], [`old`](/old/) and `[[old]]`.\n\n~~~\n[[old]]\n```\n[it](/old/)\n~~~\n\n````md\n```\n[[old]]\n```\n````\n<a href=\"../old.html\">";
+    let (updated, changed) = retarget_links(markdown, "old", "new");
+    assert_eq!(changed, 3);
+    assert_eq!(
+        updated,
+        "See [[new]], [`old`](/new/) and `[[old]]`.\n\n~~~\n[[old]]\n```\n[it](/old/)\n~~~\n\n````md\n```\n[[old]]\n```\n````\n<a href=\"../new.html\">"
+    );
+}
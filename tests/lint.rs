@@ -0,0 +1,40 @@
+//! Tests for the alt-text lint, image references and the fence and code span scanning they share
+//! with link passes.
+
+use scribe::assets;
+use scribe::lint::missing_alt_text;
+use scribe::markdown::{code_spans, CodeFences};
+use std::path::Path;
+
+fn lint(content: &str) -> Vec<(usize, String)> {
+    missing_alt_text(Path::new("post.md"), content).into_iter().map(|m| (m.line, m.src)).collect()
+}
+
+#[test]
+fn fences_close_on_the_same_character_and_length() {
+    let lines = ["~~~", "```", "still code", "~~~~", "text", "````rust", "```", "````", "text", "``` `not` a fence", "  ~~~ md", "  ~~~"];
+    let mut fences = CodeFences::default();
+    let code: Vec<bool> = lines.iter().map(|line| fences.is_code(line)).collect();
+    assert_eq!(code, [true, true, true, true, false, true, true, true, false, false, true, true]);
+}
+
+#[test]
+fn code_spans_match_backtick_runs_of_the_same_length() {
+    assert_eq!(code_spans("a `b` c"), [("a ", false), ("`b`", true), (" c", false)]);
+    assert_eq!(code_spans("``a ` b`` `c"), [("``a ` b``", true), (" `c", false)]);
+    assert_eq!(code_spans(r"\`a` é `b`"), [(r"\`a", false), ("` é `", true), ("b`", false)]);
+    assert_eq!(code_spans("plain"), [("plain", false)]);
+}
+
+#[test]
+fn images_in_code_are_not_linted() {
+    let content = "![](a.png)\n~~~\n![](b.png)\n~~~\n````md\n```\n![](c.png)\n```\n````\nUse `![](d.png)` and <img src=\"e.png\">\n";
+    assert_eq!(lint(content), [(1, "a.png".to_string()), (10, "e.png".to_string())]);
+}
+
+#[test]
+fn image_references_in_code_are_skipped() {
+    let content = "![a](a.png)\n~~~\n![a cat](cat.png)\n~~~\n````\n```\n![b](b.png)\n````\nSee `![c](c.png)` and <img src=\"d.png\" alt=\"d\">\n";
+    let targets: Vec<(usize, String)> = assets::references(Path::new("post.md"), content).into_iter().map(|r| (r.line, r.target)).collect();
+    assert_eq!(targets, [(1, "a.png".to_string()), (9, "d.png".to_string())]);
+}
@@ -0,0 +1,63 @@
+//! Tests for comparing the resources a build uses against scribe.lock.
+
+use scribe::lock::{Lockfile, Resource, ResourceKind};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+fn lockfile(resources: &[(&str, Resource)]) -> Lockfile {
+    Lockfile { resources: resources.iter().map(|(url, r)| (url.to_string(), r.clone())).collect::<BTreeMap<_, _>>() }
+}
+
+fn download(kind: ResourceKind, path: &str, sha256: Option<&str>) -> Resource {
+    Resource { kind, path: Some(path.to_string()), sha256: sha256.map(str::to_string), meta: None }
+}
+
+#[test]
+fn scripts_must_be_downloaded_and_match() {
+    let katex = "https://cdn.jsdelivr.net/npm/katex@0.16.11/dist/katex.min.js";
+    let mermaid = "https://cdn.jsdelivr.net/npm/mermaid@11.4.1/dist/mermaid.min.js";
+    let locked = lockfile(&[
+        (katex, download(ResourceKind::Katex, ".scribe/katex/katex.min.js", Some("aa"))),
+        (mermaid, download(ResourceKind::Mermaid, ".scribe/mermaid/mermaid.min.js", Some("bb"))),
+    ]);
+    assert!(locked.differences(&locked).is_empty());
+
+    let current = lockfile(&[
+        (katex, download(ResourceKind::Katex, ".scribe/katex/katex.min.js", None)),
+        (mermaid, download(ResourceKind::Mermaid, ".scribe/mermaid/mermaid.min.js", Some("cc"))),
+    ]);
+    let differences = locked.differences(&current);
+    assert_eq!(differences.len(), 2, "{:?}", differences);
+    assert!(differences[0].starts_with(".scribe/katex/katex.min.js is missing"), "{:?}", differences);
+    assert!(differences[1].starts_with(".scribe/mermaid/mermaid.min.js does not match"), "{:?}", differences);
+}
+
+#[test]
+fn webmentions_only_have_to_be_locked() {
+    let target = "https://webmention.io/api/mentions.jf2?target=https%3A%2F%2Fexample.com%2Fhello%2F";
+    let locked = lockfile(&[(target, Resource::webmentions(Some(json!([]))))]);
+    // Newer mentions in the cache don't matter; frozen builds use the locked ones
+    let current = lockfile(&[(target, Resource::webmentions(Some(json!([{ "kind": "like" }]))))]);
+    assert!(locked.differences(&current).is_empty());
+    assert_eq!(locked.webmentions().collect::<Vec<_>>(), [(target, &json!([]))]);
+    assert_eq!(locked.metadata().count(), 0);
+
+    let unlocked = lockfile(&[("https://webmention.io/api/mentions.jf2?target=other", Resource::webmentions(None))]);
+    assert_eq!(locked.differences(&unlocked).len(), 1);
+}
+
+#[test]
+fn only_fetched_resources_are_locked() {
+    let font = "https://example.com/font.woff2";
+    let favicon = "https://example.com/favicon.ico";
+    let link = "https://example.com/post";
+    let locked = lockfile(&[
+        (font, download(ResourceKind::Font, "fonts/font.woff2", None)),
+        (favicon, download(ResourceKind::Favicon, "favicons/example.com.ico", None)),
+        (link, Resource::metadata(None)),
+    ])
+    .fetched();
+    // The failed download is fetched again; the sites without a favicon or metadata stay locked as such
+    assert_eq!(locked.resources.keys().collect::<Vec<_>>(), [favicon, link]);
+    assert_eq!(locked.resources[favicon].path, None);
+}
@@ -0,0 +1,14 @@
+//! Tests for taking `::: raw` HTML blocks out of posts and putting them back.
+
+use scribe::markdown::{extract_raw_blocks, restore_raw_blocks};
+
+#[test]
+fn raw_blocks_in_code_stay_code() {
+    let source = "````md\n```\n::: raw\n<b>shown</b>\n:::\n```\n````\n\n~~~\n::: raw\n~~~\n\n::: raw iframe height=80\n<canvas></canvas>\n:::\n";
+    let (markdown, blocks) = extract_raw_blocks(source);
+    assert_eq!(blocks.len(), 1);
+    assert!(blocks[0].iframe && blocks[0].height == Some(80), "{:?}", blocks);
+    assert!(markdown.starts_with("````md\n```\n::: raw\n<b>shown</b>\n:::\n```\n````\n\n~~~\n::: raw\n~~~\n"), "{}", markdown);
+    let html = restore_raw_blocks(&markdown, &blocks);
+    assert!(html.contains("<iframe class=\"raw-html-frame\"") && !html.contains("data-raw-block"), "{}", html);
+}
@@ -0,0 +1,89 @@
+//! Tests for AsciiDoc and Org-mode posts.
+
+mod common;
+
+use scribe::config::InputFormat;
+use scribe::generator::SiteGenerator;
+use scribe::markup::to_markdown;
+use std::fs;
+
+#[test]
+fn converts_org_documents() {
+    let org = "#+TITLE: Garden Notes\n#+DATE: <2024-03-02 Sat>\n#+FILETAGS: :plants:emacs:\n\n\
+* Spring\n:PROPERTIES:\n:ID: 1234\n:END:\nSome *bold*, /italic/ and =code= with [[https://example.com][a link]] and [[file:other.org][another note]].\n\
+# a comment\n\n- one\n- two\n\n#+BEGIN_SRC rust\nlet x = *y*;\n#+END_SRC\n\n#+BEGIN_QUOTE\nQuoted /words/.\n#+END_QUOTE\n\n\
+| a | b |\n| 1 | 2 |\n";
+    let markdown = to_markdown(InputFormat::Org, org);
+    assert!(markdown.starts_with("---\n"), "{}", markdown);
+    assert!(markdown.contains("title: Garden Notes"), "{}", markdown);
+    assert!(markdown.contains("2024-03-02"), "{}", markdown);
+    assert!(markdown.contains("- plants\n- emacs"), "{}", markdown);
+    for expected in [
+        "## Spring\nSome **bold**, *italic* and `code` with [a link](https://example.com) and [[other|another note]].",
+        "- one\n- two",
+        "```rust\nlet x = *y*;\n```",
+        "> Quoted *words*.",
+        "| a | b |\n|---|---|\n| 1 | 2 |",
+    ] {
+        assert!(markdown.contains(expected), "missing {:?} in:\n{}", expected, markdown);
+    }
+    assert!(!markdown.contains("PROPERTIES") && !markdown.contains("a comment"), "{}", markdown);
+}
+
+#[test]
+fn converts_asciidoc_documents() {
+    let adoc = "= Writing Tools\nJane Doe <jane@example.com>\n:revdate: 2024-04-01\n:keywords: writing, tools\n:toc:\n\n\
+== First Steps\n\nUse *strong*, _emphasis_ and `mono`, see https://example.com[the site] or xref:setup.adoc[Setup].\n\n\
+* top\n** nested\n\n[source,python]\n----\nprint(\"*hi*\")\n----\n\nNOTE: Mind the gap.\n\n\
+|===\n| Name | Value\n| a | 1\n|===\n";
+    let markdown = to_markdown(InputFormat::Asciidoc, adoc);
+    assert!(markdown.contains("title: Writing Tools"), "{}", markdown);
+    assert!(markdown.contains("date: 2024-04-01") || markdown.contains("date: '2024-04-01'"), "{}", markdown);
+    assert!(markdown.contains("- writing\n- tools"), "{}", markdown);
+    assert!(!markdown.contains("Jane Doe") && !markdown.contains("toc"), "{}", markdown);
+    for expected in [
+        "## First Steps",
+        "Use **strong**, *emphasis* and `mono`, see [the site](https://example.com) or [[setup|Setup]].",
+        "- top\n  - nested",
+        "```python\nprint(\"*hi*\")\n```",
+        "> **Note:** Mind the gap.",
+        "| Name | Value |\n|---|---|\n| a | 1 |",
+    ] {
+        assert!(markdown.contains(expected), "missing {:?} in:\n{}", expected, markdown);
+    }
+}
+
+#[test]
+fn frontmatter_blocks_win_over_document_headers() {
+    let markdown = to_markdown(InputFormat::Org, "---\ntitle: Chosen\naliases: old\n---\n#+TITLE: Ignored\n\nBody.\n");
+    assert!(markdown.contains("title: Chosen") && !markdown.contains("Ignored"), "{}", markdown);
+    assert!(markdown.contains("aliases: old"), "{}", markdown);
+}
+
+#[tokio::test]
+async fn builds_enabled_formats_only() {
+    let dir = common::project(
+        "markup",
+        "formats",
+        &[
+            ("garden.org", "#+TITLE: Garden\n#+DATE: 2024-01-02\n\nSee [[hello]].\n"),
+            ("tools.adoc", "= Tools\n:revdate: 2024-01-03\n\nA _short_ list.\n"),
+            ("hello.md", "---\ntitle: Hello\ndate: 2024-01-01\n---\n\nHi.\n"),
+        ],
+    );
+    let mut config = common::config(&dir);
+    SiteGenerator::new(config.clone()).generate().await.unwrap();
+    assert!(!dir.join("dist/garden/index.html").exists());
+
+    config.input_formats = vec![InputFormat::Asciidoc, InputFormat::Org];
+    SiteGenerator::new(config).generate().await.unwrap();
+    let garden = fs::read_to_string(dir.join("dist/garden/index.html")).unwrap();
+    assert!(garden.contains("<h1 class=\"post-title\">Garden</h1>"), "{}", garden);
+    assert!(garden.contains("href=\"../hello/\""), "{}", garden);
+    let tools = fs::read_to_string(dir.join("dist/tools/index.html")).unwrap();
+    assert!(tools.contains("A <em>short</em> list."), "{}", tools);
+    let hello = fs::read_to_string(dir.join("dist/hello/index.html")).unwrap();
+    assert!(hello.contains("Backlinks"), "{}", hello);
+    // The sources are left as they were written
+    assert_eq!(fs::read_to_string(dir.join("posts/tools.adoc")).unwrap(), "= Tools\n:revdate: 2024-01-03\n\nA _short_ list.\n");
+}
@@ -0,0 +1,65 @@
+//! Tests for finding TeX math in posts and converting it to MathML.
+
+use scribe::config::{MarkdownOptions, MathMode};
+use scribe::markdown;
+use scribe::math::{self, Math};
+
+fn render(source: &str, mode: MathMode) -> String {
+    let (markdown, spans) = math::extract(source);
+    math::restore(&markdown::render(&markdown, &MarkdownOptions::default()), &spans, mode)
+}
+
+#[test]
+fn finds_inline_and_display_math_outside_code() {
+    let source = "Let $a_i$ and $$\\sum_i a_i$$ hold.\n\n$$\nx^2\n$$\n\n`$not$` and\n\n```\n$also not$\n```\n";
+    let (markdown, spans) = math::extract(source);
+    assert_eq!(
+        spans,
+        [
+            Math { tex: "a_i".to_string(), display: false },
+            Math { tex: "\\sum_i a_i".to_string(), display: true },
+            Math { tex: "x^2".to_string(), display: true },
+        ]
+    );
+    assert!(markdown.contains("<div data-math=\"2\"></div>\n\n"));
+    assert!(markdown.contains("`$not$`") && markdown.contains("$also not$"));
+}
+
+#[test]
+fn nested_and_tilde_fences_keep_their_dollars() {
+    let source = "````md\n```\n$x$\n```\n$y$\n````\n\n~~~\n$z$\n~~~\n\nThen $w$.\n";
+    let (markdown, spans) = math::extract(source);
+    assert_eq!(spans, [Math { tex: "w".to_string(), display: false }]);
+    assert!(markdown.contains("$x$\n```\n$y$") && markdown.contains("$z$"), "{}", markdown);
+}
+
+#[test]
+fn prices_and_escaped_dollars_stay_text() {
+    for text in ["It cost $5 and then $10.", "Between $ 1 $ and", "A \\$x$ sign", "$20$30"] {
+        let (markdown, spans) = math::extract(text);
+        assert!(spans.is_empty(), "{}", text);
+        assert_eq!(markdown, text);
+    }
+}
+
+#[test]
+fn emphasis_never_reaches_the_tex() {
+    let html = render("Both $a*b*c$ and *this*.", MathMode::Katex);
+    assert!(html.contains("<span class=\"math math-inline\">a*b*c</span>"));
+    assert!(html.contains("<em>this</em>"));
+}
+
+#[test]
+fn converts_tex_to_mathml() {
+    let html = render("$$\n\\frac{1}{\\sqrt{x}} + \\alpha^2 \\leq \\mathbb{R}\n$$\n", MathMode::Mathml);
+    assert!(html.starts_with("<math display=\"block\"><semantics><mrow>"));
+    assert!(html.contains("<mfrac><mrow><mn>1</mn></mrow><mrow><msqrt><mrow><mi>x</mi></mrow></msqrt></mrow></mfrac>"));
+    assert!(html.contains("<msup><mi>α</mi><mn>2</mn></msup><mo>≤</mo><mrow><mi>ℝ</mi></mrow>"));
+    assert!(html.contains("<annotation encoding=\"application/x-tex\">\\frac{1}{\\sqrt{x}} + \\alpha^2 \\leq \\mathbb{R}</annotation>"));
+
+    let limits = math::to_mathml("\\sum_{i=1}^n i", true);
+    assert!(limits.contains("<munderover><mo>∑</mo><mrow><mi>i</mi><mo>=</mo><mn>1</mn></mrow><mi>n</mi></munderover>"));
+    let cases = math::to_mathml("\\begin{pmatrix} a & b \\\\ c & d \\end{pmatrix}", false);
+    assert!(cases.contains("<mtable><mtr><mtd><mi>a</mi></mtd><mtd><mi>b</mi></mtd></mtr><mtr><mtd><mi>c</mi></mtd><mtd><mi>d</mi></mtd></mtr></mtable>"));
+    assert!(math::to_mathml("\\unknown", false).contains("<merror><mtext>\\unknown</mtext></merror>"));
+}
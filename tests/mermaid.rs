@@ -0,0 +1,19 @@
+//! Tests for turning ```mermaid code blocks into diagrams.
+
+use scribe::config::{MarkdownOptions, Mermaid};
+use scribe::{markdown, mermaid};
+
+#[test]
+fn marks_mermaid_blocks_and_reads_back_their_source() {
+    let html = markdown::render("```mermaid\ngraph TD;\n  A-->B;\n```\n\n```rust\nfn main() {}\n```\n", &MarkdownOptions::default());
+    let marked = mermaid::mark(&html);
+    assert!(marked.contains("<pre class=\"mermaid\">graph TD;\n  A--&gt;B;\n</pre>"));
+    assert!(marked.contains("<code class=\"language-rust\">"));
+    assert_eq!(mermaid::sources(&marked), ["graph TD;\n  A-->B;\n"]);
+}
+
+#[test]
+fn unrendered_diagrams_keep_their_source() {
+    let marked = "<pre class=\"mermaid\">graph LR; never-rendered</pre>";
+    assert_eq!(mermaid::inline(marked, &Mermaid::default()), marked);
+}
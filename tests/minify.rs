@@ -0,0 +1,60 @@
+//! Tests for minified, fingerprinted release builds.
+
+mod common;
+
+use scribe::generator::SiteGenerator;
+use scribe::minify;
+use std::fs;
+
+#[test]
+fn minifies_without_changing_rendering() {
+    let html = "<!DOCTYPE html>\n<html>\n  <head>\n    <!-- a note -->\n    <style>\n      a:hover { color: red ; }\n    </style>\n  </head>\n  <body>\n    <p>Some  <em>inline</em> <strong>text</strong>\n    wrapped.</p>\n    <pre>keep\n    this</pre>\n    <!-- scribe:begin post -->\n  </body>\n</html>\n";
+    assert_eq!(
+        minify::html(html),
+        "<!DOCTYPE html><html><head><style>a:hover{color:red}</style></head><body><p>Some <em>inline</em> <strong>text</strong>\nwrapped.</p><pre>keep\n    this</pre><!-- scribe:begin post --></body></html>"
+    );
+    // Math, SVG and other inline elements keep the spaces between them and the words around them
+    let inline = "<p>Both <math><mi>a</mi></math> <math><mi>b</mi></math> and <svg width=\"8\"></svg> here.</p>";
+    assert_eq!(minify::html(inline), inline);
+    // Non-breaking spaces are text, not whitespace to collapse
+    let nbsp = "<p>Prix\u{a0}: 10\u{a0}€</p>  <math><mtext>if\u{a0}</mtext></math>";
+    assert_eq!(minify::html(nbsp), "<p>Prix\u{a0}: 10\u{a0}€</p><math><mtext>if\u{a0}</mtext></math>");
+    let css = "/* theme */\n@media (min-width: 600px) {\n  .a > .b, .c :is(.d) {\n    width: calc(100% - 2rem);\n    font-family: \"Crimson Text\", serif;\n  }\n}\n";
+    assert_eq!(minify::css(css), "@media (min-width:600px){.a>.b,.c :is(.d){width:calc(100% - 2rem);font-family:\"Crimson Text\",serif}}");
+}
+
+#[tokio::test]
+async fn release_build_fingerprints_the_stylesheet() {
+    let dir = common::project(
+        "minify",
+        "release",
+        &[
+            ("hello.md", "---\ntitle: Hello\ndate: 2024-01-01\ncss: print-style.css\n---\n\nHi   there.\n"),
+            ("print-style.css", "p { color: black; }"),
+        ],
+    );
+    let config = common::config(&dir);
+    SiteGenerator::new(config.clone()).generate().await.unwrap();
+    assert!(dir.join("dist/style.css").is_file());
+
+    let mut generator = SiteGenerator::new(config);
+    generator.set_release(true);
+    generator.generate().await.unwrap();
+    let stylesheets: Vec<String> = fs::read_dir(dir.join("dist"))
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| name.ends_with(".css"))
+        .collect();
+    assert_eq!(stylesheets.len(), 1, "{:?}", stylesheets);
+    let name = &stylesheets[0];
+    assert!(name.starts_with("style.") && name.len() == "style.12345678.css".len(), "{}", name);
+    let page = fs::read_to_string(dir.join("dist/hello/index.html")).unwrap();
+    assert!(page.contains(&format!("href=\"../{}\"", name)), "{}", page);
+    // Other stylesheets ending in style.css keep their names
+    assert!(page.contains("href=\"../hello/print-style.css\""), "{}", page);
+    assert!(page.contains("Hi there."), "{}", page);
+    assert!(page.contains("</header><main"), "{}", page);
+    let css = fs::read_to_string(dir.join("dist").join(name)).unwrap();
+    assert!(!css.contains("/*"));
+}
@@ -0,0 +1,77 @@
+//! Tests for private posts, published encrypted behind a passphrase prompt.
+
+mod common;
+
+use base64::Engine;
+use openssl::hash::MessageDigest;
+use openssl::symm::{decrypt_aead, Cipher};
+use scribe::config::Config;
+use scribe::generator::SiteGenerator;
+use std::fs;
+
+fn site(name: &str) -> std::path::PathBuf {
+    common::project(
+        "private",
+        name,
+        &[
+            ("diary.md", "---\ntitle: Diary\ndate: 2024-01-02\nprivate: true\n---\n\nThe secret ingredient is [[hello|love]].\n"),
+            ("hello.md", "---\ntitle: Hello\ndate: 2024-01-01\n---\n\nHi.\n"),
+        ],
+    )
+}
+
+fn config(dir: &std::path::Path) -> Config {
+    Config { url: Some("https://example.com".to_string()), ..common::config(dir) }
+}
+
+/// The page sealed into a prompt page, decrypted the way the prompt's script does.
+fn unseal(prompt: &str, passphrase: &str) -> Option<String> {
+    let start = prompt.find(r#"<script type="application/json" id="sealed">"#)? + 44;
+    let end = start + prompt[start..].find("</script>")?;
+    let sealed: serde_json::Value = serde_json::from_str(&prompt[start..end]).unwrap();
+    let field = |name: &str| base64::engine::general_purpose::STANDARD.decode(sealed[name].as_str().unwrap()).unwrap();
+    let mut key = [0u8; 32];
+    let iterations = sealed["iterations"].as_u64().unwrap() as usize;
+    openssl::pkcs5::pbkdf2_hmac(passphrase.as_bytes(), &field("salt"), iterations, MessageDigest::sha256(), &mut key).unwrap();
+    let data = field("data");
+    let (data, tag) = data.split_at(data.len() - 16);
+    let page = decrypt_aead(Cipher::aes_256_gcm(), &key, Some(&field("iv")), &[], data, tag).ok()?;
+    Some(String::from_utf8(page).unwrap())
+}
+
+#[tokio::test]
+async fn private_posts_are_sealed_and_kept_out_of_other_outputs() {
+    let dir = site("sealed");
+    let config = Config { private_passphrase: Some("open sesame".to_string()), api: true, ..config(&dir) };
+    SiteGenerator::new(config.clone()).generate().await.unwrap();
+
+    let prompt = fs::read_to_string(dir.join("dist/diary/index.html")).unwrap();
+    assert!(prompt.contains("<h1 class=\"post-title\">Diary</h1>") && prompt.contains(r#"<form id="unlock""#), "{}", prompt);
+    assert!(prompt.contains(r#"<meta name="robots" content="noindex">"#), "{}", prompt);
+    assert!(!prompt.contains("secret"), "{}", prompt);
+    assert!(unseal(&prompt, "wrong").is_none());
+    let page = unseal(&prompt, "open sesame").unwrap();
+    assert!(page.contains("The secret ingredient is <a href=\"../hello/\""), "{}", page);
+    assert!(page.contains("<h1 class=\"post-title\">Diary</h1>"), "{}", page);
+
+    // Listed by title, with nothing of the content anywhere else
+    let index = fs::read_to_string(dir.join("dist/index.html")).unwrap();
+    assert!(index.contains("Diary") && !index.contains("secret"), "{}", index);
+    for file in ["hello/index.html", "feed.xml", "feed.json", "sitemap.xml"] {
+        let content = fs::read_to_string(dir.join("dist").join(file)).unwrap();
+        assert!(!content.contains("secret") && !content.contains("diary"), "{}: {}", file, content);
+    }
+    assert!(!dir.join("dist/diary/index.json").exists());
+
+    // Sealing the same page gives the same bytes
+    SiteGenerator::new(Config { output_dir: dir.join("again").display().to_string(), ..config }).generate().await.unwrap();
+    assert_eq!(fs::read_to_string(dir.join("again/diary/index.html")).unwrap(), prompt);
+}
+
+#[tokio::test]
+async fn private_posts_are_left_out_without_a_passphrase() {
+    let dir = site("unset");
+    SiteGenerator::new(config(&dir)).generate().await.unwrap();
+    assert!(!dir.join("dist/diary/index.html").exists());
+    assert!(dir.join("dist/hello/index.html").exists());
+}
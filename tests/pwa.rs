@@ -0,0 +1,41 @@
+//! Tests for the web app manifest and offline service worker.
+
+mod common;
+
+use scribe::config::{Config, Pwa};
+use scribe::generator::SiteGenerator;
+use std::fs;
+
+#[tokio::test]
+async fn precaches_the_build_and_retires_when_switched_off() {
+    let dir = common::scratch("pwa", "precache");
+    fs::create_dir_all(dir.join("posts/images")).unwrap();
+    fs::write(dir.join("posts/images/small.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+    fs::write(dir.join("posts/images/large.png"), vec![0u8; 200 * 1024]).unwrap();
+    fs::write(dir.join("posts/hello.md"), "---\ntitle: Hello\ndate: 2024-01-01\n---\n\n![Dot](/images/small.png)\n").unwrap();
+    let mut config = Config {
+        pwa: Pwa { enabled: true, short_name: Some("Notes".to_string()), precache_max_kib: 128 },
+        ..common::config(&dir)
+    };
+    SiteGenerator::new(config.clone()).generate().await.unwrap();
+
+    let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("dist/manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest["short_name"], "Notes");
+    assert_eq!(manifest["start_url"], "./");
+    let worker = fs::read_to_string(dir.join("dist/sw.js")).unwrap();
+    for cached in ["\"./\"", "\"hello/\"", "\"style.css\"", "\"images/small.png\"", "\"404.html\""] {
+        assert!(worker.contains(cached), "{} not precached:\n{}", cached, worker);
+    }
+    assert!(!worker.contains("large.png"), "{}", worker);
+    assert!(!worker.contains("_redirects"), "{}", worker);
+    let page = fs::read_to_string(dir.join("dist/hello/index.html")).unwrap();
+    assert!(page.contains(r#"<link rel="manifest" href="../manifest.json">"#), "{}", page);
+    assert!(page.contains("register('../sw.js')"), "{}", page);
+
+    config.pwa.enabled = false;
+    SiteGenerator::new(config).generate().await.unwrap();
+    let worker = fs::read_to_string(dir.join("dist/sw.js")).unwrap();
+    assert!(worker.contains("unregister()"), "{}", worker);
+    let page = fs::read_to_string(dir.join("dist/hello/index.html")).unwrap();
+    assert!(!page.contains("sw.js"), "{}", page);
+}
@@ -0,0 +1,57 @@
+//! Tests that the same posts build to the same bytes and unchanged files are left alone.
+
+mod common;
+
+use scribe::config::Config;
+use scribe::generator::SiteGenerator;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn files(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    let mut files = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(dir).into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+        files.insert(entry.path().strip_prefix(dir).unwrap().to_path_buf(), fs::read(entry.path()).unwrap());
+    }
+    files
+}
+
+#[tokio::test]
+async fn same_posts_build_to_the_same_bytes() {
+    let dir = common::project(
+        "reproducible",
+        "bytes",
+        &[
+            ("one.md", "---\ntitle: One\ndate: 2024-01-01\ntags: [b, a]\nmood: calm\nweather: rain\n---\n\nSee [[two]] and [[three]].\n"),
+            ("two.md", "---\ntitle: Two\ndate: 2024-01-02\nupdated: 2024-03-04\ntags: [a]\n---\n\nBack to [[one]].\n"),
+            ("three.md", "---\ntitle: Three\ndate: 2024-01-03\n---\n\nAlone.\n"),
+        ],
+    );
+    let config = |output: &str| Config {
+        output_dir: dir.join(output).display().to_string(),
+        build_stamp: true,
+        api: true,
+        ..common::config(&dir)
+    };
+
+    SiteGenerator::new(config("first")).generate().await.unwrap();
+    SiteGenerator::new(config("second")).generate().await.unwrap();
+    let first = files(&dir.join("first"));
+    assert_eq!(first.keys().collect::<Vec<_>>(), files(&dir.join("second")).keys().collect::<Vec<_>>());
+    for (path, bytes) in files(&dir.join("second")) {
+        assert!(first[&path] == bytes, "{} differs between builds", path.display());
+    }
+    let build = String::from_utf8(first[Path::new("build.json")].clone()).unwrap();
+    assert!(build.contains("2024-03-04T00:00:00"), "{}", build);
+
+    // Rebuilding in place rewrites nothing
+    let page = dir.join("first/one/index.html");
+    let modified = fs::metadata(&page).unwrap().modified().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    let mut generator = SiteGenerator::new(config("first"));
+    generator.generate().await.unwrap();
+    assert_eq!(fs::metadata(&page).unwrap().modified().unwrap(), modified);
+    let summary = generator.into_summary();
+    assert!(summary.created.is_empty() && summary.updated.is_empty(), "{:?}", summary);
+    assert!(summary.unchanged.contains(&PathBuf::from("one/index.html")), "{:?}", summary);
+}
@@ -0,0 +1,194 @@
+//! Integration tests for the preview server: page routing, slug redirects and hot reload.
+
+mod common;
+
+use futures_util::StreamExt;
+use scribe::serve::{self, DeployOnChange, ServeOptions, Server};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A fresh project under the temp dir whose `dist` holds an index, one post and a stylesheet.
+fn project(name: &str) -> PathBuf {
+    let dir = common::scratch("serve", name);
+    fs::create_dir_all(dir.join("dist/hello-world")).unwrap();
+    fs::write(dir.join("dist/index.html"), "<html><body>index</body></html>").unwrap();
+    fs::write(dir.join("dist/hello-world/index.html"), "<html><body>hello</body></html>").unwrap();
+    fs::write(dir.join("dist/style.css"), "body {}").unwrap();
+    dir
+}
+
+async fn start(dir: &Path, watch: bool) -> Server {
+    serve::start(ServeOptions {
+        dist: dir.join("dist"),
+        host: "127.0.0.1".to_string(),
+        port: 0,
+        config: dir.join("config.json"),
+        watch,
+        deploy: None,
+        tls: None,
+        access_log: None,
+    })
+    .await
+    .unwrap()
+}
+
+/// HTTP client that reports redirects instead of following them.
+fn client() -> reqwest::Client {
+    reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build().unwrap()
+}
+
+#[tokio::test]
+async fn serves_pages_and_assets() {
+    let dir = project("pages");
+    let server = start(&dir, false).await;
+    let url = server.url();
+
+    let index = client().get(&url).send().await.unwrap();
+    assert_eq!(index.status(), 200);
+    let body = index.text().await.unwrap();
+    assert!(body.contains("index"));
+    assert!(!body.contains("__hot_reload__"), "hot reload client injected without --watch");
+
+    let post = client().get(format!("{}hello-world/", url)).send().await.unwrap();
+    assert_eq!(post.text().await.unwrap(), "<html><body>hello</body></html>");
+
+    let css = client().get(format!("{}style.css", url)).send().await.unwrap();
+    assert_eq!(css.status(), 200);
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn redirects_unsanitized_slug_to_canonical_url() {
+    let dir = project("redirect");
+    let server = start(&dir, false).await;
+
+    let response = client().get(format!("{}Hello_World", server.url())).send().await.unwrap();
+    assert_eq!(response.status(), 303);
+    assert_eq!(response.headers()["location"], "/hello-world/");
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn missing_page_is_not_found() {
+    let dir = project("missing");
+    let server = start(&dir, false).await;
+
+    let response = client().get(format!("{}no-such-post/", server.url())).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn missing_page_serves_the_site_404_page() {
+    let dir = project("not-found-page");
+    fs::write(dir.join("dist/404.html"), "<html><body>not here</body></html>").unwrap();
+    let server = start(&dir, false).await;
+
+    let response = client().get(format!("{}no-such-post/", server.url())).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+    assert_eq!(response.text().await.unwrap(), "<html><body>not here</body></html>");
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn records_requests_in_the_access_log() {
+    let dir = project("access-log");
+    let log = dir.join(".scribe/access.log");
+    let server = serve::start(ServeOptions {
+        dist: dir.join("dist"),
+        host: "127.0.0.1".to_string(),
+        port: 0,
+        config: dir.join("config.json"),
+        watch: false,
+        deploy: None,
+        tls: None,
+        access_log: Some(log.clone()),
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+
+    let page = format!("{}hello-world/", url);
+    client().get(&page).header("referer", "https://news.example.com/item?id=1").header("user-agent", "Firefox/120.0").send().await.unwrap();
+    client().get(format!("{}style.css", url)).header("referer", &page).send().await.unwrap();
+    server.shutdown().await.unwrap();
+
+    let entries = scribe::access::read(&log).unwrap();
+    assert_eq!(entries.len(), 2, "{:?}", entries);
+    assert_eq!((entries[0].method.as_str(), entries[0].path.as_str(), entries[0].status), ("GET", "/hello-world/", 200));
+    assert_eq!(entries[0].referrer.as_deref(), Some("https://news.example.com/item?id=1"));
+    assert_eq!(entries[0].agent.as_deref(), Some("Firefox/120.0"));
+    // Links within the site aren't referrals, and no client address is kept
+    assert_eq!(entries[1].referrer, None);
+    assert!(!fs::read_to_string(&log).unwrap().contains("127.0.0.1"));
+}
+
+#[tokio::test]
+async fn missing_dist_is_an_error() {
+    let dir = project("no-dist");
+    fs::remove_dir_all(dir.join("dist")).unwrap();
+
+    let result = serve::start(ServeOptions {
+        dist: dir.join("dist"),
+        host: "127.0.0.1".to_string(),
+        port: 0,
+        config: dir.join("config.json"),
+        watch: false,
+        deploy: None,
+        tls: None,
+        access_log: None,
+    })
+    .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn hot_reload_socket_reports_changed_post() {
+    let dir = project("hot-reload");
+    fs::create_dir_all(dir.join("posts")).unwrap();
+    fs::write(dir.join("posts/hello-world.md"), "---\ntitle: Hello\ndate: 2024-01-01\n---\n\nFirst.\n").unwrap();
+    let config = common::config(&dir);
+    fs::write(dir.join("config.json"), serde_json::to_string(&config).unwrap()).unwrap();
+    // Rebuilds keep their caches in `.scribe/` under the working directory
+    std::env::set_current_dir(&dir).unwrap();
+
+    let server = start(&dir, true).await;
+    let page = client().get(format!("{}hello-world/", server.url())).send().await.unwrap();
+    assert!(page.text().await.unwrap().contains("__hot_reload__"));
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/__hot_reload__", server.addr)).await.unwrap();
+    // Rebuilds are debounced to one per second after startup
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    fs::write(dir.join("posts/hello-world.md"), "---\ntitle: Hello\ndate: 2024-01-01\n---\n\nSecond.\n").unwrap();
+
+    let message = tokio::time::timeout(Duration::from_secs(20), socket.next())
+        .await
+        .expect("no reload message")
+        .unwrap()
+        .unwrap();
+    let message: serde_json::Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+    assert_eq!(message, serde_json::json!({ "changed": ["hello-world"] }));
+    let page = fs::read_to_string(dir.join("dist/hello-world/index.html")).unwrap();
+    assert!(page.contains("Second."));
+
+    server.shutdown().await.unwrap();
+}
+
+#[test]
+fn deploy_retries_back_off_from_the_debounce() {
+    let deploy = |debounce: u64| DeployOnChange { dist: PathBuf::from("dist"), ipfs_api: String::new(), debounce: Duration::from_secs(debounce) };
+    let short = deploy(30);
+    assert_eq!(short.retry_delay(Duration::ZERO), Duration::from_secs(30));
+    assert_eq!(short.retry_delay(Duration::from_secs(30)), Duration::from_secs(60));
+    assert_eq!(short.retry_delay(Duration::from_secs(1500)), Duration::from_secs(30 * 60));
+
+    // A debounce longer than the backoff cap retries after the debounce
+    let long = deploy(3600);
+    assert_eq!(long.retry_delay(Duration::ZERO), Duration::from_secs(3600));
+    assert_eq!(long.retry_delay(Duration::from_secs(3600)), Duration::from_secs(3600));
+}
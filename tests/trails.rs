@@ -0,0 +1,16 @@
+//! Tests for reading the trails file.
+
+mod common;
+
+use scribe::trails;
+use std::fs;
+
+#[test]
+fn parse_errors_keep_their_cause() {
+    let dir = common::scratch("trails", "parse");
+    let path = dir.join("trails.yml");
+    fs::write(&path, "- name: Start here\n  posts: nope\n").unwrap();
+    let message = format!("{:#}", trails::load(&path).unwrap_err());
+    assert!(message.starts_with(&format!("Failed to parse {}: ", path.display())), "{}", message);
+    assert!(message.contains("posts"), "{}", message);
+}
@@ -0,0 +1,98 @@
+//! Tests for reading webmention.io feeds and showing mentions and comments under posts.
+
+mod common;
+
+use scribe::config::{Comments, Webmention};
+use scribe::lock::{Lockfile, Resource};
+use scribe::webmention::{self, Kind};
+use scribe::{templates, Config, SiteGenerator};
+use std::fs;
+
+fn feed() -> serde_json::Value {
+    serde_json::json!({
+        "type": "feed",
+        "children": [
+            {
+                "wm-property": "in-reply-to",
+                "url": "https://alice.example/replies/1",
+                "published": "2024-03-02T10:00:00+00:00",
+                "author": { "name": "Alice", "url": "https://alice.example/" },
+                "content": { "text": "Great <post>!" }
+            },
+            {
+                "wm-property": "like-of",
+                "url": "https://social.example/likes/9",
+                "wm-received": "2024-03-01T09:00:00Z",
+                "author": { "name": "", "url": "javascript:alert(1)" }
+            },
+            { "wm-property": "mention-of", "author": { "name": "No URL" } }
+        ]
+    })
+}
+
+#[test]
+fn parses_jf2_feeds_oldest_first() {
+    let mentions = webmention::parse(&feed());
+    assert_eq!(mentions.len(), 2);
+    assert_eq!(mentions[0].kind, Kind::Like);
+    // Nameless authors go by their host, and only web links are kept
+    assert_eq!(mentions[0].author, "social.example");
+    assert_eq!(mentions[0].author_url, None);
+    assert_eq!(mentions[1].kind, Kind::Reply);
+    assert_eq!(mentions[1].text, "Great <post>!");
+}
+
+#[tokio::test]
+async fn posts_show_mentions_endpoints_and_comments() {
+    let dir = common::project(
+        "webmention",
+        "mentions",
+        &[
+            ("hello.md", "---\ntitle: Hello\ndate: 2024-01-01\n---\n\nHi.\n"),
+            ("quiet.md", "---\ntitle: Quiet\ndate: 2024-01-02\ncomments: false\n---\n\nShh.\n"),
+        ],
+    );
+    let config = Config {
+        webmention: Some(Webmention { domain: "example.com".to_string(), pingback: true, show: true }),
+        comments: Some(Comments::Utterances {
+            repo: "me/blog".to_string(),
+            issue_term: "pathname".to_string(),
+            theme: "github-dark".to_string(),
+            label: None,
+        }),
+        ..common::config(&dir)
+    };
+    let mut generator = SiteGenerator::new(config.clone());
+    let mut posts = generator.read_posts().await.unwrap().to_vec();
+    posts[1].webmentions = webmention::parse(&feed());
+
+    let page = templates::render_post(&config, &posts[1], &posts, &[], None).unwrap();
+    assert!(page.contains(r#"<link rel="webmention" href="https://webmention.io/example.com/webmention">"#));
+    assert!(page.contains(r#"<link rel="pingback" href="https://webmention.io/example.com/xmlrpc">"#));
+    assert!(page.contains("Liked by social.example"));
+    assert!(page.contains("<p class=\"webmention-text\">Great &lt;post&gt;!</p>"));
+    assert!(page.contains(r#"repo="me/blog""#));
+
+    let quiet = templates::render_post(&config, &posts[0], &posts, &[], None).unwrap();
+    assert!(!quiet.contains("utteranc.es") && !quiet.contains("class=\"webmentions\""));
+}
+
+#[tokio::test]
+async fn frozen_builds_show_the_locked_mentions() {
+    let dir = common::project("webmention", "frozen", &[("hello.md", "---\ntitle: Hello\ndate: 2024-01-01\n---\n\nHi.\n")]);
+    let config = Config {
+        url: Some("https://frozen.example".to_string()),
+        webmention: Some(Webmention { domain: "frozen.example".to_string(), pingback: false, show: true }),
+        ..common::config(&dir)
+    };
+    let mentions = serde_json::to_value(webmention::parse(&feed())).unwrap();
+    let feed_url = webmention::feed_url("https://frozen.example/hello/");
+    let locked = Lockfile { resources: [(feed_url, Resource::webmentions(Some(mentions)))].into_iter().collect() };
+    locked.save(dir.join("scribe.lock")).unwrap();
+
+    let mut generator = SiteGenerator::new(config);
+    generator.set_frozen(true);
+    generator.generate().await.unwrap();
+    let page = fs::read_to_string(dir.join("dist/hello/index.html")).unwrap();
+    assert!(page.contains("Liked by social.example"), "{}", page);
+}